@@ -9,7 +9,6 @@ fn bench_sim_job_systems(c: &mut Criterion) {
     
     // We only create bodies once to be fair, but technically we consume them.
     // So we generate them for each bench or clone them.
-    // Bodies is Vec<Body>. Body is simple struct.
     
     // Helper to setup and bench
     let setup_sim = |job_system: Arc<JobSystem>| {
@@ -19,7 +18,7 @@ fn bench_sim_job_systems(c: &mut Criterion) {
         // but utils::uniform_disc is fairly fast compared to simulation step.
         let default_sim = Simulation::new(); 
         Simulation::with_bodies_and_job_system(
-            default_sim.bodies, 
+            default_sim.bodies.to_vec(), 
             default_sim.dt, 
             1.5, // theta (default in new() is 1.5)
             0.1, // epsilon (default in new() is 0.1)
@@ -0,0 +1,25 @@
+fn main() {
+    #[cfg(feature = "header")]
+    generate_header();
+
+    // Node.js addons resolve the N-API symbols from the host process at load time
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
+
+#[cfg(feature = "header")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml"))
+        .expect("failed to read cbindgen.toml");
+
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-changed=src");
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate C bindings")
+        .write_to_file(format!("{crate_dir}/include/nbody_simulation.h"));
+}
@@ -0,0 +1,1732 @@
+#ifndef NBODY_SIMULATION_H
+#define NBODY_SIMULATION_H
+
+/* Generated by cbindgen from src/c_api.rs. Do not edit by hand. */
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+typedef struct Vec2 {
+  float x;
+  float y;
+} Vec2;
+
+typedef struct JobSystem JobSystem;
+
+
+/**
+ * Boxes swept per chunk.
+ */
+#define SweepAndPrune_CHUNK 1024
+
+/**
+ * Settles in about 20 steps at `Simulation::DEFAULT_DT`.
+ */
+#define DragConstraint_DEFAULT_RATE 4.0
+
+#define Quadtree_ROOT 0
+
+/**
+ * Depth of the cells whose subtrees are built independently in a parallel build (see
+ * `partition`): 4^3 = 64 cells, enough to keep all threads busy when bodies are clustered.
+ */
+#define Quadtree_SPLIT_DEPTH 3
+
+#define Quadtree_DEFAULT_G 1.0
+
+#define Quadtree_DEFAULT_FORCE_EXPONENT 2.0
+
+/**
+ * Average bodies a node must hold (by mass) for its density to count as local density,
+ * so that a body's own leaf doesn't make it look arbitrarily dense.
+ */
+#define AdaptiveTheta_DENSITY_BODIES 16.0
+
+/**
+ * Orders of magnitude below the maximum that the logarithmic modes still tell apart.
+ */
+#define ColorMode_DECADES 4.0
+
+/**
+ * Default constants.
+ */
+#define Simulation_DEFAULT_DT 0.05
+
+#define Simulation_DEFAULT_N 1000000
+
+#define Simulation_DEFAULT_THETA 1.0
+
+#define Simulation_DEFAULT_EPSILON 1.0
+
+/**
+ * Jobs per thread of the finest job size tried.
+ */
+#define ChunkTuner_MAX_JOBS_PER_THREAD 64
+
+/**
+ * Gravitational constant in astronomical units, solar masses and years (4 pi^2).
+ */
+#define G_SOLAR 39.478418
+
+/**
+ * Version of the C API. Bumped whenever a signature or exported struct layout changes.
+ */
+#define NBODY_API_VERSION 1
+
+/**
+ * Status code returned by fallible C API functions.
+ * On anything other than `Ok`, `Simulation_GetLastError` describes the failure.
+ */
+typedef enum SimStatus {
+  SimStatus_Ok = 0,
+  SimStatus_NullHandle = 1,
+  SimStatus_InvalidArgument = 2,
+  SimStatus_IoError = 3,
+  /**
+   * The progress callback cancelled the operation.
+   */
+  SimStatus_Cancelled = 4,
+} SimStatus;
+
+/**
+ * Long-running operation reported to a progress callback.
+ */
+typedef enum ProgressOperation {
+  /**
+   * `step_n`, reported after each step.
+   */
+  ProgressOperation_Step = 0,
+  /**
+   * `write_checkpoint`, reported after encoding and compressing each section and after writing.
+   */
+  ProgressOperation_Checkpoint = 1,
+  /**
+   * `reset_with_generator`, reported before and after generating the bodies.
+   */
+  ProgressOperation_Generator = 2,
+} ProgressOperation;
+
+/**
+ * Parallel phases of a step on the fiber backend, see `Simulation::set_granularity`.
+ */
+typedef enum ParallelPhase {
+  /**
+   * Tree walks of `attract`, over groups of 8 bodies.
+   */
+  ParallelPhase_Force = 0,
+  /**
+   * Subtree builds of a parallel tree build, over the `Quadtree::SPLIT_DEPTH` cells.
+   */
+  ParallelPhase_Build = 1,
+  /**
+   * Center of mass propagation of an unbalanced tree, over the parents of a level.
+   */
+  ParallelPhase_Propagate = 2,
+  /**
+   * Integration in `iterate`, over bodies.
+   */
+  ParallelPhase_Integrate = 3,
+  /**
+   * Sweeps of the parallel broad phase, over `SweepAndPrune::CHUNK` boxes each.
+   */
+  ParallelPhase_Collide = 4,
+} ParallelPhase;
+
+/**
+ * How an applied force decays with distance from its origin.
+ */
+typedef enum Falloff {
+  /**
+   * Full strength everywhere inside the radius.
+   */
+  Falloff_Constant = 0,
+  /**
+   * Decays linearly to zero at the radius.
+   */
+  Falloff_Linear = 1,
+  /**
+   * Decays as 1 / (1 + d^2), full strength at the origin.
+   */
+  Falloff_InverseSquare = 2,
+} Falloff;
+
+/**
+ * How an applied force changes body velocities.
+ */
+typedef enum ForceMode {
+  /**
+   * Instantaneous velocity change, independent of mass.
+   */
+  ForceMode_Impulse = 0,
+  /**
+   * Force acting for one time step: dv = F * dt / m.
+   */
+  ForceMode_Force = 1,
+} ForceMode;
+
+/**
+ * What happens to bodies that leave the simulation bounds.
+ */
+typedef enum BoundaryMode {
+  /**
+   * Bodies move freely; the bounds are ignored.
+   */
+  BoundaryMode_None = 0,
+  /**
+   * Bodies leaving one side re-enter on the opposite side.
+   * Gravity itself is not periodic.
+   */
+  BoundaryMode_Periodic = 1,
+  /**
+   * Bodies bounce off the walls.
+   */
+  BoundaryMode_Reflective = 2,
+  /**
+   * Bodies leaving the bounds are removed.
+   */
+  BoundaryMode_Despawn = 3,
+} BoundaryMode;
+
+/**
+ * Where a simulation runs its parallel work, see `Simulation::set_backend`.
+ */
+typedef enum Backend {
+  /**
+   * RustFiber jobs on the job system (Rayon on wasm32, where RustFiber is unavailable).
+   */
+  Backend_RustFiber = 0,
+  Backend_Rayon = 1,
+  /**
+   * Forces on the GPU (see `Simulation::enable_gpu`), the other phases on the CPU backend
+   * selected before.
+   */
+  Backend_Gpu = 2,
+} Backend;
+
+/**
+ * How `Simulation::step` advances velocities and positions, see `Simulation::set_integrator`.
+ */
+typedef enum Integrator {
+  /**
+   * Kicks by the whole step with the accelerations of the last gravity pass, then drifts.
+   * First order; the default.
+   */
+  Integrator_SemiImplicitEuler = 0,
+  /**
+   * Kick-drift-kick leapfrog: half kicks either side of the gravity pass. Second order, and
+   * keeps the energy error bounded over long runs.
+   */
+  Integrator_Leapfrog = 1,
+} Integrator;
+
+#if defined(NBODY_FEATURE_SHM)
+/**
+ * Publishes each frame's body SoA into a named shared-memory ring buffer (see `RING_MAGIC`), so
+ * renderers in other processes can read frames without calling into the simulation.
+ *
+ * The buffer is removed when the publisher is dropped; readers that have it mapped keep their view.
+ */
+typedef struct RingPublisher RingPublisher;
+#endif
+
+/**
+ * A simulation behind a lock whose last published frame can be read from any thread
+ * without waiting for a step in progress.
+ */
+typedef struct SharedSimulation SharedSimulation;
+
+/**
+ * Manages the Barnes-Hut N-body simulation state and logic.
+ */
+typedef struct Simulation Simulation;
+
+/**
+ * Represents a celestial body in the simulation.
+ */
+typedef struct Body {
+  /**
+   * Position vector.
+   */
+  Vec2 pos;
+  /**
+   * Velocity vector.
+   */
+  Vec2 vel;
+  /**
+   * Acceleration vector (reset each step).
+   */
+  Vec2 acc;
+  /**
+   * Mass of the body.
+   */
+  float mass;
+  /**
+   * Visual radius of the body.
+   */
+  float radius;
+} Body;
+
+/**
+ * Represents a square region in the quadtree.
+ * Used to define the bounds of nodes.
+ */
+typedef struct Quad {
+  Vec2 center;
+  float size;
+} Quad;
+
+typedef struct Node {
+  /**
+   * Index of the first child in the nodes array (0 if leaf).
+   */
+  uint32_t children;
+  /**
+   * Index of the next sibling (or 0 if last child/root).
+   */
+  uint32_t next;
+  /**
+   * Center of mass of the node.
+   */
+  Vec2 pos;
+  /**
+   * Total mass of the node.
+   */
+  float mass;
+  /**
+   * Spatial bounds of the node.
+   */
+  struct Quad quad;
+  /**
+   * External body index (only valid if is_leaf() and mass > 0).
+   */
+  uint32_t body_index;
+} Node;
+
+typedef void (*CollisionCallbackFn)(void *user_data, uint32_t i, uint32_t j, float impulse);
+
+/**
+ * A collision resolved during the last step.
+ */
+typedef struct Collision {
+  /**
+   * Index of the first body.
+   */
+  uint32_t i;
+  /**
+   * Index of the second body.
+   */
+  uint32_t j;
+  /**
+   * Magnitude of the impulse exchanged between the bodies.
+   */
+  float impulse;
+} Collision;
+
+typedef void (*StepStatsCallbackFn)(void *user_data,
+                                    size_t frame,
+                                    double step_seconds,
+                                    size_t nodes,
+                                    size_t collisions,
+                                    float max_speed);
+
+/**
+ * A point to draw for one body or a cluster of bodies, see `Simulation::render_points`.
+ */
+typedef struct RenderPoint {
+  /**
+   * Center of mass.
+   */
+  float x;
+  float y;
+  /**
+   * Total mass.
+   */
+  float mass;
+  /**
+   * Number of bodies.
+   */
+  uint32_t count;
+} RenderPoint;
+
+/**
+ * Progress callback: receives the operation, its completion fraction in [0, 1] and a flag,
+ * initially false, that the callback sets to true to cancel the operation.
+ */
+typedef void (*ProgressCallbackFn)(void *user_data,
+                                   enum ProgressOperation operation,
+                                   float fraction,
+                                   bool *cancel);
+
+/**
+ * Mass fractions of the usual Lagrange radii, see `Simulation::lagrange_radii`.
+ */
+#define LAGRANGE_FRACTIONS { 0.1, 0.5, 0.9, }
+
+#define ParallelPhase_ALL { ParallelPhase_Force, ParallelPhase_Build, ParallelPhase_Propagate, ParallelPhase_Integrate, ParallelPhase_Collide, }
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Copies the calling thread's last error message into `buffer` as a nul-terminated UTF-8
+ * string, truncated to fit `capacity` bytes. Returns the full message length in bytes
+ * (without the terminator), so a larger buffer can be retried if it was truncated.
+ *
+ * # Safety
+ * `buffer` must be null or writable for `capacity` bytes.
+ */
+int32_t NBody_GetLastError(char *buffer, int32_t capacity);
+
+/**
+ * Creates a simulation with a uniform disc of `n` bodies. Returns its ID, or 0 on invalid input.
+ */
+int32_t NBody_Create(int32_t n, float dt, float theta, float epsilon);
+
+/**
+ * Creates a simulation from `count` bodies (see `Simulation_GetBodyStride` for the layout),
+ * which are copied. Returns its ID, or 0 on invalid input.
+ *
+ * # Safety
+ * `bodies` must be null or readable for `count` bodies.
+ */
+int32_t NBody_CreateFromBodies(const struct Body *bodies,
+                               int32_t count,
+                               float dt,
+                               float theta,
+                               float epsilon);
+
+/**
+ * Creates a simulation from a named generator and a JSON object of its parameters
+ * (see `Simulation_GetGeneratorName`). Returns its ID, or 0 on failure.
+ *
+ * # Safety
+ * `name` and `params_json` must be null or nul-terminated strings.
+ */
+int32_t NBody_CreateFromGenerator(const char *name,
+                                  const char *params_json,
+                                  float dt,
+                                  float theta,
+                                  float epsilon);
+
+/**
+ * Removes a simulation from the registry and frees it once no call is using it.
+ */
+enum SimStatus NBody_Destroy(int32_t id);
+
+/**
+ * Returns 1 if `id` refers to a live simulation, 0 otherwise.
+ */
+int32_t NBody_IsValid(int32_t id);
+
+/**
+ * Advances the simulation by `n` steps.
+ */
+enum SimStatus NBody_Step(int32_t id, int32_t n);
+
+/**
+ * Returns the number of bodies, or -1 for an unknown ID.
+ */
+int32_t NBody_GetBodyCount(int32_t id);
+
+/**
+ * Returns the frame counter, or -1 for an unknown ID.
+ */
+int64_t NBody_GetFrame(int32_t id);
+
+/**
+ * Returns the simulated time, or 0 for an unknown ID.
+ */
+double NBody_GetTime(int32_t id);
+
+enum SimStatus NBody_SetDt(int32_t id, float dt);
+
+enum SimStatus NBody_SetGravityConstant(int32_t id, float g);
+
+enum SimStatus NBody_AddBody(int32_t id,
+                             float x,
+                             float y,
+                             float vx,
+                             float vy,
+                             float mass,
+                             float radius);
+
+/**
+ * Applies a force to all bodies within `radius` of (x, y); see `Simulation_ApplyForceEx`.
+ */
+enum SimStatus NBody_ApplyForce(int32_t id,
+                                float x,
+                                float y,
+                                float fx,
+                                float fy,
+                                float radius,
+                                int32_t falloff,
+                                int32_t mode);
+
+/**
+ * Copies whole bodies (see `Simulation_GetBodyStride`) for up to `capacity` bodies.
+ * Returns the number copied, or -1 for an unknown ID.
+ *
+ * # Safety
+ * `out` must be null or writable for `capacity` bodies.
+ */
+int32_t NBody_CopyBodies(int32_t id, struct Body *out, int32_t capacity);
+
+/**
+ * Copies body positions as packed float2 for up to `capacity` bodies.
+ * Returns the number copied, or -1 for an unknown ID.
+ *
+ * # Safety
+ * `out_xy` must be null or writable for `2 * capacity` floats.
+ */
+int32_t NBody_CopyPositions(int32_t id, float *out_xy, int32_t capacity);
+
+/**
+ * Copies body velocities as packed float2 for up to `capacity` bodies.
+ * Returns the number copied, or -1 for an unknown ID.
+ *
+ * # Safety
+ * `out_xy` must be null or writable for `2 * capacity` floats.
+ */
+int32_t NBody_CopyVelocities(int32_t id, float *out_xy, int32_t capacity);
+
+/**
+ * Copies body masses for up to `capacity` bodies. Returns the number copied, or -1 for an unknown ID.
+ *
+ * # Safety
+ * `out` must be null or writable for `capacity` floats.
+ */
+int32_t NBody_CopyMasses(int32_t id,
+                         float *out,
+                         int32_t capacity);
+
+/**
+ * Copies body radii for up to `capacity` bodies. Returns the number copied, or -1 for an unknown ID.
+ *
+ * # Safety
+ * `out` must be null or writable for `capacity` floats.
+ */
+int32_t NBody_CopyRadii(int32_t id,
+                        float *out,
+                        int32_t capacity);
+
+/**
+ * Overwrites the positions of the first `count` bodies from packed float2.
+ * Returns the number written, or -1 for an unknown ID.
+ *
+ * # Safety
+ * `xy` must be null or readable for `2 * count` floats.
+ */
+int32_t NBody_WritePositions(int32_t id, const float *xy, int32_t count);
+
+/**
+ * Returns the message for the last error raised on the calling thread (empty if none).
+ * The pointer stays valid until the next failing call on the same thread.
+ */
+const char *Simulation_GetLastError(void);
+
+void Simulation_ClearLastError(void);
+
+uint32_t Simulation_GetApiVersion(void);
+
+/**
+ * Size in bytes of `Body`, for validating host-side struct layouts.
+ */
+size_t Simulation_GetBodyStride(void);
+
+/**
+ * Size in bytes of `Node`, for validating host-side struct layouts.
+ */
+size_t Simulation_GetNodeStride(void);
+
+/**
+ * # Safety
+ * No preconditions; free the returned handle with `Simulation_Destroy`.
+ */
+struct Simulation *Simulation_Create(void);
+
+/**
+ * Creates a simulation from a caller-provided array of `count` bodies, which is copied.
+ * Returns null on invalid input.
+ *
+ * # Safety
+ * `bodies` must be null or readable for `count` bodies.
+ */
+struct Simulation *Simulation_CreateFromBodies(const struct Body *bodies,
+                                               size_t count,
+                                               float dt,
+                                               float theta,
+                                               float epsilon);
+
+/**
+ * Creates a simulation from a JSON scene file.
+ * Returns null if the file cannot be read or is malformed; see `Simulation_GetLastError`.
+ *
+ * # Safety
+ * `path` must be null or a nul-terminated string.
+ */
+struct Simulation *Simulation_CreateFromSceneFile(const char *path);
+
+/**
+ * Creates an independent copy of the simulation sharing its JobSystem.
+ * The collision callback is not copied.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+struct Simulation *Simulation_Clone(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle no other thread is using; it is invalid afterwards.
+ */
+void Simulation_Destroy(struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_Step(struct Simulation *handle);
+
+/**
+ * Runs `n` steps in one call, saving hosts an FFI transition per step; each step is otherwise
+ * the same as `Simulation_Step`. Fails with `Cancelled` if the progress callback stops it early.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_StepN(struct Simulation *handle, size_t n);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_Reset(struct Simulation *handle, size_t n);
+
+/**
+ * Selects RustFiber (`use_rayon` false) or Rayon; see `Simulation_SetBackend`, which also
+ * covers the GPU.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetUseRayon(struct Simulation *handle, bool use_rayon);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+bool Simulation_GetUseRayon(const struct Simulation *handle);
+
+/**
+ * Selects the backend (a `Backend` value: 0 = RustFiber, 1 = Rayon, 2 = GPU). Fails with
+ * `InvalidArgument`, leaving the backend unchanged, if the GPU is requested but unavailable.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetBackend(struct Simulation *handle, int32_t backend);
+
+/**
+ * The selected backend as a `Backend` value, or -1 for a null handle.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+int32_t Simulation_GetBackend(const struct Simulation *handle);
+
+/**
+ * Sets how the fiber backend splits a parallel phase into jobs (see `Simulation::set_granularity`).
+ * `phase`: 0 = force, 1 = tree build, 2 = propagation, 3 = integration, 4 = collision sweep.
+ * `hint`: 0 = trivial, 1 = light, 2 = moderate, 3 = heavy work per item.
+ * `min_chunk`: minimum items per job, or 0 to size the jobs by the hint alone.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetGranularity(struct Simulation *handle,
+                                         int32_t phase,
+                                         int32_t hint,
+                                         size_t min_chunk);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetBodyCount(const struct Simulation *handle);
+
+/**
+ * Copies the bodies into an array owned by the handle and returns it.
+ * The array is a snapshot: it stays valid, and unchanged, until the next `Simulation_GetBodies`
+ * call on the handle or `Simulation_Destroy`.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+const struct Body *Simulation_GetBodies(const struct Simulation *handle);
+
+/**
+ * Copies up to `capacity` bodies into `out_bodies`. Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_bodies` null or writable for
+ * `capacity` bodies.
+ */
+size_t Simulation_CopyBodies(const struct Simulation *handle,
+                             struct Body *out_bodies,
+                             size_t capacity);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetNodeCount(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+const struct Node *Simulation_GetNodes(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_AddBody(struct Simulation *handle,
+                                  float x,
+                                  float y,
+                                  float vx,
+                                  float vy,
+                                  float mass,
+                                  float radius);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_ApplyForce(struct Simulation *handle,
+                                     float x,
+                                     float y,
+                                     float fx,
+                                     float fy,
+                                     float radius);
+
+/**
+ * Applies a force to all bodies within `radius` of (x, y).
+ * `falloff` is a `Falloff` value (0 = constant, 1 = linear, 2 = inverse-square),
+ * `mode` a `ForceMode` value (0 = impulse, 1 = force over one time step).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_ApplyForceEx(struct Simulation *handle,
+                                       float x,
+                                       float y,
+                                       float fx,
+                                       float fy,
+                                       float radius,
+                                       int32_t falloff,
+                                       int32_t mode);
+
+/**
+ * Adds a gravity well at (x, y) and writes its id to `out_id` (if not null).
+ * A positive `strength` attracts, a negative one repels; `falloff` is a `Falloff` value.
+ * A `lifetime` of 0 or less keeps the well until it is removed.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_id` null or writable.
+ */
+enum SimStatus Simulation_AddWell(struct Simulation *handle,
+                                  float x,
+                                  float y,
+                                  float strength,
+                                  float radius,
+                                  int32_t falloff,
+                                  float lifetime,
+                                  uint32_t *out_id);
+
+/**
+ * Moves the gravity well `id` to (x, y), e.g. while the user drags it.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_MoveWell(struct Simulation *handle, uint32_t id, float x, float y);
+
+/**
+ * Removes the gravity well `id`.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_RemoveWell(struct Simulation *handle, uint32_t id);
+
+/**
+ * Number of gravity wells that haven't expired or been removed.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetWellCount(const struct Simulation *handle);
+
+/**
+ * Starts dragging body `index` toward (x, y) with a critically damped spring, replacing any
+ * drag in progress.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_BeginDrag(struct Simulation *handle, size_t index, float x, float y);
+
+/**
+ * Moves the target of the drag in progress, if any, to (x, y).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_UpdateDrag(struct Simulation *handle, float x, float y);
+
+/**
+ * Releases the dragged body, if any.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_EndDrag(struct Simulation *handle);
+
+/**
+ * # Safety
+ * `job_system_handle` must be a live handle from `rustfiber::c_api`.
+ */
+struct Simulation *Simulation_CreateWithJobSystem(JobSystem *job_system_handle, size_t n, float dt);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetKineticEnergy(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetPotentialEnergy(const struct Simulation *handle);
+
+/**
+ * Virial ratio 2T / |U| on the tree from the last step (0 before the first step).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetVirialRatio(const struct Simulation *handle);
+
+/**
+ * Samples a field (0 = potential, 1 = acceleration magnitude, 2 = surface density) at the cell
+ * centers of a `width` x `height` grid of `cell_size` cells centered on (`center_x`, `center_y`),
+ * on the tree from the last step. Writes up to `capacity` values row-major, top row first, to
+ * `out_values` and returns the number written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_values` null or writable for
+ * `capacity` floats.
+ */
+size_t Simulation_SampleField(const struct Simulation *handle,
+                              int32_t kind,
+                              float center_x,
+                              float center_y,
+                              float cell_size,
+                              size_t width,
+                              size_t height,
+                              float *out_values,
+                              size_t capacity);
+
+/**
+ * Fills `out_buffer` (`width * height` floats, row-major, top row first) with the surface
+ * density at the cell centers of a grid exactly covering (min_x, min_y)-(max_x, max_y), on the
+ * tree from the last step, so a heatmap needs a single call.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_buffer` null or writable for `width *
+ * height` floats.
+ */
+enum SimStatus Simulation_SampleDensityGrid(const struct Simulation *handle,
+                                            float *out_buffer,
+                                            size_t width,
+                                            size_t height,
+                                            float min_x,
+                                            float min_y,
+                                            float max_x,
+                                            float max_y);
+
+/**
+ * Like `Simulation_SampleDensityGrid`, with the gravitational potential.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_buffer` null or writable for `width *
+ * height` floats.
+ */
+enum SimStatus Simulation_SamplePotentialGrid(const struct Simulation *handle,
+                                              float *out_buffer,
+                                              size_t width,
+                                              size_t height,
+                                              float min_x,
+                                              float min_y,
+                                              float max_x,
+                                              float max_y);
+
+/**
+ * Like `Simulation_SampleDensityGrid`, with the magnitude of the acceleration.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_buffer` null or writable for `width *
+ * height` floats.
+ */
+enum SimStatus Simulation_SampleAccelerationGrid(const struct Simulation *handle,
+                                                 float *out_buffer,
+                                                 size_t width,
+                                                 size_t height,
+                                                 float min_x,
+                                                 float min_y,
+                                                 float max_x,
+                                                 float max_y);
+
+/**
+ * Writes the acceleration a massless object at (x, y) would get, from the bodies (on the tree
+ * from the last step), external potentials, gravity wells and timeline fields, to `out_ax` and
+ * `out_ay` (each may be null).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_ax` and `out_ay` null or writable.
+ */
+enum SimStatus Simulation_GetAccelerationAt(const struct Simulation *handle,
+                                            float x,
+                                            float y,
+                                            float *out_ax,
+                                            float *out_ay);
+
+/**
+ * Gravitational potential at (x, y) from the bodies (on the tree from the last step) and
+ * external potentials. Returns 0 for a null handle.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetPotentialAt(const struct Simulation *handle, float x, float y);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_x` and `out_y` null or writable.
+ */
+enum SimStatus Simulation_GetMomentum(const struct Simulation *handle,
+                                      float *out_x,
+                                      float *out_y);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_SaveSnapshot(const struct Simulation *handle,
+                                       const char *path);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_LoadSnapshot(struct Simulation *handle,
+                                       const char *path);
+
+/**
+ * Writes all bodies to a CSV file (`x,y,vx,vy,mass,radius`).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_SaveCsv(const struct Simulation *handle,
+                                  const char *path);
+
+/**
+ * Replaces all bodies with those in a CSV file (`x,y,vx,vy,mass[,radius]`) and resets time.
+ * The simulation is left untouched if the file cannot be read.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_LoadCsv(struct Simulation *handle,
+                                  const char *path);
+
+/**
+ * Registers a callback invoked after each step, on the stepping thread, once per collision.
+ * Passing a null callback removes it.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time. `callback`, if set, must be safe to call with
+ * `user_data` on the stepping thread until it is replaced or the handle destroyed.
+ */
+enum SimStatus Simulation_SetCollisionCallback(struct Simulation *handle,
+                                               CollisionCallbackFn callback,
+                                               void *user_data);
+
+/**
+ * Number of collisions resolved during the last step, for hosts that poll instead of
+ * registering a callback.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetCollisionCount(const struct Simulation *handle);
+
+/**
+ * Copies up to `capacity` of the collisions resolved during the last step into `out_pairs`.
+ * Returns the number of collisions written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_pairs` null or writable for `capacity`
+ * collisions.
+ */
+size_t Simulation_CopyCollisions(const struct Simulation *handle,
+                                 struct Collision *out_pairs,
+                                 size_t capacity);
+
+/**
+ * Registers a callback invoked after each step, on the stepping thread, with the frame, the wall
+ * time of the step, the quadtree node count, the number of collisions and the largest body
+ * speed. Passing a null callback removes it.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time. `callback`, if set, must be safe to call with
+ * `user_data` on the stepping thread until it is replaced or the handle destroyed.
+ */
+enum SimStatus Simulation_SetStepStatsCallback(struct Simulation *handle,
+                                               StepStatsCallbackFn callback,
+                                               void *user_data);
+
+/**
+ * Writes the indices of bodies within `r` of (x, y) into `out_indices` (up to `capacity`).
+ * Returns the total number of matches, which may exceed `capacity`.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_indices` null or writable for
+ * `capacity` indices.
+ */
+size_t Simulation_QueryRadius(const struct Simulation *handle,
+                              float x,
+                              float y,
+                              float r,
+                              uint32_t *out_indices,
+                              size_t capacity);
+
+/**
+ * Writes node quad centers (packed float2) and sizes for up to `capacity` nodes.
+ * Either output may be null. Returns the number of nodes written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_centers` null or writable for `2 *
+ * capacity` floats, `out_sizes` null or writable for `capacity` floats.
+ */
+size_t Simulation_CopyNodeQuads(const struct Simulation *handle,
+                                float *out_centers,
+                                float *out_sizes,
+                                size_t capacity);
+
+/**
+ * Writes node centers of mass (packed float2) and total masses for up to `capacity` nodes.
+ * Either output may be null. Returns the number of nodes written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_com` null or writable for `2 *
+ * capacity` floats, `out_masses` null or writable for `capacity` floats.
+ */
+size_t Simulation_CopyNodeMasses(const struct Simulation *handle,
+                                 float *out_com,
+                                 float *out_masses,
+                                 size_t capacity);
+
+/**
+ * Writes body positions as tightly packed float2s for up to `capacity` bodies.
+ * Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_xy` null or writable for `2 *
+ * capacity` floats.
+ */
+size_t Simulation_CopyPositions(const struct Simulation *handle,
+                                float *out_xy,
+                                size_t capacity);
+
+/**
+ * Writes up to `count` bodies' positions (float2), velocities (float2) and masses (float) into
+ * caller buffers whose elements are `*_stride` bytes apart, so hosts can fill interleaved
+ * vertex buffers directly. A stride of 0 means tightly packed; each output may be null to skip
+ * it. Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and each output null or writable for `count`
+ * elements `*_stride` bytes apart.
+ */
+size_t Simulation_CopyBodySoA(const struct Simulation *handle,
+                              float *out_pos,
+                              size_t pos_stride,
+                              float *out_vel,
+                              size_t vel_stride,
+                              float *out_mass,
+                              size_t mass_stride,
+                              size_t count);
+
+/**
+ * Writes world-space body positions (`origin + position`, see `Simulation_SetOrigin`) as
+ * tightly packed double2s for up to `capacity` bodies, e.g. for Unreal Engine's large-world
+ * coordinates. Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_xy` null or writable for `2 *
+ * capacity` doubles.
+ */
+size_t Simulation_CopyPositionsF64(const struct Simulation *handle,
+                                   double *out_xy,
+                                   size_t capacity);
+
+/**
+ * Writes body radii for up to `capacity` bodies. Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_radii` null or writable for `capacity`
+ * floats.
+ */
+size_t Simulation_CopyRadii(const struct Simulation *handle,
+                            float *out_radii,
+                            size_t capacity);
+
+/**
+ * Writes stable body ids (see `Bodies::ids`) for up to `capacity` bodies, in index order.
+ * Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_ids` null or writable for `capacity`
+ * ids.
+ */
+size_t Simulation_CopyBodyIds(const struct Simulation *handle,
+                              uint32_t *out_ids,
+                              size_t capacity);
+
+/**
+ * Writes the quantity `mode` (0 = speed, 1 = acceleration magnitude, 2 = local density) of up
+ * to `capacity` bodies to `out_values`. Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_values` null or writable for
+ * `capacity` floats.
+ */
+size_t Simulation_CopyBodyScalars(const struct Simulation *handle,
+                                  int32_t mode,
+                                  float *out_values,
+                                  size_t capacity);
+
+/**
+ * Colors up to `capacity` bodies by `mode` (as in `Simulation_CopyBodyScalars`) through
+ * `colormap` (0 = grayscale, 1 = inferno, 2 = viridis), writing 4 RGBA bytes per body to
+ * `out_rgba`. Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_rgba` null or writable for `4 *
+ * capacity` bytes.
+ */
+size_t Simulation_CopyBodyColors(const struct Simulation *handle,
+                                 int32_t mode,
+                                 int32_t colormap,
+                                 uint8_t *out_rgba,
+                                 size_t capacity);
+
+/**
+ * Splats the body masses onto a `width` x `height` grid spanning `2 * extent` world units
+ * horizontally around the origin (see `Simulation::render_density`), blurred with a Gaussian of
+ * `sigma` cells if positive, and writes up to `capacity` densities row-major, top row first, to
+ * `out_density`. Returns the number of cells written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_density` null or writable for
+ * `capacity` floats.
+ */
+size_t Simulation_RenderDensity(const struct Simulation *handle,
+                                size_t width,
+                                size_t height,
+                                float extent,
+                                float sigma,
+                                float *out_density,
+                                size_t capacity);
+
+/**
+ * Writes the points to draw for the bodies in the view rectangle (`min_x`, `min_y`) to
+ * (`max_x`, `max_y`) to `out_points` (up to `capacity`), merging clusters smaller than
+ * `pixel_size` world units into one point (see `Simulation::render_points`).
+ * Returns the total number of points, which may exceed `capacity`.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_points` null or writable for
+ * `capacity` points.
+ */
+size_t Simulation_GetRenderPoints(const struct Simulation *handle,
+                                  float min_x,
+                                  float min_y,
+                                  float max_x,
+                                  float max_y,
+                                  float pixel_size,
+                                  struct RenderPoint *out_points,
+                                  size_t capacity);
+
+/**
+ * Writes the indices of the bodies inside the view rectangle (`min_x`, `min_y`) to
+ * (`max_x`, `max_y`) to `out_indices` and their positions (packed float2) to `out_xy`, up to
+ * `capacity` bodies (see `Simulation::bodies_in_view`). Either output may be null.
+ * Returns the total number of visible bodies, which may exceed `capacity`.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_indices` null or writable for
+ * `capacity` indices, `out_xy` null or writable for `2 * capacity` floats.
+ */
+size_t Simulation_QueryView(const struct Simulation *handle,
+                            float min_x,
+                            float min_y,
+                            float max_x,
+                            float max_y,
+                            uint32_t *out_indices,
+                            float *out_xy,
+                            size_t capacity);
+
+/**
+ * Starts recording trails of the last `length` positions, every `interval` frames, for the
+ * `count` bodies at `indices`, replacing any trails recorded so far. A `count` of 0 stops
+ * recording.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `indices` null or readable for `count`
+ * indices.
+ */
+enum SimStatus Simulation_SetTrails(struct Simulation *handle,
+                                    const uint32_t *indices,
+                                    size_t count,
+                                    size_t length,
+                                    size_t interval);
+
+/**
+ * Number of points recorded so far in each trail, the same for all of them.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetTrailLength(const struct Simulation *handle);
+
+/**
+ * Writes up to `capacity` trail points (packed float2) to `out_xy`: `Simulation_GetTrailLength`
+ * points per trail, oldest first, trails in the order their bodies were given. Returns the
+ * number of points written.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_xy` null or writable for `2 *
+ * capacity` floats.
+ */
+size_t Simulation_CopyTrailVertices(const struct Simulation *handle,
+                                    float *out_xy,
+                                    size_t capacity);
+
+/**
+ * Runs gravity every `gravity_interval` and collisions every `collision_interval` frames
+ * (0 = never, 1 = every frame, the default). Bodies keep their last acceleration between
+ * gravity updates.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetPhaseIntervals(struct Simulation *handle,
+                                            size_t gravity_interval,
+                                            size_t collision_interval);
+
+/**
+ * Splits each step into `substeps` integration steps of `dt / substeps`, each with its own
+ * collision and gravity pass (1 = no splitting, the default). Fails with `InvalidArgument` for 0.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetSubsteps(struct Simulation *handle, size_t substeps);
+
+/**
+ * Selects the integration scheme (an `Integrator` value: 0 = semi-implicit Euler, the default,
+ * 1 = leapfrog).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetIntegrator(struct Simulation *handle, int32_t integrator);
+
+/**
+ * Caps the quadtree at `budget` nodes (0 = unlimited, the default). Beyond it, bodies landing in
+ * an occupied leaf are merged into it; `Simulation_GetMergedBodies` reports how many were.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetNodeBudget(struct Simulation *handle, size_t budget);
+
+/**
+ * Number of bodies merged into occupied leaves during the last step because of the node budget.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetMergedBodies(const struct Simulation *handle);
+
+/**
+ * Sorts the bodies along a Morton curve every `interval` frames (0 = never). Body indices
+ * change with each sort; use `Simulation_CopyBodyIds` to follow bodies across steps.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetMortonInterval(struct Simulation *handle, size_t interval);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetGravityConstant(struct Simulation *handle, float g);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetGravityConstant(const struct Simulation *handle);
+
+/**
+ * Sets the exponent `k` of the force law |F| ~ 1 / r^k (2 for Newtonian gravity).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetForceExponent(struct Simulation *handle, float k);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetForceExponent(const struct Simulation *handle);
+
+/**
+ * Publishes the current frame and starts a step on a background thread. While it runs, the
+ * published frame can be read through `Simulation_GetFrontBodies` or `Simulation_LockRead`; any
+ * other call on the handle first waits for the step to finish and publishes its result.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_StepAsync(struct Simulation *handle);
+
+/**
+ * Returns true if no asynchronous step is in flight.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+bool Simulation_IsStepComplete(const struct Simulation *handle);
+
+/**
+ * Blocks until the asynchronous step in flight (if any) has finished.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_WaitStep(struct Simulation *handle);
+
+/**
+ * Publishes the current bodies to `Simulation_GetFrontBodies` and `Simulation_LockRead` readers.
+ * Frames are published on creation and by `Step`, `StepN` and `StepAsync`; call this after other
+ * changes (resets, added bodies, ...) to show them before the next step.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_Publish(struct Simulation *handle);
+
+/**
+ * Bodies of the last published frame (see `Simulation_Publish`). Never waits for or touches the
+ * simulation, so it may be called from another thread while the owning thread steps; null for a null
+ * or destroyed handle. The pointer stays valid until the next `Step`, `StepN`, `StepAsync`, `Publish`
+ * or `Destroy` on the handle, or any other call on it while an asynchronous step is in flight (which
+ * publishes the step's result); use `Simulation_LockRead` to hold a frame across those.
+ *
+ * # Safety
+ * `handle` is only used as a key, so any value is allowed, from any thread.
+ */
+const struct Body *Simulation_GetFrontBodies(const struct Simulation *handle);
+
+/**
+ * Number of bodies in the last published frame, see `Simulation_GetFrontBodies`.
+ *
+ * # Safety
+ * `handle` is only used as a key, so any value is allowed, from any thread.
+ */
+size_t Simulation_GetFrontBodyCount(const struct Simulation *handle);
+
+/**
+ * Pins the last published frame (see `Simulation_Publish`) and returns its bodies, writing their
+ * number to `out_count` (if non-null). The array stays valid and unchanged until the matching
+ * `Simulation_UnlockRead`, whatever frames are published meanwhile, so a renderer on another
+ * thread can read it without tearing while the owning thread steps. Locks nest: while the frame
+ * is pinned, further calls return the same frame and each needs its own unlock.
+ * Returns null for a null or destroyed handle.
+ *
+ * # Safety
+ * `handle` is only used as a key, so any value is allowed, from any thread, and `out_count` must be null or writable.
+ */
+const struct Body *Simulation_LockRead(const struct Simulation *handle,
+                                       size_t *out_count);
+
+/**
+ * Releases one `Simulation_LockRead`; the pinned frame is freed with the last one.
+ * Fails with `InvalidArgument` if the handle holds no read lock.
+ *
+ * # Safety
+ * `handle` is only used as a key, so any value is allowed, from any thread.
+ */
+enum SimStatus Simulation_UnlockRead(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * No preconditions; free the returned handle with `SharedSimulation_Destroy`.
+ */
+struct SharedSimulation *Simulation_CreateThreadSafe(void);
+
+/**
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle no other thread is using; it is invalid afterwards.
+ */
+void SharedSimulation_Destroy(struct SharedSimulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+enum SimStatus SharedSimulation_Step(const struct SharedSimulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+enum SimStatus SharedSimulation_StepN(const struct SharedSimulation *handle, size_t n);
+
+/**
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+enum SimStatus SharedSimulation_Reset(const struct SharedSimulation *handle, size_t n);
+
+/**
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+enum SimStatus SharedSimulation_AddBody(const struct SharedSimulation *handle,
+                                        float x,
+                                        float y,
+                                        float vx,
+                                        float vy,
+                                        float mass,
+                                        float radius);
+
+/**
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+enum SimStatus SharedSimulation_ApplyForceEx(const struct SharedSimulation *handle,
+                                             float x,
+                                             float y,
+                                             float fx,
+                                             float fy,
+                                             float radius,
+                                             int32_t falloff,
+                                             int32_t mode);
+
+/**
+ * Frame counter of the last published frame.
+ *
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+size_t SharedSimulation_GetFrame(const struct SharedSimulation *handle);
+
+/**
+ * Body count of the last published frame.
+ *
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+ */
+size_t SharedSimulation_GetBodyCount(const struct SharedSimulation *handle);
+
+/**
+ * Copies up to `capacity` bodies of the last published frame into `out_bodies`.
+ * Returns the number of bodies written.
+ *
+ * # Safety
+ * `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread, and `out_bodies` null or
+ * writable for `capacity` bodies.
+ */
+size_t SharedSimulation_CopyBodies(const struct SharedSimulation *handle,
+                                   struct Body *out_bodies,
+                                   size_t capacity);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+size_t Simulation_GetFrame(const struct Simulation *handle);
+
+/**
+ * Simulated time elapsed, accumulated per step so changes to dt are accounted for.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+double Simulation_GetTime(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+float Simulation_GetDt(const struct Simulation *handle);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetDt(struct Simulation *handle, float dt);
+
+/**
+ * Confines the simulation to a rectangle. `mode` is a `BoundaryMode` value
+ * (0 = none, 1 = periodic, 2 = reflective, 3 = despawn).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetBounds(struct Simulation *handle,
+                                    float min_x,
+                                    float min_y,
+                                    float max_x,
+                                    float max_y,
+                                    int32_t mode);
+
+/**
+ * Adds a rotating disc of `n` bodies with the given radius centered on (x, y).
+ * `spin` scales the orbital speed; negative values reverse the rotation.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SpawnDisc(struct Simulation *handle,
+                                    float x,
+                                    float y,
+                                    size_t n,
+                                    float radius,
+                                    float spin);
+
+/**
+ * Adds a Plummer star cluster of `n` bodies with scale length `radius` centered on (x, y).
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SpawnCluster(struct Simulation *handle,
+                                       float x,
+                                       float y,
+                                       size_t n,
+                                       float radius);
+
+/**
+ * Returns the number of registered generators.
+ */
+size_t Simulation_GetGeneratorCount(void);
+
+/**
+ * Returns the name of the generator at `index`, or null if out of range.
+ * The string is static and must not be freed.
+ */
+const char *Simulation_GetGeneratorName(size_t index);
+
+/**
+ * Returns a description of the parameters (with defaults) accepted by the generator at `index`,
+ * or null if out of range. The string is static and must not be freed.
+ */
+const char *Simulation_GetGeneratorParams(size_t index);
+
+/**
+ * Replaces all bodies with the output of the named generator and resets time.
+ * `params_json` is a JSON object of parameters and may be null or empty to use the defaults.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `name` and `params_json` null or
+ * nul-terminated strings.
+ */
+enum SimStatus Simulation_ResetWithGenerator(struct Simulation *handle,
+                                             const char *name,
+                                             const char *params_json);
+
+/**
+ * Replaces all bodies with `n` bodies sampled from an 8-bit grayscale image of `width` x `height`
+ * pixels (row-major, top row first), with density proportional to brightness, and resets time.
+ * The image is scaled to `size` world units wide and centered on the origin.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `pixels` null or readable for `width *
+ * height` bytes.
+ */
+enum SimStatus Simulation_ResetWithDensityMap(struct Simulation *handle,
+                                              const uint8_t *pixels,
+                                              size_t width,
+                                              size_t height,
+                                              size_t n,
+                                              float size);
+
+/**
+ * Starts recording external interactions (added bodies, forces, parameter changes, spawns and
+ * resets) from the current state, replacing any log in progress.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_StartInputLog(struct Simulation *handle);
+
+/**
+ * Writes the input log in progress to a file. Recording continues.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_SaveInputLog(const struct Simulation *handle,
+                                       const char *path);
+
+/**
+ * Stops recording the input log. Does nothing if none is running.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_StopInputLog(struct Simulation *handle);
+
+/**
+ * Restores the initial state of an input log file and re-executes its events up to `frame`
+ * (`SIZE_MAX` for the last logged event), reproducing the recorded run.
+ * The simulation is left untouched if the file cannot be read.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_ReplayInputLog(struct Simulation *handle,
+                                         const char *path,
+                                         size_t frame);
+
+/**
+ * Sets the world-space origin, translating the whole simulation without moving bodies
+ * relative to each other.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_SetOrigin(struct Simulation *handle, double x, double y);
+
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `out_x` and `out_y` null or writable.
+ */
+enum SimStatus Simulation_GetOrigin(const struct Simulation *handle,
+                                    double *out_x,
+                                    double *out_y);
+
+/**
+ * Moves the origin to (x, y) while keeping world-space positions, so bodies near it regain
+ * full f32 precision.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_ShiftOrigin(struct Simulation *handle, double x, double y);
+
+/**
+ * Moves the origin to the center of mass of the bodies while keeping world-space positions.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time.
+ */
+enum SimStatus Simulation_RecenterOrigin(struct Simulation *handle);
+
+/**
+ * Registers a callback reporting progress of `Simulation_StepN` (after each step),
+ * `Simulation_WriteCheckpoint` and `Simulation_ResetWithGenerator`, invoked on the thread running
+ * the operation. Cancelled operations return `SimStatus_Cancelled`: stepping stops after the
+ * current step, checkpoints are not written and generators leave the simulation untouched.
+ * Passing a null callback removes it.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time. `callback`, if set, must be safe to call with
+ * `user_data` on the thread running the operation until it is replaced or the handle destroyed.
+ */
+enum SimStatus Simulation_SetProgressCallback(struct Simulation *handle,
+                                              ProgressCallbackFn callback,
+                                              void *user_data);
+
+#if defined(NBODY_FEATURE_CHECKPOINT)
+/**
+ * Writes a compressed checkpoint that `Simulation_ReadCheckpoint` can resume from.
+ *
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_WriteCheckpoint(const struct Simulation *handle,
+                                          const char *path);
+#endif
+
+#if defined(NBODY_FEATURE_CHECKPOINT)
+/**
+ * # Safety
+ * `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+ */
+enum SimStatus Simulation_ReadCheckpoint(struct Simulation *handle,
+                                         const char *path);
+#endif
+
+#if defined(NBODY_FEATURE_SHM)
+/**
+ * Creates (or replaces) the ring buffer `name` with `slots` frames of up to `capacity` bodies.
+ * Returns null on failure; see `Simulation_GetLastError`.
+ *
+ * # Safety
+ * `name` must be null or a nul-terminated string; free the returned publisher with `RingPublisher_Destroy`.
+ */
+struct RingPublisher *RingPublisher_Create(const char *name,
+                                           size_t slots,
+                                           size_t capacity);
+#endif
+
+#if defined(NBODY_FEATURE_SHM)
+/**
+ * Removes the ring buffer and frees the publisher.
+ *
+ * # Safety
+ * `publisher` must be null or a live publisher no other thread is using; it is invalid afterwards.
+ */
+void RingPublisher_Destroy(struct RingPublisher *publisher);
+#endif
+
+#if defined(NBODY_FEATURE_SHM)
+/**
+ * Publishes the current bodies as the next frame, truncated to the ring buffer's capacity.
+ *
+ * # Safety
+ * `publisher` must be null or a live publisher and `handle` must be null or a live handle used by one thread at a
+ * time.
+ */
+enum SimStatus RingPublisher_Publish(struct RingPublisher *publisher,
+                                     const struct Simulation *handle);
+#endif
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NBODY_SIMULATION_H */
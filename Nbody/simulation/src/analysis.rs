@@ -0,0 +1,209 @@
+use crate::simulation::Simulation;
+
+use rayon::prelude::*;
+use ultraviolet::Vec2;
+
+/// Mass fractions of the usual Lagrange radii, see `Simulation::lagrange_radii`.
+pub const LAGRANGE_FRACTIONS: [f32; 3] = [0.1, 0.5, 0.9];
+
+/// One annulus of a `RadialProfile`. Velocities are mass-weighted means over its bodies.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RadialBin {
+    pub inner_radius: f32,
+    pub outer_radius: f32,
+    pub count: usize,
+    pub mass: f32,
+    /// Mass per unit area of the annulus.
+    pub surface_density: f32,
+    /// Mean velocity away from the center.
+    pub radial_velocity: f32,
+    /// Mean velocity around the center, counter-clockwise positive; the rotation curve.
+    pub tangential_velocity: f32,
+    /// Spread of the velocities around the two means, sqrt(<dv_r^2 + dv_t^2>).
+    pub velocity_dispersion: f32,
+}
+
+/// Radial surface density and velocity profile around a center, see `Simulation::radial_profile`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RadialProfile {
+    pub center: Vec2,
+    pub bins: Vec<RadialBin>,
+}
+
+/// Mass-weighted sums of one bin, accumulated in f64 like the other reductions.
+#[derive(Clone, Copy, Default)]
+struct BinSums {
+    count: usize,
+    mass: f64,
+    v_r: f64,
+    v_t: f64,
+    v_r_sq: f64,
+    v_t_sq: f64,
+}
+
+impl BinSums {
+    fn add(&mut self, other: &BinSums) {
+        self.count += other.count;
+        self.mass += other.mass;
+        self.v_r += other.v_r;
+        self.v_t += other.v_t;
+        self.v_r_sq += other.v_r_sq;
+        self.v_t_sq += other.v_t_sq;
+    }
+}
+
+impl Simulation {
+    /// Bins the bodies within `max_radius` of `center` into `bins` annuli of equal width and
+    /// returns their surface density and mass-weighted velocities, e.g. to plot a rotation curve
+    /// or follow a disc spreading. Velocities are taken as they are, so `center` should be at
+    /// rest or the bodies' velocities relative to it.
+    pub fn radial_profile(&self, center: Vec2, max_radius: f32, bins: usize) -> RadialProfile {
+        if bins == 0 || !max_radius.is_finite() || max_radius <= 0.0 {
+            return RadialProfile { center, bins: Vec::new() };
+        }
+
+        let width = max_radius / bins as f32;
+        let sums = self
+            .bodies
+            .positions()
+            .par_iter()
+            .zip(self.bodies.velocities())
+            .zip(self.bodies.masses())
+            .fold(
+                || vec![BinSums::default(); bins],
+                |mut sums, ((&pos, &vel), &mass)| {
+                    let offset = pos - center;
+                    let r = offset.mag();
+                    if r >= max_radius {
+                        return sums;
+                    }
+                    // A body exactly at the center has no direction; count it as at rest
+                    let (v_r, v_t) = if r > 0.0 {
+                        let dir = offset / r;
+                        (vel.dot(dir), dir.x * vel.y - dir.y * vel.x)
+                    } else {
+                        (0.0, 0.0)
+                    };
+                    let (m, v_r, v_t) = (mass as f64, v_r as f64, v_t as f64);
+                    let bin = &mut sums[((r / width) as usize).min(bins - 1)];
+                    bin.count += 1;
+                    bin.mass += m;
+                    bin.v_r += m * v_r;
+                    bin.v_t += m * v_t;
+                    bin.v_r_sq += m * v_r * v_r;
+                    bin.v_t_sq += m * v_t * v_t;
+                    sums
+                },
+            )
+            .reduce(
+                || vec![BinSums::default(); bins],
+                |mut a, b| {
+                    a.iter_mut().zip(&b).for_each(|(a, b)| a.add(b));
+                    a
+                },
+            );
+
+        let bins = sums
+            .iter()
+            .enumerate()
+            .map(|(i, sums)| {
+                let (inner, outer) = (i as f64 * width as f64, (i + 1) as f64 * width as f64);
+                let area = std::f64::consts::PI * (outer * outer - inner * inner);
+                let mut bin = RadialBin {
+                    inner_radius: inner as f32,
+                    outer_radius: outer as f32,
+                    count: sums.count,
+                    mass: sums.mass as f32,
+                    surface_density: (sums.mass / area) as f32,
+                    ..RadialBin::default()
+                };
+                if sums.mass > 0.0 {
+                    let (v_r, v_t) = (sums.v_r / sums.mass, sums.v_t / sums.mass);
+                    let variance = sums.v_r_sq / sums.mass - v_r * v_r + sums.v_t_sq / sums.mass - v_t * v_t;
+                    bin.radial_velocity = v_r as f32;
+                    bin.tangential_velocity = v_t as f32;
+                    bin.velocity_dispersion = variance.max(0.0).sqrt() as f32;
+                }
+                bin
+            })
+            .collect();
+
+        RadialProfile { center, bins }
+    }
+
+    /// Center of the densest region, the mean of the body positions weighted by the local
+    /// density around each (Casertano & Hut 1985). Unlike the center of mass it stays on the core
+    /// of a cluster when mass is flung out asymmetrically. The densities come from the quadtree
+    /// of the last step, smoothed over the mean spacing of the bodies; without a tree, this is
+    /// the center of mass.
+    pub fn density_center(&self) -> Vec2 {
+        let positions = self.bodies.positions();
+        let masses = self.bodies.masses();
+        let quadtree = &self.quadtree;
+        let resolution = self.density_resolution();
+
+        let weighted = |weight: &(dyn Fn(Vec2, f32) -> f64 + Sync)| {
+            positions
+                .par_iter()
+                .zip(masses)
+                .map(|(&pos, &mass)| {
+                    let w = weight(pos, mass);
+                    [w * pos.x as f64, w * pos.y as f64, w]
+                })
+                .reduce(|| [0.0; 3], |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2]])
+        };
+        let mut sums = if quadtree.nodes.is_empty() {
+            [0.0; 3]
+        } else {
+            weighted(&|pos, _| quadtree.density(pos, resolution) as f64)
+        };
+        if sums[2] <= 0.0 {
+            sums = weighted(&|_, mass| mass as f64);
+        }
+        if sums[2] <= 0.0 {
+            return Vec2::zero();
+        }
+        Vec2::new((sums[0] / sums[2]) as f32, (sums[1] / sums[2]) as f32)
+    }
+
+    /// Scale the local densities of the bodies are smoothed over: the mean spacing of the bodies
+    /// in the tree's root quad, or infinite without a tree.
+    pub(crate) fn density_resolution(&self) -> f32 {
+        match self.quadtree.nodes.first() {
+            Some(root) => root.quad.size / (self.bodies.len().max(1) as f32).sqrt(),
+            None => f32::INFINITY,
+        }
+    }
+
+    /// Radii around `density_center` enclosing each of the given fractions of the total mass,
+    /// e.g. `LAGRANGE_FRACTIONS`; a shrinking inner radius with a growing outer one is the
+    /// signature of core collapse. Fractions are clamped to [0, 1]. Returns zeros without bodies.
+    pub fn lagrange_radii(&self, fractions: &[f32]) -> Vec<f32> {
+        let center = self.density_center();
+        let mut shells: Vec<(f32, f32)> = self
+            .bodies
+            .positions()
+            .par_iter()
+            .zip(self.bodies.masses())
+            .map(|(&pos, &mass)| ((pos - center).mag(), mass))
+            .collect();
+        shells.par_sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+        let total: f64 = shells.iter().map(|&(_, mass)| mass as f64).sum();
+        let mut enclosed = Vec::with_capacity(shells.len());
+        let mut sum = 0.0f64;
+        for &(_, mass) in &shells {
+            sum += mass as f64;
+            enclosed.push(sum);
+        }
+
+        fractions
+            .iter()
+            .map(|&fraction| {
+                let target = fraction.clamp(0.0, 1.0) as f64 * total;
+                let i = enclosed.partition_point(|&mass| mass < target);
+                shells.get(i.min(shells.len().saturating_sub(1))).map_or(0.0, |&(r, _)| r)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,183 @@
+use crate::simulation::Simulation;
+use arrow_array::{ArrayRef, Float32Array, Float64Array, RecordBatch, UInt32Array, UInt64Array};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+
+#[cfg(feature = "parquet")]
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{Compression, ZstdLevel},
+    errors::Result as ParquetResult,
+    file::properties::WriterProperties,
+};
+#[cfg(feature = "parquet")]
+use std::{fs::File, path::Path};
+use std::sync::Arc;
+
+/// Schema of the per-body record batches, one row per body and sampled frame:
+///
+/// | column   | type | contents |
+/// |----------|------|----------|
+/// | `frame`  | u64  | simulation frame |
+/// | `time`   | f64  | simulation time |
+/// | `id`     | u32  | stable body id (see `Bodies::ids`) |
+/// | `x`, `y` | f64  | world-space position (see `Simulation::world_position`) |
+/// | `vx`, `vy` | f32 | velocity |
+/// | `mass`   | f32  | mass |
+/// | `radius` | f32  | radius |
+///
+/// Positions are in world space so runs with origin shifts stay continuous.
+pub fn body_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("frame", DataType::UInt64, false),
+        Field::new("time", DataType::Float64, false),
+        Field::new("id", DataType::UInt32, false),
+        Field::new("x", DataType::Float64, false),
+        Field::new("y", DataType::Float64, false),
+        Field::new("vx", DataType::Float32, false),
+        Field::new("vy", DataType::Float32, false),
+        Field::new("mass", DataType::Float32, false),
+        Field::new("radius", DataType::Float32, false),
+    ]))
+}
+
+/// Schema of the trajectory files written by `TrajectoryLogger::new_parquet`, the columns of
+/// `trajectory::TRAJECTORY_HEADER`: `frame` (u64), `time` (f64), `id` (u64, the selected index)
+/// and `x`, `y`, `vx`, `vy` (f32).
+pub fn trajectory_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("frame", DataType::UInt64, false),
+        Field::new("time", DataType::Float64, false),
+        Field::new("id", DataType::UInt64, false),
+        Field::new("x", DataType::Float32, false),
+        Field::new("y", DataType::Float32, false),
+        Field::new("vx", DataType::Float32, false),
+        Field::new("vy", DataType::Float32, false),
+    ]))
+}
+
+/// Properties of the Parquet files written by this crate: zstd compression.
+#[cfg(feature = "parquet")]
+pub(crate) fn writer_properties() -> WriterProperties {
+    WriterProperties::builder()
+        .set_compression(Compression::ZSTD(ZstdLevel::default()))
+        .build()
+}
+
+impl Simulation {
+    /// Returns the current bodies as a record batch with the `body_schema` columns.
+    pub fn to_record_batch(&self) -> RecordBatch {
+        let n = self.bodies.len();
+        let (x, y): (Vec<f64>, Vec<f64>) = self.world_positions().map(|[x, y]| (x, y)).unzip();
+
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(UInt64Array::from(vec![self.frame as u64; n])),
+            Arc::new(Float64Array::from(vec![self.time; n])),
+            Arc::new(UInt32Array::from(self.bodies.ids().to_vec())),
+            Arc::new(Float64Array::from(x)),
+            Arc::new(Float64Array::from(y)),
+            Arc::new(self.bodies.velocities().iter().map(|vel| vel.x).collect::<Float32Array>()),
+            Arc::new(self.bodies.velocities().iter().map(|vel| vel.y).collect::<Float32Array>()),
+            Arc::new(Float32Array::from(self.bodies.masses().to_vec())),
+            Arc::new(Float32Array::from(self.bodies.radii().to_vec())),
+        ];
+        RecordBatch::try_new(body_schema(), columns).expect("columns match the body schema")
+    }
+
+    /// Writes the current state to a new single-frame Parquet file (see `ParquetWriter`).
+    #[cfg(feature = "parquet")]
+    pub fn save_parquet(&self, path: impl AsRef<Path>) -> ParquetResult<()> {
+        let mut writer = ParquetWriter::create(path, 1)?;
+        writer.record(self)?;
+        writer.close()
+    }
+}
+
+/// Collects a record batch of the bodies every `interval` frames in memory, e.g. to hand them to
+/// an analysis pipeline over the Arrow C data interface or IPC without going through a file.
+#[derive(Debug, Default)]
+pub struct ArrowRecorder {
+    interval: usize,
+    batches: Vec<RecordBatch>,
+}
+
+impl ArrowRecorder {
+    /// Creates an empty recorder. An `interval` of 0 is treated as 1.
+    pub fn new(interval: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            batches: Vec::new(),
+        }
+    }
+
+    /// Records the bodies if the current frame is a multiple of the interval.
+    /// Returns whether a batch was recorded.
+    pub fn record(&mut self, sim: &Simulation) -> bool {
+        if !sim.frame.is_multiple_of(self.interval) {
+            return false;
+        }
+        self.batches.push(sim.to_record_batch());
+        true
+    }
+
+    /// The recorded batches, oldest first.
+    pub fn batches(&self) -> &[RecordBatch] {
+        &self.batches
+    }
+
+    /// Takes the recorded batches, leaving the recorder empty.
+    pub fn take_batches(&mut self) -> Vec<RecordBatch> {
+        std::mem::take(&mut self.batches)
+    }
+
+    /// Total number of recorded rows.
+    pub fn num_rows(&self) -> usize {
+        self.batches.iter().map(RecordBatch::num_rows).sum()
+    }
+}
+
+/// Streams a record batch of the bodies every `interval` frames into a zstd-compressed Parquet
+/// file readable by pandas, polars, DuckDB and similar tools.
+///
+/// Rows are buffered into row groups by the underlying writer, so memory use stays bounded on
+/// long runs. The file is only valid once `close` has written its footer.
+#[cfg(feature = "parquet")]
+pub struct ParquetWriter {
+    writer: ArrowWriter<File>,
+    interval: usize,
+}
+
+#[cfg(feature = "parquet")]
+impl ParquetWriter {
+    /// Creates (or truncates) the output file. An `interval` of 0 is treated as 1.
+    pub fn create(path: impl AsRef<Path>, interval: usize) -> ParquetResult<Self> {
+        Ok(Self {
+            writer: ArrowWriter::try_new(File::create(path)?, body_schema(), Some(writer_properties()))?,
+            interval: interval.max(1),
+        })
+    }
+
+    /// Writes the bodies if the current frame is a multiple of the interval.
+    /// Returns whether a frame was written.
+    pub fn record(&mut self, sim: &Simulation) -> ParquetResult<bool> {
+        if !sim.frame.is_multiple_of(self.interval) {
+            return Ok(false);
+        }
+        self.writer.write(&sim.to_record_batch())?;
+        Ok(true)
+    }
+
+    /// Writes a batch with the `body_schema` columns, e.g. one taken from an `ArrowRecorder`.
+    pub fn write_batch(&mut self, batch: &RecordBatch) -> ParquetResult<()> {
+        self.writer.write(batch)
+    }
+
+    /// Flushes the buffered rows as a row group.
+    pub fn flush(&mut self) -> ParquetResult<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes the remaining rows and writes the file footer.
+    pub fn close(self) -> ParquetResult<()> {
+        self.writer.close().map(drop)
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{body::Body, simulation::Simulation};
+
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard};
+use std::thread::JoinHandle;
+
+/// The last frame published before a background step was started.
+#[derive(Clone, Debug, Default)]
+pub struct FrontBuffer {
+    /// Frame counter of the published bodies.
+    pub frame: usize,
+    /// Copy of the bodies at that frame.
+    pub bodies: Vec<Body>,
+}
+
+/// Runs simulation steps on a worker thread while the previous frame stays readable.
+///
+/// `step_async` copies the current bodies into the front buffer and then steps the
+/// simulation on another thread, so the caller can render `front()` in the meantime.
+#[derive(Debug)]
+pub struct BackgroundStepper {
+    sim: Arc<Mutex<Simulation>>,
+    front: Arc<FrontBuffer>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl BackgroundStepper {
+    pub fn new(sim: Simulation) -> Self {
+        let front = Arc::new(FrontBuffer {
+            frame: sim.frame,
+            bodies: sim.bodies.to_vec(),
+        });
+
+        Self {
+            sim: Arc::new(Mutex::new(sim)),
+            front,
+            worker: None,
+        }
+    }
+
+    /// Starts a step on the worker thread.
+    /// Returns false if a step is already in flight.
+    pub fn step_async(&mut self) -> bool {
+        if !self.is_step_complete() {
+            return false;
+        }
+        self.wait();
+
+        {
+            // Snapshots still held keep their frame; publish into a new buffer instead
+            if Arc::strong_count(&self.front) > 1 {
+                self.front = Arc::default();
+            }
+            let front = Arc::make_mut(&mut self.front);
+            let sim = self.sim.lock().unwrap_or_else(|e| e.into_inner());
+            front.frame = sim.frame;
+            sim.bodies.copy_to(&mut front.bodies);
+        }
+
+        let sim = self.sim.clone();
+        self.worker = Some(std::thread::spawn(move || {
+            sim.lock().unwrap_or_else(|e| e.into_inner()).step();
+        }));
+        true
+    }
+
+    /// Returns true if no step is in flight.
+    pub fn is_step_complete(&self) -> bool {
+        self.worker.as_ref().is_none_or(|worker| worker.is_finished())
+    }
+
+    /// Blocks until the step in flight (if any) has finished.
+    pub fn wait(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            // A panicking step poisons nothing we rely on; the state is recovered in `lock`
+            let _ = worker.join();
+        }
+    }
+
+    /// The frame published by the last `step_async` call.
+    pub fn front(&self) -> &FrontBuffer {
+        &self.front
+    }
+
+    /// The frame published by the last `step_async` call, which stays valid and unchanged for
+    /// as long as it is held, e.g. by a renderer on another thread, whatever steps follow.
+    pub fn snapshot(&self) -> Arc<FrontBuffer> {
+        self.front.clone()
+    }
+
+    /// Waits for the step in flight and locks the simulation.
+    pub fn lock(&mut self) -> MutexGuard<'_, Simulation> {
+        self.wait();
+        self.sim.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Waits for the step in flight and returns the simulation.
+    pub fn into_inner(mut self) -> Simulation {
+        self.wait();
+        match Arc::try_unwrap(self.sim) {
+            Ok(sim) => sim.into_inner().unwrap_or_else(|e| e.into_inner()),
+            Err(_) => unreachable!("worker thread has been joined"),
+        }
+    }
+}
+
+/// A simulation behind a lock whose last published frame can be read from any thread
+/// without waiting for a step in progress.
+#[derive(Debug)]
+pub struct SharedSimulation {
+    sim: Mutex<Simulation>,
+    front: RwLock<FrontBuffer>,
+    /// Staging buffer so the front buffer is only write-locked for a swap.
+    back: Mutex<Vec<Body>>,
+}
+
+impl SharedSimulation {
+    pub fn new(sim: Simulation) -> Self {
+        let front = FrontBuffer {
+            frame: sim.frame,
+            bodies: sim.bodies.to_vec(),
+        };
+
+        Self {
+            sim: Mutex::new(sim),
+            front: RwLock::new(front),
+            back: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Locks the simulation for exclusive access.
+    /// Changes become visible to readers of `front` after the next `publish` or step.
+    pub fn lock(&self) -> MutexGuard<'_, Simulation> {
+        self.sim.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Advances the simulation by one step and publishes the result.
+    pub fn step(&self) {
+        self.step_n(1);
+    }
+
+    /// Advances the simulation by `n` steps and publishes the result.
+    pub fn step_n(&self, n: usize) {
+        let mut sim = self.lock();
+        sim.step_n(n);
+        self.publish(&sim);
+    }
+
+    /// Copies the bodies of `sim` (obtained from `lock`) into the front buffer.
+    pub fn publish(&self, sim: &Simulation) {
+        let mut back = self.back.lock().unwrap_or_else(|e| e.into_inner());
+        sim.bodies.copy_to(&mut back);
+
+        let mut front = self.front.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::swap(&mut front.bodies, &mut *back);
+        front.frame = sim.frame;
+    }
+
+    /// The last published frame. Holding the guard blocks publishing, not stepping.
+    pub fn front(&self) -> RwLockReadGuard<'_, FrontBuffer> {
+        self.front.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn into_inner(self) -> Simulation {
+        self.sim.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+}
@@ -0,0 +1,47 @@
+// Headless simulation streamed over WebSocket (see `server::SimulationServer`).
+//
+// usage: nbody-server [address] [generator] [params-json]
+// e.g.   nbody-server 0.0.0.0:9001 plummer '{"n": 200000}'
+
+use nbody_simulation::{
+    server::{ServerConfig, SimulationServer},
+    simulation::Simulation,
+};
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let addr = args.next().unwrap_or_else(|| "127.0.0.1:9001".to_owned());
+    let generator = args.next();
+    let params = args.next().unwrap_or_default();
+
+    let mut sim = Simulation::with_bodies(
+        Vec::new(),
+        Simulation::DEFAULT_DT,
+        Simulation::DEFAULT_THETA,
+        Simulation::DEFAULT_EPSILON,
+    );
+    match &generator {
+        Some(name) => {
+            if let Err(e) = sim.reset_with_generator(name, &params) {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        }
+        None => sim.reset(100_000),
+    }
+    sim.set_use_rayon(true);
+
+    let mut server = match SimulationServer::bind(sim, &addr, ServerConfig::default()) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("failed to listen on {addr}: {e}");
+            std::process::exit(1);
+        }
+    };
+    println!("streaming {} bodies on ws://{addr}", server.sim.bodies.len());
+
+    if let Err(e) = server.run() {
+        eprintln!("server stopped: {e}");
+        std::process::exit(1);
+    }
+}
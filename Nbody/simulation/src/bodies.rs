@@ -0,0 +1,322 @@
+use crate::body::Body;
+use ultraviolet::Vec2;
+
+use std::ops::Range;
+
+/// Body storage as a structure of arrays: one contiguous column per property.
+///
+/// The force and integration loops each touch only a few properties of every body, so keeping
+/// them in separate columns halves the memory traffic compared to an array of `Body` and lets
+/// the loops vectorize. All columns always have the same length; they are only exposed as
+/// slices so that cannot be broken from outside.
+///
+/// `Body` remains the by-value view of one body: `get`, `iter` and `to_vec` copy bodies out
+/// (e.g. for the C API) and `push`/`extend` copy them in.
+///
+/// Each body also has a stable id, assigned in order as bodies are added, that follows it
+/// when bodies are removed or reordered (see `permute`), unlike its index.
+#[derive(Clone, Debug, Default)]
+pub struct Bodies {
+    positions: Vec<Vec2>,
+    velocities: Vec<Vec2>,
+    accs: Vec<Vec2>,
+    masses: Vec<f32>,
+    radii: Vec<f32>,
+    ids: Vec<u32>,
+    /// Id of the next body added.
+    next_id: u32,
+}
+
+/// Mutable views of all columns at once, for loops that read some columns while writing others.
+#[derive(Debug)]
+pub struct BodiesMut<'a> {
+    pub positions: &'a mut [Vec2],
+    pub velocities: &'a mut [Vec2],
+    pub accs: &'a mut [Vec2],
+    pub masses: &'a mut [f32],
+    pub radii: &'a mut [f32],
+}
+
+impl Bodies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            positions: Vec::with_capacity(capacity),
+            velocities: Vec::with_capacity(capacity),
+            accs: Vec::with_capacity(capacity),
+            masses: Vec::with_capacity(capacity),
+            radii: Vec::with_capacity(capacity),
+            ids: Vec::with_capacity(capacity),
+            next_id: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn positions(&self) -> &[Vec2] {
+        &self.positions
+    }
+
+    pub fn positions_mut(&mut self) -> &mut [Vec2] {
+        &mut self.positions
+    }
+
+    pub fn velocities(&self) -> &[Vec2] {
+        &self.velocities
+    }
+
+    pub fn velocities_mut(&mut self) -> &mut [Vec2] {
+        &mut self.velocities
+    }
+
+    /// Accelerations computed during the last step.
+    pub fn accs(&self) -> &[Vec2] {
+        &self.accs
+    }
+
+    pub fn accs_mut(&mut self) -> &mut [Vec2] {
+        &mut self.accs
+    }
+
+    pub fn masses(&self) -> &[f32] {
+        &self.masses
+    }
+
+    pub fn masses_mut(&mut self) -> &mut [f32] {
+        &mut self.masses
+    }
+
+    pub fn radii(&self) -> &[f32] {
+        &self.radii
+    }
+
+    pub fn radii_mut(&mut self) -> &mut [f32] {
+        &mut self.radii
+    }
+
+    /// Stable id of each body.
+    pub fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    /// Id the next body added will get.
+    pub fn next_id(&self) -> u32 {
+        self.next_id
+    }
+
+    /// Restores ids saved with `ids` and `next_id`, e.g. from a snapshot, so bodies keep the ids
+    /// they had when saved rather than being numbered in their current order.
+    /// Panics unless there is one id per body.
+    pub fn set_ids(&mut self, ids: Vec<u32>, next_id: u32) {
+        assert_eq!(ids.len(), self.len(), "there must be one id per body");
+        self.ids = ids;
+        self.next_id = next_id;
+    }
+
+    /// Current index of the body with the given id, found by a linear search.
+    pub fn index_of(&self, id: u32) -> Option<usize> {
+        self.ids.iter().position(|&other| other == id)
+    }
+
+    /// Borrows all columns mutably at once.
+    pub fn columns_mut(&mut self) -> BodiesMut<'_> {
+        BodiesMut {
+            positions: &mut self.positions,
+            velocities: &mut self.velocities,
+            accs: &mut self.accs,
+            masses: &mut self.masses,
+            radii: &mut self.radii,
+        }
+    }
+
+    /// Copies out body `i`, or returns `None` if out of range.
+    pub fn get(&self, i: usize) -> Option<Body> {
+        (i < self.len()).then(|| self.body(i))
+    }
+
+    /// Copies out body `i`. Panics if out of range.
+    pub fn body(&self, i: usize) -> Body {
+        Body {
+            pos: self.positions[i],
+            vel: self.velocities[i],
+            acc: self.accs[i],
+            mass: self.masses[i],
+            radius: self.radii[i],
+        }
+    }
+
+    /// Overwrites body `i`, which keeps its id. Panics if out of range.
+    pub fn set(&mut self, i: usize, body: Body) {
+        self.positions[i] = body.pos;
+        self.velocities[i] = body.vel;
+        self.accs[i] = body.acc;
+        self.masses[i] = body.mass;
+        self.radii[i] = body.radius;
+    }
+
+    pub fn push(&mut self, body: Body) {
+        self.positions.push(body.pos);
+        self.velocities.push(body.vel);
+        self.accs.push(body.acc);
+        self.masses.push(body.mass);
+        self.radii.push(body.radius);
+        self.ids.push(self.next_id);
+        self.next_id += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.positions.clear();
+        self.velocities.clear();
+        self.accs.clear();
+        self.masses.clear();
+        self.radii.clear();
+        self.ids.clear();
+        self.next_id = 0;
+    }
+
+    /// Keeps only the bodies for which `keep` returns true, preserving their order.
+    pub fn retain(&mut self, mut keep: impl FnMut(&Body) -> bool) {
+        let mut kept = 0;
+        for i in 0..self.len() {
+            let body = self.body(i);
+            if keep(&body) {
+                self.set(kept, body);
+                self.ids[kept] = self.ids[i];
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.positions.truncate(len);
+        self.velocities.truncate(len);
+        self.accs.truncate(len);
+        self.masses.truncate(len);
+        self.radii.truncate(len);
+        self.ids.truncate(len);
+    }
+
+    /// Reorders the bodies so that body `i` is the one previously at `order[i]`.
+    /// `order` must be a permutation of the body indices.
+    pub fn permute(&mut self, order: &[u32]) {
+        fn gather<T: Copy>(column: &mut Vec<T>, order: &[u32]) {
+            *column = order.iter().map(|&i| column[i as usize]).collect();
+        }
+
+        assert_eq!(order.len(), self.len(), "order must be a permutation of the bodies");
+        gather(&mut self.positions, order);
+        gather(&mut self.velocities, order);
+        gather(&mut self.accs, order);
+        gather(&mut self.masses, order);
+        gather(&mut self.radii, order);
+        gather(&mut self.ids, order);
+    }
+
+    /// Iterates over copies of the bodies.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bodies: self,
+            range: 0..self.len(),
+        }
+    }
+
+    /// Copies the bodies out into an array of `Body`.
+    pub fn to_vec(&self) -> Vec<Body> {
+        self.iter().collect()
+    }
+
+    /// Copies the bodies out into `out`, reusing its allocation.
+    pub fn copy_to(&self, out: &mut Vec<Body>) {
+        out.clear();
+        out.extend(self.iter());
+    }
+
+    /// Copies the first `out.len()` bodies out into `out`. Panics if there are fewer bodies.
+    pub fn copy_to_slice(&self, out: &mut [Body]) {
+        for (i, out) in out.iter_mut().enumerate() {
+            *out = self.body(i);
+        }
+    }
+}
+
+impl Extend<Body> for Bodies {
+    fn extend<I: IntoIterator<Item = Body>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let additional = iter.size_hint().0;
+        self.positions.reserve(additional);
+        self.velocities.reserve(additional);
+        self.accs.reserve(additional);
+        self.masses.reserve(additional);
+        self.radii.reserve(additional);
+        self.ids.reserve(additional);
+        for body in iter {
+            self.push(body);
+        }
+    }
+}
+
+impl FromIterator<Body> for Bodies {
+    fn from_iter<I: IntoIterator<Item = Body>>(iter: I) -> Self {
+        let mut bodies = Self::new();
+        bodies.extend(iter);
+        bodies
+    }
+}
+
+impl From<Vec<Body>> for Bodies {
+    fn from(bodies: Vec<Body>) -> Self {
+        bodies.into_iter().collect()
+    }
+}
+
+impl From<&[Body]> for Bodies {
+    fn from(bodies: &[Body]) -> Self {
+        bodies.iter().copied().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a Bodies {
+    type Item = Body;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over copies of the bodies, see `Bodies::iter`.
+#[derive(Clone, Debug)]
+pub struct Iter<'a> {
+    bodies: &'a Bodies,
+    range: Range<usize>,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = Body;
+
+    fn next(&mut self) -> Option<Body> {
+        self.range.next().map(|i| self.bodies.body(i))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.range.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Body> {
+        self.range.next_back().map(|i| self.bodies.body(i))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
@@ -3,6 +3,7 @@ use ultraviolet::Vec2;
 /// Represents a celestial body in the simulation.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Body {
     /// Position vector.
     pub pos: Vec2,
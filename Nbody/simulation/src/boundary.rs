@@ -0,0 +1,82 @@
+use crate::bodies::Bodies;
+use ultraviolet::Vec2;
+
+/// What happens to bodies that leave the simulation bounds.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BoundaryMode {
+    /// Bodies move freely; the bounds are ignored.
+    #[default]
+    None = 0,
+    /// Bodies leaving one side re-enter on the opposite side.
+    /// Gravity itself is not periodic.
+    Periodic = 1,
+    /// Bodies bounce off the walls.
+    Reflective = 2,
+    /// Bodies leaving the bounds are removed.
+    Despawn = 3,
+}
+
+/// Axis-aligned playfield confining the simulation.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Boundary {
+    pub min: Vec2,
+    pub max: Vec2,
+    pub mode: BoundaryMode,
+}
+
+impl Boundary {
+    pub fn new(min: Vec2, max: Vec2, mode: BoundaryMode) -> Self {
+        Self { min, max, mode }
+    }
+
+    /// Returns true if `pos` lies inside the bounds (inclusive).
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.x >= self.min.x && pos.x <= self.max.x && pos.y >= self.min.y && pos.y <= self.max.y
+    }
+
+    /// Applies the boundary condition to all bodies.
+    /// In `Despawn` mode bodies are removed, which shifts the indices of the remaining ones.
+    pub fn apply(&self, bodies: &mut Bodies) {
+        let size = self.max - self.min;
+
+        match self.mode {
+            BoundaryMode::None => {}
+            BoundaryMode::Periodic => {
+                for pos in bodies.positions_mut() {
+                    pos.x = self.min.x + (pos.x - self.min.x).rem_euclid(size.x);
+                    pos.y = self.min.y + (pos.y - self.min.y).rem_euclid(size.y);
+                }
+            }
+            BoundaryMode::Reflective => {
+                let bodies = bodies.columns_mut();
+                for (pos, vel) in bodies.positions.iter_mut().zip(bodies.velocities.iter_mut()) {
+                    if pos.x < self.min.x {
+                        pos.x = 2.0 * self.min.x - pos.x;
+                        vel.x = vel.x.abs();
+                    } else if pos.x > self.max.x {
+                        pos.x = 2.0 * self.max.x - pos.x;
+                        vel.x = -vel.x.abs();
+                    }
+
+                    if pos.y < self.min.y {
+                        pos.y = 2.0 * self.min.y - pos.y;
+                        vel.y = vel.y.abs();
+                    } else if pos.y > self.max.y {
+                        pos.y = 2.0 * self.max.y - pos.y;
+                        vel.y = -vel.y.abs();
+                    }
+
+                    // Bodies moving faster than the box is wide are clamped back inside
+                    pos.x = pos.x.clamp(self.min.x, self.max.x);
+                    pos.y = pos.y.clamp(self.min.y, self.max.y);
+                }
+            }
+            BoundaryMode::Despawn => {
+                bodies.retain(|body| self.contains(body.pos));
+            }
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use rayon::prelude::*;
+use ultraviolet::Vec2;
+
+use std::cmp::Ordering;
+
+/// Strip height of `SweepAndPrune` in mean body diameters.
+const STRIP_DIAMETERS: f32 = 4.0;
+
+/// Bounding box of a body along with its index and strip.
+#[derive(Clone, Copy, Debug)]
+struct SweepBox {
+    min: Vec2,
+    max: Vec2,
+    index: u32,
+    /// Strip containing the bottom edge, or `u32::MAX` for a box larger than a strip.
+    strip: u32,
+}
+
+impl SweepBox {
+    fn overlaps(&self, other: &SweepBox) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x && self.min.y <= other.max.y && other.min.y <= self.max.y
+    }
+
+    /// Sweep order within a strip: by left edge, ties broken by body index.
+    fn cmp_x(&self, other: &SweepBox) -> Ordering {
+        self.min.x.total_cmp(&other.min.x).then(self.index.cmp(&other.index))
+    }
+}
+
+/// Parallel sort-and-sweep broad phase over the body bounding boxes.
+///
+/// Sweeping a single axis tests every box against all boxes in a thin slice across the whole
+/// simulation, so the boxes are first binned into horizontal strips a few body diameters high and
+/// sorted by their left edge within each strip. A box is then swept against the boxes of its own
+/// and the neighbouring strips, from its left edge until one starts right of its right edge. The
+/// few boxes taller or wider than a strip (e.g. a central star) are kept apart and swept against
+/// everything in their reach.
+///
+/// The sorted boxes are split into chunks of `CHUNK` boxes that can be swept independently.
+/// Collecting the candidate pairs per chunk and going through the chunks in order gives the same
+/// pairs in the same order however many threads swept them.
+///
+/// Bodies barely move between steps, so the boxes are kept in last step's order and re-sorted
+/// with an adaptive sort that is close to linear on nearly sorted input.
+#[derive(Clone, Debug, Default)]
+pub struct SweepAndPrune {
+    /// Boxes sorted by strip, then in sweep order; the large boxes come last.
+    boxes: Vec<SweepBox>,
+    /// Offsets of the strips in `boxes`, with the start of the large boxes last.
+    strips: Vec<u32>,
+    bottom: f32,
+    strip_height: f32,
+}
+
+impl SweepAndPrune {
+    /// Boxes swept per chunk.
+    pub const CHUNK: usize = 1024;
+
+    /// Updates the boxes to the bodies and sorts them, in parallel with rayon if `parallel`.
+    pub fn prepare(&mut self, positions: &[Vec2], radii: &[f32], parallel: bool) {
+        let len = positions.len();
+        let extent = |index: u32| {
+            let pos = positions[index as usize];
+            let radius = Vec2::broadcast(radii[index as usize]);
+            SweepBox {
+                min: pos - radius,
+                max: pos + radius,
+                index,
+                strip: 0,
+            }
+        };
+
+        // The previous order is only a valid permutation while the body count stays the same
+        if self.boxes.len() == len {
+            for b in &mut self.boxes {
+                *b = extent(b.index);
+            }
+        } else {
+            self.boxes.clear();
+            self.boxes.extend((0..len as u32).map(extent));
+        }
+
+        let (bottom, top) = positions.iter().fold((f32::MAX, f32::MIN), |(bottom, top), pos| {
+            (bottom.min(pos.y), top.max(pos.y))
+        });
+        let diameter = 2.0 * radii.iter().map(|&r| r as f64).sum::<f64>() / len.max(1) as f64;
+        let height = top - bottom;
+        let mut strip_height = STRIP_DIAMETERS * diameter as f32;
+        // Degenerate radii would make a strip per body or no strips at all
+        if strip_height.is_nan() || strip_height <= 0.0 || height / strip_height > len as f32 {
+            strip_height = (height / len.max(1) as f32).max(f32::MIN_POSITIVE);
+        }
+        let strips = ((height / strip_height) as usize + 1).min(len.max(1));
+
+        self.bottom = bottom;
+        self.strip_height = strip_height;
+        let max_strip = strips as u32 - 1;
+        for b in &mut self.boxes {
+            let size = b.max - b.min;
+            b.strip = if size.x > strip_height || size.y > strip_height {
+                u32::MAX
+            } else {
+                (((b.min.y - bottom) / strip_height) as u32).min(max_strip)
+            };
+        }
+
+        let cmp = |a: &SweepBox, b: &SweepBox| a.strip.cmp(&b.strip).then(a.cmp_x(b));
+        if parallel {
+            self.boxes.par_sort_by(cmp);
+        } else {
+            self.boxes.sort_by(cmp);
+        }
+
+        self.strips.clear();
+        self.strips.extend((0..=strips as u32).map(|strip| self.boxes.partition_point(|b| b.strip < strip) as u32));
+    }
+
+    /// Strip containing `y`, saturating at the bottom (bodies stick out below it by their radius).
+    fn strip_of(&self, y: f32) -> u32 {
+        ((y - self.bottom) / self.strip_height) as u32
+    }
+
+    /// Boxes of the strips covering `min_y..=max_y`, one slice per strip.
+    fn strip_range(&self, min_y: f32, max_y: f32) -> impl Iterator<Item = &[SweepBox]> {
+        let last = self.strips.len() - 2;
+        let first = (self.strip_of(min_y) as usize).min(last);
+        let end = (self.strip_of(max_y) as usize).min(last);
+        (first..=end).map(|strip| &self.boxes[self.strips[strip] as usize..self.strips[strip + 1] as usize])
+    }
+
+    /// Number of chunks to sweep after `prepare`.
+    pub fn chunks(&self) -> usize {
+        self.boxes.len().div_ceil(Self::CHUNK)
+    }
+
+    /// Collects the pairs of body indices with overlapping boxes found from `chunk` into `out`.
+    pub fn sweep(&self, chunk: usize, out: &mut Vec<(u32, u32)>) {
+        out.clear();
+        let start = chunk * Self::CHUNK;
+        let end = (start + Self::CHUNK).min(self.boxes.len());
+        let strips = self.strips.len() - 1;
+        // Where the sweep starts in the strips below and above, which only moves right as long
+        // as the boxes of the chunk are in the same strip
+        let mut cursors = [(u32::MAX, 0); 2];
+
+        for (i, a) in self.boxes.iter().enumerate().take(end).skip(start) {
+            let mut sweep = |boxes: &[SweepBox]| {
+                for b in boxes {
+                    if b.min.x > a.max.x {
+                        break;
+                    }
+                    if a.overlaps(b) {
+                        out.push((a.index, b.index));
+                    }
+                }
+            };
+
+            if a.strip == u32::MAX {
+                // Boxes fitting a strip start at most a strip height left of or below this one
+                let h = self.strip_height;
+                for boxes in self.strip_range(a.min.y - h, a.max.y) {
+                    sweep(&boxes[boxes.partition_point(|b| b.min.x < a.min.x - h)..]);
+                }
+                // Other large boxes only from here on, to find each pair once
+                sweep(&self.boxes[i + 1..]);
+                continue;
+            }
+
+            // Boxes fitting a strip overlapping this one start in the strip below, this strip or
+            // the strip above. Only boxes after this one in sweep order, to find each pair once.
+            let strip = a.strip as usize;
+            sweep(&self.boxes[i + 1..self.strips[strip + 1] as usize]);
+            for (cursor, neighbour) in cursors.iter_mut().zip([strip.wrapping_sub(1), strip + 1]) {
+                if neighbour >= strips {
+                    continue;
+                }
+                let boxes = &self.boxes[..self.strips[neighbour + 1] as usize];
+                let first = self.strips[neighbour] as usize;
+                if cursor.0 != neighbour as u32 {
+                    *cursor = (neighbour as u32, first + boxes[first..].partition_point(|b| b.cmp_x(a).is_le()));
+                }
+                while cursor.1 < boxes.len() && boxes[cursor.1].cmp_x(a).is_le() {
+                    cursor.1 += 1;
+                }
+                sweep(&boxes[cursor.1..]);
+            }
+        }
+    }
+}
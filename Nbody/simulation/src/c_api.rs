@@ -1,69 +1,488 @@
 use crate::{
+    background::{BackgroundStepper, FrontBuffer, SharedSimulation},
     body::Body,
+    boundary::{Boundary, BoundaryMode},
+    field::{FieldGrid, FieldKind},
+    generators::{GeneratorError, GENERATORS},
+    progress::{ProgressCallback, ProgressOperation},
     quadtree::Node,
-    simulation::Simulation,
+    render::{ColorMode, Colormap, SplatKernel},
+    replay::InputLog,
+    simulation::{
+        Backend, Collision, CollisionCallback, Falloff, ForceMode, Granularity, Integrator, ParallelPhase, Simulation,
+    },
+    telemetry::{StepStats, StepStatsCallback},
+    trails::TrailRecorder,
+    utils,
+    view::{RenderPoint, ViewRect},
+    wells::{GravityWell, WellId},
 };
-use rustfiber::JobSystem;
+#[cfg(feature = "shm")]
+use crate::shm::RingPublisher;
+use rustfiber::{GranularityHint, JobSystem};
 use ultraviolet::Vec2;
 
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+
+/// Version of the C API. Bumped whenever a signature or exported struct layout changes.
+pub const NBODY_API_VERSION: u32 = 1;
+
+/// Status code returned by fallible C API functions.
+/// On anything other than `Ok`, `Simulation_GetLastError` describes the failure.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SimStatus {
+    Ok = 0,
+    NullHandle = 1,
+    InvalidArgument = 2,
+    IoError = 3,
+    /// The progress callback cancelled the operation.
+    Cancelled = 4,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Records `msg` as the calling thread's last error and returns `status`.
+pub(crate) fn fail(status: SimStatus, msg: impl Into<String>) -> SimStatus {
+    let msg = CString::new(msg.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = msg);
+    status
+}
+
+/// Taken (`None`) once the simulation has been moved back into its handle.
+type SharedStepper = Arc<Mutex<Option<BackgroundStepper>>>;
+
+/// Background steppers for handles with an asynchronous step started, keyed by handle address.
+/// While an entry exists the handle itself holds an empty placeholder simulation.
+static ASYNC_STEPPERS: LazyLock<Mutex<HashMap<usize, SharedStepper>>> = LazyLock::new(Default::default);
+
+fn async_steppers() -> MutexGuard<'static, HashMap<usize, SharedStepper>> {
+    ASYNC_STEPPERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn async_stepper(handle: *const Simulation) -> Option<SharedStepper> {
+    async_steppers().get(&(handle as usize)).cloned()
+}
+
+fn lock_stepper(stepper: &SharedStepper) -> MutexGuard<'_, Option<BackgroundStepper>> {
+    stepper.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Frame last published for each handle, keyed by handle address; read by `Simulation_LockRead`
+/// and `Simulation_GetFrontBodies` without touching the simulation, which may be mid-step.
+static FRONT_BUFFERS: LazyLock<Mutex<HashMap<usize, Arc<FrontBuffer>>>> = LazyLock::new(Default::default);
+
+fn front_buffers() -> MutexGuard<'static, HashMap<usize, Arc<FrontBuffer>>> {
+    FRONT_BUFFERS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Copies the bodies of `sim` into the front buffer of `handle`, the way `SharedSimulation::publish` does.
+fn publish(handle: *const Simulation, sim: &Simulation) {
+    let mut fronts = front_buffers();
+    let front = fronts.entry(handle as usize).or_default();
+    // Frames pinned by read locks keep theirs; publish into a new buffer instead
+    if Arc::strong_count(front) > 1 {
+        *front = Arc::default();
+    }
+    let front = Arc::make_mut(front);
+    front.frame = sim.frame;
+    sim.bodies.copy_to(&mut front.bodies);
+}
+
+/// Moves `sim` to the heap as a handle and publishes its first frame.
+fn into_handle(sim: Simulation) -> *mut Simulation {
+    let handle = Box::into_raw(Box::new(sim));
+    publish(handle, unsafe { &*handle });
+    handle
+}
+
+/// Waits for any asynchronous step on `handle`, moves the simulation back into it and publishes
+/// the stepped frame. Every accessor goes through this so the host never observes a half-stepped state.
+unsafe fn settle(handle: *const Simulation) {
+    if handle.is_null() {
+        return;
+    }
+
+    let Some(stepper) = async_steppers().remove(&(handle as usize)) else {
+        return;
+    };
+    let Some(stepper) = lock_stepper(&stepper).take() else {
+        return;
+    };
+
+    // Handles always originate from Box::into_raw, so writing through them is sound
+    let sim = unsafe { &mut *(handle as *mut Simulation) };
+    *sim = stepper.into_inner();
+    publish(handle, sim);
+}
+
+/// Copies of the bodies handed out by `Simulation_GetBodies`, keyed by handle address.
+/// Bodies are stored as columns (see `Bodies`), so the `Body` array is refreshed on every call.
+static BODY_VIEWS: LazyLock<Mutex<HashMap<usize, Vec<Body>>>> = LazyLock::new(Default::default);
+
+fn body_views() -> MutexGuard<'static, HashMap<usize, Vec<Body>>> {
+    BODY_VIEWS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Copies the bodies of `sim` into the array owned by `handle` and returns it.
+/// The heap buffer does not move when the map does, so the pointer outlives the lock.
+fn body_view(handle: *const Simulation, sim: &Simulation) -> *const Body {
+    let mut views = body_views();
+    let view = views.entry(handle as usize).or_default();
+    sim.bodies.copy_to(view);
+    view.as_ptr()
+}
+
+/// A frame pinned by `Simulation_LockRead` and the number of locks holding it.
+type ReadLock = (Arc<FrontBuffer>, usize);
+
+/// Pinned frames, keyed by handle address.
+static READ_LOCKS: LazyLock<Mutex<HashMap<usize, ReadLock>>> = LazyLock::new(Default::default);
+
+fn read_locks() -> MutexGuard<'static, HashMap<usize, ReadLock>> {
+    READ_LOCKS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// An empty simulation sharing `sim`'s JobSystem, left in a handle while its state is stepped elsewhere.
+fn placeholder_for(sim: &Simulation) -> Simulation {
+    Simulation::with_bodies_and_job_system(Vec::new(), sim.dt, 1.0, 1.0, sim.job_system.clone())
+}
+
+unsafe fn sim_ref<'a>(handle: *const Simulation) -> Option<&'a Simulation> {
+    unsafe { settle(handle) };
+    let sim = unsafe { handle.as_ref() };
+    if sim.is_none() {
+        fail(SimStatus::NullHandle, "simulation handle is null");
+    }
+    sim
+}
+
+unsafe fn sim_mut<'a>(handle: *mut Simulation) -> Result<&'a mut Simulation, SimStatus> {
+    unsafe { settle(handle) };
+    unsafe { handle.as_mut() }.ok_or_else(|| fail(SimStatus::NullHandle, "simulation handle is null"))
+}
+
+/// Converts a nul-terminated UTF-8 C string into a `&str`.
+unsafe fn path_from_c<'a>(path: *const c_char) -> Result<&'a str, SimStatus> {
+    unsafe { str_from_c(path, "path") }
+}
+
+unsafe fn str_from_c<'a>(s: *const c_char, what: &str) -> Result<&'a str, SimStatus> {
+    if s.is_null() {
+        return Err(fail(SimStatus::InvalidArgument, format!("{what} is null")));
+    }
+    unsafe { CStr::from_ptr(s) }
+        .to_str()
+        .map_err(|_| fail(SimStatus::InvalidArgument, format!("{what} is not valid UTF-8")))
+}
+
+/// Returns the message for the last error raised on the calling thread (empty if none).
+/// The pointer stays valid until the next failing call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetLastError() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_ClearLastError() {
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::default());
+}
+
+// --- ABI checks ---
+
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetApiVersion() -> u32 {
+    NBODY_API_VERSION
+}
+
+/// Size in bytes of `Body`, for validating host-side struct layouts.
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetBodyStride() -> usize {
+    std::mem::size_of::<Body>()
+}
+
+/// Size in bytes of `Node`, for validating host-side struct layouts.
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetNodeStride() -> usize {
+    std::mem::size_of::<Node>()
+}
+
+/// # Safety
+/// No preconditions; free the returned handle with `Simulation_Destroy`.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_Create() -> *mut Simulation {
-    Box::into_raw(Box::new(Simulation::new()))
+    into_handle(Simulation::new())
+}
+
+/// Creates a simulation from a caller-provided array of `count` bodies, which is copied.
+/// Returns null on invalid input.
+///
+/// # Safety
+/// `bodies` must be null or readable for `count` bodies.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CreateFromBodies(
+    bodies: *const Body,
+    count: usize,
+    dt: f32,
+    theta: f32,
+    epsilon: f32,
+) -> *mut Simulation {
+    if bodies.is_null() && count > 0 {
+        fail(SimStatus::InvalidArgument, "bodies is null but count is non-zero");
+        return std::ptr::null_mut();
+    }
+
+    let bodies = if count == 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(bodies, count) }.to_vec()
+    };
+
+    into_handle(Simulation::with_bodies(bodies, dt, theta, epsilon))
+}
+
+/// Creates a simulation from a JSON scene file.
+/// Returns null if the file cannot be read or is malformed; see `Simulation_GetLastError`.
+///
+/// # Safety
+/// `path` must be null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CreateFromSceneFile(path: *const c_char) -> *mut Simulation {
+    let Ok(path) = (unsafe { path_from_c(path) }) else {
+        return std::ptr::null_mut();
+    };
+
+    match Simulation::from_scene_file(path) {
+        Ok(sim) => into_handle(sim),
+        Err(e) => {
+            fail(SimStatus::IoError, format!("failed to load scene '{path}': {e}"));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Creates an independent copy of the simulation sharing its JobSystem.
+/// The collision callback is not copied.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_Clone(handle: *const Simulation) -> *mut Simulation {
+    unsafe { sim_ref(handle) }.map_or(std::ptr::null_mut(), |sim| into_handle(sim.clone()))
 }
 
+/// # Safety
+/// `handle` must be null or a live handle no other thread is using; it is invalid afterwards.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_Destroy(handle: *mut Simulation) {
+    unsafe { settle(handle) };
     if !handle.is_null() {
+        body_views().remove(&(handle as usize));
+        front_buffers().remove(&(handle as usize));
+        read_locks().remove(&(handle as usize));
         unsafe { drop(Box::from_raw(handle)) };
     }
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_Step(handle: *mut Simulation) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.step();
+            publish(handle, sim);
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Runs `n` steps in one call, saving hosts an FFI transition per step; each step is otherwise
+/// the same as `Simulation_Step`. Fails with `Cancelled` if the progress callback stops it early.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn Simulation_Step(handle: *mut Simulation) {
-    if let Some(sim) = unsafe { handle.as_mut() } {
-        sim.step();
+pub unsafe extern "C" fn Simulation_StepN(handle: *mut Simulation, n: usize) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            let steps = sim.step_n(n);
+            publish(handle, sim);
+            if steps < n {
+                return fail(SimStatus::Cancelled, format!("stepping cancelled after {steps} of {n} steps"));
+            }
+            SimStatus::Ok
+        }
+        Err(status) => status,
     }
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn Simulation_Reset(handle: *mut Simulation, n: usize) {
-    if let Some(sim) = unsafe { handle.as_mut() } {
-        sim.reset(n);
+pub unsafe extern "C" fn Simulation_Reset(handle: *mut Simulation, n: usize) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.reset(n);
+            SimStatus::Ok
+        }
+        Err(status) => status,
     }
 }
 
+/// Selects RustFiber (`use_rayon` false) or Rayon; see `Simulation_SetBackend`, which also
+/// covers the GPU.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
-pub unsafe extern "C" fn Simulation_SetUseRayon(handle: *mut Simulation, use_rayon: bool) {
-    if let Some(sim) = unsafe { handle.as_mut() } {
-        sim.set_use_rayon(use_rayon);
+pub unsafe extern "C" fn Simulation_SetUseRayon(handle: *mut Simulation, use_rayon: bool) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.set_use_rayon(use_rayon);
+            SimStatus::Ok
+        }
+        Err(status) => status,
     }
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_GetUseRayon(handle: *const Simulation) -> bool {
-    unsafe { handle.as_ref() }.map_or(false, |sim| sim.use_rayon)
+    unsafe { sim_ref(handle) }.is_some_and(|sim| sim.use_rayon)
+}
+
+/// Selects the backend (a `Backend` value: 0 = RustFiber, 1 = Rayon, 2 = GPU). Fails with
+/// `InvalidArgument`, leaving the backend unchanged, if the GPU is requested but unavailable.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetBackend(handle: *mut Simulation, backend: i32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let backend = match backend {
+        0 => Backend::RustFiber,
+        1 => Backend::Rayon,
+        2 => Backend::Gpu,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown backend {backend}")),
+    };
+
+    if sim.set_backend(backend) {
+        SimStatus::Ok
+    } else {
+        fail(SimStatus::InvalidArgument, "GPU backend unavailable (built without the gpu feature or no adapter)")
+    }
+}
+
+/// The selected backend as a `Backend` value, or -1 for a null handle.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetBackend(handle: *const Simulation) -> i32 {
+    unsafe { sim_ref(handle) }.map_or(-1, |sim| sim.backend() as i32)
+}
+
+/// Sets how the fiber backend splits a parallel phase into jobs (see `Simulation::set_granularity`).
+/// `phase`: 0 = force, 1 = tree build, 2 = propagation, 3 = integration, 4 = collision sweep.
+/// `hint`: 0 = trivial, 1 = light, 2 = moderate, 3 = heavy work per item.
+/// `min_chunk`: minimum items per job, or 0 to size the jobs by the hint alone.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetGranularity(
+    handle: *mut Simulation,
+    phase: i32,
+    hint: i32,
+    min_chunk: usize,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    let Some(&phase) = usize::try_from(phase).ok().and_then(|phase| ParallelPhase::ALL.get(phase)) else {
+        return fail(SimStatus::InvalidArgument, format!("unknown parallel phase {phase}"));
+    };
+    let hint = match hint {
+        0 => GranularityHint::Trivial,
+        1 => GranularityHint::Light,
+        2 => GranularityHint::Moderate,
+        3 => GranularityHint::Heavy,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown granularity hint {hint}")),
+    };
+
+    sim.set_granularity(phase, Granularity { hint, min_chunk });
+    SimStatus::Ok
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_GetBodyCount(handle: *const Simulation) -> usize {
-    unsafe { handle.as_ref() }.map_or(0, |sim| sim.bodies.len())
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.bodies.len())
 }
 
+/// Copies the bodies into an array owned by the handle and returns it.
+/// The array is a snapshot: it stays valid, and unchanged, until the next `Simulation_GetBodies`
+/// call on the handle or `Simulation_Destroy`.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_GetBodies(handle: *const Simulation) -> *const Body {
-    unsafe { handle.as_ref() }.map_or(std::ptr::null(), |sim| sim.bodies.as_ptr())
+    unsafe { sim_ref(handle) }.map_or(std::ptr::null(), |sim| body_view(handle, sim))
+}
+
+/// Copies up to `capacity` bodies into `out_bodies`. Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_bodies` null or writable for
+/// `capacity` bodies.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyBodies(handle: *const Simulation, out_bodies: *mut Body, capacity: usize) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_bodies.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_bodies is null but capacity is non-zero");
+        return 0;
+    }
+
+    let count = sim.bodies.len().min(capacity);
+    if count > 0 {
+        sim.bodies.copy_to_slice(unsafe { std::slice::from_raw_parts_mut(out_bodies, count) });
+    }
+    count
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_GetNodeCount(handle: *const Simulation) -> usize {
-    unsafe { handle.as_ref() }.map_or(0, |sim| sim.quadtree.nodes.len())
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.quadtree.nodes.len())
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_GetNodes(handle: *const Simulation) -> *const Node {
-    unsafe { handle.as_ref() }.map_or(std::ptr::null(), |sim| sim.quadtree.nodes.as_ptr())
+    unsafe { sim_ref(handle) }.map_or(std::ptr::null(), |sim| sim.quadtree.nodes.as_ptr())
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_AddBody(
     handle: *mut Simulation,
@@ -73,17 +492,27 @@ pub unsafe extern "C" fn Simulation_AddBody(
     vy: f32,
     mass: f32,
     radius: f32,
-) {
-    if let Some(sim) = unsafe { handle.as_mut() } {
-        sim.bodies.push(Body::new(
-            Vec2::new(x, y),
-            Vec2::new(vx, vy),
-            mass,
-            radius,
-        ));
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    if !mass.is_finite() || mass <= 0.0 || !radius.is_finite() || radius < 0.0 {
+        return fail(SimStatus::InvalidArgument, "body mass must be positive and radius non-negative");
     }
+
+    sim.add_body(Body::new(
+        Vec2::new(x, y),
+        Vec2::new(vx, vy),
+        mass,
+        radius,
+    ));
+    SimStatus::Ok
 }
 
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_ApplyForce(
     handle: *mut Simulation,
@@ -92,36 +521,2164 @@ pub unsafe extern "C" fn Simulation_ApplyForce(
     fx: f32,
     fy: f32,
     radius: f32,
-) {
-    if let Some(sim) = unsafe { handle.as_mut() } {
-        let pos = Vec2::new(x, y);
-        let force = Vec2::new(fx, fy);
-        let r_sq = radius * radius;
-
-        for body in &mut sim.bodies {
-            let d = body.pos - pos;
-            if d.mag_sq() < r_sq {
-                body.vel += force;
-            }
+) -> SimStatus {
+    unsafe {
+        Simulation_ApplyForceEx(
+            handle,
+            x,
+            y,
+            fx,
+            fy,
+            radius,
+            Falloff::Constant as i32,
+            ForceMode::Impulse as i32,
+        )
+    }
+}
+
+/// Applies a force to all bodies within `radius` of (x, y).
+/// `falloff` is a `Falloff` value (0 = constant, 1 = linear, 2 = inverse-square),
+/// `mode` a `ForceMode` value (0 = impulse, 1 = force over one time step).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_ApplyForceEx(
+    handle: *mut Simulation,
+    x: f32,
+    y: f32,
+    fx: f32,
+    fy: f32,
+    radius: f32,
+    falloff: i32,
+    mode: i32,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    let falloff = match falloff {
+        0 => Falloff::Constant,
+        1 => Falloff::Linear,
+        2 => Falloff::InverseSquare,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown falloff mode {falloff}")),
+    };
+    let mode = match mode {
+        0 => ForceMode::Impulse,
+        1 => ForceMode::Force,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown force mode {mode}")),
+    };
+
+    sim.apply_force(Vec2::new(x, y), Vec2::new(fx, fy), radius, falloff, mode);
+    SimStatus::Ok
+}
+
+/// Adds a gravity well at (x, y) and writes its id to `out_id` (if not null).
+/// A positive `strength` attracts, a negative one repels; `falloff` is a `Falloff` value.
+/// A `lifetime` of 0 or less keeps the well until it is removed.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_id` null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_AddWell(
+    handle: *mut Simulation,
+    x: f32,
+    y: f32,
+    strength: f32,
+    radius: f32,
+    falloff: i32,
+    lifetime: f32,
+    out_id: *mut u32,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    let falloff = match falloff {
+        0 => Falloff::Constant,
+        1 => Falloff::Linear,
+        2 => Falloff::InverseSquare,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown falloff mode {falloff}")),
+    };
+    let well = GravityWell {
+        lifetime: (lifetime > 0.0).then_some(lifetime),
+        ..GravityWell::new(Vec2::new(x, y), strength, radius, falloff)
+    };
+
+    let id = sim.add_well(well);
+    if let Some(out) = unsafe { out_id.as_mut() } {
+        *out = id.0;
+    }
+    SimStatus::Ok
+}
+
+/// Moves the gravity well `id` to (x, y), e.g. while the user drags it.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_MoveWell(handle: *mut Simulation, id: u32, x: f32, y: f32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    match sim.wells.get_mut(WellId(id)) {
+        Some(well) => {
+            well.pos = Vec2::new(x, y);
+            SimStatus::Ok
+        }
+        None => fail(SimStatus::InvalidArgument, format!("no gravity well with id {id}")),
+    }
+}
+
+/// Removes the gravity well `id`.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_RemoveWell(handle: *mut Simulation, id: u32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    match sim.remove_well(WellId(id)) {
+        Some(_) => SimStatus::Ok,
+        None => fail(SimStatus::InvalidArgument, format!("no gravity well with id {id}")),
+    }
+}
+
+/// Number of gravity wells that haven't expired or been removed.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetWellCount(handle: *const Simulation) -> usize {
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.wells.len())
+}
+
+/// Starts dragging body `index` toward (x, y) with a critically damped spring, replacing any
+/// drag in progress.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_BeginDrag(handle: *mut Simulation, index: usize, x: f32, y: f32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if sim.begin_drag(index, Vec2::new(x, y)) {
+        SimStatus::Ok
+    } else {
+        fail(SimStatus::InvalidArgument, format!("body index {index} out of range"))
+    }
+}
+
+/// Moves the target of the drag in progress, if any, to (x, y).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_UpdateDrag(handle: *mut Simulation, x: f32, y: f32) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.update_drag(Vec2::new(x, y));
+            SimStatus::Ok
         }
+        Err(status) => status,
+    }
+}
+
+/// Releases the dragged body, if any.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_EndDrag(handle: *mut Simulation) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.end_drag();
+            SimStatus::Ok
+        }
+        Err(status) => status,
     }
 }
 // --- Extended Simulation API ---
 
+/// # Safety
+/// `job_system_handle` must be a live handle from `rustfiber::c_api`.
 #[unsafe(no_mangle)]
 pub unsafe extern "C" fn Simulation_CreateWithJobSystem(job_system_handle: *mut JobSystem, n: usize, dt: f32) -> *mut Simulation {
     // Reconstruct Arc from handle provided by rustfiber::c_api
     let job_system = match unsafe { rustfiber::c_api::job_system_from_handle(job_system_handle) } {
         Some(js) => js,
-        None => return std::ptr::null_mut(),
+        None => {
+            fail(SimStatus::NullHandle, "job system handle is invalid");
+            return std::ptr::null_mut();
+        }
     };
-    
+
     // Defaults matching Simulation::new()
     let theta = Simulation::DEFAULT_THETA;
     let epsilon = Simulation::DEFAULT_EPSILON;
-    
+
     let bodies = crate::utils::uniform_disc(n);
     let sim = Simulation::with_bodies_and_job_system(bodies, dt, theta, epsilon, job_system);
-    
-    Box::into_raw(Box::new(sim))
+
+    into_handle(sim)
+}
+
+// --- Diagnostics ---
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetKineticEnergy(handle: *const Simulation) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.kinetic_energy())
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetPotentialEnergy(handle: *const Simulation) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.potential_energy())
+}
+
+/// Virial ratio 2T / |U| on the tree from the last step (0 before the first step).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetVirialRatio(handle: *const Simulation) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.virial_ratio())
+}
+
+/// Samples a field (0 = potential, 1 = acceleration magnitude, 2 = surface density) at the cell
+/// centers of a `width` x `height` grid of `cell_size` cells centered on (`center_x`, `center_y`),
+/// on the tree from the last step. Writes up to `capacity` values row-major, top row first, to
+/// `out_values` and returns the number written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_values` null or writable for
+/// `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SampleField(
+    handle: *const Simulation,
+    kind: i32,
+    center_x: f32,
+    center_y: f32,
+    cell_size: f32,
+    width: usize,
+    height: usize,
+    out_values: *mut f32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    let kind = match kind {
+        0 => FieldKind::Potential,
+        1 => FieldKind::Acceleration,
+        2 => FieldKind::Density,
+        _ => {
+            fail(SimStatus::InvalidArgument, format!("invalid field kind {kind}"));
+            return 0;
+        }
+    };
+    if out_values.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_values is null but capacity is non-zero");
+        return 0;
+    }
+
+    let mut grid = FieldGrid::new(Vec2::new(center_x, center_y), cell_size, width, height);
+    sim.sample_field(&mut grid, kind);
+    let count = grid.values.len().min(capacity);
+    if count > 0 {
+        unsafe { std::slice::from_raw_parts_mut(out_values, count) }.copy_from_slice(&grid.values[..count]);
+    }
+    count
+}
+
+/// Samples `kind` over the rectangle from (min_x, min_y) to (max_x, max_y) into `out_buffer`,
+/// which must hold `width * height` floats (see `Simulation_SampleDensityGrid`).
+unsafe fn sample_grid(
+    handle: *const Simulation,
+    kind: FieldKind,
+    out_buffer: *mut f32,
+    width: usize,
+    height: usize,
+    min: Vec2,
+    max: Vec2,
+) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let Some(len) = width.checked_mul(height) else {
+        return fail(SimStatus::InvalidArgument, "grid size overflows");
+    };
+    if out_buffer.is_null() && len > 0 {
+        return fail(SimStatus::InvalidArgument, "out_buffer is null");
+    }
+    if !(max.x > min.x && max.y > min.y) {
+        return fail(SimStatus::InvalidArgument, "grid max must be greater than min");
+    }
+
+    let mut grid = FieldGrid::from_bounds(min, max, width, height);
+    sim.sample_field(&mut grid, kind);
+    if !grid.values.is_empty() {
+        unsafe { std::slice::from_raw_parts_mut(out_buffer, grid.values.len()) }.copy_from_slice(&grid.values);
+    }
+    SimStatus::Ok
+}
+
+/// Fills `out_buffer` (`width * height` floats, row-major, top row first) with the surface
+/// density at the cell centers of a grid exactly covering (min_x, min_y)-(max_x, max_y), on the
+/// tree from the last step, so a heatmap needs a single call.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_buffer` null or writable for `width *
+/// height` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SampleDensityGrid(
+    handle: *const Simulation,
+    out_buffer: *mut f32,
+    width: usize,
+    height: usize,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+) -> SimStatus {
+    let (min, max) = (Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+    unsafe { sample_grid(handle, FieldKind::Density, out_buffer, width, height, min, max) }
+}
+
+/// Like `Simulation_SampleDensityGrid`, with the gravitational potential.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_buffer` null or writable for `width *
+/// height` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SamplePotentialGrid(
+    handle: *const Simulation,
+    out_buffer: *mut f32,
+    width: usize,
+    height: usize,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+) -> SimStatus {
+    let (min, max) = (Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+    unsafe { sample_grid(handle, FieldKind::Potential, out_buffer, width, height, min, max) }
+}
+
+/// Like `Simulation_SampleDensityGrid`, with the magnitude of the acceleration.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_buffer` null or writable for `width *
+/// height` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SampleAccelerationGrid(
+    handle: *const Simulation,
+    out_buffer: *mut f32,
+    width: usize,
+    height: usize,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+) -> SimStatus {
+    let (min, max) = (Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+    unsafe { sample_grid(handle, FieldKind::Acceleration, out_buffer, width, height, min, max) }
+}
+
+/// Writes the acceleration a massless object at (x, y) would get, from the bodies (on the tree
+/// from the last step), external potentials, gravity wells and timeline fields, to `out_ax` and
+/// `out_ay` (each may be null).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_ax` and `out_ay` null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetAccelerationAt(
+    handle: *const Simulation,
+    x: f32,
+    y: f32,
+    out_ax: *mut f32,
+    out_ay: *mut f32,
+) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let acc = sim.acceleration_at(Vec2::new(x, y));
+
+    if let Some(ax) = unsafe { out_ax.as_mut() } {
+        *ax = acc.x;
+    }
+    if let Some(ay) = unsafe { out_ay.as_mut() } {
+        *ay = acc.y;
+    }
+    SimStatus::Ok
+}
+
+/// Gravitational potential at (x, y) from the bodies (on the tree from the last step) and
+/// external potentials. Returns 0 for a null handle.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetPotentialAt(handle: *const Simulation, x: f32, y: f32) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.potential_at(Vec2::new(x, y)))
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_x` and `out_y` null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetMomentum(handle: *const Simulation, out_x: *mut f32, out_y: *mut f32) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let momentum = sim.momentum();
+
+    if let Some(x) = unsafe { out_x.as_mut() } {
+        *x = momentum.x;
+    }
+    if let Some(y) = unsafe { out_y.as_mut() } {
+        *y = momentum.y;
+    }
+    SimStatus::Ok
+}
+
+// --- Snapshots ---
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SaveSnapshot(handle: *const Simulation, path: *const c_char) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match sim.save_snapshot(path) {
+        Ok(()) => SimStatus::Ok,
+        Err(e) => fail(SimStatus::IoError, format!("failed to save snapshot '{path}': {e}")),
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_LoadSnapshot(handle: *mut Simulation, path: *const c_char) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match sim.load_snapshot(path) {
+        Ok(()) => SimStatus::Ok,
+        Err(e) => fail(SimStatus::IoError, format!("failed to load snapshot '{path}': {e}")),
+    }
+}
+
+/// Writes all bodies to a CSV file (`x,y,vx,vy,mass,radius`).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SaveCsv(handle: *const Simulation, path: *const c_char) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match utils::save_csv(path, &sim.bodies.to_vec()) {
+        Ok(()) => SimStatus::Ok,
+        Err(e) => fail(SimStatus::IoError, format!("failed to save CSV '{path}': {e}")),
+    }
+}
+
+/// Replaces all bodies with those in a CSV file (`x,y,vx,vy,mass[,radius]`) and resets time.
+/// The simulation is left untouched if the file cannot be read.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_LoadCsv(handle: *mut Simulation, path: *const c_char) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match utils::load_csv(path) {
+        Ok(bodies) => {
+            sim.reset_with_bodies(bodies);
+            SimStatus::Ok
+        }
+        Err(e) => fail(SimStatus::IoError, format!("failed to load CSV '{path}': {e}")),
+    }
+}
+
+// --- Collision events ---
+
+pub type CollisionCallbackFn = Option<extern "C" fn(user_data: *mut c_void, i: u32, j: u32, impulse: f32)>;
+
+/// Registers a callback invoked after each step, on the stepping thread, once per collision.
+/// Passing a null callback removes it.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time. `callback`, if set, must be safe to call with
+/// `user_data` on the stepping thread until it is replaced or the handle destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetCollisionCallback(
+    handle: *mut Simulation,
+    callback: CollisionCallbackFn,
+    user_data: *mut c_void,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    // Raw pointers aren't Send; the host owns the user data and its thread-safety
+    let user_data = user_data as usize;
+    sim.set_collision_callback(callback.map(|callback| {
+        Box::new(move |c: &Collision| {
+            callback(user_data as *mut c_void, c.i, c.j, c.impulse)
+        }) as CollisionCallback
+    }));
+    SimStatus::Ok
+}
+
+/// Number of collisions resolved during the last step, for hosts that poll instead of
+/// registering a callback.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetCollisionCount(handle: *const Simulation) -> usize {
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.collisions.len())
+}
+
+/// Copies up to `capacity` of the collisions resolved during the last step into `out_pairs`.
+/// Returns the number of collisions written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_pairs` null or writable for `capacity`
+/// collisions.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyCollisions(
+    handle: *const Simulation,
+    out_pairs: *mut Collision,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_pairs.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_pairs is null but capacity is non-zero");
+        return 0;
+    }
+
+    let count = sim.collisions.len().min(capacity);
+    if count > 0 {
+        unsafe { std::slice::from_raw_parts_mut(out_pairs, count) }.copy_from_slice(&sim.collisions[..count]);
+    }
+    count
+}
+
+// --- Step stats ---
+
+pub type StepStatsCallbackFn = Option<
+    extern "C" fn(user_data: *mut c_void, frame: usize, step_seconds: f64, nodes: usize, collisions: usize, max_speed: f32),
+>;
+
+/// Registers a callback invoked after each step, on the stepping thread, with the frame, the wall
+/// time of the step, the quadtree node count, the number of collisions and the largest body
+/// speed. Passing a null callback removes it.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time. `callback`, if set, must be safe to call with
+/// `user_data` on the stepping thread until it is replaced or the handle destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetStepStatsCallback(
+    handle: *mut Simulation,
+    callback: StepStatsCallbackFn,
+    user_data: *mut c_void,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    // Raw pointers aren't Send; the host owns the user data and its thread-safety
+    let user_data = user_data as usize;
+    sim.set_step_stats_callback(callback.map(|callback| {
+        Box::new(move |s: &StepStats| {
+            callback(user_data as *mut c_void, s.frame, s.total.as_secs_f64(), s.nodes, s.collisions, s.max_speed)
+        }) as StepStatsCallback
+    }));
+    SimStatus::Ok
+}
+
+// --- Queries ---
+
+/// Writes the indices of bodies within `r` of (x, y) into `out_indices` (up to `capacity`).
+/// Returns the total number of matches, which may exceed `capacity`.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_indices` null or writable for
+/// `capacity` indices.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_QueryRadius(
+    handle: *const Simulation,
+    x: f32,
+    y: f32,
+    r: f32,
+    out_indices: *mut u32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_indices.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_indices is null but capacity is non-zero");
+        return 0;
+    }
+
+    let mut count = 0;
+    sim.query_radius(Vec2::new(x, y), r, |i| {
+        if count < capacity {
+            unsafe { *out_indices.add(count) = i as u32 };
+        }
+        count += 1;
+    });
+    count
+}
+
+// --- SoA exports ---
+
+/// Writes node quad centers (packed float2) and sizes for up to `capacity` nodes.
+/// Either output may be null. Returns the number of nodes written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_centers` null or writable for `2 *
+/// capacity` floats, `out_sizes` null or writable for `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyNodeQuads(
+    handle: *const Simulation,
+    out_centers: *mut f32,
+    out_sizes: *mut f32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+
+    let count = sim.quadtree.nodes.len().min(capacity);
+    for (i, node) in sim.quadtree.nodes[..count].iter().enumerate() {
+        unsafe {
+            if !out_centers.is_null() {
+                *out_centers.add(2 * i) = node.quad.center.x;
+                *out_centers.add(2 * i + 1) = node.quad.center.y;
+            }
+            if !out_sizes.is_null() {
+                *out_sizes.add(i) = node.quad.size;
+            }
+        }
+    }
+    count
+}
+
+/// Writes node centers of mass (packed float2) and total masses for up to `capacity` nodes.
+/// Either output may be null. Returns the number of nodes written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_com` null or writable for `2 *
+/// capacity` floats, `out_masses` null or writable for `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyNodeMasses(
+    handle: *const Simulation,
+    out_com: *mut f32,
+    out_masses: *mut f32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+
+    let count = sim.quadtree.nodes.len().min(capacity);
+    for (i, node) in sim.quadtree.nodes[..count].iter().enumerate() {
+        unsafe {
+            if !out_com.is_null() {
+                *out_com.add(2 * i) = node.pos.x;
+                *out_com.add(2 * i + 1) = node.pos.y;
+            }
+            if !out_masses.is_null() {
+                *out_masses.add(i) = node.mass;
+            }
+        }
+    }
+    count
+}
+
+/// Writes body positions as tightly packed float2s for up to `capacity` bodies.
+/// Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_xy` null or writable for `2 *
+/// capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyPositions(handle: *const Simulation, out_xy: *mut f32, capacity: usize) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_xy.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_xy is null but capacity is non-zero");
+        return 0;
+    }
+
+    let count = sim.bodies.len().min(capacity);
+    if count == 0 {
+        return 0;
+    }
+
+    let out = unsafe { std::slice::from_raw_parts_mut(out_xy, 2 * count) };
+    for (xy, pos) in out.chunks_exact_mut(2).zip(sim.bodies.positions()) {
+        xy[0] = pos.x;
+        xy[1] = pos.y;
+    }
+    count
+}
+
+/// Writes `count` elements of `value(i)` to `out`, `stride` bytes apart (0 = tightly packed).
+unsafe fn write_strided<T: Copy>(out: *mut T, stride: usize, count: usize, value: impl Fn(usize) -> T) {
+    let stride = if stride == 0 { size_of::<T>() } else { stride };
+    for i in 0..count {
+        // SAFETY: the caller guarantees room for `count` elements `stride` bytes apart, which
+        // need not be aligned for `T` in interleaved vertex formats
+        unsafe { (out as *mut u8).add(i * stride).cast::<T>().write_unaligned(value(i)) };
+    }
+}
+
+/// Writes up to `count` bodies' positions (float2), velocities (float2) and masses (float) into
+/// caller buffers whose elements are `*_stride` bytes apart, so hosts can fill interleaved
+/// vertex buffers directly. A stride of 0 means tightly packed; each output may be null to skip
+/// it. Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and each output null or writable for `count`
+/// elements `*_stride` bytes apart.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyBodySoA(
+    handle: *const Simulation,
+    out_pos: *mut f32,
+    pos_stride: usize,
+    out_vel: *mut f32,
+    vel_stride: usize,
+    out_mass: *mut f32,
+    mass_stride: usize,
+    count: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    let strides = [(pos_stride, 8, "pos_stride"), (vel_stride, 8, "vel_stride"), (mass_stride, 4, "mass_stride")];
+    if let Some((_, size, name)) = strides.iter().find(|&&(stride, size, _)| stride != 0 && stride < size) {
+        fail(SimStatus::InvalidArgument, format!("{name} is smaller than its {size}-byte element"));
+        return 0;
+    }
+
+    let count = sim.bodies.len().min(count);
+    let (positions, velocities, masses) = (sim.bodies.positions(), sim.bodies.velocities(), sim.bodies.masses());
+    unsafe {
+        if !out_pos.is_null() {
+            write_strided(out_pos as *mut [f32; 2], pos_stride, count, |i| [positions[i].x, positions[i].y]);
+        }
+        if !out_vel.is_null() {
+            write_strided(out_vel as *mut [f32; 2], vel_stride, count, |i| [velocities[i].x, velocities[i].y]);
+        }
+        if !out_mass.is_null() {
+            write_strided(out_mass, mass_stride, count, |i| masses[i]);
+        }
+    }
+    count
+}
+
+/// Writes world-space body positions (`origin + position`, see `Simulation_SetOrigin`) as
+/// tightly packed double2s for up to `capacity` bodies, e.g. for Unreal Engine's large-world
+/// coordinates. Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_xy` null or writable for `2 *
+/// capacity` doubles.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyPositionsF64(
+    handle: *const Simulation,
+    out_xy: *mut f64,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_xy.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_xy is null but capacity is non-zero");
+        return 0;
+    }
+
+    let count = sim.bodies.len().min(capacity);
+    if count == 0 {
+        return 0;
+    }
+
+    let out = unsafe { std::slice::from_raw_parts_mut(out_xy, 2 * count) };
+    for (xy, pos) in out.chunks_exact_mut(2).zip(sim.world_positions()) {
+        xy.copy_from_slice(&pos);
+    }
+    count
+}
+
+/// Writes body radii for up to `capacity` bodies. Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_radii` null or writable for `capacity`
+/// floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyRadii(handle: *const Simulation, out_radii: *mut f32, capacity: usize) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_radii.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_radii is null but capacity is non-zero");
+        return 0;
+    }
+
+    let count = sim.bodies.len().min(capacity);
+    if count == 0 {
+        return 0;
+    }
+
+    let out = unsafe { std::slice::from_raw_parts_mut(out_radii, count) };
+    out.copy_from_slice(&sim.bodies.radii()[..count]);
+    count
+}
+
+/// Writes stable body ids (see `Bodies::ids`) for up to `capacity` bodies, in index order.
+/// Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_ids` null or writable for `capacity`
+/// ids.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyBodyIds(handle: *const Simulation, out_ids: *mut u32, capacity: usize) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_ids.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_ids is null but capacity is non-zero");
+        return 0;
+    }
+
+    let count = sim.bodies.len().min(capacity);
+    if count == 0 {
+        return 0;
+    }
+
+    let out = unsafe { std::slice::from_raw_parts_mut(out_ids, count) };
+    out.copy_from_slice(&sim.bodies.ids()[..count]);
+    count
+}
+
+// --- Visualization ---
+
+fn color_mode_from_c(mode: i32) -> Result<ColorMode, SimStatus> {
+    match mode {
+        0 => Ok(ColorMode::Speed),
+        1 => Ok(ColorMode::Acceleration),
+        2 => Ok(ColorMode::Density),
+        _ => Err(fail(SimStatus::InvalidArgument, format!("invalid color mode {mode}"))),
+    }
+}
+
+/// Writes the quantity `mode` (0 = speed, 1 = acceleration magnitude, 2 = local density) of up
+/// to `capacity` bodies to `out_values`. Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_values` null or writable for
+/// `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyBodyScalars(
+    handle: *const Simulation,
+    mode: i32,
+    out_values: *mut f32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    let Ok(mode) = color_mode_from_c(mode) else {
+        return 0;
+    };
+    if out_values.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_values is null but capacity is non-zero");
+        return 0;
+    }
+
+    let mut values = Vec::new();
+    sim.body_scalars(mode, &mut values);
+    let count = values.len().min(capacity);
+    if count > 0 {
+        unsafe { std::slice::from_raw_parts_mut(out_values, count) }.copy_from_slice(&values[..count]);
+    }
+    count
+}
+
+/// Colors up to `capacity` bodies by `mode` (as in `Simulation_CopyBodyScalars`) through
+/// `colormap` (0 = grayscale, 1 = inferno, 2 = viridis), writing 4 RGBA bytes per body to
+/// `out_rgba`. Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_rgba` null or writable for `4 *
+/// capacity` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyBodyColors(
+    handle: *const Simulation,
+    mode: i32,
+    colormap: i32,
+    out_rgba: *mut u8,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    let Ok(mode) = color_mode_from_c(mode) else {
+        return 0;
+    };
+    let colormap = match colormap {
+        0 => Colormap::Grayscale,
+        1 => Colormap::Inferno,
+        2 => Colormap::Viridis,
+        _ => {
+            fail(SimStatus::InvalidArgument, format!("invalid colormap {colormap}"));
+            return 0;
+        }
+    };
+    if out_rgba.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_rgba is null but capacity is non-zero");
+        return 0;
+    }
+
+    let mut colors = Vec::new();
+    sim.compute_colors(mode, colormap, &mut Vec::new(), &mut colors);
+    let count = colors.len().min(capacity);
+    if count > 0 {
+        let out = unsafe { std::slice::from_raw_parts_mut(out_rgba as *mut [u8; 4], count) };
+        out.copy_from_slice(&colors[..count]);
+    }
+    count
+}
+
+/// Splats the body masses onto a `width` x `height` grid spanning `2 * extent` world units
+/// horizontally around the origin (see `Simulation::render_density`), blurred with a Gaussian of
+/// `sigma` cells if positive, and writes up to `capacity` densities row-major, top row first, to
+/// `out_density`. Returns the number of cells written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_density` null or writable for
+/// `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_RenderDensity(
+    handle: *const Simulation,
+    width: usize,
+    height: usize,
+    extent: f32,
+    sigma: f32,
+    out_density: *mut f32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_density.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_density is null but capacity is non-zero");
+        return 0;
+    }
+
+    let kernel = if sigma > 0.0 { SplatKernel::Gaussian { sigma } } else { SplatKernel::Nearest };
+    let mut grid = Vec::new();
+    sim.render_density_texture(width, height, extent, kernel, &mut grid);
+    let count = grid.len().min(capacity);
+    if count > 0 {
+        unsafe { std::slice::from_raw_parts_mut(out_density, count) }.copy_from_slice(&grid[..count]);
+    }
+    count
+}
+
+/// Writes the points to draw for the bodies in the view rectangle (`min_x`, `min_y`) to
+/// (`max_x`, `max_y`) to `out_points` (up to `capacity`), merging clusters smaller than
+/// `pixel_size` world units into one point (see `Simulation::render_points`).
+/// Returns the total number of points, which may exceed `capacity`.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_points` null or writable for
+/// `capacity` points.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetRenderPoints(
+    handle: *const Simulation,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+    pixel_size: f32,
+    out_points: *mut RenderPoint,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_points.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_points is null but capacity is non-zero");
+        return 0;
+    }
+
+    let mut points = Vec::new();
+    let view = ViewRect::new(Vec2::new(min_x, min_y), Vec2::new(max_x, max_y));
+    sim.render_points(view, pixel_size, &mut points);
+    let count = points.len().min(capacity);
+    if count > 0 {
+        unsafe { std::slice::from_raw_parts_mut(out_points, count) }.copy_from_slice(&points[..count]);
+    }
+    points.len()
+}
+
+/// Writes the indices of the bodies inside the view rectangle (`min_x`, `min_y`) to
+/// (`max_x`, `max_y`) to `out_indices` and their positions (packed float2) to `out_xy`, up to
+/// `capacity` bodies (see `Simulation::bodies_in_view`). Either output may be null.
+/// Returns the total number of visible bodies, which may exceed `capacity`.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_indices` null or writable for
+/// `capacity` indices, `out_xy` null or writable for `2 * capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_QueryView(
+    handle: *const Simulation,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+    out_indices: *mut u32,
+    out_xy: *mut f32,
+    capacity: usize,
+) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+
+    let mut indices = Vec::new();
+    sim.bodies_in_view(ViewRect::new(Vec2::new(min_x, min_y), Vec2::new(max_x, max_y)), &mut indices);
+    let positions = sim.bodies.positions();
+    for (k, &i) in indices.iter().take(capacity).enumerate() {
+        unsafe {
+            if !out_indices.is_null() {
+                *out_indices.add(k) = i;
+            }
+            if !out_xy.is_null() {
+                *out_xy.add(2 * k) = positions[i as usize].x;
+                *out_xy.add(2 * k + 1) = positions[i as usize].y;
+            }
+        }
+    }
+    indices.len()
+}
+
+/// Starts recording trails of the last `length` positions, every `interval` frames, for the
+/// `count` bodies at `indices`, replacing any trails recorded so far. A `count` of 0 stops
+/// recording.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `indices` null or readable for `count`
+/// indices.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetTrails(
+    handle: *mut Simulation,
+    indices: *const u32,
+    count: usize,
+    length: usize,
+    interval: usize,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if count == 0 {
+        sim.set_trail_recorder(None);
+        return SimStatus::Ok;
+    }
+    if indices.is_null() {
+        return fail(SimStatus::InvalidArgument, "indices is null but count is non-zero");
+    }
+
+    let indices = unsafe { std::slice::from_raw_parts(indices, count) };
+    let indices = indices.iter().map(|&i| i as usize).collect();
+    sim.set_trail_recorder(Some(TrailRecorder::new(indices, length, interval)));
+    SimStatus::Ok
+}
+
+/// Number of points recorded so far in each trail, the same for all of them.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetTrailLength(handle: *const Simulation) -> usize {
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.trail_recorder.as_ref().map_or(0, TrailRecorder::len))
+}
+
+/// Writes up to `capacity` trail points (packed float2) to `out_xy`: `Simulation_GetTrailLength`
+/// points per trail, oldest first, trails in the order their bodies were given. Returns the
+/// number of points written.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_xy` null or writable for `2 *
+/// capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CopyTrailVertices(handle: *const Simulation, out_xy: *mut f32, capacity: usize) -> usize {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return 0;
+    };
+    if out_xy.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_xy is null but capacity is non-zero");
+        return 0;
+    }
+    let Some(trails) = &sim.trail_recorder else {
+        return 0;
+    };
+
+    let mut vertices = Vec::new();
+    trails.write_vertices(&mut vertices);
+    let count = (vertices.len() / 2).min(capacity);
+    if count > 0 {
+        unsafe { std::slice::from_raw_parts_mut(out_xy, 2 * count) }.copy_from_slice(&vertices[..2 * count]);
+    }
+    count
+}
+
+// --- Cadence ---
+
+/// Runs gravity every `gravity_interval` and collisions every `collision_interval` frames
+/// (0 = never, 1 = every frame, the default). Bodies keep their last acceleration between
+/// gravity updates.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetPhaseIntervals(
+    handle: *mut Simulation,
+    gravity_interval: usize,
+    collision_interval: usize,
+) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.set_gravity_interval(gravity_interval);
+            sim.set_collision_interval(collision_interval);
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Splits each step into `substeps` integration steps of `dt / substeps`, each with its own
+/// collision and gravity pass (1 = no splitting, the default). Fails with `InvalidArgument` for 0.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetSubsteps(handle: *mut Simulation, substeps: usize) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if substeps == 0 {
+        return fail(SimStatus::InvalidArgument, "substeps must be at least 1");
+    }
+
+    sim.set_substeps(substeps);
+    SimStatus::Ok
+}
+
+/// Selects the integration scheme (an `Integrator` value: 0 = semi-implicit Euler, the default,
+/// 1 = leapfrog).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetIntegrator(handle: *mut Simulation, integrator: i32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let integrator = match integrator {
+        0 => Integrator::SemiImplicitEuler,
+        1 => Integrator::Leapfrog,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown integrator {integrator}")),
+    };
+
+    sim.set_integrator(integrator);
+    SimStatus::Ok
+}
+
+// --- Node budget ---
+
+/// Caps the quadtree at `budget` nodes (0 = unlimited, the default). Beyond it, bodies landing in
+/// an occupied leaf are merged into it; `Simulation_GetMergedBodies` reports how many were.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetNodeBudget(handle: *mut Simulation, budget: usize) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.set_node_budget(Some(budget).filter(|&budget| budget > 0));
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// Number of bodies merged into occupied leaves during the last step because of the node budget.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetMergedBodies(handle: *const Simulation) -> usize {
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.step_report().merged_bodies)
+}
+
+// --- Body order ---
+
+/// Sorts the bodies along a Morton curve every `interval` frames (0 = never). Body indices
+/// change with each sort; use `Simulation_CopyBodyIds` to follow bodies across steps.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetMortonInterval(handle: *mut Simulation, interval: usize) -> SimStatus {
+    match unsafe { sim_mut(handle) } {
+        Ok(sim) => {
+            sim.set_morton_interval(interval);
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+// --- Force law ---
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetGravityConstant(handle: *mut Simulation, g: f32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !g.is_finite() {
+        return fail(SimStatus::InvalidArgument, "gravity constant must be finite");
+    }
+    sim.set_gravity_constant(g);
+    SimStatus::Ok
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetGravityConstant(handle: *const Simulation) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.quadtree.g)
+}
+
+/// Sets the exponent `k` of the force law |F| ~ 1 / r^k (2 for Newtonian gravity).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetForceExponent(handle: *mut Simulation, k: f32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !k.is_finite() || k < 0.0 {
+        return fail(SimStatus::InvalidArgument, "force exponent must be finite and non-negative");
+    }
+    sim.set_force_exponent(k);
+    SimStatus::Ok
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetForceExponent(handle: *const Simulation) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.quadtree.force_exponent)
+}
+
+// --- Asynchronous stepping ---
+
+/// Publishes the current frame and starts a step on a background thread. While it runs, the
+/// published frame can be read through `Simulation_GetFrontBodies` or `Simulation_LockRead`; any
+/// other call on the handle first waits for the step to finish and publishes its result.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_StepAsync(handle: *mut Simulation) -> SimStatus {
+    if handle.is_null() {
+        return fail(SimStatus::NullHandle, "simulation handle is null");
+    }
+
+    let key = handle as usize;
+    let stepper = async_steppers()
+        .entry(key)
+        .or_insert_with(|| {
+            let sim = unsafe { &mut *handle };
+            let placeholder = placeholder_for(sim);
+            Arc::new(Mutex::new(Some(BackgroundStepper::new(std::mem::replace(sim, placeholder)))))
+        })
+        .clone();
+
+    let mut stepper = lock_stepper(&stepper);
+    let Some(stepper) = stepper.as_mut() else {
+        return fail(SimStatus::InvalidArgument, "simulation was accessed concurrently");
+    };
+    if !stepper.is_step_complete() {
+        return fail(SimStatus::InvalidArgument, "a step is already in progress");
+    }
+
+    // Readers keep seeing the old frame until the new one is in place. Dropping our reference
+    // first lets the stepper reuse the buffer unless a read lock pins it.
+    let mut fronts = front_buffers();
+    fronts.remove(&key);
+    stepper.step_async();
+    fronts.insert(key, stepper.snapshot());
+    SimStatus::Ok
+}
+
+/// Returns true if no asynchronous step is in flight.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_IsStepComplete(handle: *const Simulation) -> bool {
+    async_stepper(handle).is_none_or(|stepper| {
+        lock_stepper(&stepper).as_ref().is_none_or(|stepper| stepper.is_step_complete())
+    })
+}
+
+/// Blocks until the asynchronous step in flight (if any) has finished.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_WaitStep(handle: *mut Simulation) -> SimStatus {
+    if handle.is_null() {
+        return fail(SimStatus::NullHandle, "simulation handle is null");
+    }
+    if let Some(stepper) = async_stepper(handle)
+        && let Some(stepper) = lock_stepper(&stepper).as_mut()
+    {
+        stepper.wait();
+    }
+    SimStatus::Ok
+}
+
+/// Publishes the current bodies to `Simulation_GetFrontBodies` and `Simulation_LockRead` readers.
+/// Frames are published on creation and by `Step`, `StepN` and `StepAsync`; call this after other
+/// changes (resets, added bodies, ...) to show them before the next step.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_Publish(handle: *mut Simulation) -> SimStatus {
+    match unsafe { sim_ref(handle) } {
+        Some(sim) => {
+            publish(handle, sim);
+            SimStatus::Ok
+        }
+        None => SimStatus::NullHandle,
+    }
+}
+
+/// Bodies of the last published frame (see `Simulation_Publish`). Never waits for or touches the
+/// simulation, so it may be called from another thread while the owning thread steps; null for a null
+/// or destroyed handle. The pointer stays valid until the next `Step`, `StepN`, `StepAsync`, `Publish`
+/// or `Destroy` on the handle, or any other call on it while an asynchronous step is in flight (which
+/// publishes the step's result); use `Simulation_LockRead` to hold a frame across those.
+///
+/// # Safety
+/// `handle` is only used as a key, so any value is allowed, from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetFrontBodies(handle: *const Simulation) -> *const Body {
+    front_buffers().get(&(handle as usize)).map_or(std::ptr::null(), |front| front.bodies.as_ptr())
+}
+
+/// Number of bodies in the last published frame, see `Simulation_GetFrontBodies`.
+///
+/// # Safety
+/// `handle` is only used as a key, so any value is allowed, from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetFrontBodyCount(handle: *const Simulation) -> usize {
+    front_buffers().get(&(handle as usize)).map_or(0, |front| front.bodies.len())
+}
+
+/// Pins the last published frame (see `Simulation_Publish`) and returns its bodies, writing their
+/// number to `out_count` (if non-null). The array stays valid and unchanged until the matching
+/// `Simulation_UnlockRead`, whatever frames are published meanwhile, so a renderer on another
+/// thread can read it without tearing while the owning thread steps. Locks nest: while the frame
+/// is pinned, further calls return the same frame and each needs its own unlock.
+/// Returns null for a null or destroyed handle.
+///
+/// # Safety
+/// `handle` is only used as a key, so any value is allowed, from any thread, and `out_count` must be null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_LockRead(handle: *const Simulation, out_count: *mut usize) -> *const Body {
+    if handle.is_null() {
+        fail(SimStatus::NullHandle, "simulation handle is null");
+        return std::ptr::null();
+    }
+
+    let key = handle as usize;
+    let pinned = read_locks().get(&key).map(|(front, _)| front.clone());
+    let Some(front) = pinned.or_else(|| front_buffers().get(&key).cloned()) else {
+        fail(SimStatus::InvalidArgument, "simulation has no published frame");
+        return std::ptr::null();
+    };
+
+    // Another thread may have pinned a frame meanwhile; share it rather than replace it
+    let mut locks = read_locks();
+    let (front, count) = locks.entry(key).or_insert((front, 0));
+    *count += 1;
+    if !out_count.is_null() {
+        unsafe { *out_count = front.bodies.len() };
+    }
+    front.bodies.as_ptr()
+}
+
+/// Releases one `Simulation_LockRead`; the pinned frame is freed with the last one.
+/// Fails with `InvalidArgument` if the handle holds no read lock.
+///
+/// # Safety
+/// `handle` is only used as a key, so any value is allowed, from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_UnlockRead(handle: *const Simulation) -> SimStatus {
+    if handle.is_null() {
+        return fail(SimStatus::NullHandle, "simulation handle is null");
+    }
+
+    let mut locks = read_locks();
+    let Some((_, count)) = locks.get_mut(&(handle as usize)) else {
+        return fail(SimStatus::InvalidArgument, "simulation is not read-locked");
+    };
+    *count -= 1;
+    if *count == 0 {
+        locks.remove(&(handle as usize));
+    }
+    SimStatus::Ok
+}
+
+// --- Thread-safe handle ---
+//
+// A `SharedSimulation` handle may be used from several threads at once. Mutating calls serialize
+// on an internal lock; read accessors only touch the last published frame and never wait for a step.
+
+/// # Safety
+/// No preconditions; free the returned handle with `SharedSimulation_Destroy`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_CreateThreadSafe() -> *mut SharedSimulation {
+    Box::into_raw(Box::new(SharedSimulation::new(Simulation::new())))
+}
+
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle no other thread is using; it is invalid afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_Destroy(handle: *mut SharedSimulation) {
+    if !handle.is_null() {
+        unsafe { drop(Box::from_raw(handle)) };
+    }
+}
+
+unsafe fn shared_ref<'a>(handle: *const SharedSimulation) -> Result<&'a SharedSimulation, SimStatus> {
+    unsafe { handle.as_ref() }.ok_or_else(|| fail(SimStatus::NullHandle, "simulation handle is null"))
+}
+
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_Step(handle: *const SharedSimulation) -> SimStatus {
+    match unsafe { shared_ref(handle) } {
+        Ok(shared) => {
+            shared.step();
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_StepN(handle: *const SharedSimulation, n: usize) -> SimStatus {
+    match unsafe { shared_ref(handle) } {
+        Ok(shared) => {
+            shared.step_n(n);
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_Reset(handle: *const SharedSimulation, n: usize) -> SimStatus {
+    match unsafe { shared_ref(handle) } {
+        Ok(shared) => {
+            let mut sim = shared.lock();
+            sim.reset(n);
+            shared.publish(&sim);
+            SimStatus::Ok
+        }
+        Err(status) => status,
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_AddBody(
+    handle: *const SharedSimulation,
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    mass: f32,
+    radius: f32,
+) -> SimStatus {
+    let shared = match unsafe { shared_ref(handle) } {
+        Ok(shared) => shared,
+        Err(status) => return status,
+    };
+    let mut sim = shared.lock();
+    unsafe { Simulation_AddBody(&mut *sim, x, y, vx, vy, mass, radius) }
+}
+
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_ApplyForceEx(
+    handle: *const SharedSimulation,
+    x: f32,
+    y: f32,
+    fx: f32,
+    fy: f32,
+    radius: f32,
+    falloff: i32,
+    mode: i32,
+) -> SimStatus {
+    let shared = match unsafe { shared_ref(handle) } {
+        Ok(shared) => shared,
+        Err(status) => return status,
+    };
+    let mut sim = shared.lock();
+    unsafe { Simulation_ApplyForceEx(&mut *sim, x, y, fx, fy, radius, falloff, mode) }
+}
+
+/// Frame counter of the last published frame.
+///
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_GetFrame(handle: *const SharedSimulation) -> usize {
+    unsafe { shared_ref(handle) }.map_or(0, |shared| shared.front().frame)
+}
+
+/// Body count of the last published frame.
+///
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_GetBodyCount(handle: *const SharedSimulation) -> usize {
+    unsafe { shared_ref(handle) }.map_or(0, |shared| shared.front().bodies.len())
+}
+
+/// Copies up to `capacity` bodies of the last published frame into `out_bodies`.
+/// Returns the number of bodies written.
+///
+/// # Safety
+/// `handle` must be null or a live `SharedSimulation` handle; it may be used from any thread, and `out_bodies` null or
+/// writable for `capacity` bodies.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn SharedSimulation_CopyBodies(
+    handle: *const SharedSimulation,
+    out_bodies: *mut Body,
+    capacity: usize,
+) -> usize {
+    let shared = match unsafe { shared_ref(handle) } {
+        Ok(shared) => shared,
+        Err(_) => return 0,
+    };
+    if out_bodies.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "out_bodies is null but capacity is non-zero");
+        return 0;
+    }
+
+    let front = shared.front();
+    let count = front.bodies.len().min(capacity);
+    if count > 0 {
+        unsafe { std::ptr::copy_nonoverlapping(front.bodies.as_ptr(), out_bodies, count) };
+    }
+    count
+}
+
+// --- Time ---
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetFrame(handle: *const Simulation) -> usize {
+    unsafe { sim_ref(handle) }.map_or(0, |sim| sim.frame)
+}
+
+/// Simulated time elapsed, accumulated per step so changes to dt are accounted for.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetTime(handle: *const Simulation) -> f64 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.time)
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetDt(handle: *const Simulation) -> f32 {
+    unsafe { sim_ref(handle) }.map_or(0.0, |sim| sim.dt)
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetDt(handle: *mut Simulation, dt: f32) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !dt.is_finite() {
+        return fail(SimStatus::InvalidArgument, "dt must be finite");
+    }
+    sim.set_dt(dt);
+    SimStatus::Ok
+}
+
+// --- Boundaries ---
+
+/// Confines the simulation to a rectangle. `mode` is a `BoundaryMode` value
+/// (0 = none, 1 = periodic, 2 = reflective, 3 = despawn).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetBounds(
+    handle: *mut Simulation,
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+    mode: i32,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    let mode = match mode {
+        0 => BoundaryMode::None,
+        1 => BoundaryMode::Periodic,
+        2 => BoundaryMode::Reflective,
+        3 => BoundaryMode::Despawn,
+        _ => return fail(SimStatus::InvalidArgument, format!("unknown boundary mode {mode}")),
+    };
+    if mode != BoundaryMode::None && !(min_x < max_x && min_y < max_y) {
+        return fail(SimStatus::InvalidArgument, "bounds must have min < max on both axes");
+    }
+
+    sim.set_boundary(Boundary::new(Vec2::new(min_x, min_y), Vec2::new(max_x, max_y), mode));
+    SimStatus::Ok
+}
+
+// --- Spawners ---
+
+/// Adds a rotating disc of `n` bodies with the given radius centered on (x, y).
+/// `spin` scales the orbital speed; negative values reverse the rotation.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SpawnDisc(
+    handle: *mut Simulation,
+    x: f32,
+    y: f32,
+    n: usize,
+    radius: f32,
+    spin: f32,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !radius.is_finite() || radius <= 0.0 {
+        return fail(SimStatus::InvalidArgument, "radius must be positive");
+    }
+    sim.spawn_disc(Vec2::new(x, y), n, radius, spin);
+    SimStatus::Ok
+}
+
+/// Adds a Plummer star cluster of `n` bodies with scale length `radius` centered on (x, y).
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SpawnCluster(
+    handle: *mut Simulation,
+    x: f32,
+    y: f32,
+    n: usize,
+    radius: f32,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !radius.is_finite() || radius <= 0.0 {
+        return fail(SimStatus::InvalidArgument, "radius must be positive");
+    }
+    sim.spawn_cluster(Vec2::new(x, y), n, radius);
+    SimStatus::Ok
+}
+
+// --- Generators ---
+
+/// Nul-terminated generator names and parameter descriptions, in registry order.
+static GENERATOR_STRINGS: LazyLock<Vec<(CString, CString)>> = LazyLock::new(|| {
+    GENERATORS
+        .iter()
+        .map(|g| (CString::new(g.name).unwrap_or_default(), CString::new(g.params).unwrap_or_default()))
+        .collect()
+});
+
+/// Returns the number of registered generators.
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetGeneratorCount() -> usize {
+    GENERATORS.len()
+}
+
+/// Returns the name of the generator at `index`, or null if out of range.
+/// The string is static and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetGeneratorName(index: usize) -> *const c_char {
+    GENERATOR_STRINGS.get(index).map_or(std::ptr::null(), |(name, _)| name.as_ptr())
+}
+
+/// Returns a description of the parameters (with defaults) accepted by the generator at `index`,
+/// or null if out of range. The string is static and must not be freed.
+#[unsafe(no_mangle)]
+pub extern "C" fn Simulation_GetGeneratorParams(index: usize) -> *const c_char {
+    GENERATOR_STRINGS.get(index).map_or(std::ptr::null(), |(_, params)| params.as_ptr())
+}
+
+/// Replaces all bodies with the output of the named generator and resets time.
+/// `params_json` is a JSON object of parameters and may be null or empty to use the defaults.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `name` and `params_json` null or
+/// nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_ResetWithGenerator(
+    handle: *mut Simulation,
+    name: *const c_char,
+    params_json: *const c_char,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let name = match unsafe { str_from_c(name, "generator name") } {
+        Ok(name) => name,
+        Err(status) => return status,
+    };
+    let params_json = if params_json.is_null() {
+        ""
+    } else {
+        match unsafe { str_from_c(params_json, "generator parameters") } {
+            Ok(params) => params,
+            Err(status) => return status,
+        }
+    };
+
+    match sim.reset_with_generator(name, params_json) {
+        Ok(()) => SimStatus::Ok,
+        Err(GeneratorError::Cancelled) => fail(SimStatus::Cancelled, "generation cancelled"),
+        Err(e) => fail(SimStatus::InvalidArgument, e.to_string()),
+    }
+}
+
+/// Replaces all bodies with `n` bodies sampled from an 8-bit grayscale image of `width` x `height`
+/// pixels (row-major, top row first), with density proportional to brightness, and resets time.
+/// The image is scaled to `size` world units wide and centered on the origin.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `pixels` null or readable for `width *
+/// height` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_ResetWithDensityMap(
+    handle: *mut Simulation,
+    pixels: *const u8,
+    width: usize,
+    height: usize,
+    n: usize,
+    size: f32,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if pixels.is_null() || width == 0 || height == 0 {
+        return fail(SimStatus::InvalidArgument, "density map is empty");
+    }
+    if !size.is_finite() || size <= 0.0 {
+        return fail(SimStatus::InvalidArgument, "size must be positive");
+    }
+
+    let Some(len) = width.checked_mul(height) else {
+        return fail(SimStatus::InvalidArgument, "density map size overflows");
+    };
+
+    let pixels = unsafe { std::slice::from_raw_parts(pixels, len) };
+    let density: Vec<f32> = pixels.iter().map(|&p| p as f32).collect();
+    let g = sim.quadtree.g;
+    let bodies = utils::density_map(&density, width, height, n, size, g, sim.rng_mut());
+    if bodies.is_empty() && n > 0 {
+        return fail(SimStatus::InvalidArgument, "density map is entirely black");
+    }
+
+    sim.reset_with_bodies(bodies);
+    SimStatus::Ok
+}
+
+// --- Input logs ---
+
+/// Starts recording external interactions (added bodies, forces, parameter changes, spawns and
+/// resets) from the current state, replacing any log in progress.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_StartInputLog(handle: *mut Simulation) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    sim.start_input_log();
+    SimStatus::Ok
+}
+
+/// Writes the input log in progress to a file. Recording continues.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SaveInputLog(handle: *const Simulation, path: *const c_char) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+    let Some(log) = &sim.input_log else {
+        return fail(SimStatus::InvalidArgument, "no input log is being recorded");
+    };
+
+    match log.save(path) {
+        Ok(()) => SimStatus::Ok,
+        Err(e) => fail(SimStatus::IoError, format!("failed to save input log '{path}': {e}")),
+    }
+}
+
+/// Stops recording the input log. Does nothing if none is running.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_StopInputLog(handle: *mut Simulation) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    sim.take_input_log();
+    SimStatus::Ok
+}
+
+/// Restores the initial state of an input log file and re-executes its events up to `frame`
+/// (`SIZE_MAX` for the last logged event), reproducing the recorded run.
+/// The simulation is left untouched if the file cannot be read.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_ReplayInputLog(
+    handle: *mut Simulation,
+    path: *const c_char,
+    frame: usize,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match InputLog::load(path) {
+        Ok(log) => {
+            log.replay(sim, frame);
+            SimStatus::Ok
+        }
+        Err(e) => fail(SimStatus::IoError, format!("failed to load input log '{path}': {e}")),
+    }
+}
+
+// --- World origin ---
+//
+// Body positions are f32 offsets from a double-precision world origin. Hosts with large-world
+// coordinates keep the origin near the camera or region of interest and read world positions
+// with `Simulation_CopyPositionsF64`.
+
+/// Sets the world-space origin, translating the whole simulation without moving bodies
+/// relative to each other.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetOrigin(handle: *mut Simulation, x: f64, y: f64) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !(x.is_finite() && y.is_finite()) {
+        return fail(SimStatus::InvalidArgument, "origin must be finite");
+    }
+    sim.set_origin([x, y]);
+    SimStatus::Ok
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `out_x` and `out_y` null or writable.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_GetOrigin(handle: *const Simulation, out_x: *mut f64, out_y: *mut f64) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+
+    if let Some(x) = unsafe { out_x.as_mut() } {
+        *x = sim.origin[0];
+    }
+    if let Some(y) = unsafe { out_y.as_mut() } {
+        *y = sim.origin[1];
+    }
+    SimStatus::Ok
+}
+
+/// Moves the origin to (x, y) while keeping world-space positions, so bodies near it regain
+/// full f32 precision.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_ShiftOrigin(handle: *mut Simulation, x: f64, y: f64) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    if !(x.is_finite() && y.is_finite()) {
+        return fail(SimStatus::InvalidArgument, "origin must be finite");
+    }
+    sim.shift_origin([x, y]);
+    SimStatus::Ok
+}
+
+/// Moves the origin to the center of mass of the bodies while keeping world-space positions.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_RecenterOrigin(handle: *mut Simulation) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    sim.recenter_origin();
+    SimStatus::Ok
+}
+
+// --- Progress ---
+
+/// Progress callback: receives the operation, its completion fraction in [0, 1] and a flag,
+/// initially false, that the callback sets to true to cancel the operation.
+pub type ProgressCallbackFn = Option<
+    extern "C" fn(user_data: *mut c_void, operation: ProgressOperation, fraction: f32, cancel: *mut bool),
+>;
+
+/// Registers a callback reporting progress of `Simulation_StepN` (after each step),
+/// `Simulation_WriteCheckpoint` and `Simulation_ResetWithGenerator`, invoked on the thread running
+/// the operation. Cancelled operations return `SimStatus_Cancelled`: stepping stops after the
+/// current step, checkpoints are not written and generators leave the simulation untouched.
+/// Passing a null callback removes it.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time. `callback`, if set, must be safe to call with
+/// `user_data` on the thread running the operation until it is replaced or the handle destroyed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_SetProgressCallback(
+    handle: *mut Simulation,
+    callback: ProgressCallbackFn,
+    user_data: *mut c_void,
+) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+
+    // Raw pointers aren't Send; the host owns the user data and its thread-safety
+    let user_data = user_data as usize;
+    sim.set_progress_callback(callback.map(|callback| {
+        Box::new(move |operation: ProgressOperation, fraction: f32| {
+            let mut cancel = false;
+            callback(user_data as *mut c_void, operation, fraction, &mut cancel);
+            !cancel
+        }) as ProgressCallback
+    }));
+    SimStatus::Ok
+}
+
+/// Writes a compressed checkpoint that `Simulation_ReadCheckpoint` can resume from.
+///
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[cfg(feature = "checkpoint")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_WriteCheckpoint(handle: *const Simulation, path: *const c_char) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match sim.write_checkpoint(path) {
+        Ok(()) => SimStatus::Ok,
+        Err(e) if e.kind() == std::io::ErrorKind::Interrupted => fail(SimStatus::Cancelled, "checkpoint cancelled"),
+        Err(e) => fail(SimStatus::IoError, format!("failed to write checkpoint '{path}': {e}")),
+    }
+}
+
+/// # Safety
+/// `handle` must be null or a live handle used by one thread at a time, and `path` null or a nul-terminated string.
+#[cfg(feature = "checkpoint")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn Simulation_ReadCheckpoint(handle: *mut Simulation, path: *const c_char) -> SimStatus {
+    let sim = match unsafe { sim_mut(handle) } {
+        Ok(sim) => sim,
+        Err(status) => return status,
+    };
+    let path = match unsafe { path_from_c(path) } {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match sim.read_checkpoint(path) {
+        Ok(()) => SimStatus::Ok,
+        Err(e) => fail(SimStatus::IoError, format!("failed to read checkpoint '{path}': {e}")),
+    }
+}
+
+// --- Shared-memory ring buffer ---
+//
+// Frames published here can be read by other processes straight from shared memory; see
+// `shm::RING_MAGIC` for the layout.
+
+/// Creates (or replaces) the ring buffer `name` with `slots` frames of up to `capacity` bodies.
+/// Returns null on failure; see `Simulation_GetLastError`.
+///
+/// # Safety
+/// `name` must be null or a nul-terminated string; free the returned publisher with `RingPublisher_Destroy`.
+#[cfg(feature = "shm")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn RingPublisher_Create(name: *const c_char, slots: usize, capacity: usize) -> *mut RingPublisher {
+    let name = match unsafe { str_from_c(name, "ring buffer name") } {
+        Ok(name) => name,
+        Err(_) => return std::ptr::null_mut(),
+    };
+
+    match RingPublisher::create(name, slots, capacity) {
+        Ok(publisher) => Box::into_raw(Box::new(publisher)),
+        Err(e) => {
+            fail(SimStatus::IoError, format!("failed to create ring buffer '{name}': {e}"));
+            std::ptr::null_mut()
+        }
+    }
+}
+
+/// Removes the ring buffer and frees the publisher.
+///
+/// # Safety
+/// `publisher` must be null or a live publisher no other thread is using; it is invalid afterwards.
+#[cfg(feature = "shm")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn RingPublisher_Destroy(publisher: *mut RingPublisher) {
+    if !publisher.is_null() {
+        unsafe { drop(Box::from_raw(publisher)) };
+    }
+}
+
+/// Publishes the current bodies as the next frame, truncated to the ring buffer's capacity.
+///
+/// # Safety
+/// `publisher` must be null or a live publisher and `handle` must be null or a live handle used by one thread at a
+/// time.
+#[cfg(feature = "shm")]
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn RingPublisher_Publish(publisher: *mut RingPublisher, handle: *const Simulation) -> SimStatus {
+    let Some(sim) = (unsafe { sim_ref(handle) }) else {
+        return SimStatus::NullHandle;
+    };
+    let Some(publisher) = (unsafe { publisher.as_mut() }) else {
+        return fail(SimStatus::NullHandle, "ring publisher is null");
+    };
+    publisher.publish(sim);
+    SimStatus::Ok
 }
@@ -0,0 +1,264 @@
+use crate::{
+    boundary::{Boundary, BoundaryMode},
+    progress::ProgressOperation,
+    replay,
+    simulation::Simulation,
+    snapshot::Snapshot,
+};
+use ultraviolet::Vec2;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Cursor, Read, Write};
+use std::path::Path;
+
+/// Checkpoint layout (all values little-endian):
+///
+/// | field    | type      |
+/// |----------|-----------|
+/// | magic    | `b"NBCK"` |
+/// | version  | u32       |
+/// | count    | u32       |
+/// | index    | `count` entries of tag `[u8; 4]`, offset u64, compressed length u64 |
+/// | sections | zstd-compressed section data at the indexed offsets |
+///
+/// Sections:
+/// - `SNAP`: a binary snapshot (see `snapshot::MAGIC`).
+/// - `PARM`: time f64, G f32, force exponent f32, boundary min/max 4 x f32, boundary mode u32.
+/// - `RNG_`: the state of the simulation's random number generator, u64.
+/// - `STEP`: the step settings, laid out as in input logs (see `replay::INPUT_LOG_MAGIC`).
+///   Absent in version 1 checkpoints, and without the Morton sort interval in version 2.
+pub const CHECKPOINT_MAGIC: [u8; 4] = *b"NBCK";
+pub const CHECKPOINT_VERSION: u32 = 3;
+
+/// zstd level trading speed for size; checkpoints are written often during long runs.
+const COMPRESSION_LEVEL: i32 = 3;
+
+const INDEX_ENTRY_SIZE: u64 = 4 + 8 + 8;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_params(w: &mut impl Write, sim: &Simulation) -> io::Result<()> {
+    w.write_all(&sim.time.to_le_bytes())?;
+    w.write_all(&sim.quadtree.g.to_le_bytes())?;
+    w.write_all(&sim.quadtree.force_exponent.to_le_bytes())?;
+    for v in [sim.boundary.min.x, sim.boundary.min.y, sim.boundary.max.x, sim.boundary.max.y] {
+        w.write_all(&v.to_le_bytes())?;
+    }
+    w.write_all(&(sim.boundary.mode as u32).to_le_bytes())
+}
+
+/// State stored in the `PARM` section.
+struct Params {
+    time: f64,
+    g: f32,
+    force_exponent: f32,
+    boundary: Boundary,
+}
+
+fn read_params(data: &[u8]) -> io::Result<Params> {
+    if data.len() < 36 {
+        return Err(invalid_data("truncated checkpoint parameters"));
+    }
+    let f = |i: usize| f32::from_le_bytes(data[i..i + 4].try_into().unwrap());
+
+    let mode = match u32::from_le_bytes(data[32..36].try_into().unwrap()) {
+        0 => BoundaryMode::None,
+        1 => BoundaryMode::Periodic,
+        2 => BoundaryMode::Reflective,
+        3 => BoundaryMode::Despawn,
+        _ => return Err(invalid_data("unknown boundary mode in checkpoint")),
+    };
+
+    Ok(Params {
+        time: f64::from_le_bytes(data[0..8].try_into().unwrap()),
+        g: f(8),
+        force_exponent: f(12),
+        boundary: Boundary::new(Vec2::new(f(16), f(20)), Vec2::new(f(24), f(28)), mode),
+    })
+}
+
+impl Simulation {
+    /// Writes a zstd-compressed checkpoint with everything needed to resume the run,
+    /// including the frame counter, the step settings and the state of the simulation's `Rng`.
+    ///
+    /// The file is written next to `path` and renamed into place, so a crash while writing
+    /// never leaves a truncated checkpoint behind.
+    ///
+    /// Progress is reported to the progress callback; if it cancels before the file is written,
+    /// nothing is written and an `ErrorKind::Interrupted` error is returned.
+    pub fn write_checkpoint(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        // Encoding, one compression per section, then writing the file
+        let stages = 6.0;
+        let report = |stage: f32| {
+            if self.report_progress(ProgressOperation::Checkpoint, stage / stages) {
+                Ok(())
+            } else {
+                Err(io::Error::new(io::ErrorKind::Interrupted, "checkpoint cancelled"))
+            }
+        };
+
+        let mut snapshot = Vec::new();
+        Snapshot::capture(self).write(&mut snapshot)?;
+        let mut params = Vec::new();
+        write_params(&mut params, self)?;
+        let rng = self.rng().get_seed().to_le_bytes();
+        let mut settings = Vec::new();
+        replay::write_settings(&mut settings, &self.step_settings())?;
+        report(1.0)?;
+
+        let sections: [(&[u8; 4], &[u8]); 4] =
+            [(b"SNAP", &snapshot), (b"PARM", &params), (b"RNG_", &rng), (b"STEP", &settings)];
+        let mut compressed = Vec::with_capacity(sections.len());
+        for (_, data) in &sections {
+            compressed.push(zstd::encode_all(*data, COMPRESSION_LEVEL)?);
+            report(1.0 + compressed.len() as f32)?;
+        }
+
+        let tmp = path.with_extension("tmp");
+        {
+            let mut w = BufWriter::new(File::create(&tmp)?);
+            w.write_all(&CHECKPOINT_MAGIC)?;
+            w.write_all(&CHECKPOINT_VERSION.to_le_bytes())?;
+            w.write_all(&(sections.len() as u32).to_le_bytes())?;
+
+            let mut offset = 12 + INDEX_ENTRY_SIZE * sections.len() as u64;
+            for ((tag, _), data) in sections.iter().zip(&compressed) {
+                w.write_all(*tag)?;
+                w.write_all(&offset.to_le_bytes())?;
+                w.write_all(&(data.len() as u64).to_le_bytes())?;
+                offset += data.len() as u64;
+            }
+            for data in &compressed {
+                w.write_all(data)?;
+            }
+
+            w.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        }
+        fs::rename(tmp, path)?;
+        // Too late to cancel; the checkpoint is complete
+        self.report_progress(ProgressOperation::Checkpoint, 1.0);
+        Ok(())
+    }
+
+    /// Replaces the current state with a checkpoint written by `write_checkpoint`,
+    /// restoring the step settings and the simulation's `Rng` state as well. Version 1 checkpoints
+    /// have no step settings; the current ones are kept.
+    /// The simulation is left untouched if the file cannot be read.
+    pub fn read_checkpoint(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = Vec::new();
+        File::open(path)?.read_to_end(&mut file)?;
+        let mut r = Cursor::new(&file[..]);
+
+        let mut header = [0u8; 12];
+        r.read_exact(&mut header)?;
+        if header[0..4] != CHECKPOINT_MAGIC {
+            return Err(invalid_data("not an nbody checkpoint"));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if !(1..=CHECKPOINT_VERSION).contains(&version) {
+            return Err(invalid_data("unsupported checkpoint version"));
+        }
+        let count = u32::from_le_bytes(header[8..12].try_into().unwrap());
+
+        let (mut snapshot, mut params, mut rng, mut settings) = (None, None, None, None);
+        for _ in 0..count {
+            let mut entry = [0u8; INDEX_ENTRY_SIZE as usize];
+            r.read_exact(&mut entry)?;
+            let offset = u64::from_le_bytes(entry[4..12].try_into().unwrap()) as usize;
+            let len = u64::from_le_bytes(entry[12..20].try_into().unwrap()) as usize;
+
+            let data = offset
+                .checked_add(len)
+                .and_then(|end| file.get(offset..end))
+                .ok_or_else(|| invalid_data("checkpoint section out of bounds"))?;
+            let data = zstd::decode_all(data)?;
+
+            match &entry[0..4] {
+                b"SNAP" => snapshot = Some(data),
+                b"PARM" => params = Some(data),
+                b"RNG_" => rng = Some(data),
+                b"STEP" => settings = Some(data),
+                // Sections added by later versions are skipped
+                _ => {}
+            }
+        }
+
+        let snapshot = snapshot.ok_or_else(|| invalid_data("checkpoint has no snapshot"))?;
+        let snapshot = Snapshot::read(&mut &snapshot[..])?;
+        let params = read_params(&params.ok_or_else(|| invalid_data("checkpoint has no parameters"))?)?;
+        let rng = match rng.as_deref() {
+            Some(&[a, b, c, d, e, f, g, h]) => Some(u64::from_le_bytes([a, b, c, d, e, f, g, h])),
+            Some(_) => return Err(invalid_data("invalid random number generator state")),
+            None => None,
+        };
+        let settings = settings.map(|data| replay::read_settings(&mut &data[..], version >= 3)).transpose()?;
+
+        snapshot.restore(self);
+        self.time = params.time;
+        self.quadtree.g = params.g;
+        self.quadtree.force_exponent = params.force_exponent;
+        self.boundary = params.boundary;
+        self.collisions.clear();
+        if let Some(seed) = rng {
+            self.seed_rng(seed);
+        }
+        if let Some(settings) = settings {
+            self.set_step_settings(settings);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempFile, sorted_multirate_sim, state_hash};
+
+    #[test]
+    fn checkpoint_resumes_bit_identically() {
+        let mut sim = sorted_multirate_sim(1000);
+        sim.set_gravity_constant(1.5);
+        sim.set_boundary(Boundary::new(
+            Vec2::new(-500.0, -500.0),
+            Vec2::new(500.0, 500.0),
+            BoundaryMode::Periodic,
+        ));
+        for _ in 0..10 {
+            sim.step();
+        }
+
+        let file = TempFile::new("checkpoint.nbck");
+        sim.write_checkpoint(file.path()).unwrap();
+        let mut resumed = Simulation::with_params(0, 1.0, 1.0, 1.0);
+        resumed.read_checkpoint(file.path()).unwrap();
+
+        assert_eq!(resumed.morton_interval(), sim.morton_interval());
+        assert_eq!(resumed.multirate(), sim.multirate());
+        // Spawning draws from the restored random number generator
+        for sim in [&mut sim, &mut resumed] {
+            sim.spawn_cluster(Vec2::new(50.0, 0.0), 100, 5.0);
+            for _ in 0..10 {
+                sim.step();
+            }
+        }
+        assert_eq!(resumed.frame, sim.frame);
+        assert_eq!(resumed.time, sim.time);
+        assert_eq!(resumed.bodies.ids(), sim.bodies.ids());
+        assert_eq!(state_hash(&resumed), state_hash(&sim));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let file = TempFile::new("not-a-checkpoint.nbck");
+        Simulation::with_params(10, 0.1, 1.0, 1.0)
+            .save_snapshot(file.path())
+            .unwrap();
+        let err = Simulation::with_params(0, 0.1, 1.0, 1.0)
+            .read_checkpoint(file.path())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
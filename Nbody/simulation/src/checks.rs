@@ -0,0 +1,56 @@
+use crate::simulation::Simulation;
+
+use rayon::prelude::*;
+use ultraviolet::Vec2;
+
+/// Largest change of the total momentum across the collision phase, relative to the sum of the
+/// momentum magnitudes, that isn't reported; rounding of the impulses in f32 stays well below.
+const MOMENTUM_TOLERANCE: f64 = 1e-4;
+
+/// Total momentum in f64 and the sum of the momentum magnitudes, the scale of its rounding error.
+pub(crate) fn momentum_sums(sim: &Simulation) -> ([f64; 2], f64) {
+    sim.bodies
+        .velocities()
+        .par_iter()
+        .zip(sim.bodies.masses())
+        .map(|(vel, &mass)| {
+            let m = mass as f64;
+            ([m * vel.x as f64, m * vel.y as f64], m * vel.mag() as f64)
+        })
+        .reduce(|| ([0.0; 2], 0.0), |a, b| ([a.0[0] + b.0[0], a.0[1] + b.0[1]], a.1 + b.1))
+}
+
+impl Simulation {
+    /// Panics naming `phase` if a position, velocity or acceleration is NaN or infinite, so the
+    /// phase that introduced the corruption shows up in the message rather than a later one.
+    pub(crate) fn check_finite(&self, phase: &str) {
+        let columns: [(&str, &[Vec2]); 3] = [
+            ("position", self.bodies.positions()),
+            ("velocity", self.bodies.velocities()),
+            ("acceleration", self.bodies.accs()),
+        ];
+        for (name, values) in columns {
+            if let Some(i) = values.par_iter().position_first(|v| !v.x.is_finite() || !v.y.is_finite()) {
+                panic!(
+                    "debug-checks: {phase} left body {i} (id {}) with {name} {:?} at frame {}",
+                    self.bodies.ids()[i],
+                    values[i],
+                    self.frame
+                );
+            }
+        }
+    }
+
+    /// Panics if the collision phase changed the total momentum from `before` (see
+    /// `momentum_sums`); elastic collisions exchange momentum between bodies but conserve it.
+    pub(crate) fn check_momentum(&self, before: ([f64; 2], f64)) {
+        let (after, scale) = momentum_sums(self);
+        let change = ((after[0] - before.0[0]).powi(2) + (after[1] - before.0[1]).powi(2)).sqrt();
+        if change > MOMENTUM_TOLERANCE * scale.max(before.1) {
+            panic!(
+                "debug-checks: collide changed the total momentum by {change} (from {:?} to {after:?}) at frame {}",
+                before.0, self.frame
+            );
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use crate::{
+    body::Body,
+    replay::InputEvent,
+    simulation::{Falloff, ForceMode, Simulation},
+};
+use ultraviolet::Vec2;
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Handle through which other threads (e.g. a UI thread) queue changes to a simulation that is
+/// stepped elsewhere. Cloning it gives another handle to the same queue.
+///
+/// Commands are `InputEvent`s, applied in the order they were pushed at the start of the next
+/// `Simulation::step`, before the bodies move, so they are recorded by an input log like direct
+/// calls. Get the handle of a simulation with `Simulation::command_queue`.
+#[derive(Clone, Debug, Default)]
+pub struct CommandQueue {
+    commands: Arc<Mutex<Vec<InputEvent>>>,
+}
+
+impl CommandQueue {
+    pub fn push(&self, command: InputEvent) {
+        self.lock().push(command);
+    }
+
+    pub fn add_body(&self, body: Body) {
+        self.push(InputEvent::AddBody(body));
+    }
+
+    /// Queues an impulse (change in velocity) for the bodies within `radius` of `pos`
+    /// (see `Simulation::apply_force`).
+    pub fn apply_impulse(&self, pos: Vec2, impulse: Vec2, radius: f32, falloff: Falloff) {
+        self.push(InputEvent::ApplyForce { pos, force: impulse, radius, falloff, mode: ForceMode::Impulse });
+    }
+
+    pub fn set_dt(&self, dt: f32) {
+        self.push(InputEvent::SetDt(dt));
+    }
+
+    /// Number of commands waiting for the next step.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Takes the queued commands, leaving the queue empty.
+    fn take(&self) -> Vec<InputEvent> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<InputEvent>> {
+        // A thread panicking while pushing leaves the queue itself intact
+        self.commands.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Simulation {
+    /// A handle to the queue of commands applied at the start of each step (see `CommandQueue`).
+    pub fn command_queue(&self) -> CommandQueue {
+        self.commands.clone()
+    }
+
+    /// Applies the queued commands now rather than at the start of the next step.
+    pub fn apply_commands(&mut self) {
+        // The queue is released before applying, so other threads can keep pushing meanwhile
+        for command in self.commands.take() {
+            command.apply(self);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{bodies::Bodies, simulation::Simulation};
+use ultraviolet::Vec2;
+
+/// A body held by the user and pulled toward `target` by a critically damped spring, so it
+/// follows the pointer smoothly, without overshooting, while gravity keeps acting on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DragConstraint {
+    /// Stable id of the dragged body (see `Bodies::ids`).
+    pub id: u32,
+    /// Index the body was last seen at; looked up again by id if bodies were reordered.
+    pub index: usize,
+    pub target: Vec2,
+    /// Natural angular frequency of the spring: the body closes most of the distance to the
+    /// target within about 4 / `rate` time units.
+    pub rate: f32,
+}
+
+impl DragConstraint {
+    /// Settles in about 20 steps at `Simulation::DEFAULT_DT`.
+    pub const DEFAULT_RATE: f32 = 4.0;
+
+    /// Sets the velocity of the dragged body to that of the exact solution of the spring over
+    /// `dt`, which stays stable however stiff the spring is compared to the step.
+    /// Returns false if the body no longer exists.
+    fn apply(&mut self, bodies: &mut Bodies, dt: f32) -> bool {
+        if bodies.ids().get(self.index) != Some(&self.id) {
+            match bodies.index_of(self.id) {
+                Some(index) => self.index = index,
+                None => return false,
+            }
+        }
+
+        let bodies = bodies.columns_mut();
+        let (offset, vel) = (bodies.positions[self.index] - self.target, bodies.velocities[self.index]);
+        let decay = (-self.rate * dt).exp();
+        bodies.velocities[self.index] = (vel - (vel + offset * self.rate) * (self.rate * dt)) * decay;
+        true
+    }
+}
+
+impl Simulation {
+    /// Starts dragging body `body` toward `target`, replacing any drag in progress.
+    /// Returns false if there is no such body.
+    pub fn begin_drag(&mut self, body: usize, target: Vec2) -> bool {
+        let Some(&id) = self.bodies.ids().get(body) else {
+            return false;
+        };
+        self.drag = Some(DragConstraint { id, index: body, target, rate: DragConstraint::DEFAULT_RATE });
+        true
+    }
+
+    /// Moves the target of the drag in progress, if any.
+    pub fn update_drag(&mut self, target: Vec2) {
+        if let Some(drag) = &mut self.drag {
+            drag.target = target;
+        }
+    }
+
+    /// Releases the dragged body, which keeps its current velocity.
+    pub fn end_drag(&mut self) {
+        self.drag = None;
+    }
+
+    /// The drag in progress, e.g. to change its `rate`.
+    pub fn drag_mut(&mut self) -> Option<&mut DragConstraint> {
+        self.drag.as_mut()
+    }
+
+    /// Pulls the dragged body toward its target; ends the drag if the body was removed.
+    pub(crate) fn apply_drag(&mut self) {
+        if let Some(drag) = &mut self.drag
+            && !drag.apply(&mut self.bodies, self.dt)
+        {
+            self.drag = None;
+        }
+    }
+}
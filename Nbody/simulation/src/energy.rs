@@ -0,0 +1,95 @@
+/// Total energy of the simulation at one frame, see `EnergyTracker`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnergySample {
+    pub frame: usize,
+    pub time: f64,
+    pub kinetic: f32,
+    pub potential: f32,
+}
+
+impl EnergySample {
+    pub fn total(&self) -> f64 {
+        self.kinetic as f64 + self.potential as f64
+    }
+}
+
+/// Samples the total energy every `interval` frames and keeps the history, so the drift caused by
+/// the integrator, time step or opening angle can be watched while a long run is going.
+///
+/// The potential is evaluated on the tree of the last step, so with `Simulation::set_gravity_interval`
+/// above 1 the interval should be a multiple of it. Each sample costs about as much as a force
+/// pass; keep the interval large for big simulations.
+/// Attach it with `Simulation::set_energy_tracker` to sample after every step.
+#[derive(Clone, Debug)]
+pub struct EnergyTracker {
+    interval: usize,
+    samples: Vec<EnergySample>,
+}
+
+impl EnergyTracker {
+    /// An `interval` of 0 is treated as 1.
+    pub fn new(interval: usize) -> Self {
+        Self {
+            interval: interval.max(1),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Frames between samples.
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    /// Whether a sample should be taken at `frame`.
+    pub fn is_due(&self, frame: usize) -> bool {
+        frame.is_multiple_of(self.interval)
+    }
+
+    pub fn record(&mut self, sample: EnergySample) {
+        self.samples.push(sample);
+    }
+
+    /// Samples taken so far, oldest first.
+    pub fn samples(&self) -> &[EnergySample] {
+        &self.samples
+    }
+
+    /// Forgets the history, so drift is measured from the next sample on.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Relative change of the total energy between the first and the last sample,
+    /// (E - E0) / |E0|, or 0 with fewer than two samples or zero initial energy.
+    pub fn relative_drift(&self) -> f64 {
+        match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last)) => Self::relative(first.total(), last.total()),
+            _ => 0.0,
+        }
+    }
+
+    /// Relative drift per unit of simulated time, as the least-squares slope of the relative
+    /// energy error over all samples, which is less sensitive to the oscillations of close
+    /// encounters than the end points alone. 0 with fewer than two samples.
+    pub fn drift_rate(&self) -> f64 {
+        let Some(first) = self.samples.first() else {
+            return 0.0;
+        };
+        let e0 = first.total();
+        let n = self.samples.len() as f64;
+        let mean_t = self.samples.iter().map(|s| s.time).sum::<f64>() / n;
+        let mean_e = self.samples.iter().map(|s| Self::relative(e0, s.total())).sum::<f64>() / n;
+
+        let (mut cov, mut var) = (0.0, 0.0);
+        for sample in &self.samples {
+            let dt = sample.time - mean_t;
+            cov += dt * (Self::relative(e0, sample.total()) - mean_e);
+            var += dt * dt;
+        }
+        if var > 0.0 { cov / var } else { 0.0 }
+    }
+
+    fn relative(e0: f64, e: f64) -> f64 {
+        if e0 == 0.0 { 0.0 } else { (e - e0) / e0.abs() }
+    }
+}
@@ -0,0 +1,47 @@
+use ultraviolet::Vec2;
+
+/// A fixed background potential acting on every body in addition to the tree forces.
+/// Bodies do not act back on it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExternalPotential {
+    /// Softened point mass fixed at `pos`, scaled by the simulation's gravitational constant.
+    PointMass { pos: Vec2, mass: f32 },
+    /// Uniform field with constant acceleration `acc`.
+    Uniform { acc: Vec2 },
+    /// Isotropic harmonic well around `center`: a = -omega^2 (x - center).
+    Harmonic { center: Vec2, omega: f32 },
+}
+
+impl ExternalPotential {
+    /// Acceleration at `pos`, using the gravitational constant `g` and softening `e_sq` for point masses.
+    #[inline(always)]
+    pub fn acc(&self, pos: Vec2, g: f32, e_sq: f32) -> Vec2 {
+        match *self {
+            Self::PointMass { pos: p, mass } => {
+                let d = p - pos;
+                let r_sq = d.mag_sq() + e_sq;
+                d * (g * mass / (r_sq * r_sq.sqrt()))
+            }
+            Self::Uniform { acc } => acc,
+            Self::Harmonic { center, omega } => (center - pos) * (omega * omega),
+        }
+    }
+
+    /// Potential per unit mass at `pos`, consistent with `acc`.
+    pub fn potential(&self, pos: Vec2, g: f32, e_sq: f32) -> f32 {
+        match *self {
+            Self::PointMass { pos: p, mass } => -g * mass / ((p - pos).mag_sq() + e_sq).sqrt(),
+            Self::Uniform { acc } => -acc.dot(pos),
+            Self::Harmonic { center, omega } => 0.5 * omega * omega * (pos - center).mag_sq(),
+        }
+    }
+}
+
+/// Sum of the accelerations of all `potentials` at `pos`.
+#[inline(always)]
+pub fn total_acc(potentials: &[ExternalPotential], pos: Vec2, g: f32, e_sq: f32) -> Vec2 {
+    potentials
+        .iter()
+        .fold(Vec2::zero(), |acc, potential| acc + potential.acc(pos, g, e_sq))
+}
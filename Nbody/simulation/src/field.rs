@@ -0,0 +1,105 @@
+use crate::{external::ExternalPotential, quadtree::Quadtree, simulation::Simulation};
+
+use rayon::prelude::*;
+use ultraviolet::Vec2;
+
+/// Quantity sampled by `Simulation::sample_field`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Gravitational potential, including external potentials.
+    Potential = 0,
+    /// Magnitude of the acceleration, including external potentials, gravity wells and timeline
+    /// fields (see `Simulation::acceleration_at`).
+    Acceleration = 1,
+    /// Surface density (mass per unit area), smoothed over about one grid cell.
+    Density = 2,
+}
+
+/// Regular grid of cells that `Simulation::sample_field` evaluates a field at, one value
+/// per cell center, row-major with the top (largest y) row first like `render_density`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldGrid {
+    pub center: Vec2,
+    /// Width and height of a cell in world units.
+    pub cell_size: Vec2,
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+impl FieldGrid {
+    /// A zeroed grid of `width` x `height` square cells of `cell_size` centered on `center`.
+    pub fn new(center: Vec2, cell_size: f32, width: usize, height: usize) -> Self {
+        Self {
+            center,
+            cell_size: Vec2::broadcast(cell_size),
+            width,
+            height,
+            values: vec![0.0; width * height],
+        }
+    }
+
+    /// A zeroed grid of `width` x `height` cells exactly covering the rectangle from `min` to `max`.
+    pub fn from_bounds(min: Vec2, max: Vec2, width: usize, height: usize) -> Self {
+        Self {
+            center: (min + max) * 0.5,
+            cell_size: Vec2::new((max.x - min.x) / width.max(1) as f32, (max.y - min.y) / height.max(1) as f32),
+            width,
+            height,
+            values: vec![0.0; width * height],
+        }
+    }
+
+    /// World position of the center of the cell in `row` and `col`.
+    pub fn cell_center(&self, row: usize, col: usize) -> Vec2 {
+        let offset = Vec2::new(col as f32 + 0.5 - 0.5 * self.width as f32, 0.5 * self.height as f32 - row as f32 - 0.5);
+        self.center + offset * self.cell_size
+    }
+}
+
+impl Simulation {
+    /// Evaluates `kind` at each cell center of `grid` on the quadtree built during the last step,
+    /// in parallel over the rows. All values are 0 if no tree has been built yet.
+    pub fn sample_field(&self, grid: &mut FieldGrid, kind: FieldKind) {
+        grid.values.resize(grid.width * grid.height, 0.0);
+        if self.quadtree.nodes.is_empty() || grid.width == 0 {
+            grid.values.fill(0.0);
+            return;
+        }
+
+        let (quadtree, external) = (&self.quadtree, &self.external[..]);
+        let background = self.background();
+        let layout = FieldGrid { values: Vec::new(), ..*grid };
+        let resolution = layout.cell_size.x.max(layout.cell_size.y);
+        grid.values.par_chunks_mut(grid.width).enumerate().for_each(|(row, values)| {
+            for (col, value) in values.iter_mut().enumerate() {
+                let pos = layout.cell_center(row, col);
+                *value = match kind {
+                    FieldKind::Potential => potential_at(quadtree, external, pos),
+                    FieldKind::Acceleration => (quadtree.acc(pos) + background.acc(pos)).mag(),
+                    FieldKind::Density => quadtree.density(pos, resolution),
+                };
+            }
+        });
+    }
+
+    /// Acceleration a massless test particle at `pos` would get on the tree from the last step:
+    /// the gravity of the bodies plus the external potentials, gravity wells and timeline fields,
+    /// as the bodies got it. Hosts can drive objects outside the simulation (spacecraft, camera
+    /// effects) with it. Only the background forces act before the first step.
+    pub fn acceleration_at(&self, pos: Vec2) -> Vec2 {
+        self.quadtree.acc(pos) + self.background().acc(pos)
+    }
+
+    /// Gravitational potential at `pos` on the tree from the last step, including external
+    /// potentials.
+    pub fn potential_at(&self, pos: Vec2) -> f32 {
+        potential_at(&self.quadtree, &self.external, pos)
+    }
+}
+
+fn potential_at(quadtree: &Quadtree, external: &[ExternalPotential], pos: Vec2) -> f32 {
+    let phi: f32 = external.iter().map(|p| p.potential(pos, quadtree.g, quadtree.e_sq)).sum();
+    quadtree.potential(pos) + phi
+}
@@ -0,0 +1,45 @@
+use crate::{bodies::BodiesMut, quadtree::Quadtree, simulation::Simulation};
+
+use std::sync::Arc;
+
+/// User-defined physics (magnetic fields, wind, drag...) run by `Simulation::step` after each
+/// gravity pass, so it can be added without changing `attract`.
+///
+/// `bodies.accs` holds the fresh gravitational accelerations when `apply` runs: add to them for
+/// a force integrated together with gravity, or change `bodies.velocities` directly for an
+/// impulse over `dt`. `tree` is the quadtree the accelerations were computed on, e.g. for
+/// neighbour queries. Forces run in the order they were added.
+///
+/// Any `Fn(BodiesMut, &Quadtree, f32)` closure is a `Force`.
+pub trait Force: Send + Sync {
+    fn apply(&self, bodies: BodiesMut<'_>, tree: &Quadtree, dt: f32);
+}
+
+impl<F> Force for F
+where
+    F: Fn(BodiesMut<'_>, &Quadtree, f32) + Send + Sync,
+{
+    fn apply(&self, bodies: BodiesMut<'_>, tree: &Quadtree, dt: f32) {
+        self(bodies, tree, dt)
+    }
+}
+
+impl Simulation {
+    /// Registers a force run after every gravity pass (see `Force`). Gravity passes follow
+    /// `set_gravity_interval`; with `set_multirate`, bodies skipped on a frame keep their previous
+    /// acceleration, including what forces added to it then.
+    pub fn add_force(&mut self, force: impl Force + 'static) {
+        self.forces.push(Arc::new(force));
+    }
+
+    /// Unregisters all forces.
+    pub fn clear_forces(&mut self) {
+        self.forces.clear();
+    }
+
+    pub(crate) fn apply_forces(&mut self) {
+        for force in &self.forces {
+            force.apply(self.bodies.columns_mut(), &self.quadtree, self.dt);
+        }
+    }
+}
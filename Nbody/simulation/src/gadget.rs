@@ -0,0 +1,274 @@
+use crate::{body::Body, simulation::Simulation, utils};
+use ultraviolet::Vec2;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Size in bytes of the Gadget-2 header block.
+const HEADER_SIZE: usize = 256;
+
+/// Number of particle types in a Gadget-2 snapshot.
+const TYPES: usize = 6;
+
+/// Particle type bodies are written as (1 = halo / collisionless).
+const BODY_TYPE: usize = 1;
+
+/// Contents of a single-file Gadget-2 snapshot (SnapFormat = 1, little-endian).
+///
+/// The simulation is two-dimensional: z coordinates are dropped on reading and written as 0.
+/// All particle types are read as bodies, in file order; bodies are written as type 1.
+/// Radii are not stored by Gadget and are derived from the masses on reading.
+#[derive(Clone, Debug)]
+pub struct GadgetSnapshot {
+    /// Simulation time (scale factor for cosmological runs).
+    pub time: f64,
+    /// Periodic box size, 0 if not periodic.
+    pub box_size: f64,
+    pub bodies: Vec<Body>,
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Reads one Fortran unformatted record: a length marker, the data and the same marker again.
+fn read_record(r: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut marker = [0u8; 4];
+    r.read_exact(&mut marker)?;
+    let len = u32::from_le_bytes(marker) as usize;
+
+    let mut data = vec![0u8; len];
+    r.read_exact(&mut data)?;
+
+    let mut end = [0u8; 4];
+    r.read_exact(&mut end)?;
+    if end != marker {
+        return Err(invalid_data("mismatched Gadget record markers"));
+    }
+    Ok(data)
+}
+
+fn write_record(w: &mut impl Write, data: &[u8]) -> io::Result<()> {
+    let marker = u32::try_from(data.len())
+        .map_err(|_| invalid_data("Gadget record exceeds 4 GiB"))?
+        .to_le_bytes();
+    w.write_all(&marker)?;
+    w.write_all(data)?;
+    w.write_all(&marker)
+}
+
+fn f32_at(data: &[u8], i: usize) -> f32 {
+    f32::from_le_bytes(data[i * 4..i * 4 + 4].try_into().unwrap())
+}
+
+fn f64_at(data: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+impl GadgetSnapshot {
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let header = read_record(r)?;
+        if header.len() != HEADER_SIZE {
+            return Err(invalid_data("not a little-endian Gadget-2 snapshot"));
+        }
+
+        let npart: [usize; TYPES] =
+            std::array::from_fn(|t| u32::from_le_bytes(header[t * 4..t * 4 + 4].try_into().unwrap()) as usize);
+        let masstable: [f64; TYPES] = std::array::from_fn(|t| f64_at(&header, 24 + t * 8));
+        let time = f64_at(&header, 72);
+        let box_size = f64_at(&header, 128);
+        let n: usize = npart.iter().sum();
+
+        let pos = read_record(r)?;
+        let vel = read_record(r)?;
+        // Particle IDs are not needed; bodies are identified by index
+        let _ids = read_record(r)?;
+        if pos.len() != n * 12 || vel.len() != n * 12 {
+            return Err(invalid_data("Gadget position/velocity blocks don't match the header"));
+        }
+
+        // Masses are only stored for types without a fixed entry in the mass table
+        let variable = (0..TYPES).filter(|&t| npart[t] > 0 && masstable[t] == 0.0).map(|t| npart[t]).sum::<usize>();
+        let masses = if variable > 0 { read_record(r)? } else { Vec::new() };
+        if masses.len() != variable * 4 {
+            return Err(invalid_data("Gadget mass block doesn't match the header"));
+        }
+
+        let mut bodies = Vec::with_capacity(n);
+        let mut next_mass = 0;
+        for (t, &count) in npart.iter().enumerate() {
+            for _ in 0..count {
+                let i = bodies.len();
+                let mass = if masstable[t] == 0.0 {
+                    next_mass += 1;
+                    f32_at(&masses, next_mass - 1)
+                } else {
+                    masstable[t] as f32
+                };
+
+                let pos = Vec2::new(f32_at(&pos, i * 3), f32_at(&pos, i * 3 + 1));
+                let vel = Vec2::new(f32_at(&vel, i * 3), f32_at(&vel, i * 3 + 1));
+                bodies.push(Body::new(pos, vel, mass, utils::radius_for_mass(mass)));
+            }
+        }
+
+        Ok(Self { time, box_size, bodies })
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        let n = u32::try_from(self.bodies.len()).map_err(|_| invalid_data("too many bodies for Gadget-2"))?;
+
+        // A shared mass goes into the mass table instead of a mass block
+        let shared_mass = match self.bodies.first() {
+            Some(first) if self.bodies.iter().all(|body| body.mass == first.mass) => first.mass as f64,
+            _ => 0.0,
+        };
+
+        let mut header = Vec::with_capacity(HEADER_SIZE);
+        let mut npart = [0u32; TYPES];
+        npart[BODY_TYPE] = n;
+        let mut masstable = [0f64; TYPES];
+        masstable[BODY_TYPE] = shared_mass;
+
+        npart.iter().for_each(|v| header.extend(v.to_le_bytes()));
+        masstable.iter().for_each(|v| header.extend(v.to_le_bytes()));
+        header.extend(self.time.to_le_bytes());
+        header.extend(0f64.to_le_bytes()); // redshift
+        header.extend([0i32; 2].iter().flat_map(|v| v.to_le_bytes())); // flag_sfr, flag_feedback
+        npart.iter().for_each(|v| header.extend(v.to_le_bytes())); // npartTotal
+        header.extend(0i32.to_le_bytes()); // flag_cooling
+        header.extend(1i32.to_le_bytes()); // num_files
+        header.extend(self.box_size.to_le_bytes());
+        header.extend([0f64; 3].iter().flat_map(|v| v.to_le_bytes())); // Omega0, OmegaLambda, HubbleParam
+        header.resize(HEADER_SIZE, 0);
+        write_record(w, &header)?;
+
+        let xyz = |v: Vec2| [v.x, v.y, 0.0].into_iter().flat_map(f32::to_le_bytes);
+        let pos: Vec<u8> = self.bodies.iter().flat_map(|body| xyz(body.pos)).collect();
+        let vel: Vec<u8> = self.bodies.iter().flat_map(|body| xyz(body.vel)).collect();
+        let ids: Vec<u8> = (1..=n).flat_map(u32::to_le_bytes).collect();
+        write_record(w, &pos)?;
+        write_record(w, &vel)?;
+        write_record(w, &ids)?;
+
+        if n > 0 && shared_mass == 0.0 {
+            let masses: Vec<u8> = self.bodies.iter().flat_map(|body| body.mass.to_le_bytes()).collect();
+            write_record(w, &masses)?;
+        }
+        Ok(())
+    }
+}
+
+impl Simulation {
+    /// Saves the bodies and time to a Gadget-2 snapshot file (see `GadgetSnapshot`).
+    pub fn save_gadget(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let snapshot = GadgetSnapshot {
+            time: self.time,
+            box_size: 0.0,
+            bodies: self.bodies.to_vec(),
+        };
+
+        let mut w = BufWriter::new(File::create(path)?);
+        snapshot.write(&mut w)?;
+        w.flush()
+    }
+
+    /// Replaces all bodies with those in a Gadget-2 snapshot file and resets the frame counter.
+    /// The simulation time is taken from the file.
+    /// The simulation is left untouched if the file cannot be read.
+    pub fn load_gadget(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut r = BufReader::new(File::open(path)?);
+        let snapshot = GadgetSnapshot::read(&mut r)?;
+        self.reset_with_bodies(snapshot.bodies);
+        self.time = snapshot.time;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempFile, bodies_bits};
+
+    /// `bodies` as read back from Gadget: without accelerations, with radii derived from the masses.
+    fn as_read(bodies: &[Body]) -> Vec<[u32; 8]> {
+        let bodies: Vec<Body> = bodies
+            .iter()
+            .map(|body| Body::new(body.pos, body.vel, body.mass, utils::radius_for_mass(body.mass)))
+            .collect();
+        bodies_bits(&bodies)
+    }
+
+    fn round_trip(snapshot: &GadgetSnapshot) -> GadgetSnapshot {
+        let mut data = Vec::new();
+        snapshot.write(&mut data).unwrap();
+        GadgetSnapshot::read(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn gadget_round_trips_individual_masses() {
+        let snapshot = GadgetSnapshot {
+            time: 12.5,
+            box_size: 400.0,
+            bodies: utils::uniform_disc(100),
+        };
+
+        let read = round_trip(&snapshot);
+        assert_eq!(read.time, snapshot.time);
+        assert_eq!(read.box_size, snapshot.box_size);
+        assert_eq!(bodies_bits(&read.bodies), as_read(&snapshot.bodies));
+    }
+
+    #[test]
+    fn gadget_round_trips_shared_mass() {
+        let bodies: Vec<Body> = (0..10)
+            .map(|i| Body::new(Vec2::new(i as f32, -(i as f32)), Vec2::new(0.5, i as f32), 3.0, 1.0))
+            .collect();
+        let snapshot = GadgetSnapshot {
+            time: 0.0,
+            box_size: 0.0,
+            bodies,
+        };
+
+        let mut data = Vec::new();
+        snapshot.write(&mut data).unwrap();
+        // Header, positions, velocities and ids; the mass is in the header's mass table
+        let record = |len: usize| len + 8;
+        assert_eq!(data.len(), record(HEADER_SIZE) + 2 * record(10 * 12) + record(10 * 4));
+        assert_eq!(bodies_bits(&round_trip(&snapshot).bodies), as_read(&snapshot.bodies));
+    }
+
+    #[test]
+    fn gadget_file_restores_bodies_and_time() {
+        let mut sim = Simulation::with_params(50, 0.1, 1.0, 1.0);
+        for _ in 0..3 {
+            sim.step();
+        }
+
+        let file = TempFile::new("snapshot.gadget");
+        sim.save_gadget(file.path()).unwrap();
+        let mut loaded = Simulation::with_params(0, 0.1, 1.0, 1.0);
+        loaded.load_gadget(file.path()).unwrap();
+
+        assert_eq!(loaded.time, sim.time);
+        assert_eq!(bodies_bits(&loaded.bodies.to_vec()), as_read(&sim.bodies.to_vec()));
+    }
+
+    #[test]
+    fn rejects_mismatched_record_markers() {
+        let mut data = Vec::new();
+        GadgetSnapshot {
+            time: 0.0,
+            box_size: 0.0,
+            bodies: Vec::new(),
+        }
+        .write(&mut data)
+        .unwrap();
+        data[4 + HEADER_SIZE] ^= 1;
+        assert_eq!(
+            GadgetSnapshot::read(&mut &data[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{
+    body::Body,
+    generators::GENERATORS,
+    simulation::{Falloff, ForceMode, Simulation},
+};
+use godot::prelude::*;
+use ultraviolet::Vec2;
+
+struct NBodyExtension;
+
+#[gdextension]
+unsafe impl ExtensionLibrary for NBodyExtension {}
+
+fn to_vec2(v: Vector2) -> Vec2 {
+    Vec2::new(v.x, v.y)
+}
+
+fn to_vector2(v: Vec2) -> Vector2 {
+    Vector2::new(v.x, v.y)
+}
+
+/// Godot node wrapping `Simulation`, registered as `NBodySimulation`.
+///
+/// Body data is returned as packed array copies, since the body columns may be reallocated by
+/// later calls. With `auto_step` enabled the node advances `steps_per_frame` steps every physics
+/// frame; otherwise call `step` from a script.
+#[derive(GodotClass)]
+#[class(base = Node)]
+pub struct NBodySimulation {
+    sim: Simulation,
+    #[export]
+    auto_step: bool,
+    #[export]
+    steps_per_frame: i32,
+    #[var(get = get_dt, set = set_dt)]
+    #[export]
+    dt: PhantomVar<f32>,
+    #[var(get = get_gravity_constant, set = set_gravity_constant)]
+    #[export]
+    gravity_constant: PhantomVar<f32>,
+    #[var(get = get_theta, set = set_theta)]
+    #[export]
+    theta: PhantomVar<f32>,
+    #[var(get = get_epsilon, set = set_epsilon)]
+    #[export]
+    epsilon: PhantomVar<f32>,
+    #[var(get = get_use_rayon, set = set_use_rayon)]
+    #[export]
+    use_rayon: PhantomVar<bool>,
+    base: Base<Node>,
+}
+
+#[godot_api]
+impl INode for NBodySimulation {
+    fn init(base: Base<Node>) -> Self {
+        Self {
+            sim: Simulation::with_bodies(
+                Vec::new(),
+                Simulation::DEFAULT_DT,
+                Simulation::DEFAULT_THETA,
+                Simulation::DEFAULT_EPSILON,
+            ),
+            auto_step: false,
+            steps_per_frame: 1,
+            dt: PhantomVar::default(),
+            gravity_constant: PhantomVar::default(),
+            theta: PhantomVar::default(),
+            epsilon: PhantomVar::default(),
+            use_rayon: PhantomVar::default(),
+            base,
+        }
+    }
+
+    fn physics_process(&mut self, _delta: f64) {
+        if self.auto_step {
+            self.sim.step_n(self.steps_per_frame.max(0) as usize);
+        }
+    }
+}
+
+#[godot_api]
+impl NBodySimulation {
+    /// Advances the simulation by `n` steps.
+    #[func]
+    fn step(&mut self, n: i64) {
+        self.sim.step_n(n.max(0) as usize);
+    }
+
+    /// Replaces all bodies with a uniform disc of `n` bodies and resets time.
+    #[func]
+    fn reset(&mut self, n: i64) {
+        self.sim.reset(n.max(0) as usize);
+    }
+
+    /// Replaces all bodies with those of a named generator (see `generator_names`) and its JSON
+    /// parameters. Returns false and logs an error if the name or parameters are invalid.
+    #[func]
+    fn reset_with_generator(&mut self, name: GString, params_json: GString) -> bool {
+        match self.sim.reset_with_generator(&name.to_string(), &params_json.to_string()) {
+            Ok(()) => true,
+            Err(e) => {
+                godot_error!("{e}");
+                false
+            }
+        }
+    }
+
+    #[func]
+    fn add_body(&mut self, position: Vector2, velocity: Vector2, mass: f32, radius: f32) {
+        self.sim.add_body(Body::new(to_vec2(position), to_vec2(velocity), mass, radius));
+    }
+
+    #[func]
+    fn spawn_disc(&mut self, center: Vector2, n: i64, radius: f32, spin: f32) {
+        self.sim.spawn_disc(to_vec2(center), n.max(0) as usize, radius, spin);
+    }
+
+    #[func]
+    fn spawn_cluster(&mut self, center: Vector2, n: i64, radius: f32) {
+        self.sim.spawn_cluster(to_vec2(center), n.max(0) as usize, radius);
+    }
+
+    /// Adds an impulse to every body within `radius` of `position`, fading linearly to the edge.
+    #[func]
+    fn apply_impulse(&mut self, position: Vector2, impulse: Vector2, radius: f32) {
+        self.sim.apply_force(to_vec2(position), to_vec2(impulse), radius, Falloff::Linear, ForceMode::Impulse);
+    }
+
+    #[func]
+    fn get_body_count(&self) -> i64 {
+        self.sim.bodies.len() as i64
+    }
+
+    #[func]
+    fn get_frame(&self) -> i64 {
+        self.sim.frame as i64
+    }
+
+    #[func]
+    fn get_time(&self) -> f64 {
+        self.sim.time
+    }
+
+    #[func]
+    fn get_positions(&self) -> PackedVector2Array {
+        let positions: Vec<Vector2> = self.sim.bodies.positions().iter().map(|&pos| to_vector2(pos)).collect();
+        PackedVector2Array::from(&positions[..])
+    }
+
+    #[func]
+    fn get_velocities(&self) -> PackedVector2Array {
+        let velocities: Vec<Vector2> = self.sim.bodies.velocities().iter().map(|&vel| to_vector2(vel)).collect();
+        PackedVector2Array::from(&velocities[..])
+    }
+
+    #[func]
+    fn get_masses(&self) -> PackedFloat32Array {
+        PackedFloat32Array::from(self.sim.bodies.masses())
+    }
+
+    #[func]
+    fn get_radii(&self) -> PackedFloat32Array {
+        PackedFloat32Array::from(self.sim.bodies.radii())
+    }
+
+    /// Overwrites the body positions. Returns false if the array length doesn't match the body count.
+    #[func]
+    fn set_positions(&mut self, positions: PackedVector2Array) -> bool {
+        if positions.len() != self.sim.bodies.len() {
+            return false;
+        }
+        for (pos, &value) in self.sim.bodies.positions_mut().iter_mut().zip(positions.as_slice()) {
+            *pos = to_vec2(value);
+        }
+        true
+    }
+
+    #[func]
+    fn kinetic_energy(&self) -> f32 {
+        self.sim.kinetic_energy()
+    }
+
+    /// Names of the available generators.
+    #[func]
+    fn generator_names() -> PackedStringArray {
+        GENERATORS.iter().map(|generator| GString::from(generator.name)).collect()
+    }
+
+    #[func]
+    fn get_dt(&self) -> f32 {
+        self.sim.dt
+    }
+
+    #[func]
+    fn set_dt(&mut self, dt: f32) {
+        self.sim.set_dt(dt);
+    }
+
+    #[func]
+    fn get_gravity_constant(&self) -> f32 {
+        self.sim.quadtree.g
+    }
+
+    #[func]
+    fn set_gravity_constant(&mut self, g: f32) {
+        self.sim.set_gravity_constant(g);
+    }
+
+    /// Barnes-Hut opening angle.
+    #[func]
+    fn get_theta(&self) -> f32 {
+        self.sim.quadtree.t_sq.sqrt()
+    }
+
+    #[func]
+    fn set_theta(&mut self, theta: f32) {
+        self.sim.quadtree.t_sq = theta * theta;
+    }
+
+    /// Gravitational softening length.
+    #[func]
+    fn get_epsilon(&self) -> f32 {
+        self.sim.quadtree.e_sq.sqrt()
+    }
+
+    #[func]
+    fn set_epsilon(&mut self, epsilon: f32) {
+        self.sim.quadtree.e_sq = epsilon * epsilon;
+    }
+
+    #[func]
+    fn get_use_rayon(&self) -> bool {
+        self.sim.use_rayon
+    }
+
+    #[func]
+    fn set_use_rayon(&mut self, use_rayon: bool) {
+        self.sim.set_use_rayon(use_rayon);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{
+    body::Body,
+    rng::Rng,
+    utils::{self, DiscParams, MassFunction},
+};
+use serde_json::Value;
+use ultraviolet::Vec2;
+
+use std::fmt;
+
+/// Builds the bodies of a generator, see `Generator::generate`.
+pub type GenerateFn = fn(&Params, f32, &mut dyn Rng) -> Result<Vec<Body>, GeneratorError>;
+
+/// A named initial-condition generator that frontends can list and invoke by name.
+#[derive(Clone, Copy, Debug)]
+pub struct Generator {
+    pub name: &'static str,
+    /// Parameters read from the JSON object, with their defaults.
+    pub params: &'static str,
+    /// Gravitational constant the preset is defined in, if it needs a specific one.
+    pub gravity_constant: Option<f32>,
+    /// Builds the bodies from the parameters, the simulation's gravitational constant and its
+    /// random number generator, or fails with `GeneratorError::InvalidParams`.
+    pub generate: GenerateFn,
+}
+
+/// Error returned when a generator cannot be found or its parameters are malformed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeneratorError {
+    UnknownGenerator(String),
+    InvalidParams(String),
+    /// The progress callback cancelled the generation.
+    Cancelled,
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownGenerator(name) => write!(f, "unknown generator '{name}'"),
+            Self::InvalidParams(msg) => write!(f, "invalid generator parameters: {msg}"),
+            Self::Cancelled => write!(f, "generation cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+/// Generator parameters parsed from a JSON object. Missing keys fall back to defaults.
+#[derive(Clone, Debug, Default)]
+pub struct Params(serde_json::Map<String, Value>);
+
+impl Params {
+    /// Parses a JSON object. An empty string is treated as `{}`.
+    pub fn from_json(json: &str) -> Result<Self, GeneratorError> {
+        if json.trim().is_empty() {
+            return Ok(Self::default());
+        }
+
+        match serde_json::from_str(json) {
+            Ok(Value::Object(map)) => Ok(Self(map)),
+            Ok(_) => Err(GeneratorError::InvalidParams("expected a JSON object".into())),
+            Err(e) => Err(GeneratorError::InvalidParams(e.to_string())),
+        }
+    }
+
+    pub fn f32(&self, key: &str, default: f32) -> f32 {
+        self.0.get(key).and_then(Value::as_f64).map_or(default, |v| v as f32)
+    }
+
+    pub fn usize(&self, key: &str, default: usize) -> usize {
+        self.0.get(key).and_then(Value::as_u64).map_or(default, |v| v as usize)
+    }
+
+    pub fn str(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(Value::as_str)
+    }
+
+    pub fn vec2(&self, x: &str, y: &str) -> Vec2 {
+        Vec2::new(self.f32(x, 0.0), self.f32(y, 0.0))
+    }
+}
+
+impl From<serde_json::Map<String, Value>> for Params {
+    fn from(map: serde_json::Map<String, Value>) -> Self {
+        Self(map)
+    }
+}
+
+/// All registered generators.
+pub const GENERATORS: &[Generator] = &[
+    Generator {
+        name: "uniform_disc",
+        params: "n=10000, seed=0, inner_radius=25, outer_radius=sqrt(n)*5, central_mass=1e6, \
+                 min_mass=1 (0.1 with an imf), max_mass=1 (100 with an imf), imf=none|salpeter|kroupa, \
+                 spin=1, eccentricity_spread=0, temperature=0",
+        gravity_constant: None,
+        generate: |p, _, _| {
+            let n = p.usize("n", 10_000);
+            let defaults = DiscParams::default();
+            let imf = p.str("imf").filter(|&imf| imf != "none");
+            // A mass function needs a range to spread the masses over
+            let (min_mass, max_mass) = match imf {
+                Some(_) => (p.f32("min_mass", 0.1), p.f32("max_mass", 100.0)),
+                None => (p.f32("min_mass", 1.0), p.f32("max_mass", 1.0)),
+            };
+            let mass_function = match imf {
+                Some("salpeter") => Some(MassFunction::Salpeter { min: min_mass, max: max_mass }),
+                Some("kroupa") => Some(MassFunction::Kroupa { min: min_mass, max: max_mass }),
+                Some(imf) => return Err(GeneratorError::InvalidParams(format!("unknown imf '{imf}'"))),
+                None => None,
+            };
+            if let Some(mass_function) = &mass_function {
+                mass_function.validate().map_err(GeneratorError::InvalidParams)?;
+            }
+            let params = DiscParams {
+                seed: p.usize("seed", 0) as u64,
+                inner_radius: p.f32("inner_radius", defaults.inner_radius),
+                outer_radius: Some(p.f32("outer_radius", (n as f32).sqrt() * 5.0)),
+                central_mass: p.f32("central_mass", defaults.central_mass),
+                mass_range: (min_mass, max_mass),
+                mass_function,
+                spin: p.f32("spin", defaults.spin),
+                eccentricity_spread: p.f32("eccentricity_spread", defaults.eccentricity_spread),
+                temperature: p.f32("temperature", defaults.temperature),
+            };
+            Ok(utils::uniform_disc_with(n, &params))
+        },
+    },
+    Generator {
+        name: "disc",
+        params: "n=10000, radius=500, spin=1, x=0, y=0, temperature=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let center = p.vec2("x", "y");
+            let (radius, spin) = (p.f32("radius", 500.0), p.f32("spin", 1.0));
+            let mut bodies = utils::disc_at(center, p.usize("n", 10_000), radius, spin, g, rng);
+            utils::add_velocity_dispersion(&mut bodies, p.f32("temperature", 0.0), rng);
+            Ok(bodies)
+        },
+    },
+    Generator {
+        name: "two_galaxies",
+        params: "n=10000, radius=300, separation=1000, speed=1, temperature=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let n = p.usize("n", 10_000);
+            let radius = p.f32("radius", 300.0);
+            let offset = Vec2::new(p.f32("separation", 1000.0) * 0.5, 0.0);
+            let velocity = Vec2::new(0.0, p.f32("speed", 1.0));
+
+            let temperature = p.f32("temperature", 0.0);
+
+            let mut bodies = utils::disc_at(-offset, n / 2, radius, 1.0, g, rng);
+            utils::add_velocity_dispersion(&mut bodies, temperature, rng);
+            bodies.iter_mut().for_each(|body| body.vel += velocity);
+            let mut other = utils::disc_at(offset, n - n / 2, radius, -1.0, g, rng);
+            utils::add_velocity_dispersion(&mut other, temperature, rng);
+            other.iter_mut().for_each(|body| body.vel -= velocity);
+            bodies.extend(other);
+            Ok(bodies)
+        },
+    },
+    Generator {
+        name: "spiral_galaxy",
+        params: "n=10000, arms=2, pitch=0.3, amplitude=0.5, temperature=0",
+        gravity_constant: None,
+        generate: |p, _, rng| {
+            let arms = p.usize("arms", 2) as u32;
+            let (pitch, amplitude) = (p.f32("pitch", 0.3), p.f32("amplitude", 0.5));
+            let mut bodies = utils::spiral_galaxy(p.usize("n", 10_000), arms, pitch, amplitude, rng);
+            utils::add_velocity_dispersion(&mut bodies, p.f32("temperature", 0.0), rng);
+            Ok(bodies)
+        },
+    },
+    Generator {
+        name: "plummer",
+        params: "n=10000, radius=100, x=0, y=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            Ok(utils::plummer_at(p.vec2("x", "y"), p.usize("n", 10_000), p.f32("radius", 100.0), g, rng))
+        },
+    },
+    Generator {
+        name: "king",
+        params: "n=10000, core_radius=20, concentration=1.5, x=0, y=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let center = p.vec2("x", "y");
+            let concentration = p.f32("concentration", 1.5);
+            Ok(utils::king_at(center, p.usize("n", 10_000), p.f32("core_radius", 20.0), concentration, g, rng))
+        },
+    },
+    Generator {
+        name: "kuzmin",
+        params: "n=10000, scale=100, mass=10000, x=0, y=0, temperature=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let center = p.vec2("x", "y");
+            let mut bodies =
+                utils::kuzmin_disc_at(center, p.usize("n", 10_000), p.f32("scale", 100.0), p.f32("mass", 1e4), g, rng);
+            utils::add_velocity_dispersion(&mut bodies, p.f32("temperature", 0.0), rng);
+            Ok(bodies)
+        },
+    },
+    Generator {
+        name: "exponential_disc",
+        params: "n=10000, scale=100, mass=10000, x=0, y=0, temperature=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let center = p.vec2("x", "y");
+            let (scale, mass) = (p.f32("scale", 100.0), p.f32("mass", 1e4));
+            let mut bodies = utils::exponential_disc_at(center, p.usize("n", 10_000), scale, mass, g, rng);
+            utils::add_velocity_dispersion(&mut bodies, p.f32("temperature", 0.0), rng);
+            Ok(bodies)
+        },
+    },
+    Generator {
+        name: "cold_square",
+        params: "n=10000, size=1000",
+        gravity_constant: None,
+        generate: |p, _, rng| Ok(utils::cold_square(p.usize("n", 10_000), p.f32("size", 1000.0), rng)),
+    },
+    Generator {
+        name: "noise",
+        params: "n=10000, size=1000, resolution=128, scale=32, seed=0",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let resolution = p.usize("resolution", 128);
+            let field = utils::noise_field(resolution, resolution, p.f32("scale", 32.0), p.usize("seed", 0) as u64);
+            Ok(utils::density_map(&field, resolution, resolution, p.usize("n", 10_000), p.f32("size", 1000.0), g, rng))
+        },
+    },
+    Generator {
+        name: "planetary_ring",
+        params: "n=10000, planet_mass=1e6, inner_radius=200, outer_radius=300",
+        gravity_constant: None,
+        generate: |p, g, rng| {
+            let inner = p.f32("inner_radius", 200.0);
+            let outer = p.f32("outer_radius", 300.0);
+            Ok(utils::planetary_ring(p.usize("n", 10_000), p.f32("planet_mass", 1e6), inner, outer, g, rng))
+        },
+    },
+    Generator {
+        name: "solar_system",
+        params: "",
+        gravity_constant: Some(utils::G_SOLAR),
+        generate: |_, _, _| Ok(utils::solar_system()),
+    },
+    Generator {
+        name: "figure_eight",
+        params: "",
+        gravity_constant: Some(1.0),
+        generate: |_, _, _| Ok(utils::figure_eight()),
+    },
+];
+
+/// Looks up a generator by name.
+pub fn find(name: &str) -> Result<&'static Generator, GeneratorError> {
+    GENERATORS
+        .iter()
+        .find(|generator| generator.name == name)
+        .ok_or_else(|| GeneratorError::UnknownGenerator(name.to_owned()))
+}
@@ -0,0 +1,217 @@
+use crate::quadtree::{Node, Quadtree};
+use ultraviolet::Vec2;
+
+use std::sync::mpsc;
+
+/// Invocations per workgroup, matching `@workgroup_size` in `gpu.wgsl`.
+const WORKGROUP_SIZE: usize = 64;
+
+/// Uniform parameters of the force shader.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+struct Params {
+    t_sq: f32,
+    e_sq: f32,
+    g: f32,
+    count: u32,
+}
+
+/// Reinterprets a slice of plain `repr(C)` values as bytes for upload.
+fn as_bytes<T>(values: &[T]) -> &[u8] {
+    // SAFETY: only used with `Params`, `Node`, `Vec2` and `u32`, which consist of 4-byte
+    // fields without padding, so every byte is initialized
+    unsafe { std::slice::from_raw_parts(values.as_ptr() as *const u8, size_of_val(values)) }
+}
+
+/// Buffers sized for up to `nodes` tree nodes and `bodies` bodies, regrown as needed.
+#[derive(Debug)]
+struct Buffers {
+    nodes: usize,
+    bodies: usize,
+    params: wgpu::Buffer,
+    node_buffer: wgpu::Buffer,
+    positions: wgpu::Buffer,
+    order: wgpu::Buffer,
+    accs: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+}
+
+/// Computes tree accelerations in a WGSL compute shader.
+///
+/// Each step uploads the flat node array as is, along with the body positions and the tree order
+/// of the bodies (see `Quadtree::leaf_order`), runs one invocation per body and reads the
+/// accelerations back. The tree is still built on the CPU.
+#[derive(Debug)]
+pub struct GpuForces {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    buffers: Option<Buffers>,
+}
+
+impl GpuForces {
+    /// Sets up the first available GPU adapter. Returns `None` if there is none or it fails to
+    /// create a device, in which case forces stay on the CPU.
+    pub fn new() -> Option<Self> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }))
+        .ok()?;
+
+        // Large simulations need the adapter's full storage buffer sizes
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            label: Some("nbody"),
+            required_limits: adapter.limits(),
+            ..Default::default()
+        }))
+        .ok()?;
+
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("nbody forces"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("gpu.wgsl").into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("nbody forces"),
+            layout: None,
+            module: &module,
+            entry_point: Some("main"),
+            compilation_options: Default::default(),
+            cache: None,
+        });
+
+        Some(Self {
+            device,
+            queue,
+            pipeline,
+            buffers: None,
+        })
+    }
+
+    /// Calculates the accelerations of all bodies from `quadtree` into `accs`, processing them
+    /// in the given `order`. Returns false without touching `accs` if this tree can't be handled
+    /// on the GPU (a non-Newtonian force law, buffers beyond the device limits or a lost device),
+    /// so the caller should fall back to the CPU.
+    pub fn compute(&mut self, quadtree: &Quadtree, positions: &[Vec2], order: &[u32], accs: &mut [Vec2]) -> bool {
+        let count = positions.len();
+        if count == 0 {
+            return true;
+        }
+        if quadtree.force_exponent != 2.0 || quadtree.nodes.is_empty() || order.len() != count {
+            return false;
+        }
+
+        let limits = self.device.limits();
+        let max_binding = limits.max_storage_buffer_binding_size as usize;
+        let groups = count.div_ceil(WORKGROUP_SIZE);
+        let max_groups = limits.max_compute_workgroups_per_dimension as usize;
+        if size_of_val(&quadtree.nodes[..]) > max_binding
+            || size_of_val(positions) > max_binding
+            || groups > max_groups * max_groups
+        {
+            return false;
+        }
+
+        self.reserve(quadtree.nodes.len(), count, max_binding);
+        let Some(buffers) = &self.buffers else {
+            return false;
+        };
+
+        let params = Params {
+            t_sq: quadtree.t_sq,
+            e_sq: quadtree.e_sq,
+            g: quadtree.g,
+            count: count as u32,
+        };
+        self.queue.write_buffer(&buffers.params, 0, as_bytes(&[params]));
+        self.queue.write_buffer(&buffers.node_buffer, 0, as_bytes(&quadtree.nodes));
+        self.queue.write_buffer(&buffers.positions, 0, as_bytes(positions));
+        self.queue.write_buffer(&buffers.order, 0, as_bytes(order));
+
+        let size = size_of_val(positions) as wgpu::BufferAddress;
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor::default());
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &buffers.bind_group, &[]);
+            // Spill into a second dimension past the per-dimension workgroup limit
+            let x = groups.min(max_groups);
+            pass.dispatch_workgroups(x as u32, groups.div_ceil(x) as u32, 1);
+        }
+        encoder.copy_buffer_to_buffer(&buffers.accs, 0, &buffers.readback, 0, size);
+        self.queue.submit([encoder.finish()]);
+
+        let slice = buffers.readback.slice(..size);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        if self.device.poll(wgpu::PollType::Wait).is_err() || !matches!(receiver.recv(), Ok(Ok(()))) {
+            return false;
+        }
+
+        {
+            let data = slice.get_mapped_range();
+            for (acc, xy) in accs.iter_mut().zip(data.chunks_exact(size_of::<Vec2>())) {
+                let f = |i: usize| f32::from_le_bytes(xy[i..i + 4].try_into().unwrap());
+                *acc = Vec2::new(f(0), f(4));
+            }
+        }
+        buffers.readback.unmap();
+        true
+    }
+
+    /// Makes sure the buffers can hold `nodes` nodes and `bodies` bodies, growing them by
+    /// powers of two (up to `max_binding` bytes) so a slowly growing simulation doesn't
+    /// reallocate every step.
+    fn reserve(&mut self, nodes: usize, bodies: usize, max_binding: usize) {
+        if self.buffers.as_ref().is_some_and(|b| b.nodes >= nodes && b.bodies >= bodies) {
+            return;
+        }
+        let nodes = nodes.next_power_of_two().min(max_binding / size_of::<Node>());
+        let bodies = bodies.next_power_of_two().min(max_binding / size_of::<Vec2>());
+
+        let buffer = |label, size: usize, usage| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(label),
+                size: size as wgpu::BufferAddress,
+                usage,
+                mapped_at_creation: false,
+            })
+        };
+        let input = wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST;
+        let params = buffer("params", size_of::<Params>(), wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST);
+        let node_buffer = buffer("nodes", nodes * size_of::<Node>(), input);
+        let positions = buffer("positions", bodies * size_of::<Vec2>(), input);
+        let order = buffer("order", bodies * size_of::<u32>(), input);
+        let accs = buffer("accs", bodies * size_of::<Vec2>(), wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC);
+        let readback = buffer("readback", bodies * size_of::<Vec2>(), wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST);
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("nbody forces"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[&params, &node_buffer, &positions, &order, &accs]
+                .iter()
+                .enumerate()
+                .map(|(i, buffer)| wgpu::BindGroupEntry {
+                    binding: i as u32,
+                    resource: buffer.as_entire_binding(),
+                })
+                .collect::<Vec<_>>(),
+        });
+
+        self.buffers = Some(Buffers {
+            nodes,
+            bodies,
+            params,
+            node_buffer,
+            positions,
+            order,
+            accs,
+            readback,
+            bind_group,
+        });
+    }
+}
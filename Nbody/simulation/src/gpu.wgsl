@@ -0,0 +1,68 @@
+// Barnes-Hut tree walk, one invocation per body. Mirrors `Quadtree::acc` for the Newtonian
+// force law; see `gpu.rs` for the buffer layouts.
+
+struct Node {
+    children: u32,
+    next: u32,
+    pos_x: f32,
+    pos_y: f32,
+    mass: f32,
+    center_x: f32,
+    center_y: f32,
+    size: f32,
+    body_index: u32,
+}
+
+struct Params {
+    t_sq: f32,
+    e_sq: f32,
+    g: f32,
+    count: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> nodes: array<Node>;
+@group(0) @binding(2) var<storage, read> positions: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read> order: array<u32>;
+@group(0) @binding(4) var<storage, read_write> accs: array<vec2<f32>>;
+
+const WORKGROUP_SIZE: u32 = 64u;
+
+@compute @workgroup_size(64)
+fn main(
+    @builtin(global_invocation_id) id: vec3<u32>,
+    @builtin(num_workgroups) groups: vec3<u32>,
+) {
+    let i = id.x + id.y * groups.x * WORKGROUP_SIZE;
+    if i >= params.count {
+        return;
+    }
+
+    // Neighbouring invocations get neighbouring bodies, so they take the same branches
+    let body = order[i];
+    let pos = positions[body];
+    var acc = vec2<f32>(0.0, 0.0);
+
+    var node = 0u;
+    loop {
+        let n = nodes[node];
+        let d = vec2<f32>(n.pos_x, n.pos_y) - pos;
+        let d_sq = dot(d, d);
+
+        if n.children == 0u || n.size * n.size < d_sq * params.t_sq {
+            if n.mass > 1e-10 {
+                let denom_term = d_sq + params.e_sq;
+                acc += d * (n.mass / (denom_term * sqrt(denom_term)));
+            }
+
+            if n.next == 0u {
+                break;
+            }
+            node = n.next;
+        } else {
+            node = n.children;
+        }
+    }
+
+    accs[body] = acc * params.g;
+}
@@ -0,0 +1,92 @@
+use crate::simulation::Simulation;
+use ndarray::{Array1, Array2};
+
+use std::path::Path;
+
+/// Writes simulation output steps into an HDF5 file readable by h5py, yt and similar tools.
+///
+/// Each `write_step` call adds a group `/step_<frame>` (frame zero-padded to 8 digits) with
+/// the attributes `frame`, `time`, `dt` and `gravity_constant` and these datasets:
+///
+/// | dataset      | shape  | contents |
+/// |--------------|--------|----------|
+/// | `positions`  | (N, 2) | body positions |
+/// | `velocities` | (N, 2) | body velocities |
+/// | `masses`     | (N)    | body masses |
+/// | `radii`      | (N)    | body radii |
+///
+/// With tree output enabled the group also gets a `tree` subgroup holding the quadtree built
+/// during the last step: `centers` (M, 2) center of mass, `masses` (M), `quads` (M, 3) as
+/// center x, center y and size, and the `children` and `next` (M) node links.
+pub struct Hdf5Writer {
+    file: hdf5::File,
+    write_tree: bool,
+}
+
+impl Hdf5Writer {
+    /// Creates (or truncates) the output file.
+    pub fn create(path: impl AsRef<Path>) -> hdf5::Result<Self> {
+        Ok(Self {
+            file: hdf5::File::create(path)?,
+            write_tree: false,
+        })
+    }
+
+    /// Sets whether the quadtree nodes are written along with the bodies.
+    pub fn set_write_tree(&mut self, write_tree: bool) {
+        self.write_tree = write_tree;
+    }
+
+    /// Writes the current state of `sim` as a new output step.
+    pub fn write_step(&mut self, sim: &Simulation) -> hdf5::Result<()> {
+        let group = self.file.create_group(&format!("step_{:08}", sim.frame))?;
+
+        group.new_attr::<u64>().create("frame")?.write_scalar(&(sim.frame as u64))?;
+        group.new_attr::<f64>().create("time")?.write_scalar(&sim.time)?;
+        group.new_attr::<f32>().create("dt")?.write_scalar(&sim.dt)?;
+        group.new_attr::<f32>().create("gravity_constant")?.write_scalar(&sim.quadtree.g)?;
+
+        let n = sim.bodies.len();
+        let (pos, vel) = (sim.bodies.positions(), sim.bodies.velocities());
+        let positions = Array2::from_shape_fn((n, 2), |(i, c)| pos[i][c]);
+        let velocities = Array2::from_shape_fn((n, 2), |(i, c)| vel[i][c]);
+        let masses = Array1::from(sim.bodies.masses().to_vec());
+        let radii = Array1::from(sim.bodies.radii().to_vec());
+
+        group.new_dataset_builder().with_data(&positions).create("positions")?;
+        group.new_dataset_builder().with_data(&velocities).create("velocities")?;
+        group.new_dataset_builder().with_data(&masses).create("masses")?;
+        group.new_dataset_builder().with_data(&radii).create("radii")?;
+
+        if self.write_tree {
+            let nodes = &sim.quadtree.nodes;
+            let tree = group.create_group("tree")?;
+
+            let m = nodes.len();
+            let centers = Array2::from_shape_fn((m, 2), |(i, c)| nodes[i].pos[c]);
+            let quads = Array2::from_shape_fn((m, 3), |(i, c)| match c {
+                0 => nodes[i].quad.center.x,
+                1 => nodes[i].quad.center.y,
+                _ => nodes[i].quad.size,
+            });
+            let masses: Array1<f32> = nodes.iter().map(|node| node.mass).collect();
+            let children: Array1<u32> = nodes.iter().map(|node| node.children).collect();
+            let next: Array1<u32> = nodes.iter().map(|node| node.next).collect();
+
+            tree.new_dataset_builder().with_data(&centers).create("centers")?;
+            tree.new_dataset_builder().with_data(&masses).create("masses")?;
+            tree.new_dataset_builder().with_data(&quads).create("quads")?;
+            tree.new_dataset_builder().with_data(&children).create("children")?;
+            tree.new_dataset_builder().with_data(&next).create("next")?;
+        }
+
+        self.file.flush()
+    }
+}
+
+impl Simulation {
+    /// Writes the current state to a new single-step HDF5 file (see `Hdf5Writer`).
+    pub fn save_hdf5(&self, path: impl AsRef<Path>) -> hdf5::Result<()> {
+        Hdf5Writer::create(path)?.write_step(self)
+    }
+}
@@ -0,0 +1,312 @@
+// C entry points for managed hosts such as Unity (C#).
+//
+// Simulations are referred to by integer IDs from a registry instead of raw pointers, all
+// counts are `int32_t`, and data is only ever copied into caller-owned buffers; no function
+// returns a pointer to Rust-owned memory. Positions and velocities are copied as packed
+// float2 (`Vector2`), scalars as float arrays, so `NativeArray`s or pinned managed arrays can be
+// passed directly.
+//
+// Calls on different IDs may run concurrently; calls on the same ID are serialized.
+
+use crate::{
+    body::Body,
+    c_api::{self, SimStatus, fail},
+    simulation::Simulation,
+};
+
+use std::collections::{hash_map::Entry, HashMap};
+use std::ffi::c_char;
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+
+type SimEntry = Arc<Mutex<Simulation>>;
+
+#[derive(Default)]
+struct Registry {
+    sims: HashMap<i32, SimEntry>,
+    next_id: i32,
+}
+
+static REGISTRY: LazyLock<Mutex<Registry>> = LazyLock::new(Default::default);
+
+fn registry() -> MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Registers `sim` and returns its ID (always positive).
+fn register(sim: Simulation) -> i32 {
+    let mut registry = registry();
+    loop {
+        registry.next_id = registry.next_id.checked_add(1).unwrap_or(1);
+        let id = registry.next_id;
+        if let Entry::Vacant(entry) = registry.sims.entry(id) {
+            entry.insert(Arc::new(Mutex::new(sim)));
+            return id;
+        }
+    }
+}
+
+fn lookup(id: i32) -> Result<SimEntry, SimStatus> {
+    registry()
+        .sims
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| fail(SimStatus::NullHandle, format!("unknown simulation id {id}")))
+}
+
+/// Runs `f` on the simulation registered as `id` while holding its lock.
+fn with_sim<T>(id: i32, f: impl FnOnce(&mut Simulation) -> T) -> Result<T, SimStatus> {
+    let entry = lookup(id)?;
+    let mut sim = entry.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(f(&mut sim))
+}
+
+/// Converts a caller capacity into the number of bodies to copy.
+fn copy_count(sim: &Simulation, capacity: i32) -> usize {
+    sim.bodies.len().min(capacity.max(0) as usize)
+}
+
+/// Copies the calling thread's last error message into `buffer` as a nul-terminated UTF-8
+/// string, truncated to fit `capacity` bytes. Returns the full message length in bytes
+/// (without the terminator), so a larger buffer can be retried if it was truncated.
+///
+/// # Safety
+/// `buffer` must be null or writable for `capacity` bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_GetLastError(buffer: *mut c_char, capacity: i32) -> i32 {
+    let msg = unsafe { std::ffi::CStr::from_ptr(c_api::Simulation_GetLastError()) }.to_bytes();
+    if !buffer.is_null() && capacity > 0 {
+        let n = msg.len().min(capacity as usize - 1);
+        unsafe {
+            std::ptr::copy_nonoverlapping(msg.as_ptr(), buffer as *mut u8, n);
+            *buffer.add(n) = 0;
+        }
+    }
+    msg.len() as i32
+}
+
+/// Creates a simulation with a uniform disc of `n` bodies. Returns its ID, or 0 on invalid input.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_Create(n: i32, dt: f32, theta: f32, epsilon: f32) -> i32 {
+    if n < 0 {
+        fail(SimStatus::InvalidArgument, "body count must be non-negative");
+        return 0;
+    }
+    register(Simulation::with_params(n as usize, dt, theta, epsilon))
+}
+
+/// Creates a simulation from `count` bodies (see `Simulation_GetBodyStride` for the layout),
+/// which are copied. Returns its ID, or 0 on invalid input.
+///
+/// # Safety
+/// `bodies` must be null or readable for `count` bodies.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CreateFromBodies(
+    bodies: *const Body,
+    count: i32,
+    dt: f32,
+    theta: f32,
+    epsilon: f32,
+) -> i32 {
+    if count < 0 || (bodies.is_null() && count > 0) {
+        fail(SimStatus::InvalidArgument, "bodies is null or count is negative");
+        return 0;
+    }
+
+    let bodies = if count == 0 {
+        Vec::new()
+    } else {
+        unsafe { std::slice::from_raw_parts(bodies, count as usize) }.to_vec()
+    };
+    register(Simulation::with_bodies(bodies, dt, theta, epsilon))
+}
+
+/// Creates a simulation from a named generator and a JSON object of its parameters
+/// (see `Simulation_GetGeneratorName`). Returns its ID, or 0 on failure.
+///
+/// # Safety
+/// `name` and `params_json` must be null or nul-terminated strings.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CreateFromGenerator(
+    name: *const c_char,
+    params_json: *const c_char,
+    dt: f32,
+    theta: f32,
+    epsilon: f32,
+) -> i32 {
+    let mut sim = Simulation::with_bodies(Vec::new(), dt, theta, epsilon);
+    match unsafe { c_api::Simulation_ResetWithGenerator(&mut sim, name, params_json) } {
+        SimStatus::Ok => register(sim),
+        _ => 0,
+    }
+}
+
+/// Removes a simulation from the registry and frees it once no call is using it.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_Destroy(id: i32) -> SimStatus {
+    match registry().sims.remove(&id) {
+        Some(_) => SimStatus::Ok,
+        None => fail(SimStatus::NullHandle, format!("unknown simulation id {id}")),
+    }
+}
+
+/// Returns 1 if `id` refers to a live simulation, 0 otherwise.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_IsValid(id: i32) -> i32 {
+    registry().sims.contains_key(&id) as i32
+}
+
+/// Advances the simulation by `n` steps.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_Step(id: i32, n: i32) -> SimStatus {
+    if n < 0 {
+        return fail(SimStatus::InvalidArgument, "step count must be non-negative");
+    }
+    with_sim(id, |sim| {
+        sim.step_n(n as usize);
+        SimStatus::Ok
+    })
+    .unwrap_or_else(|status| status)
+}
+
+/// Returns the number of bodies, or -1 for an unknown ID.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_GetBodyCount(id: i32) -> i32 {
+    with_sim(id, |sim| sim.bodies.len().min(i32::MAX as usize) as i32).unwrap_or(-1)
+}
+
+/// Returns the frame counter, or -1 for an unknown ID.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_GetFrame(id: i32) -> i64 {
+    with_sim(id, |sim| sim.frame as i64).unwrap_or(-1)
+}
+
+/// Returns the simulated time, or 0 for an unknown ID.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_GetTime(id: i32) -> f64 {
+    with_sim(id, |sim| sim.time).unwrap_or(0.0)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_SetDt(id: i32, dt: f32) -> SimStatus {
+    with_sim(id, |sim| unsafe { c_api::Simulation_SetDt(sim, dt) }).unwrap_or_else(|status| status)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_SetGravityConstant(id: i32, g: f32) -> SimStatus {
+    with_sim(id, |sim| unsafe { c_api::Simulation_SetGravityConstant(sim, g) }).unwrap_or_else(|status| status)
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_AddBody(id: i32, x: f32, y: f32, vx: f32, vy: f32, mass: f32, radius: f32) -> SimStatus {
+    with_sim(id, |sim| unsafe { c_api::Simulation_AddBody(sim, x, y, vx, vy, mass, radius) })
+        .unwrap_or_else(|status| status)
+}
+
+/// Applies a force to all bodies within `radius` of (x, y); see `Simulation_ApplyForceEx`.
+#[unsafe(no_mangle)]
+pub extern "C" fn NBody_ApplyForce(
+    id: i32,
+    x: f32,
+    y: f32,
+    fx: f32,
+    fy: f32,
+    radius: f32,
+    falloff: i32,
+    mode: i32,
+) -> SimStatus {
+    with_sim(id, |sim| unsafe { c_api::Simulation_ApplyForceEx(sim, x, y, fx, fy, radius, falloff, mode) })
+        .unwrap_or_else(|status| status)
+}
+
+/// Copies whole bodies (see `Simulation_GetBodyStride`) for up to `capacity` bodies.
+/// Returns the number copied, or -1 for an unknown ID.
+///
+/// # Safety
+/// `out` must be null or writable for `capacity` bodies.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CopyBodies(id: i32, out: *mut Body, capacity: i32) -> i32 {
+    unsafe { copy_with(id, out, capacity, |body| *body) }
+}
+
+/// Copies body positions as packed float2 for up to `capacity` bodies.
+/// Returns the number copied, or -1 for an unknown ID.
+///
+/// # Safety
+/// `out_xy` must be null or writable for `2 * capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CopyPositions(id: i32, out_xy: *mut f32, capacity: i32) -> i32 {
+    unsafe { copy_with(id, out_xy as *mut [f32; 2], capacity, |body| [body.pos.x, body.pos.y]) }
+}
+
+/// Copies body velocities as packed float2 for up to `capacity` bodies.
+/// Returns the number copied, or -1 for an unknown ID.
+///
+/// # Safety
+/// `out_xy` must be null or writable for `2 * capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CopyVelocities(id: i32, out_xy: *mut f32, capacity: i32) -> i32 {
+    unsafe { copy_with(id, out_xy as *mut [f32; 2], capacity, |body| [body.vel.x, body.vel.y]) }
+}
+
+/// Copies body masses for up to `capacity` bodies. Returns the number copied, or -1 for an unknown ID.
+///
+/// # Safety
+/// `out` must be null or writable for `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CopyMasses(id: i32, out: *mut f32, capacity: i32) -> i32 {
+    unsafe { copy_with(id, out, capacity, |body| body.mass) }
+}
+
+/// Copies body radii for up to `capacity` bodies. Returns the number copied, or -1 for an unknown ID.
+///
+/// # Safety
+/// `out` must be null or writable for `capacity` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_CopyRadii(id: i32, out: *mut f32, capacity: i32) -> i32 {
+    unsafe { copy_with(id, out, capacity, |body| body.radius) }
+}
+
+/// Overwrites the positions of the first `count` bodies from packed float2.
+/// Returns the number written, or -1 for an unknown ID.
+///
+/// # Safety
+/// `xy` must be null or readable for `2 * count` floats.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn NBody_WritePositions(id: i32, xy: *const f32, count: i32) -> i32 {
+    if xy.is_null() && count > 0 {
+        fail(SimStatus::InvalidArgument, "xy is null but count is non-zero");
+        return 0;
+    }
+
+    with_sim(id, |sim| {
+        let n = copy_count(sim, count);
+        if n > 0 {
+            let src = unsafe { std::slice::from_raw_parts(xy as *const [f32; 2], n) };
+            for (pos, xy) in sim.bodies.positions_mut().iter_mut().zip(src) {
+                pos.x = xy[0];
+                pos.y = xy[1];
+            }
+        }
+        n as i32
+    })
+    .unwrap_or(-1)
+}
+
+unsafe fn copy_with<T>(id: i32, out: *mut T, capacity: i32, f: impl Fn(&Body) -> T) -> i32 {
+    if out.is_null() && capacity > 0 {
+        fail(SimStatus::InvalidArgument, "output buffer is null but capacity is non-zero");
+        return 0;
+    }
+
+    with_sim(id, |sim| {
+        let n = copy_count(sim, capacity);
+        if n > 0 {
+            let out = unsafe { std::slice::from_raw_parts_mut(out, n) };
+            for (out, body) in out.iter_mut().zip(&sim.bodies) {
+                *out = f(&body);
+            }
+        }
+        n as i32
+    })
+    .unwrap_or(-1)
+}
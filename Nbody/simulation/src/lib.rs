@@ -1,10 +1,103 @@
+pub mod analysis;
+#[cfg(feature = "arrow")]
+pub mod arrow_output;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod background;
+pub mod bodies;
 pub mod body;
+pub mod boundary;
+pub mod broadphase;
+#[cfg(feature = "checkpoint")]
+pub mod checkpoint;
+#[cfg(feature = "debug-checks")]
+mod checks;
+pub mod commands;
+pub mod drag;
+pub mod energy;
+pub mod external;
+pub mod field;
+pub mod force;
+#[cfg(feature = "gadget")]
+pub mod gadget;
+#[cfg(feature = "godot")]
+pub mod gdext;
+pub mod generators;
+#[cfg(feature = "gpu")]
+pub mod gpu;
+#[cfg(feature = "hdf5")]
+pub mod hdf5_output;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod interop;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod morton;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+#[cfg(feature = "node")]
+pub mod node;
+pub mod origin;
+pub mod progress;
+#[cfg(feature = "python")]
+pub mod python;
 pub mod quadtree;
+pub mod recording;
+pub mod render;
+pub mod replay;
+pub mod report;
+pub mod rng;
+pub mod scene;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "shm")]
+pub mod shm;
 pub mod simulation;
+pub mod snapshot;
+pub mod telemetry;
+#[cfg(test)]
+mod test_support;
+pub mod timeline;
+pub mod trails;
+pub mod trajectory;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tuning;
 pub mod utils;
+pub mod view;
+pub mod vtk;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod wells;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod c_api;
 
+pub use analysis::{RadialBin, RadialProfile, LAGRANGE_FRACTIONS};
+pub use bodies::Bodies;
 pub use body::Body;
-pub use quadtree::{Node, Quad, Quadtree};
-pub use simulation::Simulation;
+pub use boundary::{Boundary, BoundaryMode};
+pub use broadphase::SweepAndPrune;
+pub use commands::CommandQueue;
+pub use drag::DragConstraint;
+pub use energy::{EnergySample, EnergyTracker};
+pub use external::ExternalPotential;
+pub use field::{FieldGrid, FieldKind};
+pub use force::Force;
+pub use generators::{Generator, GeneratorError};
+pub use quadtree::{
+    AdaptiveTheta, LeafWalk, Node, NodeLayout, NodeReorder, ParentLevels, Partition, Quad, Quadtree, TreeMetrics,
+};
+pub use render::{ColorMode, Colormap, SplatKernel};
+pub use replay::{InputEvent, InputLog};
+pub use report::{StepReport, StepWarning};
+pub use rng::Rng;
+pub use scene::{Scene, SceneError};
+pub use simulation::{
+    Backend, Collision, Falloff, ForceMode, Integrator, MultiRate, Simulation, SimulationState, StepSettings,
+};
+pub use telemetry::{StepStats, StepStatsCallback};
+pub use timeline::{TimedField, Timeline, TimelineEvent};
+pub use trails::TrailRecorder;
+pub use view::{RenderPoint, ViewRect};
+pub use wells::{GravityWell, WellId, Wells};
+#[cfg(not(target_arch = "wasm32"))]
+pub use simulation::{Granularity, ParallelPhase};
+#[cfg(not(target_arch = "wasm32"))]
 pub use rustfiber;
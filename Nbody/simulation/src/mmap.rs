@@ -0,0 +1,170 @@
+use crate::{
+    body::Body,
+    simulation::Simulation,
+    snapshot::{BODIES_MAGIC, BODIES_VERSION, BODY_RECORD_SIZE, MAGIC, VERSION},
+};
+use memmap2::Mmap;
+
+use std::fs::File;
+use std::io;
+use std::ops::Deref;
+use std::path::Path;
+
+// Body records are read in place, so `Body` must have exactly the record layout
+const _: () = assert!(std::mem::size_of::<Body>() == BODY_RECORD_SIZE);
+const _: () = assert!(std::mem::align_of::<Body>() <= 4);
+
+/// Size in bytes of the snapshot header before the body records.
+const SNAPSHOT_HEADER_SIZE: usize = 4 + 4 + 8 + 4 + 4 + 4 + 8;
+
+/// Size in bytes of the body array header before the body records.
+const BODIES_HEADER_SIZE: usize = 4 + 4 + 8;
+
+/// Parameters stored in a snapshot header (see `snapshot::MAGIC`).
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotParams {
+    pub frame: usize,
+    pub dt: f32,
+    pub t_sq: f32,
+    pub e_sq: f32,
+}
+
+/// A binary snapshot (`snapshot::MAGIC`) or body array (`snapshot::BODIES_MAGIC`) file mapped
+/// into memory and viewed as `&[Body]` without parsing or copying.
+///
+/// The records are used in place, which requires a little-endian target; on others `open`
+/// fails and the files have to be read with `Snapshot::read` or `read_bodies` instead.
+/// The file must not be modified while it is mapped.
+#[derive(Debug)]
+pub struct MappedBodies {
+    mmap: Mmap,
+    offset: usize,
+    count: usize,
+    params: Option<SnapshotParams>,
+    /// Whether the records are followed by the ids of version 2 snapshots.
+    has_ids: bool,
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl MappedBodies {
+    /// Maps a file and checks its header and length.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        if cfg!(target_endian = "big") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "memory-mapped snapshots require a little-endian target",
+            ));
+        }
+
+        let file = File::open(path)?;
+        // Safety: the mapping is read-only; callers must not modify the file while it is mapped
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let u32_at = |i: usize| mmap.get(i..i + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+        let u64_at = |i: usize| mmap.get(i..i + 8).map(|b| u64::from_le_bytes(b.try_into().unwrap()));
+        let f32_at = |i: usize| u32_at(i).map(f32::from_bits);
+        let truncated = || invalid_data("truncated snapshot header");
+
+        let (offset, count, params) = match mmap.get(0..4) {
+            Some(magic) if magic == MAGIC => {
+                let version = u32_at(4).ok_or_else(truncated)?;
+                if !(1..=VERSION).contains(&version) {
+                    return Err(invalid_data("unsupported snapshot version"));
+                }
+                let params = SnapshotParams {
+                    frame: u64_at(8).ok_or_else(truncated)? as usize,
+                    dt: f32_at(16).ok_or_else(truncated)?,
+                    t_sq: f32_at(20).ok_or_else(truncated)?,
+                    e_sq: f32_at(24).ok_or_else(truncated)?,
+                };
+                let count = u64_at(28).ok_or_else(truncated)?;
+                (SNAPSHOT_HEADER_SIZE, count, Some((params, version >= 2)))
+            }
+            Some(magic) if magic == BODIES_MAGIC => {
+                if u32_at(4) != Some(BODIES_VERSION) {
+                    return Err(invalid_data("unsupported body array version"));
+                }
+                (BODIES_HEADER_SIZE, u64_at(8).ok_or_else(truncated)?, None)
+            }
+            _ => return Err(invalid_data("not an nbody snapshot or body array")),
+        };
+
+        let has_ids = params.is_some_and(|(_, has_ids)| has_ids);
+        let params = params.map(|(params, _)| params);
+        let count = usize::try_from(count).map_err(|_| invalid_data("body count too large"))?;
+        // Version 2 snapshots end with the next id and one id per body
+        let record_size = if has_ids { BODY_RECORD_SIZE + 4 } else { BODY_RECORD_SIZE };
+        let trailer = if has_ids { 4 } else { 0 };
+        let expected = count
+            .checked_mul(record_size)
+            .and_then(|len| len.checked_add(offset + trailer));
+        if expected.is_none_or(|len| len > mmap.len()) {
+            return Err(invalid_data("file is shorter than its body count"));
+        }
+        if !(mmap.as_ptr() as usize + offset).is_multiple_of(std::mem::align_of::<Body>()) {
+            return Err(invalid_data("body records are misaligned"));
+        }
+
+        Ok(Self { mmap, offset, count, params, has_ids })
+    }
+
+    /// The mapped bodies.
+    pub fn bodies(&self) -> &[Body] {
+        // Safety: `open` checked that `count` records fit in the mapping at a suitably aligned
+        // offset, `Body` is `repr(C)` with the record layout, and any bit pattern is a valid f32
+        unsafe { std::slice::from_raw_parts(self.mmap.as_ptr().add(self.offset) as *const Body, self.count) }
+    }
+
+    /// The ids of the bodies and the next id, saved after the records by version 2 snapshots
+    /// (see `snapshot::MAGIC`); `None` for older snapshots and body arrays.
+    pub fn ids(&self) -> Option<(Vec<u32>, u32)> {
+        if !self.has_ids {
+            return None;
+        }
+        let start = self.offset + self.count * BODY_RECORD_SIZE;
+        let u32_at = |i: usize| u32::from_le_bytes(self.mmap[i..i + 4].try_into().unwrap());
+        let ids = (0..self.count).map(|i| u32_at(start + 4 + i * 4)).collect();
+        Some((ids, u32_at(start)))
+    }
+
+    /// Header parameters if the file is a snapshot, `None` for body arrays.
+    pub fn params(&self) -> Option<SnapshotParams> {
+        self.params
+    }
+}
+
+impl Deref for MappedBodies {
+    type Target = [Body];
+
+    fn deref(&self) -> &[Body] {
+        self.bodies()
+    }
+}
+
+impl Simulation {
+    /// Replaces all bodies with those in a memory-mapped snapshot or body array file
+    /// (see `MappedBodies`), copying them directly without a parse pass.
+    /// Snapshots also restore the frame, time step, tree parameters and body ids like `load_snapshot`;
+    /// body arrays reset the frame counter and time like `reset_with_bodies`.
+    /// The simulation is left untouched if the file cannot be mapped.
+    pub fn load_mapped(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mapped = MappedBodies::open(path)?;
+        self.reset_with_bodies(mapped.to_vec());
+
+        if let Some(params) = mapped.params() {
+            self.frame = params.frame;
+            self.dt = params.dt;
+            // As in `Snapshot::restore`, assume a constant time step
+            self.time = params.frame as f64 * params.dt as f64;
+            self.quadtree.t_sq = params.t_sq;
+            self.quadtree.e_sq = params.e_sq;
+        }
+        if let Some((ids, next_id)) = mapped.ids() {
+            self.bodies.set_ids(ids, next_id);
+        }
+        Ok(())
+    }
+}
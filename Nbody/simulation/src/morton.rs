@@ -0,0 +1,74 @@
+use crate::{quadtree::Quad, simulation::Simulation};
+use rayon::prelude::*;
+use ultraviolet::Vec2;
+
+// Spatial sorting of bodies along a Morton (Z-order) curve.
+//
+// Bodies start out in generator order and drift apart as the simulation evolves, so neighbours
+// in space end up far apart in memory. Every force and collision query walks the bodies in index
+// order, and the tree build inserts them in that order, so re-sorting them from time to time
+// keeps those walks cache-friendly.
+
+/// Morton code of `pos` within `quad`: 16 bits per axis, interleaved with x in the low bit.
+pub fn morton_code(pos: Vec2, quad: Quad) -> u32 {
+    fn spread(v: u32) -> u32 {
+        let v = (v | (v << 8)) & 0x00ff_00ff;
+        let v = (v | (v << 4)) & 0x0f0f_0f0f;
+        let v = (v | (v << 2)) & 0x3333_3333;
+        (v | (v << 1)) & 0x5555_5555
+    }
+
+    let scale = if quad.size > 0.0 { 65535.0 / quad.size } else { 0.0 };
+    let min = quad.center - Vec2::broadcast(0.5 * quad.size);
+    let cell = |v: f32, min: f32| ((v - min) * scale).clamp(0.0, 65535.0) as u32;
+    spread(cell(pos.x, min.x)) | spread(cell(pos.y, min.y)) << 1
+}
+
+impl Simulation {
+    /// Sorts the bodies along a Morton curve so that bodies close in space are close in memory.
+    ///
+    /// Body indices change; each body's id (`Bodies::ids`) follows it, so hosts that track
+    /// bodies across a sort should refer to them by id. The trajectory logger and the collisions
+    /// of the last step are remapped to the new indices, and the tree is dropped as it refers to
+    /// the old ones (it is rebuilt by the next step).
+    pub fn sort_bodies_morton(&mut self) {
+        let positions = self.bodies.positions();
+        if positions.len() < 2 {
+            return;
+        }
+
+        let quad = Quad::new_containing(positions);
+        let mut keys: Vec<(u32, u32)> = positions
+            .iter()
+            .enumerate()
+            .map(|(i, &pos)| (morton_code(pos, quad), i as u32))
+            .collect();
+        if self.use_rayon {
+            keys.par_sort_unstable();
+        } else {
+            keys.sort_unstable();
+        }
+
+        let order: Vec<u32> = keys.iter().map(|&(_, i)| i).collect();
+        self.bodies.permute(&order);
+
+        // New index of each old index
+        let mut moved = vec![0u32; order.len()];
+        for (new, &old) in order.iter().enumerate() {
+            moved[old as usize] = new as u32;
+        }
+        for collision in &mut self.collisions {
+            collision.i = moved[collision.i as usize];
+            collision.j = moved[collision.j as usize];
+        }
+        if let Some(logger) = &mut self.trajectory_logger {
+            logger.remap(&moved);
+        }
+        if let Some(trails) = &mut self.trail_recorder {
+            trails.remap(&moved);
+        }
+
+        self.quadtree.nodes.clear();
+        self.quadtree.parents.clear();
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{body::Body, simulation::Simulation, snapshot::Snapshot};
+use serde::{Deserialize, Serialize};
+use ultraviolet::Vec2;
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// MessagePack form of a snapshot: a map with the scalar fields of `Snapshot` and one array per
+/// body attribute, so frontends can decode it with any MessagePack library and hand the columns
+/// straight to numpy or typed arrays.
+#[derive(Serialize, Deserialize)]
+struct Columns {
+    frame: u64,
+    dt: f32,
+    t_sq: f32,
+    e_sq: f32,
+    x: Vec<f32>,
+    y: Vec<f32>,
+    vx: Vec<f32>,
+    vy: Vec<f32>,
+    ax: Vec<f32>,
+    ay: Vec<f32>,
+    mass: Vec<f32>,
+    radius: Vec<f32>,
+    /// Absent in snapshots written before ids were saved; bodies are then numbered in order.
+    #[serde(default)]
+    id: Vec<u32>,
+    #[serde(default)]
+    next_id: u32,
+}
+
+fn invalid_data(msg: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Snapshot {
+    /// Encodes the snapshot as a MessagePack map with the keys `frame`, `dt`, `t_sq`, `e_sq`,
+    /// `next_id`, the f32 arrays `x`, `y`, `vx`, `vy`, `ax`, `ay`, `mass` and `radius` and the u32
+    /// array `id`, one entry per body.
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        let column = |f: fn(&Body) -> f32| self.bodies.iter().map(f).collect();
+        let columns = Columns {
+            frame: self.frame as u64,
+            dt: self.dt,
+            t_sq: self.t_sq,
+            e_sq: self.e_sq,
+            x: column(|body| body.pos.x),
+            y: column(|body| body.pos.y),
+            vx: column(|body| body.vel.x),
+            vy: column(|body| body.vel.y),
+            ax: column(|body| body.acc.x),
+            ay: column(|body| body.acc.y),
+            mass: column(|body| body.mass),
+            radius: column(|body| body.radius),
+            id: self.ids.clone(),
+            next_id: self.next_id,
+        };
+        // Serializing plain numbers and vectors into memory cannot fail
+        rmp_serde::to_vec_named(&columns).expect("MessagePack encoding failed")
+    }
+
+    /// Decodes a snapshot encoded by `to_msgpack`.
+    pub fn from_msgpack(data: &[u8]) -> io::Result<Self> {
+        let c: Columns = rmp_serde::from_slice(data).map_err(invalid_data)?;
+
+        let n = c.x.len();
+        let columns = [&c.y, &c.vx, &c.vy, &c.ax, &c.ay, &c.mass, &c.radius];
+        if columns.iter().any(|column| column.len() != n) || !(c.id.is_empty() || c.id.len() == n) {
+            return Err(invalid_data("MessagePack snapshot columns differ in length"));
+        }
+
+        let bodies = (0..n)
+            .map(|i| Body {
+                pos: Vec2::new(c.x[i], c.y[i]),
+                vel: Vec2::new(c.vx[i], c.vy[i]),
+                acc: Vec2::new(c.ax[i], c.ay[i]),
+                mass: c.mass[i],
+                radius: c.radius[i],
+            })
+            .collect();
+
+        Ok(Self {
+            frame: c.frame as usize,
+            dt: c.dt,
+            t_sq: c.t_sq,
+            e_sq: c.e_sq,
+            bodies,
+            ids: c.id,
+            next_id: c.next_id,
+        })
+    }
+}
+
+impl Simulation {
+    /// Encodes the current state as a MessagePack snapshot (see `Snapshot::to_msgpack`),
+    /// e.g. to send a frame over a socket.
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        Snapshot::capture(self).to_msgpack()
+    }
+
+    /// Saves the current state to a MessagePack snapshot file.
+    pub fn save_msgpack(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_msgpack())
+    }
+
+    /// Replaces the current state with the contents of a MessagePack snapshot file.
+    /// The simulation is left untouched if the file cannot be read.
+    pub fn load_msgpack(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        Snapshot::from_msgpack(&fs::read(path)?)?.restore(self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempFile, bodies_bits, sorted_multirate_sim, state_hash};
+
+    #[test]
+    fn msgpack_round_trips() {
+        let mut sim = sorted_multirate_sim(300);
+        for _ in 0..7 {
+            sim.step();
+        }
+
+        let snapshot = Snapshot::capture(&sim);
+        let read = Snapshot::from_msgpack(&snapshot.to_msgpack()).unwrap();
+        assert_eq!(read.frame, snapshot.frame);
+        assert_eq!(read.dt.to_bits(), snapshot.dt.to_bits());
+        assert_eq!(read.t_sq.to_bits(), snapshot.t_sq.to_bits());
+        assert_eq!(read.e_sq.to_bits(), snapshot.e_sq.to_bits());
+        assert_eq!(bodies_bits(&read.bodies), bodies_bits(&snapshot.bodies));
+        assert_eq!(read.ids, snapshot.ids);
+        assert_eq!(read.next_id, snapshot.next_id);
+    }
+
+    #[test]
+    fn msgpack_file_restores_state() {
+        let mut sim = sorted_multirate_sim(300);
+        for _ in 0..7 {
+            sim.step();
+        }
+
+        let file = TempFile::new("snapshot.msgpack");
+        sim.save_msgpack(file.path()).unwrap();
+        let mut loaded = Simulation::with_params(0, 1.0, 1.0, 1.0);
+        loaded.load_msgpack(file.path()).unwrap();
+
+        assert_eq!(loaded.frame, sim.frame);
+        assert_eq!(loaded.bodies.ids(), sim.bodies.ids());
+        assert_eq!(state_hash(&loaded), state_hash(&sim));
+    }
+
+    #[test]
+    fn rejects_columns_of_different_lengths() {
+        let mut snapshot = Snapshot::capture(&Simulation::with_params(10, 0.1, 1.0, 1.0));
+        snapshot.ids.pop();
+        let err = Snapshot::from_msgpack(&snapshot.to_msgpack()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    body::Body,
+    generators::GENERATORS,
+    simulation::{Falloff, ForceMode, Simulation},
+};
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use ultraviolet::Vec2;
+
+fn generator_error(e: impl ToString) -> Error {
+    Error::new(Status::InvalidArg, e.to_string())
+}
+
+/// Node.js wrapper around `Simulation`, exported as `Simulation`.
+///
+/// Body data is returned as `Float32Array`s that take over a freshly filled buffer, so there is
+/// one copy out of the body columns and none into JavaScript. They are snapshots: the body columns
+/// may be reallocated by later calls, so live views into it would not be sound.
+/// Positions and velocities are interleaved as `[x0, y0, x1, y1, ...]`.
+#[napi(js_name = "Simulation")]
+pub struct NodeSimulation {
+    sim: Simulation,
+}
+
+#[napi]
+impl NodeSimulation {
+    /// Creates a simulation with a uniform disc of `n` bodies.
+    #[napi(constructor)]
+    pub fn new(n: Option<u32>, dt: Option<f64>, theta: Option<f64>, epsilon: Option<f64>) -> Self {
+        Self {
+            sim: Simulation::with_params(
+                n.map_or(Simulation::DEFAULT_N, |n| n as usize),
+                dt.map_or(Simulation::DEFAULT_DT, |dt| dt as f32),
+                theta.map_or(Simulation::DEFAULT_THETA, |theta| theta as f32),
+                epsilon.map_or(Simulation::DEFAULT_EPSILON, |epsilon| epsilon as f32),
+            ),
+        }
+    }
+
+    /// Creates a simulation from a named generator and a JSON object of its parameters.
+    #[napi(factory)]
+    pub fn from_generator(
+        name: String,
+        params: Option<String>,
+        dt: Option<f64>,
+        theta: Option<f64>,
+        epsilon: Option<f64>,
+    ) -> Result<Self> {
+        let mut sim = Simulation::with_bodies(
+            Vec::new(),
+            dt.map_or(Simulation::DEFAULT_DT, |dt| dt as f32),
+            theta.map_or(Simulation::DEFAULT_THETA, |theta| theta as f32),
+            epsilon.map_or(Simulation::DEFAULT_EPSILON, |epsilon| epsilon as f32),
+        );
+        sim.reset_with_generator(&name, params.as_deref().unwrap_or_default())
+            .map_err(generator_error)?;
+        Ok(Self { sim })
+    }
+
+    /// Advances the simulation by `n` steps (default 1).
+    #[napi]
+    pub fn step(&mut self, n: Option<u32>) {
+        self.sim.step_n(n.unwrap_or(1) as usize);
+    }
+
+    /// Replaces all bodies with a uniform disc of `n` bodies and resets time.
+    #[napi]
+    pub fn reset(&mut self, n: u32) {
+        self.sim.reset(n as usize);
+    }
+
+    /// Replaces all bodies with those of a named generator and resets time.
+    #[napi]
+    pub fn reset_with_generator(&mut self, name: String, params: Option<String>) -> Result<()> {
+        self.sim
+            .reset_with_generator(&name, params.as_deref().unwrap_or_default())
+            .map_err(generator_error)
+    }
+
+    #[napi]
+    pub fn add_body(&mut self, x: f64, y: f64, vx: f64, vy: f64, mass: f64, radius: f64) {
+        let pos = Vec2::new(x as f32, y as f32);
+        let vel = Vec2::new(vx as f32, vy as f32);
+        self.sim.add_body(Body::new(pos, vel, mass as f32, radius as f32));
+    }
+
+    /// Adds an impulse to every body within `radius` of (x, y), fading linearly to the edge.
+    #[napi]
+    pub fn apply_impulse(&mut self, x: f64, y: f64, fx: f64, fy: f64, radius: f64) {
+        self.sim.apply_force(
+            Vec2::new(x as f32, y as f32),
+            Vec2::new(fx as f32, fy as f32),
+            radius as f32,
+            Falloff::Linear,
+            ForceMode::Impulse,
+        );
+    }
+
+    #[napi]
+    pub fn set_use_rayon(&mut self, use_rayon: bool) {
+        self.sim.set_use_rayon(use_rayon);
+    }
+
+    #[napi(getter)]
+    pub fn frame(&self) -> i64 {
+        self.sim.frame as i64
+    }
+
+    #[napi(getter)]
+    pub fn time(&self) -> f64 {
+        self.sim.time
+    }
+
+    #[napi(getter)]
+    pub fn body_count(&self) -> u32 {
+        self.sim.bodies.len() as u32
+    }
+
+    #[napi(getter)]
+    pub fn dt(&self) -> f64 {
+        self.sim.dt as f64
+    }
+
+    #[napi(setter)]
+    pub fn set_dt(&mut self, dt: f64) {
+        self.sim.set_dt(dt as f32);
+    }
+
+    #[napi(getter)]
+    pub fn gravity_constant(&self) -> f64 {
+        self.sim.quadtree.g as f64
+    }
+
+    #[napi(setter)]
+    pub fn set_gravity_constant(&mut self, g: f64) {
+        self.sim.set_gravity_constant(g as f32);
+    }
+
+    #[napi]
+    pub fn get_positions(&self) -> Float32Array {
+        Float32Array::new(self.sim.bodies.positions().iter().flat_map(|pos| [pos.x, pos.y]).collect())
+    }
+
+    #[napi]
+    pub fn get_velocities(&self) -> Float32Array {
+        Float32Array::new(self.sim.bodies.velocities().iter().flat_map(|vel| [vel.x, vel.y]).collect())
+    }
+
+    #[napi]
+    pub fn get_masses(&self) -> Float32Array {
+        Float32Array::new(self.sim.bodies.masses().to_vec())
+    }
+
+    #[napi]
+    pub fn get_radii(&self) -> Float32Array {
+        Float32Array::new(self.sim.bodies.radii().to_vec())
+    }
+
+    /// Overwrites the body positions from interleaved `[x0, y0, ...]` with two values per body.
+    #[napi]
+    pub fn set_positions(&mut self, positions: Float32Array) -> Result<()> {
+        if positions.len() != 2 * self.sim.bodies.len() {
+            return Err(Error::new(
+                Status::InvalidArg,
+                format!("expected {} values", 2 * self.sim.bodies.len()),
+            ));
+        }
+        for (pos, xy) in self.sim.bodies.positions_mut().iter_mut().zip(positions.chunks_exact(2)) {
+            *pos = Vec2::new(xy[0], xy[1]);
+        }
+        Ok(())
+    }
+
+    #[napi]
+    pub fn kinetic_energy(&self) -> f64 {
+        self.sim.kinetic_energy() as f64
+    }
+}
+
+/// Names of the available generators.
+#[napi]
+pub fn generator_names() -> Vec<String> {
+    GENERATORS.iter().map(|generator| generator.name.to_owned()).collect()
+}
+
+/// JSON parameter description of a generator, or `undefined` for unknown names.
+#[napi]
+pub fn generator_params(name: String) -> Option<String> {
+    GENERATORS
+        .iter()
+        .find(|generator| generator.name == name)
+        .map(|generator| generator.params.to_owned())
+}
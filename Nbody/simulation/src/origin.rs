@@ -0,0 +1,76 @@
+use crate::{external::ExternalPotential, replay::InputEvent, simulation::Simulation};
+use ultraviolet::Vec2;
+
+// Floating origin for hosts with large-world coordinates (e.g. Unreal Engine's LWC).
+//
+// Bodies are integrated in f32 relative to `Simulation::origin`, which is kept in f64. World
+// positions are `origin + pos` evaluated in f64, so precision only depends on the distance of a
+// body from the origin, not from the world's zero. Re-centering the origin on the region of
+// interest keeps the f32 offsets small.
+
+impl Simulation {
+    /// World-space position of body `i` in f64, i.e. `origin + bodies[i].pos`.
+    pub fn world_position(&self, i: usize) -> [f64; 2] {
+        let pos = self.bodies.positions()[i];
+        [self.origin[0] + pos.x as f64, self.origin[1] + pos.y as f64]
+    }
+
+    /// Iterates over the world-space positions of all bodies in f64.
+    pub fn world_positions(&self) -> impl ExactSizeIterator<Item = [f64; 2]> + '_ {
+        let [ox, oy] = self.origin;
+        self.bodies.positions().iter().map(move |pos| [ox + pos.x as f64, oy + pos.y as f64])
+    }
+
+    /// Sets the world-space origin without moving the bodies relative to it,
+    /// i.e. translates the whole simulation to `origin` in world space.
+    pub fn set_origin(&mut self, origin: [f64; 2]) {
+        self.origin = origin;
+    }
+
+    /// Moves the origin to `origin` while keeping world-space positions, by translating the
+    /// bodies, the boundary and the external potentials by the opposite offset.
+    /// The offset is rounded to f32 once and applied to everything alike, so relative positions,
+    /// and hence the dynamics, are unaffected beyond that rounding.
+    pub fn shift_origin(&mut self, origin: [f64; 2]) {
+        self.log_input(|| InputEvent::ShiftOrigin(origin));
+
+        let delta = Vec2::new((origin[0] - self.origin[0]) as f32, (origin[1] - self.origin[1]) as f32);
+        // Account for the rounding so world positions stay put as closely as f64 allows
+        self.origin = [self.origin[0] + delta.x as f64, self.origin[1] + delta.y as f64];
+        if delta == Vec2::zero() {
+            return;
+        }
+
+        for pos in self.bodies.positions_mut() {
+            *pos -= delta;
+        }
+        self.boundary.min -= delta;
+        self.boundary.max -= delta;
+        for potential in &mut self.external {
+            match potential {
+                ExternalPotential::PointMass { pos, .. } => *pos -= delta,
+                ExternalPotential::Harmonic { center, .. } => *center -= delta,
+                ExternalPotential::Uniform { .. } => {}
+            }
+        }
+
+        // The tree refers to the old positions
+        self.quadtree.nodes.clear();
+        self.quadtree.parents.clear();
+    }
+
+    /// Shifts the origin to the center of mass of the bodies (see `shift_origin`).
+    /// Does nothing if there are no bodies or their total mass is zero.
+    pub fn recenter_origin(&mut self) {
+        let (x, y, mass) = self.bodies.positions().iter().zip(self.bodies.masses()).fold(
+            (0.0f64, 0.0f64, 0.0f64),
+            |(x, y, total), (&pos, &mass)| {
+                let mass = mass as f64;
+                (x + pos.x as f64 * mass, y + pos.y as f64 * mass, total + mass)
+            },
+        );
+        if mass > 0.0 {
+            self.shift_origin([self.origin[0] + x / mass, self.origin[1] + y / mass]);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::simulation::Simulation;
+
+/// Long-running operation reported to a progress callback.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressOperation {
+    /// `step_n`, reported after each step.
+    Step = 0,
+    /// `write_checkpoint`, reported after encoding and compressing each section and after writing.
+    Checkpoint = 1,
+    /// `reset_with_generator`, reported before and after generating the bodies.
+    Generator = 2,
+}
+
+/// Callback receiving the operation in progress and its completion fraction in [0, 1].
+/// Returning `false` cancels the operation at the next opportunity.
+///
+/// It is called on the thread running the operation, which may be a background thread.
+pub type ProgressCallback = Box<dyn Fn(ProgressOperation, f32) -> bool + Send + Sync>;
+
+impl Simulation {
+    /// Sets the callback reporting progress of `step_n`, `write_checkpoint` and
+    /// `reset_with_generator`, and allowing them to be cancelled.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        self.progress_callback = callback;
+    }
+
+    /// Reports progress of `operation`; returns `false` if the callback asked to cancel it.
+    pub(crate) fn report_progress(&self, operation: ProgressOperation, fraction: f32) -> bool {
+        self.progress_callback
+            .as_ref()
+            .is_none_or(|callback| callback(operation, fraction.clamp(0.0, 1.0)))
+    }
+}
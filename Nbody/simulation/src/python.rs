@@ -0,0 +1,242 @@
+use crate::{
+    bodies::Bodies,
+    body::Body,
+    generators::GENERATORS,
+    scene::SceneError,
+    simulation::Simulation,
+};
+use numpy::{ndarray::Array2, IntoPyArray, PyArray1, PyArray2, PyReadonlyArray2, PyUntypedArrayMethods};
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+};
+use ultraviolet::Vec2;
+
+fn value_error(e: impl ToString) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Python wrapper around `Simulation`, exposed as `nbody_simulation.Simulation`.
+///
+/// Body data is exchanged as numpy arrays. The accessors return copies, since the body columns
+/// may be reallocated by later calls; write changes back with the matching setter.
+#[pyclass(name = "Simulation", module = "nbody_simulation", unsendable)]
+pub struct PySimulation {
+    pub sim: Simulation,
+}
+
+impl PySimulation {
+    fn vec2_array<'py>(py: Python<'py>, column: &[Vec2]) -> Bound<'py, PyArray2<f32>> {
+        let data = column.iter().flat_map(|v| [v.x, v.y]);
+        Array2::from_shape_vec((column.len(), 2), data.collect())
+            .expect("two values per body")
+            .into_pyarray(py)
+    }
+
+    fn set_vec2(&mut self, values: PyReadonlyArray2<'_, f32>, column: fn(&mut Bodies) -> &mut [Vec2]) -> PyResult<()> {
+        if values.shape() != [self.sim.bodies.len(), 2] {
+            return Err(value_error(format!("expected an array of shape ({}, 2)", self.sim.bodies.len())));
+        }
+        let values = values.as_array();
+        for (v, row) in column(&mut self.sim.bodies).iter_mut().zip(values.rows()) {
+            *v = Vec2::new(row[0], row[1]);
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PySimulation {
+    /// Creates a simulation with a uniform disc of `n` bodies.
+    #[new]
+    #[pyo3(signature = (
+        n = Simulation::DEFAULT_N,
+        dt = Simulation::DEFAULT_DT,
+        theta = Simulation::DEFAULT_THETA,
+        epsilon = Simulation::DEFAULT_EPSILON,
+    ))]
+    fn new(n: usize, dt: f32, theta: f32, epsilon: f32) -> Self {
+        Self { sim: Simulation::with_params(n, dt, theta, epsilon) }
+    }
+
+    /// Creates a simulation from a named generator (see `generators()`) and its JSON parameters.
+    #[staticmethod]
+    #[pyo3(signature = (
+        name,
+        params = "{}",
+        dt = Simulation::DEFAULT_DT,
+        theta = Simulation::DEFAULT_THETA,
+        epsilon = Simulation::DEFAULT_EPSILON,
+    ))]
+    fn from_generator(name: &str, params: &str, dt: f32, theta: f32, epsilon: f32) -> PyResult<Self> {
+        let mut sim = Simulation::with_bodies(Vec::new(), dt, theta, epsilon);
+        sim.reset_with_generator(name, params).map_err(value_error)?;
+        Ok(Self { sim })
+    }
+
+    /// Creates a simulation from a JSON scene file.
+    #[staticmethod]
+    fn from_scene(path: &str) -> PyResult<Self> {
+        match Simulation::from_scene_file(path) {
+            Ok(sim) => Ok(Self { sim }),
+            Err(SceneError::Io(e)) => Err(PyIOError::new_err(e.to_string())),
+            Err(e) => Err(value_error(e)),
+        }
+    }
+
+    /// Creates the solar system preset in AU, solar masses and years.
+    #[staticmethod]
+    fn solar_system() -> Self {
+        Self { sim: Simulation::solar_system() }
+    }
+
+    /// Advances the simulation by `n` steps, releasing the GIL while stepping.
+    #[pyo3(signature = (n = 1))]
+    fn step(&mut self, py: Python<'_>, n: usize) {
+        let sim = &mut self.sim;
+        py.detach(|| sim.step_n(n));
+    }
+
+    /// Replaces all bodies with those of a named generator and resets time.
+    #[pyo3(signature = (name, params = "{}"))]
+    fn reset_with_generator(&mut self, name: &str, params: &str) -> PyResult<()> {
+        self.sim.reset_with_generator(name, params).map_err(value_error)
+    }
+
+    fn add_body(&mut self, x: f32, y: f32, vx: f32, vy: f32, mass: f32, radius: f32) {
+        self.sim.add_body(Body::new(Vec2::new(x, y), Vec2::new(vx, vy), mass, radius));
+    }
+
+    fn spawn_disc(&mut self, x: f32, y: f32, n: usize, radius: f32, spin: f32) {
+        self.sim.spawn_disc(Vec2::new(x, y), n, radius, spin);
+    }
+
+    fn spawn_cluster(&mut self, x: f32, y: f32, n: usize, radius: f32) {
+        self.sim.spawn_cluster(Vec2::new(x, y), n, radius);
+    }
+
+    fn set_use_rayon(&mut self, use_rayon: bool) {
+        self.sim.set_use_rayon(use_rayon);
+    }
+
+    #[getter]
+    fn frame(&self) -> usize {
+        self.sim.frame
+    }
+
+    #[getter]
+    fn time(&self) -> f64 {
+        self.sim.time
+    }
+
+    #[getter]
+    fn dt(&self) -> f32 {
+        self.sim.dt
+    }
+
+    #[setter]
+    fn set_dt(&mut self, dt: f32) {
+        self.sim.set_dt(dt);
+    }
+
+    #[getter]
+    fn gravity_constant(&self) -> f32 {
+        self.sim.quadtree.g
+    }
+
+    #[setter]
+    fn set_gravity_constant(&mut self, g: f32) {
+        self.sim.set_gravity_constant(g);
+    }
+
+    #[getter]
+    fn force_exponent(&self) -> f32 {
+        self.sim.quadtree.force_exponent
+    }
+
+    #[setter]
+    fn set_force_exponent(&mut self, k: f32) {
+        self.sim.set_force_exponent(k);
+    }
+
+    fn __len__(&self) -> usize {
+        self.sim.bodies.len()
+    }
+
+    /// Body positions as an (N, 2) float32 array.
+    fn positions<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        Self::vec2_array(py, self.sim.bodies.positions())
+    }
+
+    /// Body velocities as an (N, 2) float32 array.
+    fn velocities<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        Self::vec2_array(py, self.sim.bodies.velocities())
+    }
+
+    /// Body accelerations from the last step as an (N, 2) float32 array.
+    fn accelerations<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray2<f32>> {
+        Self::vec2_array(py, self.sim.bodies.accs())
+    }
+
+    fn masses<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        self.sim.bodies.masses().to_vec().into_pyarray(py)
+    }
+
+    fn radii<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<f32>> {
+        self.sim.bodies.radii().to_vec().into_pyarray(py)
+    }
+
+    /// Overwrites the body positions from an (N, 2) float32 array.
+    fn set_positions(&mut self, positions: PyReadonlyArray2<'_, f32>) -> PyResult<()> {
+        self.set_vec2(positions, Bodies::positions_mut)
+    }
+
+    /// Overwrites the body velocities from an (N, 2) float32 array.
+    fn set_velocities(&mut self, velocities: PyReadonlyArray2<'_, f32>) -> PyResult<()> {
+        self.set_vec2(velocities, Bodies::velocities_mut)
+    }
+
+    fn kinetic_energy(&self) -> f32 {
+        self.sim.kinetic_energy()
+    }
+
+    /// Potential energy on the tree from the last step (0 before the first step).
+    fn potential_energy(&self) -> f32 {
+        self.sim.potential_energy()
+    }
+
+    /// Virial ratio 2T / |U| on the tree from the last step (0 before the first step).
+    fn virial_ratio(&self) -> f32 {
+        self.sim.virial_ratio()
+    }
+
+    fn momentum(&self) -> (f32, f32) {
+        let p = self.sim.momentum();
+        (p.x, p.y)
+    }
+
+    fn save_snapshot(&self, path: &str) -> PyResult<()> {
+        Ok(self.sim.save_snapshot(path)?)
+    }
+
+    fn load_snapshot(&mut self, path: &str) -> PyResult<()> {
+        Ok(self.sim.load_snapshot(path)?)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Simulation(bodies={}, frame={}, dt={})", self.sim.bodies.len(), self.sim.frame, self.sim.dt)
+    }
+}
+
+/// Names and JSON parameter descriptions of the available generators.
+#[pyfunction]
+fn generators() -> Vec<(&'static str, &'static str)> {
+    GENERATORS.iter().map(|generator| (generator.name, generator.params)).collect()
+}
+
+#[pymodule]
+fn nbody_simulation(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PySimulation>()?;
+    m.add_function(wrap_pyfunction!(generators, m)?)?;
+    Ok(())
+}
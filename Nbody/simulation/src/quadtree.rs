@@ -1,29 +1,30 @@
-use crate::body::Body;
 use ultraviolet::Vec2;
+use wide::{f32x8, CmpEq, CmpLt};
 
 /// Represents a square region in the quadtree.
 /// Used to define the bounds of nodes.
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quad {
     pub center: Vec2,
     pub size: f32,
 }
 
 impl Quad {
-    /// Creates a new Quad that encompasses all the given bodies.
-    /// It calculates the bounding box of the bodies and centers the Quad on it.
-    pub fn new_containing(bodies: &[Body]) -> Self {
+    /// Creates a new Quad that encompasses all the given body positions.
+    /// It calculates the bounding box of the positions and centers the Quad on it.
+    pub fn new_containing(positions: &[Vec2]) -> Self {
         let mut min_x = f32::MAX;
         let mut min_y = f32::MAX;
         let mut max_x = f32::MIN;
         let mut max_y = f32::MIN;
 
-        for body in bodies {
-            min_x = min_x.min(body.pos.x);
-            min_y = min_y.min(body.pos.y);
-            max_x = max_x.max(body.pos.x);
-            max_y = max_y.max(body.pos.y);
+        for pos in positions {
+            min_x = min_x.min(pos.x);
+            min_y = min_y.min(pos.y);
+            max_x = max_x.max(pos.x);
+            max_y = max_y.max(pos.y);
         }
 
         let center = Vec2::new(min_x + max_x, min_y + max_y) * 0.5;
@@ -56,6 +57,7 @@ impl Quad {
 
 #[repr(C)]
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     /// Index of the first child in the nodes array (0 if leaf).
     pub children: u32,
@@ -94,20 +96,72 @@ impl Node {
     pub fn is_empty(&self) -> bool {
         self.mass == 0.0
     }
+
+    /// Center of mass and total mass of a group of nodes, e.g. the 4 children of a parent.
+    ///
+    /// The sums are accumulated in f64 and only the results are stored as f32, so each node's
+    /// center of mass is rounded once instead of at every product and sum. With f32 sums the
+    /// error compounds up the levels of a deep tree and shifts the far-field forces.
+    pub fn center_of_mass(nodes: &[Node]) -> (Vec2, f32) {
+        let (mut x, mut y, mut mass) = (0.0f64, 0.0f64, 0.0f64);
+        for node in nodes {
+            let m = node.mass as f64;
+            x += node.pos.x as f64 * m;
+            y += node.pos.y as f64 * m;
+            mass += m;
+        }
+
+        if mass > 0.0 {
+            (Vec2::new((x / mass) as f32, (y / mass) as f32), mass as f32)
+        } else {
+            (Vec2::zero(), 0.0)
+        }
+    }
+
+    /// Copy of a node of a subtree moved into a larger tree (see `Quadtree::splice`):
+    /// indices are offset by `base` and the end of the traversal continues at `next`.
+    fn relocated(&self, base: u32, next: u32) -> Node {
+        Node {
+            children: if self.children == 0 { 0 } else { self.children + base },
+            next: if self.next == 0 { next } else { self.next + base },
+            ..self.clone()
+        }
+    }
 }
 
 /// The Quadtree data structure for the Barnes-Hut simulation.
 /// Uses a flat vector `nodes` for better cache locality.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Quadtree {
     /// Theta squared (opening angle threshold for approximation).
     pub t_sq: f32,
     /// Epsilon squared (softening parameter to avoid singularities).
     pub e_sq: f32,
+    /// Gravitational constant.
+    pub g: f32,
+    /// Exponent `k` of the force law |F| ~ 1 / r^k (2 for Newtonian gravity).
+    pub force_exponent: f32,
     /// Linearized tree nodes.
     pub nodes: Vec<Node>,
     /// Indices of parent nodes, used for bottom-up center of mass propagation.
     pub parents: Vec<usize>,
+    /// Maximum number of nodes. Once another subdivision would exceed it, a body landing in an
+    /// occupied leaf is merged into it instead (see `merged`).
+    #[cfg_attr(feature = "serde", serde(default = "unlimited_nodes"))]
+    pub node_budget: usize,
+    /// Bodies merged into an occupied leaf since the last `clear` because of `node_budget`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub merged: usize,
+    /// Bodies merged into an occupied leaf at exactly their position since the last `clear`.
+    /// Like those in `merged`, the tree doesn't reference them.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub coincident: usize,
+}
+
+#[cfg(feature = "serde")]
+fn unlimited_nodes() -> usize {
+    usize::MAX
 }
 
 impl Default for Quadtree {
@@ -118,13 +172,23 @@ impl Default for Quadtree {
 
 impl Quadtree {
     pub const ROOT: usize = 0;
+    /// Depth of the cells whose subtrees are built independently in a parallel build (see
+    /// `partition`): 4^3 = 64 cells, enough to keep all threads busy when bodies are clustered.
+    pub const SPLIT_DEPTH: u32 = 3;
+    pub const DEFAULT_G: f32 = 1.0;
+    pub const DEFAULT_FORCE_EXPONENT: f32 = 2.0;
 
     pub fn new(theta: f32, epsilon: f32) -> Self {
         Self {
             t_sq: theta * theta,
             e_sq: epsilon * epsilon,
+            g: Self::DEFAULT_G,
+            force_exponent: Self::DEFAULT_FORCE_EXPONENT,
             nodes: Vec::new(),
             parents: Vec::new(),
+            node_budget: usize::MAX,
+            merged: 0,
+            coincident: 0,
         }
     }
 
@@ -132,6 +196,8 @@ impl Quadtree {
     pub fn clear(&mut self, quad: Quad) {
         self.nodes.clear();
         self.parents.clear();
+        self.merged = 0;
+        self.coincident = 0;
         self.nodes.push(Node::new(0, quad));
     }
 
@@ -183,11 +249,22 @@ impl Quadtree {
         // If positions are identical, just add mass (merge bodies/star collision)
         if pos == p {
             self.nodes[node].mass += mass;
+            self.coincident += 1;
             return;
         }
 
         // Otherwise, split the node until the bodies are in different quadrants
         loop {
+            if self.nodes.len() + 4 > self.node_budget {
+                // Out of nodes: the leaf stands for both bodies at their center of mass
+                let total = m + mass;
+                let leaf = &mut self.nodes[node];
+                leaf.pos = if total > 0.0 { (p * m + pos * mass) / total } else { (p + pos) * 0.5 };
+                leaf.mass = total;
+                leaf.body_index = idx;
+                self.merged += 1;
+                return;
+            }
             let children = self.subdivide(node);
 
             let q1 = self.nodes[node].quad.find_quadrant(p);
@@ -213,28 +290,236 @@ impl Quadtree {
         }
     }
 
-    /// Calculates center of mass and total mass for all nodes (bottom-up).
+    /// Calculates center of mass and total mass for all nodes (bottom-up), see `Node::center_of_mass`.
     /// Should be called after all insertions are done.
     pub fn propagate(&mut self) {
         // Iterate parents in reverse insertion order (deepest first)
         for &node in self.parents.iter().rev() {
             let i = self.nodes[node].children as usize;
+            let (pos, mass) = Node::center_of_mass(&self.nodes[i..i + 4]);
+
+            let n = &mut self.nodes[node];
+            n.pos = pos;
+            n.mass = mass;
+        }
+    }
+
+    /// Propagates parent `node` from its children through a pointer to the node array, so the
+    /// parents of one level (see `parent_levels`) can be propagated from several threads.
+    ///
+    /// # Safety
+    /// `nodes` must point to the nodes of a tree in which `node` is a parent, and no other thread
+    /// may access `node` or its children concurrently, except to read the children.
+    pub unsafe fn propagate_node(nodes: *mut Node, node: usize) {
+        unsafe {
+            let n = &mut *nodes.add(node);
+            let children = std::slice::from_raw_parts(nodes.add(n.children as usize), 4);
+            (n.pos, n.mass) = Node::center_of_mass(children);
+        }
+    }
+
+    /// Groups the parents by depth into `levels`, so that `propagate` can be done one level at a
+    /// time, deepest first, with the parents of a level spread across threads.
+    pub fn parent_levels(&self, levels: &mut ParentLevels) {
+        // A node is always subdivided after its parent, so a single pass in `parents` order
+        // reaches each parent after its depth is known
+        levels.depths.clear();
+        levels.depths.resize(self.nodes.len(), 0);
+        let mut max_depth = 0;
+        for &node in &self.parents {
+            let depth = levels.depths[node];
+            let i = self.nodes[node].children as usize;
+            levels.depths[i..i + 4].fill(depth + 1);
+            max_depth = max_depth.max(depth as usize);
+        }
+
+        // Counting sort by depth
+        levels.offsets.clear();
+        levels.offsets.resize(max_depth + 2, 0);
+        for &node in &self.parents {
+            levels.offsets[levels.depths[node] as usize + 1] += 1;
+        }
+        for depth in 0..=max_depth {
+            levels.offsets[depth + 1] += levels.offsets[depth];
+        }
+
+        let mut cursors = levels.offsets[..=max_depth].to_vec();
+        levels.parents.clear();
+        levels.parents.resize(self.parents.len(), 0);
+        for &node in &self.parents {
+            let depth = levels.depths[node] as usize;
+            levels.parents[cursors[depth]] = node as u32;
+            cursors[depth] += 1;
+        }
+        if self.parents.is_empty() {
+            levels.offsets.clear();
+        }
+    }
+
+    /// Sorts the bodies into the cells at `SPLIT_DEPTH` below `quad`, the first phase of a
+    /// parallel build: the subtree of each cell is then built independently with
+    /// `build_subtree` and the subtrees are joined with `splice`.
+    pub fn partition(quad: Quad, positions: &[Vec2], partition: &mut Partition) {
+        let cells = 1 << (2 * Self::SPLIT_DEPTH);
+
+        // A cell's index is its path of quadrants from the root, most significant first,
+        // which is the order `splice` creates the nodes of the last top level in
+        partition.quads.clear();
+        partition.quads.extend((0..cells).map(|cell| {
+            (0..Self::SPLIT_DEPTH)
+                .rev()
+                .fold(quad, |q, level| q.into_quadrant(cell >> (2 * level) & 3))
+        }));
+
+        partition.cells.clear();
+        partition.cells.extend(positions.iter().map(|&pos| {
+            let (mut q, mut cell) = (quad, 0);
+            for _ in 0..Self::SPLIT_DEPTH {
+                let quadrant = q.find_quadrant(pos);
+                cell = cell << 2 | quadrant;
+                q = q.into_quadrant(quadrant);
+            }
+            cell as u32
+        }));
+
+        // Counting sort, keeping the bodies of each cell in their original order
+        partition.offsets.clear();
+        partition.offsets.resize(cells + 1, 0);
+        for &cell in &partition.cells {
+            partition.offsets[cell as usize + 1] += 1;
+        }
+        for cell in 0..cells {
+            partition.offsets[cell + 1] += partition.offsets[cell];
+        }
+
+        let mut cursors = partition.offsets[..cells].to_vec();
+        partition.bodies.clear();
+        partition.bodies.resize(positions.len(), 0);
+        for (i, &cell) in partition.cells.iter().enumerate() {
+            partition.bodies[cursors[cell as usize]] = i as u32;
+            cursors[cell as usize] += 1;
+        }
+    }
+
+    /// Inserts the bodies of one cell of `partition` into this tree, after clearing it to the
+    /// bounds of the cell. The tree isn't propagated yet, see `splice`.
+    pub fn build_subtree(&mut self, partition: &Partition, cell: usize, positions: &[Vec2], masses: &[f32]) {
+        self.clear(partition.quads[cell]);
+        for &i in partition.bodies(cell) {
+            self.insert(positions[i as usize], masses[i as usize], i as usize);
+        }
+    }
+
+    /// Joins the subtrees of the cells of a `partition` of `quad` into this tree, which ends up
+    /// as if all bodies had been inserted into it, and propagates the top levels.
+    ///
+    /// If the subtrees were propagated beforehand the result is fully propagated. Otherwise the
+    /// whole tree needs propagating afterwards, e.g. level by level in parallel (see
+    /// `parent_levels`), which balances better than propagating the subtrees in parallel when
+    /// the bodies crowd into a few cells.
+    ///
+    /// The top `SPLIT_DEPTH` levels are always fully subdivided, which a serial build skips for
+    /// nodes holding a single body; that adds up to 85 nodes but doesn't change any forces, as
+    /// such a branch has the same mass and center of mass as the body.
+    pub fn splice(&mut self, quad: Quad, subtrees: &[Quadtree]) {
+        self.clear(quad);
+
+        // The nodes of each level are created together and in quadrant order
+        let mut level = Self::ROOT..Self::ROOT + 1;
+        for _ in 0..Self::SPLIT_DEPTH {
+            let start = self.nodes.len();
+            for node in level {
+                self.subdivide(node);
+            }
+            level = start..self.nodes.len();
+        }
+        debug_assert_eq!(level.len(), subtrees.len());
+
+        let top_parents = self.parents.len();
+        for (cell, subtree) in level.zip(subtrees) {
+            // Local node k > 0 moves to base + k, and the local root replaces the cell node,
+            // whose `next` ends the subtree's traversal instead of 0
+            let base = self.nodes.len() as u32 - 1;
+            let next = self.nodes[cell].next;
+            self.nodes[cell] = subtree.nodes[Self::ROOT].relocated(base, next);
+            self.nodes.extend(subtree.nodes[1..].iter().map(|n| n.relocated(base, next)));
+            self.parents.extend(
+                subtree
+                    .parents
+                    .iter()
+                    .map(|&p| if p == Self::ROOT { cell } else { p + base as usize }),
+            );
+            self.merged += subtree.merged;
+            self.coincident += subtree.coincident;
+        }
+
+        for &node in self.parents[..top_parents].iter().rev() {
+            // SAFETY: `node` is a parent of this tree
+            unsafe { Self::propagate_node(self.nodes.as_mut_ptr(), node) };
+        }
+    }
+
+    /// Moves the nodes into the given `layout`, rewriting the `children` and `next` indices and
+    /// `parents`. `scratch` holds the previous node array afterwards, to be reused next time.
+    ///
+    /// The tree is unchanged apart from the node indices; each group of 4 siblings stays
+    /// contiguous and the root stays first.
+    pub fn reorder(&mut self, layout: NodeLayout, scratch: &mut NodeReorder) {
+        let len = self.nodes.len();
+        if len == 0 || layout == NodeLayout::Build {
+            return;
+        }
+
+        // Old index of each node in the new order: sibling groups are placed in the order their
+        // parents are visited, either level by level or depth-first with a stack
+        let order = &mut scratch.order;
+        order.clear();
+        order.reserve(len);
+        order.push(Self::ROOT as u32);
+        let stack = &mut scratch.stack;
+        stack.clear();
+        stack.push(Self::ROOT as u32);
+
+        if layout == NodeLayout::BreadthFirst {
+            let mut visited = 0;
+            while visited < order.len() {
+                let children = self.nodes[order[visited] as usize].children;
+                if children != 0 {
+                    order.extend(children..children + 4);
+                }
+                visited += 1;
+            }
+        } else {
+            while let Some(node) = stack.pop() {
+                let children = self.nodes[node as usize].children;
+                if children != 0 {
+                    order.extend(children..children + 4);
+                    stack.extend((children..children + 4).rev());
+                }
+            }
+        }
+
+        let map = &mut scratch.map;
+        map.clear();
+        map.resize(len, 0);
+        for (new, &old) in order.iter().enumerate() {
+            map[old as usize] = new as u32;
+        }
 
-            // Compute center of mass: (Sum(pos * mass) / TotalMass)
-            self.nodes[node].pos = self.nodes[i].pos * self.nodes[i].mass
-                + self.nodes[i + 1].pos * self.nodes[i + 1].mass
-                + self.nodes[i + 2].pos * self.nodes[i + 2].mass
-                + self.nodes[i + 3].pos * self.nodes[i + 3].mass;
-            
-            self.nodes[node].mass = self.nodes[i].mass
-                + self.nodes[i + 1].mass
-                + self.nodes[i + 2].mass
-                + self.nodes[i + 3].mass;
-
-            let mass = self.nodes[node].mass;
-            if mass > 0.0 {
-                self.nodes[node].pos /= mass;
+        let nodes = &mut scratch.nodes;
+        nodes.clear();
+        nodes.extend(order.iter().map(|&old| {
+            let n = &self.nodes[old as usize];
+            Node {
+                children: if n.children == 0 { 0 } else { map[n.children as usize] },
+                next: map[n.next as usize],
+                ..n.clone()
             }
+        }));
+        std::mem::swap(&mut self.nodes, nodes);
+
+        for parent in &mut self.parents {
+            *parent = map[*parent] as usize;
         }
     }
 
@@ -242,6 +527,13 @@ impl Quadtree {
     /// Uses the Barnes-Hut approximation criteria.
     #[inline(always)]
     pub fn acc(&self, pos: Vec2) -> Vec2 {
+        self.acc_with(pos, self.t_sq)
+    }
+
+    /// Like `acc`, with the squared opening angle `t_sq` instead of the tree's (see
+    /// `leaf_order_adaptive`).
+    #[inline(always)]
+    pub fn acc_with(&self, pos: Vec2, t_sq: f32) -> Vec2 {
         let mut acc = Vec2::zero();
 
         let mut node_idx = Self::ROOT;
@@ -259,11 +551,16 @@ impl Quadtree {
 
             // Check Barnes-Hut criterion: s/d < theta
             // Equivalent to: s^2 < d^2 * theta^2
-            if n.is_leaf() || n.quad.size * n.quad.size < d_sq * self.t_sq {
+            if n.is_leaf() || n.quad.size * n.quad.size < d_sq * t_sq {
                 // Treat node as a single body
                 if n.mass > 1e-10 {
                     let denom_term = d_sq + self.e_sq;
-                    let denom = denom_term * denom_term.sqrt();
+                    // Fast path for the Newtonian inverse-square law
+                    let denom = if self.force_exponent == 2.0 {
+                        denom_term * denom_term.sqrt()
+                    } else {
+                        denom_term.powf(0.5 * (self.force_exponent + 1.0))
+                    };
                     acc += d * (n.mass / denom);
                 }
 
@@ -278,7 +575,352 @@ impl Quadtree {
             }
         }
 
-        acc
+        acc * self.g
+    }
+
+    /// Calculates the accelerations at 8 positions at once with SIMD, one per lane.
+    ///
+    /// The lanes share a single tree walk: a node is evaluated for the lanes that accept it under
+    /// the Barnes-Hut criterion and opened for the others, so every lane sees exactly the nodes
+    /// `acc` would, in the same order, and gets the same result. The walk is the dominant cost,
+    /// so lanes should be spatially close (see `leaf_order`) to share most of it.
+    /// `scopes` is scratch space reused across calls.
+    /// Non-Newtonian force exponents fall back to `acc` per lane.
+    #[inline(always)]
+    pub fn acc8(&self, x: f32x8, y: f32x8, scopes: &mut Vec<(u32, f32x8)>) -> (f32x8, f32x8) {
+        self.acc8_with(x, y, f32x8::splat(self.t_sq), scopes)
+    }
+
+    /// Like `acc8`, with a squared opening angle per lane instead of the tree's (see
+    /// `leaf_order_adaptive`).
+    #[inline(always)]
+    pub fn acc8_with(&self, x: f32x8, y: f32x8, t_sq: f32x8, scopes: &mut Vec<(u32, f32x8)>) -> (f32x8, f32x8) {
+        if self.force_exponent != 2.0 {
+            let (x, y, t_sq) = (x.to_array(), y.to_array(), t_sq.to_array());
+            let acc: [Vec2; 8] = std::array::from_fn(|i| self.acc_with(Vec2::new(x[i], y[i]), t_sq[i]));
+            return (f32x8::from(acc.map(|a| a.x)), f32x8::from(acc.map(|a| a.y)));
+        }
+
+        let (mut ax, mut ay) = (f32x8::ZERO, f32x8::ZERO);
+        if self.nodes.is_empty() {
+            return (ax, ay);
+        }
+
+        let e_sq = f32x8::splat(self.e_sq);
+        // Lanes still walking the current subtree; `scopes` holds the node where each opened
+        // subtree ends (the opened node's `next`) and the lanes to restore there
+        let mut active = f32x8::ZERO.cmp_eq(f32x8::ZERO);
+        scopes.clear();
+
+        let mut node_idx = Self::ROOT;
+        loop {
+            // SAFETY: as in `acc`
+            let n = unsafe { self.nodes.get_unchecked(node_idx) };
+
+            let dx = f32x8::splat(n.pos.x) - x;
+            let dy = f32x8::splat(n.pos.y) - y;
+            let d_sq = dx * dx + dy * dy;
+
+            let accept = if n.is_leaf() {
+                active
+            } else {
+                active & f32x8::splat(n.quad.size * n.quad.size).cmp_lt(d_sq * t_sq)
+            };
+
+            if n.mass > 1e-10 && accept.any() {
+                let denom_term = d_sq + e_sq;
+                let f = f32x8::splat(n.mass) / (denom_term * denom_term.sqrt());
+                let f = accept.blend(f, f32x8::ZERO);
+                ax += dx * f;
+                ay += dy * f;
+            }
+
+            let open = active & !accept;
+            if n.is_branch() && open.any() {
+                scopes.push((n.next, active));
+                active = open;
+                node_idx = n.children as usize;
+                continue;
+            }
+
+            // Skip children, go to next sibling/node
+            if n.next == 0 {
+                break;
+            }
+            node_idx = n.next as usize;
+            while let Some(&(end, lanes)) = scopes.last() {
+                if end as usize != node_idx {
+                    break;
+                }
+                active = lanes;
+                scopes.pop();
+            }
+        }
+
+        let g = f32x8::splat(self.g);
+        (ax * g, ay * g)
+    }
+
+    /// Writes the indices of `count` bodies in tree order, so that runs of consecutive entries
+    /// are spatially close (for `acc8`). Bodies that share a leaf with a coincident body and so
+    /// are not referenced by the tree are appended at the end.
+    pub fn leaf_order(&self, count: usize, order: &mut Vec<u32>, scratch: &mut LeafWalk) {
+        self.walk_leaves(count, order, None, scratch);
+    }
+
+    /// Like `leaf_order`, also writing the squared opening angle of each body under `adaptive`
+    /// to `t_sq`, in the same order. It follows from the mass density around the body's leaf:
+    /// the mass of the smallest enclosing node holding at least `AdaptiveTheta::DENSITY_BODIES`
+    /// average bodies over its area, relative to the geometric mean over all bodies (so that a
+    /// single dominant mass doesn't skew it). Bodies the tree doesn't reference get the tree's
+    /// `t_sq`.
+    pub fn leaf_order_adaptive(
+        &self,
+        count: usize,
+        adaptive: &AdaptiveTheta,
+        order: &mut Vec<u32>,
+        t_sq: &mut Vec<f32>,
+        scratch: &mut LeafWalk,
+    ) {
+        // First the density of each body
+        self.walk_leaves(count, order, Some(t_sq), scratch);
+        t_sq.resize(order.len(), f32::NAN);
+
+        // Then the opening angles relative to the mean log density
+        let measured = |rho: f32| rho.is_finite() && rho > 0.0;
+        let (sum, n) = t_sq.iter().filter(|&&rho| measured(rho)).fold((0.0f64, 0usize), |(sum, n), &rho| {
+            (sum + (rho as f64).ln(), n + 1)
+        });
+        let mean = if n > 0 { (sum / n as f64).exp() as f32 } else { 1.0 };
+        let theta = self.t_sq.sqrt();
+        for t_sq in t_sq.iter_mut() {
+            *t_sq = if measured(*t_sq) {
+                let local = (theta * (*t_sq / mean).powf(-adaptive.strength)).clamp(adaptive.min, adaptive.max);
+                local * local
+            } else {
+                self.t_sq
+            };
+        }
+    }
+
+    /// Tree walk of `leaf_order`, also writing the local density of each body it finds to
+    /// `densities` if given (see `leaf_order_adaptive`).
+    fn walk_leaves(
+        &self,
+        count: usize,
+        order: &mut Vec<u32>,
+        mut densities: Option<&mut Vec<f32>>,
+        scratch: &mut LeafWalk,
+    ) {
+        order.clear();
+        if let Some(densities) = densities.as_deref_mut() {
+            densities.clear();
+        }
+        let LeafWalk { seen, scopes } = scratch;
+        seen.clear();
+        seen.resize(count, false);
+        scopes.clear();
+
+        if !self.nodes.is_empty() {
+            let root = &self.nodes[Self::ROOT];
+            let density = |n: &Node| n.mass / (n.quad.size * n.quad.size);
+            let min_mass = root.mass / count.max(1) as f32 * AdaptiveTheta::DENSITY_BODIES;
+            // Density of the innermost node heavy enough to measure it; `scopes` holds where
+            // each opened subtree ends and the density to restore there
+            let mut current = density(root);
+
+            let mut node_idx = Self::ROOT;
+            loop {
+                let n = &self.nodes[node_idx];
+                if n.is_branch() {
+                    if densities.is_some() {
+                        scopes.push((n.next, current));
+                        if n.mass >= min_mass {
+                            current = density(n);
+                        }
+                    }
+                    node_idx = n.children as usize;
+                    continue;
+                }
+
+                let i = n.body_index as usize;
+                if n.mass > 0.0 && i < count && !seen[i] {
+                    seen[i] = true;
+                    order.push(n.body_index);
+                    if let Some(densities) = densities.as_deref_mut() {
+                        densities.push(current);
+                    }
+                }
+
+                if n.next == 0 {
+                    break;
+                }
+                node_idx = n.next as usize;
+                while let Some(&(end, restored)) = scopes.last() {
+                    if end as usize != node_idx {
+                        break;
+                    }
+                    current = restored;
+                    scopes.pop();
+                }
+            }
+        }
+
+        order.extend((0..count as u32).filter(|&i| !seen[i as usize]));
+    }
+
+    /// Softened potential of a point mass at squared distance `d_sq`,
+    /// consistent with the configured gravitational constant and force exponent.
+    #[inline(always)]
+    pub fn pair_potential(&self, mass: f32, d_sq: f32) -> f32 {
+        let r_sq = d_sq + self.e_sq;
+        let k = self.force_exponent;
+
+        let phi = if k == 2.0 {
+            -mass / r_sq.sqrt()
+        } else if k == 1.0 {
+            0.5 * mass * r_sq.ln()
+        } else {
+            -mass * r_sq.powf(0.5 * (1.0 - k)) / (k - 1.0)
+        };
+
+        self.g * phi
+    }
+
+    /// Calculates the softened gravitational potential at a given position.
+    /// Uses the same Barnes-Hut acceptance criterion as `acc`.
+    pub fn potential(&self, pos: Vec2) -> f32 {
+        // Summed in f64, as energies are compared across steps to small relative tolerances
+        let mut phi = 0.0f64;
+
+        let mut node_idx = Self::ROOT;
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+
+        loop {
+            let n = &self.nodes[node_idx];
+
+            let d_sq = (n.pos - pos).mag_sq();
+
+            if n.is_leaf() || n.quad.size * n.quad.size < d_sq * self.t_sq {
+                if n.mass > 1e-10 {
+                    phi += self.pair_potential(n.mass, d_sq) as f64;
+                }
+
+                if n.next == 0 {
+                    break;
+                }
+                node_idx = n.next as usize;
+            } else {
+                node_idx = n.children as usize;
+            }
+        }
+
+        phi as f32
+    }
+
+    /// Surface density around `pos`: the mass per unit area of the smallest node containing it
+    /// that is still at least `resolution` wide, so the density is smoothed over about that scale.
+    /// Returns 0 outside the tree.
+    pub fn density(&self, pos: Vec2, resolution: f32) -> f32 {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        let mut n = &self.nodes[Self::ROOT];
+        let half = 0.5 * n.quad.size;
+        if (pos.x - n.quad.center.x).abs() > half || (pos.y - n.quad.center.y).abs() > half {
+            return 0.0;
+        }
+
+        while n.is_branch() && 0.5 * n.quad.size >= resolution {
+            n = &self.nodes[n.children as usize + n.quad.find_quadrant(pos)];
+        }
+        n.mass / (n.quad.size * n.quad.size)
+    }
+
+    /// Index of the leaf whose quad contains `pos`, which is where `insert` put a body there.
+    pub fn find_leaf(&self, pos: Vec2) -> usize {
+        let mut node = Self::ROOT;
+        while self.nodes[node].is_branch() {
+            node = self.nodes[node].children as usize + self.nodes[node].quad.find_quadrant(pos);
+        }
+        node
+    }
+
+    /// Shape of the tree: its depth and leaf occupancy from `leaf_bodies`, the number of bodies
+    /// in each node's quad (only read for leaves, see `find_leaf`), and how often the opening
+    /// criterion accepts a branch on force walks from the `samples` positions.
+    pub fn metrics(&self, leaf_bodies: &[u32], samples: &[Vec2]) -> TreeMetrics {
+        let mut metrics = TreeMetrics::default();
+        if self.nodes.is_empty() {
+            return metrics;
+        }
+
+        // Walk the nodes in traversal order; `ends` holds where each opened subtree ends
+        let mut ends: Vec<u32> = Vec::new();
+        let mut empty = 0;
+        let mut node = Self::ROOT;
+        loop {
+            let n = &self.nodes[node];
+            metrics.max_depth = metrics.max_depth.max(ends.len() as u32);
+            if n.is_branch() {
+                ends.push(n.next);
+                node = n.children as usize;
+                continue;
+            }
+
+            metrics.leaves += 1;
+            match leaf_bodies.get(node).copied().unwrap_or(0) {
+                0 => empty += 1,
+                bodies => {
+                    let bucket = bodies.ilog2() as usize;
+                    if metrics.bodies_per_leaf.len() <= bucket {
+                        metrics.bodies_per_leaf.resize(bucket + 1, 0);
+                    }
+                    metrics.bodies_per_leaf[bucket] += 1;
+                }
+            }
+
+            if n.next == 0 {
+                break;
+            }
+            node = n.next as usize;
+            while ends.last() == Some(&(node as u32)) {
+                ends.pop();
+            }
+        }
+        metrics.empty_leaf_fraction = empty as f32 / metrics.leaves as f32;
+
+        let (mut opened, mut accepted, mut interactions) = (0usize, 0usize, 0usize);
+        for &pos in samples {
+            let mut node = Self::ROOT;
+            loop {
+                let n = &self.nodes[node];
+                if n.is_branch() && n.quad.size * n.quad.size >= (n.pos - pos).mag_sq() * self.t_sq {
+                    opened += 1;
+                    node = n.children as usize;
+                    continue;
+                }
+                if n.is_branch() {
+                    accepted += 1;
+                }
+                if n.mass > 1e-10 {
+                    interactions += 1;
+                }
+                if n.next == 0 {
+                    break;
+                }
+                node = n.next as usize;
+            }
+        }
+        if opened + accepted > 0 {
+            metrics.acceptance_ratio = accepted as f32 / (opened + accepted) as f32;
+        }
+        if !samples.is_empty() {
+            metrics.interactions_per_body = interactions as f32 / samples.len() as f32;
+        }
+        metrics
     }
 
     /// Finds potential collisions for a body using the quadtree.
@@ -322,3 +964,150 @@ impl Quadtree {
         }
     }
 }
+
+/// Opening angle that varies with the local mass density, see `Quadtree::leaf_order_adaptive`.
+///
+/// Each body opens nodes at `theta * (density / mean density)^-strength`, clamped to
+/// `min..=max`: tighter in dense regions such as a galactic core, where most of the force
+/// error comes from, and looser in the sparse outskirts, where it saves most of the walk.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AdaptiveTheta {
+    /// Exponent of the density ratio; 0 leaves the opening angle uniform.
+    pub strength: f32,
+    /// Smallest opening angle, in the densest regions.
+    pub min: f32,
+    /// Largest opening angle, in the sparsest regions.
+    pub max: f32,
+}
+
+impl AdaptiveTheta {
+    /// Average bodies a node must hold (by mass) for its density to count as local density,
+    /// so that a body's own leaf doesn't make it look arbitrarily dense.
+    pub const DENSITY_BODIES: f32 = 16.0;
+}
+
+impl Default for AdaptiveTheta {
+    fn default() -> Self {
+        Self {
+            strength: 0.25,
+            min: 0.3,
+            max: 1.5,
+        }
+    }
+}
+
+/// Order of the nodes in memory, see `Quadtree::reorder`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NodeLayout {
+    /// The order nodes are created in while inserting, which scatters deep subtrees.
+    #[default]
+    Build = 0,
+    /// Sibling groups in the order of a depth-first traversal, which is the order force and
+    /// collision queries visit them in.
+    DepthFirst = 1,
+    /// Sibling groups level by level, keeping the top levels that every query visits together.
+    BreadthFirst = 2,
+}
+
+/// Scratch space of `Quadtree::leaf_order` and `Quadtree::leaf_order_adaptive`.
+#[derive(Clone, Debug, Default)]
+pub struct LeafWalk {
+    /// Whether each body has been found in a leaf.
+    seen: Vec<bool>,
+    /// Where each opened subtree ends and the density to restore there.
+    scopes: Vec<(u32, f32)>,
+}
+
+/// Scratch space of `Quadtree::reorder`.
+#[derive(Clone, Debug, Default)]
+pub struct NodeReorder {
+    order: Vec<u32>,
+    stack: Vec<u32>,
+    map: Vec<u32>,
+    nodes: Vec<Node>,
+}
+
+/// Bodies sorted by the cell at `Quadtree::SPLIT_DEPTH` they fall into, see `Quadtree::partition`.
+#[derive(Clone, Debug, Default)]
+pub struct Partition {
+    /// Bounds of each cell.
+    pub quads: Vec<Quad>,
+    /// Body indices grouped by cell, in their original order within a cell.
+    pub bodies: Vec<u32>,
+    /// Start of each cell's bodies in `bodies`, followed by the body count.
+    pub offsets: Vec<usize>,
+    /// Cell of each body.
+    cells: Vec<u32>,
+}
+
+impl Partition {
+    /// Number of cells.
+    pub fn len(&self) -> usize {
+        self.quads.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.quads.is_empty()
+    }
+
+    /// Indices of the bodies in `cell`.
+    pub fn bodies(&self, cell: usize) -> &[u32] {
+        &self.bodies[self.offsets[cell]..self.offsets[cell + 1]]
+    }
+
+    /// Number of bodies in the fullest cell.
+    pub fn largest_cell(&self) -> usize {
+        self.offsets.windows(2).map(|w| w[1] - w[0]).max().unwrap_or(0)
+    }
+}
+
+/// Parents of a tree grouped by depth, see `Quadtree::parent_levels`.
+#[derive(Clone, Debug, Default)]
+pub struct ParentLevels {
+    /// Parent node indices, sorted by depth.
+    pub parents: Vec<u32>,
+    /// Start of each depth's parents in `parents`, followed by the parent count.
+    pub offsets: Vec<usize>,
+    /// Depth of each node.
+    depths: Vec<u16>,
+}
+
+impl ParentLevels {
+    /// Number of depths holding parents.
+    pub fn len(&self) -> usize {
+        self.offsets.len().saturating_sub(1)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Parents at `depth`, where the root is at depth 0.
+    pub fn level(&self, depth: usize) -> &[u32] {
+        &self.parents[self.offsets[depth]..self.offsets[depth + 1]]
+    }
+}
+
+/// Shape of a tree, see `Quadtree::metrics`. A growing depth, many empty leaves or leaves holding
+/// several bodies mean the distribution degrades the tree: more nodes to build and walk for the
+/// same bodies.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TreeMetrics {
+    /// Depth of the deepest node, where the root is at depth 0.
+    pub max_depth: u32,
+    pub leaves: usize,
+    /// Fraction of the leaves holding no body, left over from subdividing crowded quads.
+    pub empty_leaf_fraction: f32,
+    /// Occupied leaves by the number of bodies in their quad: `bodies_per_leaf[k]` counts the
+    /// leaves holding 2^k to 2^(k+1) - 1 bodies. Only coincident bodies and the node budget put
+    /// more than one body in a leaf.
+    pub bodies_per_leaf: Vec<usize>,
+    /// Fraction of the branches reached on a force walk that the opening criterion accepts
+    /// rather than opens, estimated from a sample of bodies.
+    pub acceptance_ratio: f32,
+    /// Mean number of nodes a body interacts with, from the same sample.
+    pub interactions_per_body: f32,
+}
@@ -0,0 +1,364 @@
+use crate::{bodies::Bodies, simulation::Simulation};
+use ultraviolet::Vec2;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Recording layout (all values little-endian):
+///
+/// | field   | type      |
+/// |---------|-----------|
+/// | magic   | `b"NBRC"` |
+/// | version | u32       |
+/// | frames  | frame records until the end of the file |
+///
+/// Each frame record is: kind u8, frame u64, time f64, count u64, payload length u64, payload.
+/// - Keyframes (kind 0) store `count` records of pos.x, pos.y, radius as f32.
+/// - Delta frames (kind 1) store, for each coordinate of each position, the XOR of its f32 bits
+///   with the previous frame's as a LEB128 varint. Bodies move little per frame, so the sign,
+///   exponent and upper mantissa bits cancel and most values take 1-3 bytes instead of 4.
+///   Radii are carried over from the previous frame.
+pub const RECORDING_MAGIC: [u8; 4] = *b"NBRC";
+pub const RECORDING_VERSION: u32 = 1;
+
+const KEYFRAME: u8 = 0;
+const DELTA: u8 = 1;
+
+/// Size in bytes of a frame record header.
+const FRAME_HEADER_SIZE: u64 = 1 + 8 + 8 + 8 + 8;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        out.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn read_varint(data: &[u8], cursor: &mut usize) -> io::Result<u32> {
+    let mut v = 0u32;
+    for shift in (0..35).step_by(7) {
+        let byte = *data.get(*cursor).ok_or_else(|| invalid_data("truncated delta frame"))?;
+        *cursor += 1;
+        v |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(v);
+        }
+    }
+    Err(invalid_data("invalid varint in delta frame"))
+}
+
+/// Appends frames to a recording file during a run (see `RECORDING_MAGIC` for the layout).
+///
+/// A keyframe is written every `keyframe_interval` frames and whenever the body count changes;
+/// all other frames are stored as deltas against the previous one.
+#[derive(Debug)]
+pub struct Recorder {
+    writer: BufWriter<File>,
+    keyframe_interval: usize,
+    frames_since_keyframe: usize,
+    /// Position bits of the last recorded frame.
+    prev: Vec<[u32; 2]>,
+    payload: Vec<u8>,
+}
+
+impl Recorder {
+    /// Creates the recording file. A `keyframe_interval` of 0 is treated as 1 (keyframes only).
+    pub fn create(path: impl AsRef<Path>, keyframe_interval: usize) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&RECORDING_MAGIC)?;
+        writer.write_all(&RECORDING_VERSION.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            keyframe_interval: keyframe_interval.max(1),
+            frames_since_keyframe: 0,
+            prev: Vec::new(),
+            payload: Vec::new(),
+        })
+    }
+
+    /// Appends the current bodies of `sim` as a new frame.
+    pub fn record(&mut self, sim: &Simulation) -> io::Result<()> {
+        self.record_bodies(sim.frame, sim.time, &sim.bodies)
+    }
+
+    pub fn record_bodies(&mut self, frame: usize, time: f64, bodies: &Bodies) -> io::Result<()> {
+        let keyframe = self.prev.is_empty()
+            || self.prev.len() != bodies.len()
+            || self.frames_since_keyframe + 1 >= self.keyframe_interval;
+
+        self.payload.clear();
+        if keyframe {
+            for (pos, radius) in bodies.positions().iter().zip(bodies.radii()) {
+                for v in [pos.x, pos.y, *radius] {
+                    self.payload.extend(v.to_le_bytes());
+                }
+            }
+            self.frames_since_keyframe = 0;
+        } else {
+            for (pos, prev) in bodies.positions().iter().zip(&self.prev) {
+                write_varint(&mut self.payload, pos.x.to_bits() ^ prev[0]);
+                write_varint(&mut self.payload, pos.y.to_bits() ^ prev[1]);
+            }
+            self.frames_since_keyframe += 1;
+        }
+
+        self.prev.clear();
+        self.prev.extend(bodies.positions().iter().map(|pos| [pos.x.to_bits(), pos.y.to_bits()]));
+
+        self.writer.write_all(&[if keyframe { KEYFRAME } else { DELTA }])?;
+        self.writer.write_all(&(frame as u64).to_le_bytes())?;
+        self.writer.write_all(&time.to_le_bytes())?;
+        self.writer.write_all(&(bodies.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&(self.payload.len() as u64).to_le_bytes())?;
+        self.writer.write_all(&self.payload)
+    }
+
+    /// Flushes buffered frames to the file.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Location of a recorded frame in the file.
+#[derive(Clone, Copy, Debug)]
+pub struct FrameEntry {
+    pub frame: usize,
+    pub time: f64,
+    pub count: usize,
+    keyframe: bool,
+    offset: u64,
+    len: u64,
+}
+
+/// Plays back a recording, seeking forwards and backwards between frames.
+///
+/// Seeking decodes from the nearest keyframe at or before the target,
+/// so scrubbing costs at most one keyframe interval of decoding.
+#[derive(Debug)]
+pub struct Player {
+    reader: BufReader<File>,
+    index: Vec<FrameEntry>,
+    /// Position in `index` of the decoded frame.
+    current: Option<usize>,
+    positions: Vec<Vec2>,
+    radii: Vec<f32>,
+    payload: Vec<u8>,
+}
+
+impl Player {
+    /// Opens a recording and indexes its frames. A truncated last frame (from an interrupted run) is ignored.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let file_len = reader.get_ref().metadata()?.len();
+
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if header[0..4] != RECORDING_MAGIC {
+            return Err(invalid_data("not an nbody recording"));
+        }
+        if u32::from_le_bytes(header[4..8].try_into().unwrap()) != RECORDING_VERSION {
+            return Err(invalid_data("unsupported recording version"));
+        }
+
+        let mut index = Vec::new();
+        let mut offset = 8u64;
+        while offset + FRAME_HEADER_SIZE <= file_len {
+            let mut record = [0u8; FRAME_HEADER_SIZE as usize];
+            reader.read_exact(&mut record)?;
+            let u = |i: usize| u64::from_le_bytes(record[i..i + 8].try_into().unwrap());
+
+            let len = u(25);
+            let payload_offset = offset + FRAME_HEADER_SIZE;
+            // Also catches lengths so corrupt that adding them would overflow
+            if len > file_len - payload_offset {
+                break;
+            }
+
+            index.push(FrameEntry {
+                frame: u(1) as usize,
+                time: f64::from_bits(u(9)),
+                count: u(17) as usize,
+                keyframe: record[0] == KEYFRAME,
+                offset: payload_offset,
+                len,
+            });
+            offset = payload_offset + len;
+            reader.seek(SeekFrom::Start(offset))?;
+        }
+
+        if index.first().is_some_and(|entry| !entry.keyframe) {
+            return Err(invalid_data("recording does not start with a keyframe"));
+        }
+
+        Ok(Self {
+            reader,
+            index,
+            current: None,
+            positions: Vec::new(),
+            radii: Vec::new(),
+            payload: Vec::new(),
+        })
+    }
+
+    /// All recorded frames, in order.
+    pub fn frames(&self) -> &[FrameEntry] {
+        &self.index
+    }
+
+    /// Position in `frames()` of the decoded frame, if any.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// Decodes the frame at position `i` in `frames()`.
+    pub fn seek(&mut self, i: usize) -> io::Result<()> {
+        if i >= self.index.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "frame index out of range"));
+        }
+
+        let keyframe = (0..=i).rev().find(|&k| self.index[k].keyframe).unwrap_or(0);
+        // Continue from the decoded frame if no keyframe lies in between
+        let start = match self.current {
+            Some(c) if c < i && c >= keyframe => c + 1,
+            _ => keyframe,
+        };
+
+        for k in start..=i {
+            if let Err(e) = self.decode(k) {
+                self.current = None;
+                return Err(e);
+            }
+            self.current = Some(k);
+        }
+        Ok(())
+    }
+
+    /// Advances to the next frame. Returns false at the end of the recording.
+    pub fn step_forward(&mut self) -> io::Result<bool> {
+        let next = self.current.map_or(0, |c| c + 1);
+        if next >= self.index.len() {
+            return Ok(false);
+        }
+        self.seek(next)?;
+        Ok(true)
+    }
+
+    /// Goes back to the previous frame. Returns false at the start of the recording.
+    pub fn step_back(&mut self) -> io::Result<bool> {
+        match self.current {
+            Some(c) if c > 0 => {
+                self.seek(c - 1)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// Body positions of the decoded frame.
+    pub fn positions(&self) -> &[Vec2] {
+        &self.positions
+    }
+
+    /// Body radii of the decoded frame.
+    pub fn radii(&self) -> &[f32] {
+        &self.radii
+    }
+
+    fn decode(&mut self, k: usize) -> io::Result<()> {
+        let entry = self.index[k];
+        // `open` checked that the payload lies within the file, but not that it fits in memory
+        let len = usize::try_from(entry.len).map_err(|_| invalid_data("frame too large"))?;
+        if entry.keyframe && entry.count.checked_mul(12) != Some(len) {
+            return Err(invalid_data("keyframe size doesn't match its body count"));
+        }
+        self.payload.resize(len, 0);
+        self.reader.seek(SeekFrom::Start(entry.offset))?;
+        self.reader.read_exact(&mut self.payload)?;
+
+        if entry.keyframe {
+            let f = |i: usize| f32::from_le_bytes(self.payload[i * 4..i * 4 + 4].try_into().unwrap());
+            self.positions = (0..entry.count).map(|i| Vec2::new(f(i * 3), f(i * 3 + 1))).collect();
+            self.radii = (0..entry.count).map(|i| f(i * 3 + 2)).collect();
+        } else {
+            if self.positions.len() != entry.count {
+                return Err(invalid_data("delta frame doesn't match the previous body count"));
+            }
+            let mut cursor = 0;
+            for pos in self.positions.iter_mut() {
+                pos.x = f32::from_bits(pos.x.to_bits() ^ read_varint(&self.payload, &mut cursor)?);
+                pos.y = f32::from_bits(pos.y.to_bits() ^ read_varint(&self.payload, &mut cursor)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{body::Body, test_support::TempFile};
+
+    fn bits(positions: &[Vec2]) -> Vec<[u32; 2]> {
+        positions.iter().map(|pos| [pos.x.to_bits(), pos.y.to_bits()]).collect()
+    }
+
+    #[test]
+    fn player_decodes_recorded_frames() {
+        let file = TempFile::new("recording.nbrc");
+        let mut sim = Simulation::with_params(200, 0.1, 1.0, 1.0);
+        let mut recorder = Recorder::create(file.path(), 4).unwrap();
+        let mut expected = Vec::new();
+        for frame in 0..12 {
+            if frame == 6 {
+                // A new body count forces a keyframe
+                sim.add_body(Body::new(Vec2::new(1.0, 1.0), Vec2::zero(), 1.0, 2.5));
+            }
+            recorder.record(&sim).unwrap();
+            expected.push((sim.frame, bits(sim.bodies.positions()), sim.bodies.radii().to_vec()));
+            sim.step();
+        }
+        recorder.flush().unwrap();
+
+        let mut player = Player::open(file.path()).unwrap();
+        assert_eq!(player.frames().len(), expected.len());
+        let keyframes: Vec<bool> = player.frames().iter().map(|entry| entry.keyframe).collect();
+        assert_eq!(keyframes.iter().filter(|&&k| k).count(), 4);
+        assert!(keyframes[6]);
+
+        let check = |player: &Player, i: usize| {
+            let (frame, positions, radii) = &expected[i];
+            assert_eq!(player.current(), Some(i));
+            assert_eq!(player.frames()[i].frame, *frame);
+            assert_eq!(bits(player.positions()), *positions);
+            assert_eq!(player.radii(), &radii[..]);
+        };
+        for i in 0..expected.len() {
+            assert!(player.step_forward().unwrap());
+            check(&player, i);
+        }
+        assert!(!player.step_forward().unwrap());
+        for i in (0..expected.len() - 1).rev() {
+            assert!(player.step_back().unwrap());
+            check(&player, i);
+        }
+        player.seek(9).unwrap();
+        check(&player, 9);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let file = TempFile::new("not-a-recording.nbrc");
+        std::fs::write(file.path(), b"NBSS\x02\0\0\0").unwrap();
+        assert_eq!(
+            Player::open(file.path()).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
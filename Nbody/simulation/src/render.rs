@@ -0,0 +1,262 @@
+use crate::simulation::Simulation;
+use rayon::prelude::*;
+
+#[cfg(feature = "png")]
+use std::{fs::File, io::BufWriter, path::Path};
+
+/// Color ramps for mapping density grids to RGB.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Colormap {
+    Grayscale,
+    /// Black through purple and orange to pale yellow, approximating matplotlib's "inferno".
+    #[default]
+    Inferno,
+    /// Dark blue through teal and green to yellow, approximating matplotlib's "viridis".
+    Viridis,
+}
+
+/// Control points of the inferno ramp, evenly spaced over [0, 1].
+const INFERNO: [[f32; 3]; 6] = [
+    [0.0, 0.0, 4.0],
+    [66.0, 10.0, 104.0],
+    [147.0, 38.0, 103.0],
+    [221.0, 81.0, 58.0],
+    [252.0, 165.0, 10.0],
+    [252.0, 255.0, 164.0],
+];
+
+/// Control points of the viridis ramp, evenly spaced over [0, 1].
+const VIRIDIS: [[f32; 3]; 6] = [
+    [68.0, 1.0, 84.0],
+    [65.0, 68.0, 135.0],
+    [42.0, 120.0, 142.0],
+    [34.0, 168.0, 132.0],
+    [122.0, 209.0, 81.0],
+    [253.0, 231.0, 37.0],
+];
+
+/// How `Simulation::render_density_texture` spreads each body's mass over the grid.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SplatKernel {
+    /// All of it into the cell containing the body.
+    #[default]
+    Nearest,
+    /// A Gaussian with a standard deviation of `sigma` cells, giving the smooth "glow" of a
+    /// galaxy render. Mass spread past the edges of the grid is lost.
+    Gaussian { sigma: f32 },
+}
+
+/// Per-body quantity mapped to colors by `Simulation::compute_colors`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Speed, on a linear scale from rest to the fastest body.
+    #[default]
+    Speed = 0,
+    /// Magnitude of the acceleration, on a logarithmic scale.
+    Acceleration = 1,
+    /// Local surface density from the quadtree (see `Quadtree::density`), on a logarithmic scale.
+    Density = 2,
+}
+
+impl ColorMode {
+    /// Orders of magnitude below the maximum that the logarithmic modes still tell apart.
+    pub const DECADES: f32 = 4.0;
+}
+
+impl Colormap {
+    /// Maps `t` in [0, 1] (clamped) to an RGB color.
+    pub fn color(self, t: f32) -> [u8; 3] {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        match self {
+            Self::Grayscale => {
+                let v = (t * 255.0).round() as u8;
+                [v, v, v]
+            }
+            Self::Inferno => Self::ramp(&INFERNO, t),
+            Self::Viridis => Self::ramp(&VIRIDIS, t),
+        }
+    }
+
+    /// Interpolates the evenly spaced control points of a ramp at `t` in [0, 1].
+    fn ramp(points: &[[f32; 3]], t: f32) -> [u8; 3] {
+        let x = t * (points.len() - 1) as f32;
+        let i = (x as usize).min(points.len() - 2);
+        let f = x - i as f32;
+        let (a, b) = (points[i], points[i + 1]);
+        std::array::from_fn(|c| (a[c] + (b[c] - a[c]) * f).round() as u8)
+    }
+}
+
+/// Maps a density grid to RGB bytes (3 per cell) on a logarithmic scale.
+///
+/// The densest cell maps to the top of the colormap and densities `decades` orders of magnitude
+/// below it (or empty cells) to the bottom, so both cores and sparse outskirts stay visible.
+pub fn colorize(grid: &[f32], colormap: Colormap, decades: f32) -> Vec<u8> {
+    let max = grid.iter().copied().fold(0.0f32, f32::max);
+    let log_max = max.log10();
+    let decades = decades.max(f32::EPSILON);
+
+    grid.par_iter()
+        .flat_map_iter(|&density| {
+            let t = if density > 0.0 { 1.0 + (density.log10() - log_max) / decades } else { 0.0 };
+            colormap.color(t)
+        })
+        .collect()
+}
+
+/// Encodes RGB bytes (3 per pixel, row-major, top row first) as a PNG image.
+#[cfg(feature = "png")]
+pub fn write_png(path: impl AsRef<Path>, width: usize, height: usize, rgb: &[u8]) -> std::io::Result<()> {
+    let invalid = |e: png::EncodingError| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(invalid)?;
+    writer.write_image_data(rgb).map_err(invalid)?;
+    writer.finish().map_err(invalid)
+}
+
+impl Simulation {
+    /// Splats body masses onto a `width` x `height` grid and returns the surface density
+    /// (mass per unit area) of each cell, row-major with the top (largest y) row first.
+    ///
+    /// The grid is centered on the origin and spans `2 * extent` world units horizontally;
+    /// cells are square, so the vertical span follows the aspect ratio.
+    /// Each body adds its whole mass to the cell containing its center; bodies outside are ignored.
+    pub fn render_density(&self, width: usize, height: usize, extent: f32) -> Vec<f32> {
+        let mut grid = Vec::new();
+        self.render_density_texture(width, height, extent, SplatKernel::Nearest, &mut grid);
+        grid
+    }
+
+    /// Like `render_density`, spreading the masses with `kernel` and writing the densities to
+    /// `out`, ready to upload as a single-channel float texture.
+    pub fn render_density_texture(&self, width: usize, height: usize, extent: f32, kernel: SplatKernel, out: &mut Vec<f32>) {
+        out.clear();
+        if width == 0 || height == 0 || !extent.is_finite() || extent <= 0.0 {
+            out.resize(width * height, 0.0);
+            return;
+        }
+
+        let cell = 2.0 * extent / width as f32;
+        let top = 0.5 * cell * height as f32;
+        let inv_area = 1.0 / (cell * cell);
+
+        // Each rayon job accumulates into its own grid; the grids are summed afterwards
+        let grid = self
+            .bodies
+            .positions()
+            .par_iter()
+            .zip(self.bodies.masses())
+            .fold(
+                || vec![0.0f32; width * height],
+                |mut grid, (pos, &mass)| {
+                    let col = ((pos.x + extent) / cell).floor();
+                    let row = ((top - pos.y) / cell).floor();
+                    if col >= 0.0 && row >= 0.0 && (col as usize) < width && (row as usize) < height {
+                        grid[row as usize * width + col as usize] += mass * inv_area;
+                    }
+                    grid
+                },
+            )
+            .reduce_with(|mut a, b| {
+                a.iter_mut().zip(&b).for_each(|(a, b)| *a += b);
+                a
+            })
+            .unwrap_or_else(|| vec![0.0; width * height]);
+        *out = grid;
+
+        if let SplatKernel::Gaussian { sigma } = kernel
+            && sigma > 0.0
+        {
+            gaussian_blur(out, width, height, sigma);
+        }
+    }
+
+    /// Writes the quantity `mode` of each body to `out`, in body order.
+    /// Densities are 0 before the first step, as they come from the quadtree.
+    pub fn body_scalars(&self, mode: ColorMode, out: &mut Vec<f32>) {
+        out.clear();
+        match mode {
+            ColorMode::Speed => out.par_extend(self.bodies.velocities().par_iter().map(|vel| vel.mag())),
+            ColorMode::Acceleration => out.par_extend(self.bodies.accs().par_iter().map(|acc| acc.mag())),
+            ColorMode::Density => {
+                let (quadtree, resolution) = (&self.quadtree, self.density_resolution());
+                out.par_extend(self.bodies.positions().par_iter().map(|&pos| quadtree.density(pos, resolution)));
+            }
+        }
+    }
+
+    /// Colors each body by `mode` through `colormap` into `out` as RGBA bytes (opaque), in body
+    /// order, so renderers can upload them directly instead of mapping the values every frame.
+    /// `scratch` holds the values of `body_scalars` afterwards.
+    pub fn compute_colors(&self, mode: ColorMode, colormap: Colormap, scratch: &mut Vec<f32>, out: &mut Vec<[u8; 4]>) {
+        self.body_scalars(mode, scratch);
+        let max = scratch.par_iter().copied().reduce(|| 0.0, f32::max);
+
+        out.clear();
+        out.par_extend(scratch.par_iter().map(|&value| {
+            let t = match mode {
+                ColorMode::Speed => value / max,
+                ColorMode::Acceleration | ColorMode::Density if value > 0.0 => {
+                    1.0 + (value / max).log10() / ColorMode::DECADES
+                }
+                _ => 0.0,
+            };
+            let [r, g, b] = colormap.color(t);
+            [r, g, b, 255]
+        }));
+    }
+
+    /// Renders the density grid (see `render_density`) and saves it as a PNG image using a
+    /// logarithmic color scale spanning 4 decades (see `colorize`).
+    /// Calling this every N frames from a headless run produces a preview image sequence.
+    #[cfg(feature = "png")]
+    pub fn save_density_png(
+        &self,
+        path: impl AsRef<Path>,
+        width: usize,
+        height: usize,
+        extent: f32,
+        colormap: Colormap,
+    ) -> std::io::Result<()> {
+        let grid = self.render_density(width, height, extent);
+        write_png(path, width, height, &colorize(&grid, colormap, 4.0))
+    }
+}
+
+/// Blurs a row-major grid in place with a normalized Gaussian of `sigma` cells, as two 1D passes
+/// (rows, then columns) in parallel. Splatting into cells and blurring equals splatting each body
+/// with the Gaussian, up to the cell size, at a cost independent of the body count.
+fn gaussian_blur(grid: &mut [f32], width: usize, height: usize, sigma: f32) {
+    let radius = (3.0 * sigma).ceil() as isize;
+    let weights: Vec<f32> = (-radius..=radius).map(|k| (-0.5 * (k as f32 / sigma).powi(2)).exp()).collect();
+    let total: f32 = weights.iter().sum();
+    let weights: Vec<f32> = weights.iter().map(|w| w / total).collect();
+
+    let blur_line = |src: &[f32], at: &dyn Fn(usize) -> usize, len: usize, k: usize| -> f32 {
+        weights
+            .iter()
+            .enumerate()
+            .filter_map(|(w, &weight)| {
+                let j = k as isize + w as isize - radius;
+                (0..len as isize).contains(&j).then(|| weight * src[at(j as usize)])
+            })
+            .sum()
+    };
+
+    let mut rows = vec![0.0; grid.len()];
+    rows.par_chunks_mut(width).enumerate().for_each(|(row, out)| {
+        let src = &grid[row * width..(row + 1) * width];
+        for (col, value) in out.iter_mut().enumerate() {
+            *value = blur_line(src, &|j| j, width, col);
+        }
+    });
+    grid.par_chunks_mut(width).enumerate().for_each(|(row, out)| {
+        for (col, value) in out.iter_mut().enumerate() {
+            *value = blur_line(&rows, &|j| j * width + col, height, row);
+        }
+    });
+}
@@ -0,0 +1,624 @@
+use crate::{
+    body::Body,
+    boundary::{Boundary, BoundaryMode},
+    external::ExternalPotential,
+    quadtree::{AdaptiveTheta, Quadtree},
+    simulation::{Falloff, ForceMode, Integrator, MultiRate, Simulation, SimulationState, StepSettings},
+    snapshot::{self, Snapshot},
+};
+use ultraviolet::Vec2;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Input log layout (all values little-endian):
+///
+/// | field      | type      |
+/// |------------|-----------|
+/// | magic      | `b"NBIL"` |
+/// | version    | u32       |
+/// | seed       | u64, state of the simulation's RNG when logging started |
+/// | time       | f64       |
+/// | origin     | 2 x f64 (absent in version 1 logs, where it is zero) |
+/// | parameters | G, force exponent, boundary min/max as f32, boundary mode u32 |
+/// | potentials | count u32, then per potential: kind u8, 4 x f32 |
+/// | settings   | step settings (absent before version 3, where they are the defaults), see below |
+/// | snapshot   | the initial state as a binary snapshot (see `snapshot::MAGIC`) |
+/// | events     | count u64, then per event: frame u64, kind u8, event data |
+///
+/// Step settings are the integrator u8, substeps, gravity interval and collision interval as u64,
+/// then the multi-rate settings (u8 1 followed by interval u64, radius and min acceleration f32,
+/// or u8 0 if off), the adaptive opening angle (u8 1 followed by strength, min and max f32,
+/// or u8 0 if off) and the Morton sort interval u64 (absent in version 3, where it is 0).
+pub const INPUT_LOG_MAGIC: [u8; 4] = *b"NBIL";
+pub const INPUT_LOG_VERSION: u32 = 4;
+
+/// An external interaction with a simulation, recorded by `InputLog`.
+#[derive(Clone, Debug)]
+pub enum InputEvent {
+    AddBody(Body),
+    ApplyForce { pos: Vec2, force: Vec2, radius: f32, falloff: Falloff, mode: ForceMode },
+    SetDt(f32),
+    SetGravityConstant(f32),
+    SetForceExponent(f32),
+    SetBoundary(Boundary),
+    /// `seed` is the RNG state the bodies were generated with (see `Rng::get_seed`).
+    SpawnDisc { center: Vec2, n: usize, radius: f32, spin: f32, seed: u64 },
+    SpawnCluster { center: Vec2, n: usize, radius: f32, seed: u64 },
+    /// All bodies were replaced (reset, generator, file load) and time restarted.
+    ResetBodies(Vec<Body>),
+    ShiftOrigin([f64; 2]),
+    SetIntegrator(Integrator),
+    SetSubsteps(usize),
+    SetGravityInterval(usize),
+    SetCollisionInterval(usize),
+    SetMultiRate(Option<MultiRate>),
+    SetAdaptiveTheta(Option<AdaptiveTheta>),
+    SetMortonInterval(usize),
+}
+
+impl InputEvent {
+    /// Applies the event to `sim` the same way the original call did.
+    pub fn apply(&self, sim: &mut Simulation) {
+        match self {
+            Self::AddBody(body) => sim.add_body(*body),
+            Self::ApplyForce { pos, force, radius, falloff, mode } => {
+                sim.apply_force(*pos, *force, *radius, *falloff, *mode)
+            }
+            Self::SetDt(dt) => sim.set_dt(*dt),
+            Self::SetGravityConstant(g) => sim.set_gravity_constant(*g),
+            Self::SetForceExponent(k) => sim.set_force_exponent(*k),
+            Self::SetBoundary(boundary) => sim.set_boundary(*boundary),
+            Self::SpawnDisc { center, n, radius, spin, seed } => {
+                sim.seed_rng(*seed);
+                sim.spawn_disc(*center, *n, *radius, *spin);
+            }
+            Self::SpawnCluster { center, n, radius, seed } => {
+                sim.seed_rng(*seed);
+                sim.spawn_cluster(*center, *n, *radius);
+            }
+            Self::ResetBodies(bodies) => sim.reset_with_bodies(bodies.clone()),
+            Self::ShiftOrigin(origin) => sim.shift_origin(*origin),
+            Self::SetIntegrator(integrator) => sim.set_integrator(*integrator),
+            Self::SetSubsteps(substeps) => sim.set_substeps(*substeps),
+            Self::SetGravityInterval(interval) => sim.set_gravity_interval(*interval),
+            Self::SetCollisionInterval(interval) => sim.set_collision_interval(*interval),
+            Self::SetMultiRate(multirate) => sim.set_multirate(*multirate),
+            Self::SetAdaptiveTheta(adaptive) => sim.set_adaptive_theta(*adaptive),
+            Self::SetMortonInterval(interval) => sim.set_morton_interval(*interval),
+        }
+    }
+}
+
+/// Records every external interaction with a simulation, tagged with the frame it happened on,
+/// so the run can be reproduced bit-for-bit from the initial state.
+///
+/// Start logging with `Simulation::start_input_log`; the simulation then records its own mutating
+/// calls (`add_body`, `apply_force`, `set_dt`, `set_gravity_constant`, `set_force_exponent`,
+/// `set_boundary`, `shift_origin`, the setters of `StepSettings`, the spawners and resets).
+/// Stepping itself is deterministic (forces are computed per body and collisions resolved
+/// sequentially), so replaying the events on the same frames reproduces the original run;
+/// spawners are replayed with the random seed they ran with.
+///
+/// Direct writes to public fields, gravity wells, drags and snapshot or checkpoint loads are not recorded;
+/// start a new log after loading.
+#[derive(Clone, Debug)]
+pub struct InputLog {
+    /// State of the simulation's random number generator when logging started.
+    pub seed: u64,
+    /// State when logging started.
+    pub initial: SimulationState,
+    /// Events in the order they happened, with the frame they were applied on.
+    pub events: Vec<(usize, InputEvent)>,
+}
+
+impl InputLog {
+    /// Starts a log from the current state of `sim`.
+    pub fn new(sim: &Simulation) -> Self {
+        Self {
+            seed: sim.rng().get_seed(),
+            initial: sim.state(),
+            events: Vec::new(),
+        }
+    }
+
+    /// Restores the initial state into `sim` and re-executes the logged events, stepping
+    /// until `sim.frame` reaches `frame`. Events logged on `frame` itself are applied as well.
+    /// A `frame` of `usize::MAX` stops after the last logged event.
+    ///
+    /// The backend choice (`use_rayon`) of `sim` is kept; both backends give identical results.
+    /// Events are not recorded into a log running on `sim` while replaying.
+    pub fn replay(&self, sim: &mut Simulation, frame: usize) {
+        let logging = sim.input_log.take();
+        let use_rayon = sim.use_rayon;
+        sim.set_state(self.initial.clone());
+        sim.use_rayon = use_rayon;
+        sim.seed_rng(self.seed);
+
+        let end = if frame == usize::MAX {
+            self.events.last().map_or(sim.frame, |(f, _)| *f)
+        } else {
+            frame
+        };
+
+        let mut events = self.events.iter().peekable();
+        loop {
+            while let Some((_, event)) = events.next_if(|(f, _)| *f <= sim.frame) {
+                event.apply(sim);
+            }
+            if sim.frame >= end {
+                break;
+            }
+            sim.step();
+        }
+
+        sim.input_log = logging;
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        let state = &self.initial;
+        w.write_all(&INPUT_LOG_MAGIC)?;
+        w.write_all(&INPUT_LOG_VERSION.to_le_bytes())?;
+        w.write_all(&self.seed.to_le_bytes())?;
+        w.write_all(&state.time.to_le_bytes())?;
+        w.write_all(&state.origin[0].to_le_bytes())?;
+        w.write_all(&state.origin[1].to_le_bytes())?;
+        write_f32s(w, &[state.quadtree.g, state.quadtree.force_exponent])?;
+        write_boundary(w, &state.boundary)?;
+
+        w.write_all(&(state.external.len() as u32).to_le_bytes())?;
+        for potential in &state.external {
+            let (kind, values) = match *potential {
+                ExternalPotential::PointMass { pos, mass } => (0u8, [pos.x, pos.y, mass, 0.0]),
+                ExternalPotential::Uniform { acc } => (1, [acc.x, acc.y, 0.0, 0.0]),
+                ExternalPotential::Harmonic { center, omega } => (2, [center.x, center.y, omega, 0.0]),
+            };
+            w.write_all(&[kind])?;
+            write_f32s(w, &values)?;
+        }
+        write_settings(w, &state.settings)?;
+
+        Snapshot {
+            frame: state.frame,
+            dt: state.dt,
+            t_sq: state.quadtree.t_sq,
+            e_sq: state.quadtree.e_sq,
+            bodies: state.bodies.clone(),
+            ids: state.ids.clone(),
+            next_id: state.next_id,
+        }
+        .write(w)?;
+
+        w.write_all(&(self.events.len() as u64).to_le_bytes())?;
+        for (frame, event) in &self.events {
+            w.write_all(&(*frame as u64).to_le_bytes())?;
+            write_event(w, event)?;
+        }
+        Ok(())
+    }
+
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != INPUT_LOG_MAGIC {
+            return Err(invalid_data("not an nbody input log"));
+        }
+        let version = read_u32(r)?;
+        if !(1..=INPUT_LOG_VERSION).contains(&version) {
+            return Err(invalid_data("unsupported input log version"));
+        }
+
+        let seed = read_u64(r)?;
+        let time = f64::from_bits(read_u64(r)?);
+        let origin = if version >= 2 {
+            [f64::from_bits(read_u64(r)?), f64::from_bits(read_u64(r)?)]
+        } else {
+            [0.0; 2]
+        };
+        let [g, force_exponent] = read_f32s(r)?;
+        let boundary = read_boundary(r)?;
+
+        let potentials = read_u32(r)?;
+        let mut external = Vec::new();
+        for _ in 0..potentials {
+            let kind = read_u8(r)?;
+            let [a, b, c, _] = read_f32s(r)?;
+            external.push(match kind {
+                0 => ExternalPotential::PointMass { pos: Vec2::new(a, b), mass: c },
+                1 => ExternalPotential::Uniform { acc: Vec2::new(a, b) },
+                2 => ExternalPotential::Harmonic { center: Vec2::new(a, b), omega: c },
+                _ => return Err(invalid_data("unknown external potential in input log")),
+            });
+        }
+        let settings = if version >= 3 { read_settings(r, version >= 4)? } else { StepSettings::default() };
+
+        let snapshot = Snapshot::read(r)?;
+        let quadtree = Quadtree {
+            t_sq: snapshot.t_sq,
+            e_sq: snapshot.e_sq,
+            g,
+            force_exponent,
+            ..Quadtree::default()
+        };
+
+        let initial = SimulationState {
+            dt: snapshot.dt,
+            frame: snapshot.frame,
+            time,
+            origin,
+            bodies: snapshot.bodies,
+            ids: snapshot.ids,
+            next_id: snapshot.next_id,
+            quadtree,
+            use_rayon: false,
+            boundary,
+            external,
+            settings,
+        };
+
+        let count = read_u64(r)?;
+        let mut events = Vec::new();
+        for _ in 0..count {
+            let frame = read_u64(r)? as usize;
+            events.push((frame, read_event(r)?));
+        }
+
+        Ok(Self { seed, initial, events })
+    }
+
+    /// Saves the log to a file.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        self.write(&mut w)?;
+        w.flush()
+    }
+
+    /// Loads a log saved by `save`.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::read(&mut BufReader::new(File::open(path)?))
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u8(r: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0; 1];
+    r.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f32s<const N: usize>(r: &mut impl Read) -> io::Result<[f32; N]> {
+    let mut values = [0.0; N];
+    for v in values.iter_mut() {
+        *v = f32::from_bits(read_u32(r)?);
+    }
+    Ok(values)
+}
+
+fn write_f32s(w: &mut impl Write, values: &[f32]) -> io::Result<()> {
+    values.iter().try_for_each(|v| w.write_all(&v.to_le_bytes()))
+}
+
+fn write_boundary(w: &mut impl Write, boundary: &Boundary) -> io::Result<()> {
+    write_f32s(w, &[boundary.min.x, boundary.min.y, boundary.max.x, boundary.max.y])?;
+    w.write_all(&(boundary.mode as u32).to_le_bytes())
+}
+
+fn read_boundary(r: &mut impl Read) -> io::Result<Boundary> {
+    let [min_x, min_y, max_x, max_y] = read_f32s(r)?;
+    let mode = match read_u32(r)? {
+        0 => BoundaryMode::None,
+        1 => BoundaryMode::Periodic,
+        2 => BoundaryMode::Reflective,
+        3 => BoundaryMode::Despawn,
+        _ => return Err(invalid_data("unknown boundary mode in input log")),
+    };
+    Ok(Boundary::new(Vec2::new(min_x, min_y), Vec2::new(max_x, max_y), mode))
+}
+
+fn read_integrator(r: &mut impl Read) -> io::Result<Integrator> {
+    match read_u8(r)? {
+        0 => Ok(Integrator::SemiImplicitEuler),
+        1 => Ok(Integrator::Leapfrog),
+        _ => Err(invalid_data("unknown integrator")),
+    }
+}
+
+fn write_multirate(w: &mut impl Write, multirate: &Option<MultiRate>) -> io::Result<()> {
+    let Some(multirate) = multirate else {
+        return w.write_all(&[0]);
+    };
+    w.write_all(&[1])?;
+    w.write_all(&(multirate.interval as u64).to_le_bytes())?;
+    write_f32s(w, &[multirate.radius, multirate.min_acc])
+}
+
+fn read_multirate(r: &mut impl Read) -> io::Result<Option<MultiRate>> {
+    if read_u8(r)? == 0 {
+        return Ok(None);
+    }
+    let interval = read_u64(r)? as usize;
+    let [radius, min_acc] = read_f32s(r)?;
+    Ok(Some(MultiRate { interval, radius, min_acc }))
+}
+
+fn write_adaptive_theta(w: &mut impl Write, adaptive: &Option<AdaptiveTheta>) -> io::Result<()> {
+    let Some(adaptive) = adaptive else {
+        return w.write_all(&[0]);
+    };
+    w.write_all(&[1])?;
+    write_f32s(w, &[adaptive.strength, adaptive.min, adaptive.max])
+}
+
+fn read_adaptive_theta(r: &mut impl Read) -> io::Result<Option<AdaptiveTheta>> {
+    if read_u8(r)? == 0 {
+        return Ok(None);
+    }
+    let [strength, min, max] = read_f32s(r)?;
+    Ok(Some(AdaptiveTheta { strength, min, max }))
+}
+
+/// Writes step settings in the layout documented on `INPUT_LOG_MAGIC`, also used by checkpoints.
+pub(crate) fn write_settings(w: &mut impl Write, settings: &StepSettings) -> io::Result<()> {
+    w.write_all(&[settings.integrator as u8])?;
+    for v in [settings.substeps, settings.gravity_interval, settings.collision_interval] {
+        w.write_all(&(v as u64).to_le_bytes())?;
+    }
+    write_multirate(w, &settings.multirate)?;
+    write_adaptive_theta(w, &settings.adaptive_theta)?;
+    w.write_all(&(settings.morton_interval as u64).to_le_bytes())
+}
+
+/// Reads step settings written by `write_settings`, or by an older version without the Morton
+/// sort interval if `morton_interval` is false.
+pub(crate) fn read_settings(r: &mut impl Read, morton_interval: bool) -> io::Result<StepSettings> {
+    Ok(StepSettings {
+        integrator: read_integrator(r)?,
+        substeps: read_u64(r)? as usize,
+        gravity_interval: read_u64(r)? as usize,
+        collision_interval: read_u64(r)? as usize,
+        multirate: read_multirate(r)?,
+        adaptive_theta: read_adaptive_theta(r)?,
+        morton_interval: if morton_interval { read_u64(r)? as usize } else { 0 },
+    })
+}
+
+fn write_event(w: &mut impl Write, event: &InputEvent) -> io::Result<()> {
+    match event {
+        InputEvent::AddBody(body) => {
+            w.write_all(&[0])?;
+            snapshot::write_body_records(w, std::slice::from_ref(body))
+        }
+        InputEvent::ApplyForce { pos, force, radius, falloff, mode } => {
+            w.write_all(&[1, *falloff as u8, *mode as u8])?;
+            write_f32s(w, &[pos.x, pos.y, force.x, force.y, *radius])
+        }
+        InputEvent::SetDt(v) => {
+            w.write_all(&[2])?;
+            write_f32s(w, &[*v])
+        }
+        InputEvent::SetGravityConstant(v) => {
+            w.write_all(&[3])?;
+            write_f32s(w, &[*v])
+        }
+        InputEvent::SetForceExponent(v) => {
+            w.write_all(&[4])?;
+            write_f32s(w, &[*v])
+        }
+        InputEvent::SetBoundary(boundary) => {
+            w.write_all(&[5])?;
+            write_boundary(w, boundary)
+        }
+        InputEvent::SpawnDisc { center, n, radius, spin, seed } => {
+            w.write_all(&[6])?;
+            w.write_all(&(*n as u64).to_le_bytes())?;
+            w.write_all(&seed.to_le_bytes())?;
+            write_f32s(w, &[center.x, center.y, *radius, *spin])
+        }
+        InputEvent::SpawnCluster { center, n, radius, seed } => {
+            w.write_all(&[7])?;
+            w.write_all(&(*n as u64).to_le_bytes())?;
+            w.write_all(&seed.to_le_bytes())?;
+            write_f32s(w, &[center.x, center.y, *radius])
+        }
+        InputEvent::ResetBodies(bodies) => {
+            w.write_all(&[8])?;
+            w.write_all(&(bodies.len() as u64).to_le_bytes())?;
+            snapshot::write_body_records(w, bodies)
+        }
+        InputEvent::ShiftOrigin(origin) => {
+            w.write_all(&[9])?;
+            w.write_all(&origin[0].to_le_bytes())?;
+            w.write_all(&origin[1].to_le_bytes())
+        }
+        InputEvent::SetIntegrator(integrator) => w.write_all(&[10, *integrator as u8]),
+        InputEvent::SetSubsteps(v) => {
+            w.write_all(&[11])?;
+            w.write_all(&(*v as u64).to_le_bytes())
+        }
+        InputEvent::SetGravityInterval(v) => {
+            w.write_all(&[12])?;
+            w.write_all(&(*v as u64).to_le_bytes())
+        }
+        InputEvent::SetCollisionInterval(v) => {
+            w.write_all(&[13])?;
+            w.write_all(&(*v as u64).to_le_bytes())
+        }
+        InputEvent::SetMultiRate(multirate) => {
+            w.write_all(&[14])?;
+            write_multirate(w, multirate)
+        }
+        InputEvent::SetAdaptiveTheta(adaptive) => {
+            w.write_all(&[15])?;
+            write_adaptive_theta(w, adaptive)
+        }
+        InputEvent::SetMortonInterval(v) => {
+            w.write_all(&[16])?;
+            w.write_all(&(*v as u64).to_le_bytes())
+        }
+    }
+}
+
+fn read_event(r: &mut impl Read) -> io::Result<InputEvent> {
+    Ok(match read_u8(r)? {
+        0 => InputEvent::AddBody(snapshot::read_body_records(r, 1)?[0]),
+        1 => {
+            let falloff = match read_u8(r)? {
+                0 => Falloff::Constant,
+                1 => Falloff::Linear,
+                2 => Falloff::InverseSquare,
+                _ => return Err(invalid_data("unknown falloff in input log")),
+            };
+            let mode = match read_u8(r)? {
+                0 => ForceMode::Impulse,
+                1 => ForceMode::Force,
+                _ => return Err(invalid_data("unknown force mode in input log")),
+            };
+            let [x, y, fx, fy, radius] = read_f32s(r)?;
+            InputEvent::ApplyForce { pos: Vec2::new(x, y), force: Vec2::new(fx, fy), radius, falloff, mode }
+        }
+        2 => InputEvent::SetDt(read_f32s::<1>(r)?[0]),
+        3 => InputEvent::SetGravityConstant(read_f32s::<1>(r)?[0]),
+        4 => InputEvent::SetForceExponent(read_f32s::<1>(r)?[0]),
+        5 => InputEvent::SetBoundary(read_boundary(r)?),
+        6 => {
+            let n = read_u64(r)? as usize;
+            let seed = read_u64(r)?;
+            let [x, y, radius, spin] = read_f32s(r)?;
+            InputEvent::SpawnDisc { center: Vec2::new(x, y), n, radius, spin, seed }
+        }
+        7 => {
+            let n = read_u64(r)? as usize;
+            let seed = read_u64(r)?;
+            let [x, y, radius] = read_f32s(r)?;
+            InputEvent::SpawnCluster { center: Vec2::new(x, y), n, radius, seed }
+        }
+        8 => {
+            let count = read_u64(r)? as usize;
+            InputEvent::ResetBodies(snapshot::read_body_records(r, count)?)
+        }
+        9 => InputEvent::ShiftOrigin([f64::from_bits(read_u64(r)?), f64::from_bits(read_u64(r)?)]),
+        10 => InputEvent::SetIntegrator(read_integrator(r)?),
+        11 => InputEvent::SetSubsteps(read_u64(r)? as usize),
+        12 => InputEvent::SetGravityInterval(read_u64(r)? as usize),
+        13 => InputEvent::SetCollisionInterval(read_u64(r)? as usize),
+        14 => InputEvent::SetMultiRate(read_multirate(r)?),
+        15 => InputEvent::SetAdaptiveTheta(read_adaptive_theta(r)?),
+        16 => InputEvent::SetMortonInterval(read_u64(r)? as usize),
+        _ => return Err(invalid_data("unknown event in input log")),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempFile, bodies_bits, sorted_multirate_sim, state_hash};
+
+    #[test]
+    fn replay_reproduces_sorted_multirate_run() {
+        let mut sim = sorted_multirate_sim(3000);
+        for _ in 0..10 {
+            sim.step();
+        }
+
+        sim.start_input_log();
+        for frame in 10..30 {
+            match frame {
+                14 => sim.add_body(Body::new(Vec2::new(5.0, 5.0), Vec2::new(-1.0, 0.0), 10.0, 1.0)),
+                20 => sim.apply_force(
+                    Vec2::zero(),
+                    Vec2::new(2.0, 1.0),
+                    50.0,
+                    Falloff::Linear,
+                    ForceMode::Impulse,
+                ),
+                25 => sim.set_morton_interval(2),
+                _ => {}
+            }
+            sim.step();
+        }
+        let log = sim.take_input_log().unwrap();
+
+        let file = TempFile::new("replay.nbil");
+        log.save(file.path()).unwrap();
+        let mut replayed = Simulation::with_params(0, 1.0, 1.0, 1.0);
+        InputLog::load(file.path()).unwrap().replay(&mut replayed, 30);
+
+        assert_eq!(replayed.frame, sim.frame);
+        assert_eq!(replayed.bodies.ids(), sim.bodies.ids());
+        assert_eq!(state_hash(&replayed), state_hash(&sim));
+    }
+
+    #[test]
+    fn input_log_round_trips() {
+        let mut sim = Simulation::with_params(50, 0.1, 1.0, 1.0);
+        sim.set_multirate(Some(MultiRate::default()));
+        sim.set_morton_interval(4);
+        sim.start_input_log();
+
+        sim.add_body(Body::new(Vec2::new(1.0, 2.0), Vec2::new(3.0, 4.0), 5.0, 6.0));
+        sim.apply_force(
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, -1.0),
+            3.0,
+            Falloff::InverseSquare,
+            ForceMode::Force,
+        );
+        sim.set_dt(0.05);
+        sim.set_gravity_constant(2.0);
+        sim.set_force_exponent(1.5);
+        sim.set_boundary(Boundary::new(
+            Vec2::new(-100.0, -50.0),
+            Vec2::new(100.0, 50.0),
+            BoundaryMode::Reflective,
+        ));
+        sim.step();
+        sim.spawn_disc(Vec2::new(10.0, 0.0), 20, 5.0, -1.0);
+        sim.spawn_cluster(Vec2::new(-10.0, 0.0), 20, 2.0);
+        sim.shift_origin([1e6, -1e6]);
+        sim.set_integrator(Integrator::Leapfrog);
+        sim.set_substeps(2);
+        sim.set_gravity_interval(2);
+        sim.set_collision_interval(3);
+        sim.set_multirate(None);
+        sim.set_adaptive_theta(Some(AdaptiveTheta {
+            strength: 0.5,
+            min: 0.3,
+            max: 1.2,
+        }));
+        sim.set_morton_interval(0);
+        sim.step();
+        sim.reset_with_bodies(vec![Body::default(); 3]);
+        let log = sim.take_input_log().unwrap();
+
+        let mut data = Vec::new();
+        log.write(&mut data).unwrap();
+        let read = InputLog::read(&mut &data[..]).unwrap();
+
+        assert_eq!(read.seed, log.seed);
+        assert_eq!(read.initial.frame, log.initial.frame);
+        assert_eq!(read.initial.ids, log.initial.ids);
+        assert_eq!(bodies_bits(&read.initial.bodies), bodies_bits(&log.initial.bodies));
+        assert_eq!(
+            format!("{:?}", read.initial.settings),
+            format!("{:?}", log.initial.settings)
+        );
+        assert_eq!(read.events.len(), 17);
+        assert_eq!(format!("{:?}", read.events), format!("{:?}", log.events));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{quadtree::TreeMetrics, simulation::Simulation};
+
+/// Bodies whose force walks are retraced for `TreeMetrics::acceptance_ratio`.
+const ACCEPTANCE_SAMPLES: usize = 256;
+
+/// Something that went wrong during a step without stopping it, see `StepReport`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepWarning {
+    /// The quadtree hit its node budget (see `Simulation::set_node_budget`) and `merged` bodies
+    /// were merged into occupied leaves, so their forces are approximate.
+    NodeBudgetReached { budget: usize, merged: usize },
+}
+
+/// Summary of the last step, see `Simulation::step_report`.
+#[derive(Clone, Debug, Default)]
+pub struct StepReport {
+    /// Frame the step advanced to.
+    pub frame: usize,
+    /// Nodes in the quadtree, or 0 if gravity was skipped.
+    pub nodes: usize,
+    /// Bodies the quadtree was built from, or 0 if gravity was skipped.
+    pub bodies: usize,
+    /// Bodies merged into occupied leaves because of the node budget.
+    pub merged_bodies: usize,
+    /// Shape of the tree built this step, if enabled with `Simulation::set_tree_metrics` and
+    /// gravity ran.
+    pub tree: Option<TreeMetrics>,
+    pub warnings: Vec<StepWarning>,
+}
+
+impl Simulation {
+    /// Caps the quadtree at `budget` nodes, or lifts the cap if `None` (the default). Beyond it,
+    /// leaves stop subdividing and a body landing in an occupied one is merged into it, which
+    /// keeps pathological inputs such as many nearly coincident bodies from exhausting memory.
+    /// Each step that hits the cap reports `StepWarning::NodeBudgetReached`.
+    pub fn set_node_budget(&mut self, budget: Option<usize>) {
+        self.quadtree.node_budget = budget.unwrap_or(usize::MAX);
+    }
+
+    /// Maximum number of quadtree nodes, see `set_node_budget`.
+    pub fn node_budget(&self) -> Option<usize> {
+        Some(self.quadtree.node_budget).filter(|&budget| budget != usize::MAX)
+    }
+
+    /// Measures the shape of the tree after each build into `StepReport::tree`, to tell when the
+    /// body distribution is degrading tree performance. Locating every body's leaf makes this a
+    /// sizeable fraction of a step, so it is off by default.
+    pub fn set_tree_metrics(&mut self, enabled: bool) {
+        self.tree_metrics = enabled;
+    }
+
+    /// Whether tree metrics are measured, see `set_tree_metrics`.
+    pub fn tree_metrics(&self) -> bool {
+        self.tree_metrics
+    }
+
+    /// Measures the shape of the current tree, see `TreeMetrics`.
+    pub fn measure_tree(&self) -> TreeMetrics {
+        let quadtree = &self.quadtree;
+        let positions = self.bodies.positions();
+        if quadtree.nodes.is_empty() {
+            return TreeMetrics::default();
+        }
+
+        let leaf_bodies = self.node_body_counts();
+        let stride = positions.len().div_ceil(ACCEPTANCE_SAMPLES).max(1);
+        let samples: Vec<_> = positions.iter().step_by(stride).copied().collect();
+        quadtree.metrics(&leaf_bodies, &samples)
+    }
+
+    /// Report of the last `step`.
+    pub fn step_report(&self) -> &StepReport {
+        &self.report
+    }
+
+    /// Fills in `report` at the end of a step; `gravity` tells whether the tree was rebuilt.
+    pub(crate) fn update_report(&mut self, gravity: bool) {
+        let report = &mut self.report;
+        report.frame = self.frame;
+        report.warnings.clear();
+        if !gravity {
+            report.nodes = 0;
+            report.bodies = 0;
+            report.merged_bodies = 0;
+            report.tree = None;
+            return;
+        }
+
+        report.nodes = self.quadtree.nodes.len();
+        report.bodies = self.bodies.len();
+        report.merged_bodies = self.quadtree.merged;
+        if report.merged_bodies > 0 {
+            report.warnings.push(StepWarning::NodeBudgetReached {
+                budget: self.quadtree.node_budget,
+                merged: report.merged_bodies,
+            });
+        }
+        self.report.tree = self.tree_metrics.then(|| self.measure_tree());
+    }
+}
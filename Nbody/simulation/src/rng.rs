@@ -0,0 +1,37 @@
+/// Source of the random numbers used by generators and spawners.
+///
+/// Each `Simulation` owns one (see `Simulation::set_rng`), so simulations on the same thread
+/// don't draw from each other's stream, and the stream can be restored exactly: input logs and
+/// checkpoints record `get_seed` and restart it with `seed`.
+pub trait Rng: Send + Sync {
+    /// Uniform in [0, 1).
+    fn f32(&mut self) -> f32;
+
+    /// Restarts the stream from `seed`.
+    fn seed(&mut self, seed: u64);
+
+    /// State that `seed` restarts the stream from, as it is now.
+    fn get_seed(&self) -> u64;
+
+    /// An independent generator continuing the same stream.
+    fn box_clone(&self) -> Box<dyn Rng>;
+}
+
+/// The default generator.
+impl Rng for fastrand::Rng {
+    fn f32(&mut self) -> f32 {
+        fastrand::Rng::f32(self)
+    }
+
+    fn seed(&mut self, seed: u64) {
+        fastrand::Rng::seed(self, seed)
+    }
+
+    fn get_seed(&self) -> u64 {
+        fastrand::Rng::get_seed(self)
+    }
+
+    fn box_clone(&self) -> Box<dyn Rng> {
+        Box::new(self.clone())
+    }
+}
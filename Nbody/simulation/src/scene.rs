@@ -0,0 +1,303 @@
+use crate::{
+    body::Body,
+    boundary::{Boundary, BoundaryMode},
+    external::ExternalPotential,
+    generators::{self, GeneratorError, Params},
+    quadtree::Quadtree,
+    simulation::{Falloff, Simulation},
+    timeline::{TimedField, Timeline, TimelineEvent},
+    utils,
+};
+use serde_json::{Map, Value};
+use ultraviolet::Vec2;
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// JSON scene layout. Every key is optional; vectors are `[x, y]` arrays.
+///
+/// ```json
+/// {
+///     "dt": 0.05, "theta": 1.0, "epsilon": 1.0, "seed": 42,
+///     "gravity_constant": 1.0, "force_exponent": 2.0,
+///     "boundary": { "min": [-1000, -1000], "max": [1000, 1000], "mode": "periodic" },
+///     "bodies": [{ "pos": [0, 0], "vel": [0, 0], "mass": 1000, "radius": 10 }],
+///     "emitters": [{ "generator": "plummer", "params": { "n": 1000, "x": 300 } }],
+///     "potentials": [
+///         { "type": "point_mass", "pos": [0, 0], "mass": 1e6 },
+///         { "type": "uniform", "acc": [0, -0.1] },
+///         { "type": "harmonic", "center": [0, 0], "omega": 0.01 }
+///     ],
+///     "timeline": [
+///         { "type": "rotating_bar", "start": 0, "end": 5000, "center": [0, 0],
+///           "strength": 0.5, "length": 200, "pattern_speed": 0.01, "angle": 0 },
+///         { "type": "potential", "start": 100, "end": 200, "potential": { "type": "uniform", "acc": [0.1, 0] } },
+///         { "type": "pulse", "frame": 1000, "center": [0, 0], "strength": 5, "radius": 100, "falloff": "linear" }
+///     ]
+/// }
+/// ```
+///
+/// Emitters run a named generator (see `generators::GENERATORS`) once when the scene is loaded,
+/// adding its bodies after the explicit ones. With a `seed`, emitters and the simulation's random
+/// number generator are reproducible; without one they are seeded randomly. A body without a radius gets one derived from its mass.
+/// Boundary modes are `none`, `periodic`, `reflective` and `despawn`.
+/// Timeline fields without an `end` stay on for good; pulse falloffs are `constant` (the
+/// default), `linear` and `inverse_square` (see `Timeline`).
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub dt: f32,
+    pub theta: f32,
+    pub epsilon: f32,
+    pub gravity_constant: f32,
+    pub force_exponent: f32,
+    pub boundary: Boundary,
+    pub bodies: Vec<Body>,
+    pub potentials: Vec<ExternalPotential>,
+    pub timeline: Timeline,
+    /// State of the random number generator after the emitters ran, handed to the simulation.
+    pub seed: u64,
+}
+
+/// Error returned when a scene file cannot be read or is malformed.
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "{e}"),
+            Self::Invalid(msg) => write!(f, "invalid scene: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<io::Error> for SceneError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<GeneratorError> for SceneError {
+    fn from(e: GeneratorError) -> Self {
+        Self::Invalid(e.to_string())
+    }
+}
+
+fn invalid(msg: impl Into<String>) -> SceneError {
+    SceneError::Invalid(msg.into())
+}
+
+fn get_f32(obj: &Map<String, Value>, key: &str, default: f32) -> Result<f32, SceneError> {
+    match obj.get(key) {
+        None => Ok(default),
+        Some(v) => v.as_f64().map(|v| v as f32).ok_or_else(|| invalid(format!("'{key}' must be a number"))),
+    }
+}
+
+fn get_vec2(obj: &Map<String, Value>, key: &str, default: Vec2) -> Result<Vec2, SceneError> {
+    let Some(v) = obj.get(key) else {
+        return Ok(default);
+    };
+
+    match v.as_array().map(|a| a.iter().map(Value::as_f64).collect::<Vec<_>>()).as_deref() {
+        Some(&[Some(x), Some(y)]) => Ok(Vec2::new(x as f32, y as f32)),
+        _ => Err(invalid(format!("'{key}' must be an [x, y] array"))),
+    }
+}
+
+fn get_array<'a>(obj: &'a Map<String, Value>, key: &str) -> Result<&'a [Value], SceneError> {
+    match obj.get(key) {
+        None => Ok(&[]),
+        Some(Value::Array(a)) => Ok(a),
+        Some(_) => Err(invalid(format!("'{key}' must be an array"))),
+    }
+}
+
+fn as_object<'a>(v: &'a Value, what: &str) -> Result<&'a Map<String, Value>, SceneError> {
+    v.as_object().ok_or_else(|| invalid(format!("{what} must be an object")))
+}
+
+fn parse_boundary(obj: &Map<String, Value>) -> Result<Boundary, SceneError> {
+    let mode = match obj.get("mode").and_then(Value::as_str).unwrap_or("none") {
+        "none" => BoundaryMode::None,
+        "periodic" => BoundaryMode::Periodic,
+        "reflective" => BoundaryMode::Reflective,
+        "despawn" => BoundaryMode::Despawn,
+        other => return Err(invalid(format!("unknown boundary mode '{other}'"))),
+    };
+    let min = get_vec2(obj, "min", Vec2::zero())?;
+    let max = get_vec2(obj, "max", Vec2::zero())?;
+
+    if mode != BoundaryMode::None && (max.x <= min.x || max.y <= min.y) {
+        return Err(invalid("boundary max must be greater than min"));
+    }
+    Ok(Boundary::new(min, max, mode))
+}
+
+fn parse_body(obj: &Map<String, Value>) -> Result<Body, SceneError> {
+    let mass = get_f32(obj, "mass", 1.0)?;
+    if !mass.is_finite() || mass <= 0.0 {
+        return Err(invalid("body mass must be positive"));
+    }
+    let radius = get_f32(obj, "radius", utils::radius_for_mass(mass))?;
+    let pos = get_vec2(obj, "pos", Vec2::zero())?;
+    let vel = get_vec2(obj, "vel", Vec2::zero())?;
+    Ok(Body::new(pos, vel, mass, radius))
+}
+
+fn parse_potential(obj: &Map<String, Value>) -> Result<ExternalPotential, SceneError> {
+    match obj.get("type").and_then(Value::as_str) {
+        Some("point_mass") => Ok(ExternalPotential::PointMass {
+            pos: get_vec2(obj, "pos", Vec2::zero())?,
+            mass: get_f32(obj, "mass", 1.0)?,
+        }),
+        Some("uniform") => Ok(ExternalPotential::Uniform {
+            acc: get_vec2(obj, "acc", Vec2::zero())?,
+        }),
+        Some("harmonic") => Ok(ExternalPotential::Harmonic {
+            center: get_vec2(obj, "center", Vec2::zero())?,
+            omega: get_f32(obj, "omega", 0.0)?,
+        }),
+        Some(other) => Err(invalid(format!("unknown potential type '{other}'"))),
+        None => Err(invalid("potential is missing a 'type'")),
+    }
+}
+
+fn get_frame(obj: &Map<String, Value>, key: &str, default: usize) -> Result<usize, SceneError> {
+    match obj.get(key) {
+        None => Ok(default),
+        Some(v) => v
+            .as_u64()
+            .map(|v| v as usize)
+            .ok_or_else(|| invalid(format!("'{key}' must be a non-negative integer"))),
+    }
+}
+
+fn parse_event(obj: &Map<String, Value>) -> Result<TimelineEvent, SceneError> {
+    let field = |field| -> Result<TimelineEvent, SceneError> {
+        Ok(TimelineEvent::Field {
+            start: get_frame(obj, "start", 0)?,
+            end: get_frame(obj, "end", usize::MAX)?,
+            field,
+        })
+    };
+
+    match obj.get("type").and_then(Value::as_str) {
+        Some("pulse") => {
+            let falloff = match obj.get("falloff").and_then(Value::as_str).unwrap_or("constant") {
+                "constant" => Falloff::Constant,
+                "linear" => Falloff::Linear,
+                "inverse_square" => Falloff::InverseSquare,
+                other => return Err(invalid(format!("unknown falloff '{other}'"))),
+            };
+            Ok(TimelineEvent::Pulse {
+                frame: get_frame(obj, "frame", 0)?,
+                center: get_vec2(obj, "center", Vec2::zero())?,
+                strength: get_f32(obj, "strength", 1.0)?,
+                radius: get_f32(obj, "radius", f32::INFINITY)?,
+                falloff,
+            })
+        }
+        Some("rotating_bar") => field(TimedField::RotatingBar {
+            center: get_vec2(obj, "center", Vec2::zero())?,
+            strength: get_f32(obj, "strength", 1.0)?,
+            length: get_f32(obj, "length", 1.0)?,
+            pattern_speed: get_f32(obj, "pattern_speed", 0.0)?,
+            angle: get_f32(obj, "angle", 0.0)?,
+        }),
+        Some("potential") => {
+            let potential = obj.get("potential").ok_or_else(|| invalid("potential event is missing a 'potential'"))?;
+            field(TimedField::Potential(parse_potential(as_object(potential, "potential")?)?))
+        }
+        Some(other) => Err(invalid(format!("unknown timeline event type '{other}'"))),
+        None => Err(invalid("timeline event is missing a 'type'")),
+    }
+}
+
+impl Scene {
+    /// Parses a scene from a JSON string (see the layout on `Scene`).
+    pub fn from_json(json: &str) -> Result<Self, SceneError> {
+        let root: Value = serde_json::from_str(json).map_err(|e| invalid(e.to_string()))?;
+        let root = as_object(&root, "scene")?;
+
+        let g = get_f32(root, "gravity_constant", Quadtree::DEFAULT_G)?;
+        let boundary = match root.get("boundary") {
+            Some(v) => parse_boundary(as_object(v, "boundary")?)?,
+            None => Boundary::default(),
+        };
+
+        let mut bodies = get_array(root, "bodies")?
+            .iter()
+            .map(|v| parse_body(as_object(v, "body")?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut rng = match root.get("seed") {
+            Some(v) => {
+                let seed = v.as_u64().ok_or_else(|| invalid("'seed' must be a non-negative integer"))?;
+                fastrand::Rng::with_seed(seed)
+            }
+            None => fastrand::Rng::new(),
+        };
+        for emitter in get_array(root, "emitters")? {
+            let emitter = as_object(emitter, "emitter")?;
+            let name = emitter
+                .get("generator")
+                .and_then(Value::as_str)
+                .ok_or_else(|| invalid("emitter is missing a 'generator'"))?;
+            let params = match emitter.get("params") {
+                Some(v) => Params::from(as_object(v, "emitter params")?.clone()),
+                None => Params::default(),
+            };
+            bodies.extend((generators::find(name)?.generate)(&params, g, &mut rng)?);
+        }
+
+        let potentials = get_array(root, "potentials")?
+            .iter()
+            .map(|v| parse_potential(as_object(v, "potential")?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let events = get_array(root, "timeline")?
+            .iter()
+            .map(|v| parse_event(as_object(v, "timeline event")?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            dt: get_f32(root, "dt", Simulation::DEFAULT_DT)?,
+            theta: get_f32(root, "theta", Simulation::DEFAULT_THETA)?,
+            epsilon: get_f32(root, "epsilon", Simulation::DEFAULT_EPSILON)?,
+            gravity_constant: g,
+            force_exponent: get_f32(root, "force_exponent", Quadtree::DEFAULT_FORCE_EXPONENT)?,
+            boundary,
+            bodies,
+            potentials,
+            timeline: Timeline::new(events),
+            seed: rng.get_seed(),
+        })
+    }
+
+    /// Creates a simulation running this scene.
+    pub fn into_simulation(self) -> Simulation {
+        let mut sim = Simulation::with_bodies(self.bodies, self.dt, self.theta, self.epsilon);
+        sim.set_gravity_constant(self.gravity_constant);
+        sim.set_force_exponent(self.force_exponent);
+        sim.set_boundary(self.boundary);
+        sim.external = self.potentials;
+        sim.timeline = self.timeline;
+        sim.seed_rng(self.seed);
+        sim
+    }
+}
+
+impl Simulation {
+    /// Creates a simulation from a JSON scene file (see `Scene`).
+    pub fn from_scene_file(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(Scene::from_json(&json)?.into_simulation())
+    }
+}
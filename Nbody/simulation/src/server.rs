@@ -0,0 +1,391 @@
+use crate::{
+    generators::{self, Params},
+    simulation::{Falloff, ForceMode, Simulation},
+};
+use flate2::{write::ZlibEncoder, Compression};
+use serde_json::{json, Value};
+use tungstenite::{protocol::WebSocketConfig, Message, WebSocket};
+use ultraviolet::Vec2;
+
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+/// Position frame layout, sent as a binary WebSocket message (all values little-endian):
+///
+/// | field     | type      |
+/// |-----------|-----------|
+/// | magic     | `b"NBWF"` |
+/// | frame     | u64       |
+/// | time      | f64       |
+/// | origin    | 2 x f64, see `Simulation::origin` |
+/// | count     | u32       |
+/// | positions | zlib stream of `count` x (x, y) f32 |
+///
+/// The zlib stream can be decoded in browsers with `DecompressionStream("deflate")`.
+pub const FRAME_MAGIC: [u8; 4] = *b"NBWF";
+
+/// How long a paused server sleeps between polls for control messages.
+const IDLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// How long a connecting client may take to complete the WebSocket handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Settings for `SimulationServer`.
+#[derive(Clone, Debug)]
+pub struct ServerConfig {
+    /// Minimum time between position frames; steps in between are not sent.
+    pub frame_interval: Duration,
+    /// zlib compression level of position frames, 0 (none) to 9 (smallest).
+    pub compression: u32,
+    /// Bytes that may be queued for a slow client before frames are dropped for it.
+    pub client_buffer: usize,
+    /// Most bodies a client may reset the simulation to; larger resets get an `error` reply.
+    pub max_bodies: usize,
+    /// Most steps a single `step` message may ask for, as they run on the serving thread.
+    pub max_steps: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            frame_interval: Duration::from_secs_f32(1.0 / 30.0),
+            compression: 1,
+            client_buffer: 16 << 20,
+            max_bodies: Simulation::DEFAULT_N,
+            max_steps: 1000,
+        }
+    }
+}
+
+/// Control message sent by a client as a JSON text message with a `type` key.
+#[derive(Clone, Debug)]
+enum Control {
+    /// `{"type": "pause"}`
+    Pause,
+    /// `{"type": "resume"}`
+    Resume,
+    /// `{"type": "step", "n": 1}`: steps while paused.
+    Step(usize),
+    /// `{"type": "reset", "n": 10000}` for a uniform disc.
+    Reset(usize),
+    /// `{"type": "reset", "generator": "plummer", "params": {...}}`
+    ResetWithGenerator { name: String, params: String },
+    /// `{"type": "apply_force", "x", "y", "fx", "fy", "radius", "falloff", "mode"}`, with `falloff`
+    /// one of `constant`, `linear` (default), `inverse_square` and `mode` `impulse` (default) or `force`.
+    ApplyForce { pos: Vec2, force: Vec2, radius: f32, falloff: Falloff, mode: ForceMode },
+    /// `{"type": "set_dt", "dt": 0.05}`
+    SetDt(f32),
+}
+
+fn parse_control(text: &str) -> Result<Control, String> {
+    let value: Value = serde_json::from_str(text).map_err(|e| format!("invalid JSON: {e}"))?;
+    let f32_field = |key: &str| {
+        value[key]
+            .as_f64()
+            .map(|v| v as f32)
+            .ok_or_else(|| format!("missing or non-numeric '{key}'"))
+    };
+
+    match value["type"].as_str() {
+        Some("pause") => Ok(Control::Pause),
+        Some("resume") => Ok(Control::Resume),
+        Some("step") => Ok(Control::Step(value["n"].as_u64().unwrap_or(1) as usize)),
+        Some("reset") => match value["generator"].as_str() {
+            Some(name) => Ok(Control::ResetWithGenerator {
+                name: name.to_owned(),
+                params: value.get("params").map_or_else(String::new, Value::to_string),
+            }),
+            None => value["n"]
+                .as_u64()
+                .map(|n| Control::Reset(n as usize))
+                .ok_or_else(|| "reset needs 'generator' or 'n'".to_owned()),
+        },
+        Some("apply_force") => {
+            let falloff = match value["falloff"].as_str().unwrap_or("linear") {
+                "constant" => Falloff::Constant,
+                "linear" => Falloff::Linear,
+                "inverse_square" => Falloff::InverseSquare,
+                other => return Err(format!("unknown falloff '{other}'")),
+            };
+            let mode = match value["mode"].as_str().unwrap_or("impulse") {
+                "impulse" => ForceMode::Impulse,
+                "force" => ForceMode::Force,
+                other => return Err(format!("unknown force mode '{other}'")),
+            };
+            Ok(Control::ApplyForce {
+                pos: Vec2::new(f32_field("x")?, f32_field("y")?),
+                force: Vec2::new(f32_field("fx")?, f32_field("fy")?),
+                radius: f32_field("radius")?,
+                falloff,
+                mode,
+            })
+        }
+        Some("set_dt") => Ok(Control::SetDt(f32_field("dt")?)),
+        Some(other) => Err(format!("unknown control message '{other}'")),
+        None => Err("control message has no 'type'".to_owned()),
+    }
+}
+
+/// Encodes the current positions as a position frame (see `FRAME_MAGIC`).
+pub fn encode_frame(sim: &Simulation, compression: u32) -> Vec<u8> {
+    let mut header = Vec::with_capacity(40 + 4 * sim.bodies.len());
+    header.extend_from_slice(&FRAME_MAGIC);
+    header.extend_from_slice(&(sim.frame as u64).to_le_bytes());
+    header.extend_from_slice(&sim.time.to_le_bytes());
+    header.extend_from_slice(&sim.origin[0].to_le_bytes());
+    header.extend_from_slice(&sim.origin[1].to_le_bytes());
+    header.extend_from_slice(&(sim.bodies.len() as u32).to_le_bytes());
+
+    let positions: Vec<u8> = sim
+        .bodies
+        .iter()
+        .flat_map(|body| [body.pos.x.to_le_bytes(), body.pos.y.to_le_bytes()])
+        .flatten()
+        .collect();
+
+    let mut encoder = ZlibEncoder::new(header, Compression::new(compression.min(9)));
+    // Compressing into memory cannot fail
+    encoder.write_all(&positions).expect("zlib compression failed");
+    encoder.finish().expect("zlib compression failed")
+}
+
+#[derive(Debug)]
+struct Client {
+    ws: WebSocket<TcpStream>,
+    addr: SocketAddr,
+}
+
+/// Runs a simulation headless and streams it to WebSocket clients.
+///
+/// Every `ServerConfig::frame_interval` the current positions are broadcast as a binary position
+/// frame (see `FRAME_MAGIC`). Clients control the run with JSON text messages (pause, resume,
+/// step, reset, apply_force, set_dt); after each one the server broadcasts a JSON `state`
+/// message, and replies with an `error` message to malformed ones and to those exceeding the
+/// `ServerConfig` limits, which bound what an untrusted client can make the server allocate or
+/// compute. New clients get a `state`
+/// message and the current position frame on connect, so they can draw a paused run.
+///
+/// Everything runs on the thread calling `run` or `poll`; slow clients get frames dropped rather
+/// than stalling the simulation.
+#[derive(Debug)]
+pub struct SimulationServer {
+    pub sim: Simulation,
+    pub config: ServerConfig,
+    listener: TcpListener,
+    clients: Vec<Client>,
+    paused: bool,
+    last_frame: Option<Instant>,
+}
+
+impl SimulationServer {
+    /// Listens on `addr` for WebSocket connections.
+    pub fn bind(sim: Simulation, addr: impl ToSocketAddrs, config: ServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+
+        Ok(Self {
+            sim,
+            config,
+            listener,
+            clients: Vec::new(),
+            paused: false,
+            last_frame: None,
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Number of connected clients.
+    pub fn client_count(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Serves clients until accepting connections fails.
+    pub fn run(&mut self) -> io::Result<()> {
+        loop {
+            self.poll()?;
+        }
+    }
+
+    /// Accepts new clients, handles their control messages, advances the simulation by one step
+    /// unless paused, and broadcasts a position frame if one is due.
+    pub fn poll(&mut self) -> io::Result<()> {
+        self.accept_clients()?;
+        let changed = self.handle_controls();
+
+        if self.paused {
+            if !changed {
+                std::thread::sleep(IDLE_INTERVAL);
+            }
+        } else {
+            self.sim.step();
+        }
+
+        let due = self.last_frame.is_none_or(|t| t.elapsed() >= self.config.frame_interval);
+        if changed || (due && !self.paused) {
+            self.broadcast_frame();
+        }
+        Ok(())
+    }
+
+    fn accept_clients(&mut self) -> io::Result<()> {
+        loop {
+            let (stream, addr) = match self.listener.accept() {
+                Ok(connection) => connection,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            // Handshake in blocking mode, bounded so a stalled client can't hold up the run
+            let _ = stream.set_nonblocking(false);
+            let _ = stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT));
+            let _ = stream.set_nodelay(true);
+            let config = WebSocketConfig::default().max_write_buffer_size(self.config.client_buffer);
+            let Ok(mut ws) = tungstenite::accept_with_config(stream, Some(config)) else {
+                continue;
+            };
+            if ws.get_mut().set_nonblocking(true).is_err() {
+                continue;
+            }
+
+            let state = Message::text(self.state_message().to_string());
+            let frame = Message::binary(encode_frame(&self.sim, self.config.compression));
+            if send(&mut ws, state) && send(&mut ws, frame) {
+                self.clients.push(Client { ws, addr });
+            }
+        }
+    }
+
+    /// Reads and applies pending control messages. Returns whether any were applied.
+    fn handle_controls(&mut self) -> bool {
+        let mut messages = Vec::new();
+        self.clients.retain_mut(|client| loop {
+            match client.ws.read() {
+                Ok(Message::Text(text)) => messages.push((client.addr, text.to_string())),
+                Ok(_) => {}
+                Err(tungstenite::Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => break true,
+                Err(_) => break false,
+            }
+        });
+
+        let mut changed = false;
+        for (addr, text) in messages {
+            match parse_control(&text).and_then(|control| self.apply(control)) {
+                Ok(()) => changed = true,
+                Err(message) => {
+                    let reply = json!({ "type": "error", "message": message }).to_string();
+                    if let Some(client) = self.clients.iter_mut().find(|client| client.addr == addr) {
+                        send(&mut client.ws, Message::text(reply));
+                    }
+                }
+            }
+        }
+
+        if changed {
+            let state = self.state_message().to_string();
+            self.clients.retain_mut(|client| send(&mut client.ws, Message::text(state.clone())));
+        }
+        changed
+    }
+
+    fn apply(&mut self, control: Control) -> Result<(), String> {
+        match control {
+            Control::Pause => self.paused = true,
+            Control::Resume => self.paused = false,
+            Control::Step(n) => {
+                if n > self.config.max_steps {
+                    return Err(format!("at most {} steps per message", self.config.max_steps));
+                }
+                self.sim.step_n(n);
+            }
+            Control::Reset(n) => {
+                self.check_body_count(n)?;
+                self.sim.reset(n)
+            }
+            Control::ResetWithGenerator { name, params } => {
+                self.check_generator(&name, &params)?;
+                self.sim.reset_with_generator(&name, &params).map_err(|e| e.to_string())?
+            }
+            Control::ApplyForce { pos, force, radius, falloff, mode } => {
+                self.sim.apply_force(pos, force, radius, falloff, mode)
+            }
+            Control::SetDt(dt) => {
+                if !(dt.is_finite() && dt > 0.0) {
+                    return Err("dt must be finite and positive".to_owned());
+                }
+                self.sim.set_dt(dt)
+            }
+        }
+        Ok(())
+    }
+
+    fn check_body_count(&self, n: usize) -> Result<(), String> {
+        if n > self.config.max_bodies {
+            return Err(format!("at most {} bodies", self.config.max_bodies));
+        }
+        Ok(())
+    }
+
+    /// Checks the sizes a generator would allocate before running it; the generator itself
+    /// rejects other invalid parameters.
+    fn check_generator(&self, name: &str, params: &str) -> Result<(), String> {
+        generators::find(name).map_err(|e| e.to_string())?;
+        let params = Params::from_json(params).map_err(|e| e.to_string())?;
+        self.check_body_count(params.usize("n", 10_000))?;
+        // The noise generator builds a resolution^2 density grid first
+        let resolution = params.usize("resolution", 0);
+        if resolution.checked_mul(resolution).is_none_or(|cells| cells > self.config.max_bodies) {
+            return Err(format!("resolution^2 must be at most {}", self.config.max_bodies));
+        }
+        Ok(())
+    }
+
+    fn state_message(&self) -> Value {
+        json!({
+            "type": "state",
+            "frame": self.sim.frame,
+            "time": self.sim.time,
+            "dt": self.sim.dt,
+            "bodies": self.sim.bodies.len(),
+            "paused": self.paused,
+        })
+    }
+
+    fn broadcast_frame(&mut self) {
+        self.last_frame = Some(Instant::now());
+        if self.clients.is_empty() {
+            return;
+        }
+
+        let frame = Message::binary(encode_frame(&self.sim, self.config.compression));
+        self.clients.retain_mut(|client| send(&mut client.ws, frame.clone()));
+    }
+}
+
+/// Queues `message` and flushes as much as the socket takes without blocking.
+/// Returns false if the client is gone; a full buffer drops the message but keeps the client.
+fn send(ws: &mut WebSocket<TcpStream>, message: Message) -> bool {
+    match ws.write(message) {
+        Ok(()) | Err(tungstenite::Error::WriteBufferFull(_)) => {}
+        Err(tungstenite::Error::Io(e)) if e.kind() == io::ErrorKind::WouldBlock => {}
+        Err(_) => return false,
+    }
+    match ws.flush() {
+        Ok(()) => true,
+        Err(tungstenite::Error::Io(e)) => e.kind() == io::ErrorKind::WouldBlock,
+        Err(_) => false,
+    }
+}
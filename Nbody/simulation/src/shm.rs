@@ -0,0 +1,287 @@
+use crate::simulation::Simulation;
+use memmap2::{Mmap, MmapMut};
+
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+/// Shared-memory ring buffer layout (native byte order; all offsets are 8-byte aligned):
+///
+/// Header, 64 bytes:
+///
+/// | offset | field      | type      |
+/// |--------|------------|-----------|
+/// | 0      | magic      | `b"NBRB"` |
+/// | 4      | version    | u32       |
+/// | 8      | slot count | u32       |
+/// | 12     | capacity   | u32, bodies per slot |
+/// | 16     | slot size  | u64, bytes |
+/// | 24     | latest     | u64, sequence number of the last complete frame (0 before the first) |
+///
+/// followed by `slot count` slots of `slot size` bytes. Frame `s` (numbered from 1) is written to
+/// slot `(s - 1) % slot count`, which starts with a 64-byte header:
+///
+/// | offset | field      | type |
+/// |--------|------------|------|
+/// | 0      | begin      | u64, sequence number, stored before the slot is written |
+/// | 8      | end        | u64, sequence number, stored after the slot is written |
+/// | 16     | frame      | u64  |
+/// | 24     | time       | f64  |
+/// | 32     | origin     | 2 x f64, see `Simulation::origin` |
+/// | 48     | count      | u32, bodies in the slot |
+/// | 52     | total      | u32, bodies in the simulation (more than `count` if truncated) |
+///
+/// and then the arrays `x`, `y`, `vx`, `vy`, `mass` and `radius`, each `capacity` f32s,
+/// of which the first `count` are valid.
+///
+/// Readers load `latest` (acquire), copy the slot, then check (acquire fence) that both `begin` and
+/// `end` equal the sequence number; otherwise the writer lapped them and they retry.
+pub const RING_MAGIC: [u8; 4] = *b"NBRB";
+pub const RING_VERSION: u32 = 1;
+
+const HEADER_SIZE: usize = 64;
+const SLOT_HEADER_SIZE: usize = 64;
+const LATEST_OFFSET: usize = 24;
+
+/// Per-body arrays in a slot: x, y, vx, vy, mass, radius.
+const ARRAYS: usize = 6;
+
+/// Path of the ring buffer `name`: `/dev/shm/<name>` on Linux, where it is the same object as
+/// `shm_open("/<name>")`, and a file in the temporary directory elsewhere.
+pub fn ring_path(name: &str) -> PathBuf {
+    if cfg!(target_os = "linux") {
+        Path::new("/dev/shm").join(name)
+    } else {
+        std::env::temp_dir().join(name)
+    }
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn slot_size(capacity: usize) -> usize {
+    (SLOT_HEADER_SIZE + ARRAYS * 4 * capacity).next_multiple_of(64)
+}
+
+/// Returns the atomic u64 at `offset` of a mapping.
+///
+/// # Safety
+/// `offset + 8` must be in bounds of the mapping at `base` and `base + offset` 8-byte aligned.
+unsafe fn atomic_at<'a>(base: *const u8, offset: usize) -> &'a AtomicU64 {
+    unsafe { &*(base.add(offset) as *const AtomicU64) }
+}
+
+/// Publishes each frame's body SoA into a named shared-memory ring buffer (see `RING_MAGIC`), so
+/// renderers in other processes can read frames without calling into the simulation.
+///
+/// The buffer is removed when the publisher is dropped; readers that have it mapped keep their view.
+#[derive(Debug)]
+pub struct RingPublisher {
+    mmap: MmapMut,
+    path: PathBuf,
+    slots: usize,
+    capacity: usize,
+    slot_size: usize,
+    sequence: u64,
+}
+
+impl RingPublisher {
+    /// Creates (or replaces) the ring buffer `name` (see `ring_path`) with `slots` frames of
+    /// up to `capacity` bodies each.
+    pub fn create(name: &str, slots: usize, capacity: usize) -> io::Result<Self> {
+        Self::create_at(ring_path(name), slots, capacity)
+    }
+
+    /// Creates (or replaces) a ring buffer backed by the file at `path`.
+    pub fn create_at(path: impl AsRef<Path>, slots: usize, capacity: usize) -> io::Result<Self> {
+        if slots == 0 || slots > u32::MAX as usize || capacity == 0 || capacity > u32::MAX as usize {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid ring buffer size"));
+        }
+        let path = path.as_ref().to_path_buf();
+        let slot_size = slot_size(capacity);
+        let len = slots
+            .checked_mul(slot_size)
+            .and_then(|len| len.checked_add(HEADER_SIZE))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "ring buffer too large"))?;
+
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path)?;
+        file.set_len(len as u64)?;
+        // Safety: the file was just created for this publisher; readers only map it read-only
+        let mut mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        mmap[4..8].copy_from_slice(&RING_VERSION.to_ne_bytes());
+        mmap[8..12].copy_from_slice(&(slots as u32).to_ne_bytes());
+        mmap[12..16].copy_from_slice(&(capacity as u32).to_ne_bytes());
+        mmap[16..24].copy_from_slice(&(slot_size as u64).to_ne_bytes());
+        // Readers check the magic first, so it goes in last
+        fence(Ordering::Release);
+        mmap[0..4].copy_from_slice(&RING_MAGIC);
+
+        Ok(Self { mmap, path, slots, capacity, slot_size, sequence: 0 })
+    }
+
+    /// Writes the current bodies of `sim` as the next frame and returns how many were written,
+    /// which is less than the body count if it exceeds the capacity.
+    pub fn publish(&mut self, sim: &Simulation) -> usize {
+        self.sequence += 1;
+        let sequence = self.sequence;
+        let slot = HEADER_SIZE + ((sequence - 1) as usize % self.slots) * self.slot_size;
+        let count = sim.bodies.len().min(self.capacity);
+        let base = self.mmap.as_mut_ptr();
+
+        // Safety: the slot lies within the mapping and its header fields are 8-byte aligned
+        unsafe { atomic_at(base, slot) }.store(sequence, Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let header = &mut self.mmap[slot + 16..slot + SLOT_HEADER_SIZE];
+        header[0..8].copy_from_slice(&(sim.frame as u64).to_ne_bytes());
+        header[8..16].copy_from_slice(&sim.time.to_ne_bytes());
+        header[16..24].copy_from_slice(&sim.origin[0].to_ne_bytes());
+        header[24..32].copy_from_slice(&sim.origin[1].to_ne_bytes());
+        header[32..36].copy_from_slice(&(count as u32).to_ne_bytes());
+        header[36..40].copy_from_slice(&(sim.bodies.len().min(u32::MAX as usize) as u32).to_ne_bytes());
+
+        // Safety: the arrays lie within the slot, are 4-byte aligned and don't overlap
+        let arrays = unsafe {
+            std::slice::from_raw_parts_mut(base.add(slot + SLOT_HEADER_SIZE) as *mut f32, ARRAYS * self.capacity)
+        };
+        let mut arrays = arrays.chunks_exact_mut(self.capacity).map(|array| &mut array[..count]);
+        for column in [sim.bodies.positions(), sim.bodies.velocities()] {
+            for component in 0..2 {
+                let array = arrays.next().expect("one array per vector component");
+                for (value, v) in array.iter_mut().zip(column) {
+                    *value = v[component];
+                }
+            }
+        }
+        for column in [sim.bodies.masses(), sim.bodies.radii()] {
+            arrays.next().expect("one array per scalar").copy_from_slice(&column[..count]);
+        }
+
+        unsafe { atomic_at(base, slot + 8) }.store(sequence, Ordering::Release);
+        unsafe { atomic_at(base, LATEST_OFFSET) }.store(sequence, Ordering::Release);
+        count
+    }
+
+    /// Sequence number of the last published frame (0 before the first).
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Path of the backing file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for RingPublisher {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// A frame read from a ring buffer by `RingSubscriber`.
+#[derive(Clone, Debug, Default)]
+pub struct RingFrame {
+    /// Sequence number of the frame in the ring buffer.
+    pub sequence: u64,
+    pub frame: u64,
+    pub time: f64,
+    pub origin: [f64; 2],
+    /// Bodies in the simulation, which may exceed the length of the arrays if truncated.
+    pub total: usize,
+    pub x: Vec<f32>,
+    pub y: Vec<f32>,
+    pub vx: Vec<f32>,
+    pub vy: Vec<f32>,
+    pub mass: Vec<f32>,
+    pub radius: Vec<f32>,
+}
+
+/// Reads frames from a ring buffer written by `RingPublisher`, possibly in another process.
+#[derive(Debug)]
+pub struct RingSubscriber {
+    mmap: Mmap,
+    slots: usize,
+    capacity: usize,
+    slot_size: usize,
+}
+
+impl RingSubscriber {
+    /// Opens the ring buffer `name` (see `ring_path`).
+    pub fn open(name: &str) -> io::Result<Self> {
+        Self::open_at(ring_path(name))
+    }
+
+    /// Opens a ring buffer backed by the file at `path`.
+    pub fn open_at(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: the mapping is only read; concurrent writes are detected by the sequence numbers
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        if mmap.len() < HEADER_SIZE || mmap[0..4] != RING_MAGIC {
+            return Err(invalid_data("not an nbody ring buffer"));
+        }
+        fence(Ordering::Acquire);
+        let u32_at = |i: usize| u32::from_ne_bytes(mmap[i..i + 4].try_into().unwrap());
+        if u32_at(4) != RING_VERSION {
+            return Err(invalid_data("unsupported ring buffer version"));
+        }
+        let slots = u32_at(8) as usize;
+        let capacity = u32_at(12) as usize;
+        let slot_size = u64::from_ne_bytes(mmap[16..24].try_into().unwrap()) as usize;
+        if slots == 0 || capacity == 0 || slot_size != self::slot_size(capacity) || mmap.len() < HEADER_SIZE + slots * slot_size {
+            return Err(invalid_data("ring buffer is shorter than its header describes"));
+        }
+
+        Ok(Self { mmap, slots, capacity, slot_size })
+    }
+
+    /// Sequence number of the last complete frame (0 before the first).
+    pub fn latest_sequence(&self) -> u64 {
+        // Safety: the header was checked to be in bounds; mappings are page aligned
+        unsafe { atomic_at(self.mmap.as_ptr(), LATEST_OFFSET) }.load(Ordering::Acquire)
+    }
+
+    /// Copies the latest frame into `frame`, reusing its buffers. Returns false if nothing newer
+    /// than `frame.sequence` has been published, or if the writer kept overwriting the slot.
+    pub fn read_latest(&self, frame: &mut RingFrame) -> bool {
+        let base = self.mmap.as_ptr();
+        for _ in 0..8 {
+            let sequence = self.latest_sequence();
+            if sequence == 0 || sequence == frame.sequence {
+                return false;
+            }
+            let slot = HEADER_SIZE + ((sequence - 1) as usize % self.slots) * self.slot_size;
+
+            let header = &self.mmap[slot + 16..slot + SLOT_HEADER_SIZE];
+            let u64_at = |i: usize| u64::from_ne_bytes(header[i..i + 8].try_into().unwrap());
+            let count = (u32::from_ne_bytes(header[32..36].try_into().unwrap()) as usize).min(self.capacity);
+            frame.frame = u64_at(0);
+            frame.time = f64::from_bits(u64_at(8));
+            frame.origin = [f64::from_bits(u64_at(16)), f64::from_bits(u64_at(24))];
+            frame.total = u32::from_ne_bytes(header[36..40].try_into().unwrap()) as usize;
+
+            // Safety: the arrays lie within the slot and are 4-byte aligned
+            let arrays = unsafe {
+                std::slice::from_raw_parts(base.add(slot + SLOT_HEADER_SIZE) as *const f32, ARRAYS * self.capacity)
+            };
+            let columns = [&mut frame.x, &mut frame.y, &mut frame.vx, &mut frame.vy, &mut frame.mass, &mut frame.radius];
+            for (column, array) in columns.into_iter().zip(arrays.chunks_exact(self.capacity)) {
+                column.clear();
+                column.extend_from_slice(&array[..count]);
+            }
+
+            fence(Ordering::Acquire);
+            let begin = unsafe { atomic_at(base, slot) }.load(Ordering::Relaxed);
+            let end = unsafe { atomic_at(base, slot + 8) }.load(Ordering::Relaxed);
+            if begin == sequence && end == sequence {
+                frame.sequence = sequence;
+                return true;
+            }
+        }
+        false
+    }
+}
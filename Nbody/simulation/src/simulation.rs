@@ -1,18 +1,272 @@
 #![allow(unused)]
 
+#[cfg(feature = "gpu")]
+use crate::gpu::GpuForces;
+#[cfg(feature = "debug-checks")]
+use crate::checks;
 use crate::{
+    bodies::Bodies,
     body::Body,
-    quadtree::{Quad, Quadtree},
+    boundary::Boundary,
+    broadphase::SweepAndPrune,
+    commands::CommandQueue,
+    drag::DragConstraint,
+    energy::{EnergySample, EnergyTracker},
+    external::{self, ExternalPotential},
+    force::Force,
+    generators::{self, GeneratorError, Params},
+    progress::{ProgressCallback, ProgressOperation},
+    rng::Rng,
+    quadtree::{AdaptiveTheta, LeafWalk, Node, NodeLayout, NodeReorder, ParentLevels, Partition, Quad, Quadtree},
+    replay::{InputEvent, InputLog},
+    report::StepReport,
+    telemetry::{PhaseTimer, StepStats, StepStatsCallback},
+    trails::TrailRecorder,
+    trajectory::TrajectoryLogger,
+    timeline::Timeline,
     utils,
+    wells::{self, GravityWell, WellId, Wells},
 };
 
 use broccoli::{aabb::Rect, Tree};
 use ultraviolet::Vec2;
-use rustfiber::{JobSystem, ParallelSliceMut};
+use wide::f32x8;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::tuning::{ChunkTuner, JobTimes};
+#[cfg(not(target_arch = "wasm32"))]
+use rustfiber::{GranularityHint, JobSystem};
 use rayon::prelude::*;
 
+use std::ops::Range;
 use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
 
+/// Enters a `tracing` span named `$name` until the end of the enclosing scope with the `tracing`
+/// feature; expands to nothing without it.
+macro_rules! span {
+    ($name:literal $(, $field:ident = $value:expr)* $(,)?) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($name $(, $field = $value)*).entered();
+    };
+}
+
+/// A collision resolved during the last step.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Collision {
+    /// Index of the first body.
+    pub i: u32,
+    /// Index of the second body.
+    pub j: u32,
+    /// Magnitude of the impulse exchanged between the bodies.
+    pub impulse: f32,
+}
+
+/// How an applied force decays with distance from its origin.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Falloff {
+    /// Full strength everywhere inside the radius.
+    Constant = 0,
+    /// Decays linearly to zero at the radius.
+    Linear = 1,
+    /// Decays as 1 / (1 + d^2), full strength at the origin.
+    InverseSquare = 2,
+}
+
+/// How an applied force changes body velocities.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForceMode {
+    /// Instantaneous velocity change, independent of mass.
+    Impulse = 0,
+    /// Force acting for one time step: dv = F * dt / m.
+    Force = 1,
+}
+
+/// Where a simulation runs its parallel work, see `Simulation::set_backend`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// RustFiber jobs on the job system (Rayon on wasm32, where RustFiber is unavailable).
+    RustFiber = 0,
+    Rayon = 1,
+    /// Forces on the GPU (see `Simulation::enable_gpu`), the other phases on the CPU backend
+    /// selected before.
+    Gpu = 2,
+}
+
+/// How `Simulation::step` advances velocities and positions, see `Simulation::set_integrator`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Integrator {
+    /// Kicks by the whole step with the accelerations of the last gravity pass, then drifts.
+    /// First order; the default.
+    #[default]
+    SemiImplicitEuler = 0,
+    /// Kick-drift-kick leapfrog: half kicks either side of the gravity pass. Second order, and
+    /// keeps the energy error bounded over long runs.
+    Leapfrog = 1,
+}
+
+/// Multi-rate force updates, see `Simulation::set_multirate`.
+///
+/// Slow bodies, those beyond `radius` from the center of mass or with an acceleration below
+/// `min_acc`, only get their forces recomputed every `interval` frames and keep their last
+/// acceleration in between. They are staggered by id, so each frame updates an even share.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MultiRate {
+    /// Frames between force updates of slow bodies (1 updates every body every frame).
+    pub interval: usize,
+    /// Distance from the center of mass beyond which bodies are slow.
+    pub radius: f32,
+    /// Acceleration magnitude below which bodies are slow.
+    pub min_acc: f32,
+}
+
+impl Default for MultiRate {
+    /// Every 4 frames beyond any distance or below any acceleration, i.e. for no body until
+    /// `radius` or `min_acc` is set.
+    fn default() -> Self {
+        Self {
+            interval: 4,
+            radius: f32::INFINITY,
+            min_acc: 0.0,
+        }
+    }
+}
+
+/// How `Simulation::step` integrates, as set with `set_integrator`, `set_substeps`,
+/// `set_gravity_interval`, `set_collision_interval`, `set_multirate`, `set_adaptive_theta`
+/// and `set_morton_interval`.
+/// Runs only reproduce with the same settings, so they are part of `SimulationState`,
+/// input logs and checkpoints.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepSettings {
+    pub integrator: Integrator,
+    pub substeps: usize,
+    pub gravity_interval: usize,
+    pub collision_interval: usize,
+    pub multirate: Option<MultiRate>,
+    pub adaptive_theta: Option<AdaptiveTheta>,
+    /// Reorders bodies, which changes the multi-rate schedule (staggered by id) and the order
+    /// collisions are resolved in.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub morton_interval: usize,
+}
+
+impl Default for StepSettings {
+    /// The settings of a new simulation.
+    fn default() -> Self {
+        Self {
+            integrator: Integrator::default(),
+            substeps: 1,
+            gravity_interval: 1,
+            collision_interval: 1,
+            multirate: None,
+            adaptive_theta: None,
+            morton_interval: 0,
+        }
+    }
+}
+
+/// Plain-data state of a simulation: everything except the job system and callbacks.
+/// With the `serde` feature enabled this is the canonical serializable form of a simulation.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulationState {
+    pub dt: f32,
+    pub frame: usize,
+    pub time: f64,
+    pub origin: [f64; 2],
+    pub bodies: Vec<Body>,
+    /// Id of each body (see `Bodies::ids`), or empty to number the bodies in order.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ids: Vec<u32>,
+    /// See `Bodies::next_id`; unused if `ids` is empty.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub next_id: u32,
+    pub quadtree: Quadtree,
+    pub use_rayon: bool,
+    pub boundary: Boundary,
+    pub external: Vec<ExternalPotential>,
+    /// Defaults for states saved before the settings were part of them.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub settings: StepSettings,
+}
+
+/// Body count from which the tree is built in parallel; below it the fixed cost of splitting
+/// the work isn't worth it.
+const PARALLEL_BUILD_MIN_BODIES: usize = 16_384;
+
+/// A cell of the parallel tree build holding more than 1 / `UNBALANCED_CELL_SHARE` of the bodies
+/// makes `build_tree` propagate the tree by levels rather than per cell.
+const UNBALANCED_CELL_SHARE: usize = 8;
+
+/// Parents in a tree level from which the level is propagated in parallel.
+const PARALLEL_PROPAGATE_MIN_PARENTS: usize = 4096;
+
+/// Body count from which `collide` runs the parallel sweep-and-prune broad phase instead of a
+/// (single-threaded) broccoli tree.
+const PARALLEL_COLLIDE_MIN_BODIES: usize = 16_384;
+
+/// Parallel phases of a step on the fiber backend, see `Simulation::set_granularity`.
+#[cfg(not(target_arch = "wasm32"))]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ParallelPhase {
+    /// Tree walks of `attract`, over groups of 8 bodies.
+    Force = 0,
+    /// Subtree builds of a parallel tree build, over the `Quadtree::SPLIT_DEPTH` cells.
+    Build = 1,
+    /// Center of mass propagation of an unbalanced tree, over the parents of a level.
+    Propagate = 2,
+    /// Integration in `iterate`, over bodies.
+    Integrate = 3,
+    /// Sweeps of the parallel broad phase, over `SweepAndPrune::CHUNK` boxes each.
+    Collide = 4,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ParallelPhase {
+    pub const ALL: [ParallelPhase; 5] = [Self::Force, Self::Build, Self::Propagate, Self::Integrate, Self::Collide];
+}
+
+/// How the fiber backend splits a parallel phase into jobs, see `Simulation::set_granularity`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Granularity {
+    /// Cost of each item, from which the job system sizes the jobs.
+    pub hint: GranularityHint,
+    /// Minimum items per job (see `JobSystem::parallel_for_chunked`), or 0 to size the jobs by
+    /// `hint` alone.
+    pub min_chunk: usize,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Granularity {
+    /// Default granularity of `phase`, matching the cost of its items.
+    pub fn default_for(phase: ParallelPhase) -> Self {
+        let hint = match phase {
+            ParallelPhase::Force => GranularityHint::Light,
+            ParallelPhase::Build | ParallelPhase::Collide => GranularityHint::Heavy,
+            ParallelPhase::Propagate | ParallelPhase::Integrate => GranularityHint::Trivial,
+        };
+        Self { hint, min_chunk: 0 }
+    }
+}
+
+/// Job system shared by all simulations created without one, see `Simulation::shared_job_system`.
+#[cfg(not(target_arch = "wasm32"))]
+static SHARED_JOB_SYSTEM: OnceLock<Arc<JobSystem>> = OnceLock::new();
+
+/// Callback invoked for each collision after a step.
+pub type CollisionCallback = Box<dyn FnMut(&Collision) + Send>;
 
 /// Manages the Barnes-Hut N-body simulation state and logic.
 // #[derive(Debug)] // JobSystem doesn't implement Debug
@@ -22,14 +276,99 @@ pub struct Simulation {
     pub dt: f32,
     /// Current frame count.
     pub frame: usize,
-    /// Collection of all bodies in the simulation.
-    pub bodies: Vec<Body>,
+    /// Simulated time elapsed, accumulated per step so changes to `dt` are accounted for.
+    pub time: f64,
+    /// World-space position (in f64) that body positions are relative to; see `shift_origin`.
+    pub origin: [f64; 2],
+    /// Collection of all bodies in the simulation, stored as columns (see `Bodies`).
+    pub bodies: Bodies,
     /// The Quadtree used for spatial acceleration of gravitational calculations.
     pub quadtree: Quadtree,
     /// The JobSystem for parallel execution.
+    #[cfg(not(target_arch = "wasm32"))]
     pub job_system: Arc<JobSystem>,
     /// Whether to use Rayon instead of RustFiber.
+    /// RustFiber is unavailable on wasm32, which always uses Rayon (on the calling thread).
     pub use_rayon: bool,
+    /// Playfield bounds applied after each integration step.
+    pub boundary: Boundary,
+    /// Collisions resolved during the last step.
+    pub collisions: Vec<Collision>,
+    /// Fixed background potentials added to the tree forces.
+    pub external: Vec<ExternalPotential>,
+    /// Interactive attractors and repulsors added to the tree forces (see `add_well`).
+    pub wells: Wells,
+    /// Scheduled time-varying forces and pulses (see `Timeline`).
+    pub timeline: Timeline,
+    /// Body being dragged by the user, if any (see `begin_drag`).
+    pub(crate) drag: Option<DragConstraint>,
+    /// User-defined forces run after each gravity pass (see `add_force`).
+    pub forces: Vec<Arc<dyn Force>>,
+    /// Commands queued by other threads, applied at the start of each step.
+    pub(crate) commands: CommandQueue,
+    /// Random number generator of the generators and spawners (see `set_rng`).
+    rng: Box<dyn Rng>,
+    /// Optional callback invoked for each collision after a step.
+    pub collision_callback: Option<CollisionCallback>,
+    /// Optional logger recording selected bodies after a step.
+    pub trajectory_logger: Option<TrajectoryLogger>,
+    /// Optional recorder keeping the recent positions of selected bodies after a step.
+    pub trail_recorder: Option<TrailRecorder>,
+    /// Optional tracker sampling the total energy after a step.
+    pub energy_tracker: Option<EnergyTracker>,
+    /// Optional log recording external interactions for deterministic replay.
+    pub input_log: Option<InputLog>,
+    /// Optional callback reporting progress of long operations and allowing them to be cancelled.
+    pub progress_callback: Option<ProgressCallback>,
+    /// Optional callback receiving the stats of each step.
+    pub step_stats_callback: Option<StepStatsCallback>,
+    /// Body indices in tree order, reused across steps to group bodies for `Quadtree::acc8`.
+    body_order: Vec<u32>,
+    /// Bounding boxes of the bodies, reused across steps by `collide`. The broccoli tree itself
+    /// borrows them, so only its (much smaller) node array is still allocated per step.
+    collision_rects: Vec<(Rect<f32>, usize)>,
+    /// Scratch space of the parallel broad phase, with the candidate pairs of each chunk.
+    sweep: SweepAndPrune,
+    collision_pairs: Vec<Vec<(u32, u32)>>,
+    /// Scratch space of the parallel tree build, see `build_tree`.
+    partition: Partition,
+    subtrees: Vec<Quadtree>,
+    parent_levels: ParentLevels,
+    /// Scratch space of reordering the tree (one per subtree in a parallel build).
+    reorders: Vec<NodeReorder>,
+    /// Order the tree nodes are stored in, see `set_node_layout`.
+    node_layout: NodeLayout,
+    /// Frames between Morton sorts of the bodies (0 = never), see `set_morton_interval`.
+    morton_interval: usize,
+    /// Density-dependent opening angle, see `set_adaptive_theta`.
+    adaptive_theta: Option<AdaptiveTheta>,
+    /// Job split of each `ParallelPhase` on the fiber backend, see `set_granularity`.
+    #[cfg(not(target_arch = "wasm32"))]
+    granularity: [Granularity; ParallelPhase::ALL.len()],
+    /// Job size search of the force phase, see `set_auto_tune`.
+    #[cfg(not(target_arch = "wasm32"))]
+    chunk_tuner: Option<ChunkTuner>,
+    /// Squared opening angle of each body in `body_order` under `adaptive_theta`.
+    body_t_sq: Vec<f32>,
+    /// Scratch space of the tree walk producing `body_order`.
+    leaf_walk: LeafWalk,
+    /// Reduced force update rate of slow bodies, see `set_multirate`.
+    multirate: Option<MultiRate>,
+    /// Frames between runs of `attract` and `collide` in `step` (0 = never), see
+    /// `set_gravity_interval` and `set_collision_interval`.
+    gravity_interval: usize,
+    collision_interval: usize,
+    /// Integration scheme and integration steps per `step`, see `set_integrator` and
+    /// `set_substeps`.
+    integrator: Integrator,
+    substeps: usize,
+    /// Summary of the last step, see `step_report`.
+    pub(crate) report: StepReport,
+    /// Whether `report` includes tree metrics, see `set_tree_metrics`.
+    pub(crate) tree_metrics: bool,
+    /// GPU force backend used by `attract` when enabled, see `enable_gpu`.
+    #[cfg(feature = "gpu")]
+    gpu: Option<GpuForces>,
 }
 
 impl std::fmt::Debug for Simulation {
@@ -37,10 +376,28 @@ impl std::fmt::Debug for Simulation {
         f.debug_struct("Simulation")
             .field("dt", &self.dt)
             .field("frame", &self.frame)
+            .field("time", &self.time)
+            .field("origin", &self.origin)
             .field("bodies", &self.bodies)
             .field("quadtree", &self.quadtree)
-            .field("job_system", &"JobSystem")
+            .field("job_system", &cfg!(not(target_arch = "wasm32")))
             .field("use_rayon", &self.use_rayon)
+            .field("boundary", &self.boundary)
+            .field("collisions", &self.collisions)
+            .field("external", &self.external)
+            .field("wells", &self.wells)
+            .field("timeline", &self.timeline)
+            .field("drag", &self.drag)
+            .field("forces", &self.forces.len())
+            .field("commands", &self.commands.len())
+            .field("rng_seed", &self.rng.get_seed())
+            .field("collision_callback", &self.collision_callback.is_some())
+            .field("trajectory_logger", &self.trajectory_logger)
+            .field("trail_recorder", &self.trail_recorder.as_ref().map(TrailRecorder::trails))
+            .field("energy_tracker", &self.energy_tracker)
+            .field("input_log", &self.input_log.as_ref().map(|log| log.events.len()))
+            .field("progress_callback", &self.progress_callback.is_some())
+            .field("step_stats_callback", &self.step_stats_callback.is_some())
             .finish()
     }
 }
@@ -48,6 +405,67 @@ impl std::fmt::Debug for Simulation {
 
 
 
+impl Clone for Simulation {
+    /// Creates an independent copy sharing the same JobSystem and registered forces.
+    /// The collision, progress and step stats callbacks, trajectory logger, input log, command
+    /// queue and GPU backend are not cloned.
+    fn clone(&self) -> Self {
+        Self {
+            dt: self.dt,
+            frame: self.frame,
+            time: self.time,
+            origin: self.origin,
+            bodies: self.bodies.clone(),
+            quadtree: self.quadtree.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            job_system: self.job_system.clone(),
+            use_rayon: self.use_rayon,
+            boundary: self.boundary,
+            collisions: self.collisions.clone(),
+            external: self.external.clone(),
+            wells: self.wells.clone(),
+            timeline: self.timeline.clone(),
+            drag: self.drag,
+            forces: self.forces.clone(),
+            commands: CommandQueue::default(),
+            rng: self.rng.box_clone(),
+            collision_callback: None,
+            trajectory_logger: None,
+            trail_recorder: self.trail_recorder.clone(),
+            energy_tracker: self.energy_tracker.clone(),
+            input_log: None,
+            progress_callback: None,
+            step_stats_callback: None,
+            body_order: Vec::new(),
+            collision_rects: Vec::new(),
+            sweep: SweepAndPrune::default(),
+            collision_pairs: Vec::new(),
+            partition: Partition::default(),
+            subtrees: Vec::new(),
+            parent_levels: ParentLevels::default(),
+            reorders: Vec::new(),
+            node_layout: self.node_layout,
+            morton_interval: self.morton_interval,
+            adaptive_theta: self.adaptive_theta,
+            #[cfg(not(target_arch = "wasm32"))]
+            granularity: self.granularity,
+            #[cfg(not(target_arch = "wasm32"))]
+            chunk_tuner: self.chunk_tuner.clone(),
+            body_t_sq: Vec::new(),
+            leaf_walk: LeafWalk::default(),
+            multirate: self.multirate,
+            gravity_interval: self.gravity_interval,
+            collision_interval: self.collision_interval,
+            integrator: self.integrator,
+            substeps: self.substeps,
+            report: self.report.clone(),
+            tree_metrics: self.tree_metrics,
+            #[cfg(feature = "gpu")]
+            gpu: None,
+        }
+    }
+}
+
 impl Default for Simulation {
     fn default() -> Self {
         Self::new()
@@ -77,133 +495,1120 @@ impl Simulation {
         Self::with_bodies(bodies, dt, theta, epsilon)
     }
 
-    /// Initializes a new simulation with the given bodies and parameters.
+    /// Initializes a new simulation with the given bodies and parameters, running on the
+    /// `shared_job_system`.
     pub fn with_bodies(bodies: Vec<Body>, dt: f32, theta: f32, epsilon: f32) -> Self {
+        Self::from_parts(
+            bodies,
+            dt,
+            theta,
+            epsilon,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::shared_job_system(),
+        )
+    }
+
+    /// Process-wide job system that simulations run on unless given their own with
+    /// `with_bodies_and_job_system`. It is built (see `build_job_system`) when the first
+    /// simulation is created and lives until the process exits, so hosts that create and destroy
+    /// simulations frequently don't pay for a fiber pool each time.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn shared_job_system() -> Arc<JobSystem> {
+        SHARED_JOB_SYSTEM.get_or_init(|| Arc::new(Self::build_job_system())).clone()
+    }
+
+    /// Builds a job system in the configuration simulations use by default, e.g. to give a
+    /// simulation its own with `with_bodies_and_job_system`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn build_job_system() -> JobSystem {
         // Use a robust configuration for the job system
-        let job_system = JobSystem::builder()
+        JobSystem::builder()
             .stack_size(2 * 1024 * 1024) // 2MB stack to match OS threads and prevent overflow
             .initial_pool_size(64)       // Larger initial pool
             .target_pool_size(512)       // Allow more growth
             .pinning_strategy(rustfiber::PinningStrategy::AvoidSMT)
-            .build();
-            
-        Self::with_bodies_and_job_system(bodies, dt, theta, epsilon, Arc::new(job_system))
+            .build()
     }
 
+    /// Initializes the solar system preset (see `utils::solar_system`) in AU, solar masses and years,
+    /// with a time step of roughly a third of a day.
+    pub fn solar_system() -> Self {
+        let mut sim = Self::with_bodies(utils::solar_system(), 1e-3, 0.5, 1e-4);
+        sim.set_gravity_constant(utils::G_SOLAR);
+        sim
+    }
+
+    /// Initializes a new simulation running on the given job system instead of the shared one,
+    /// e.g. one the host also schedules its own work on.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn with_bodies_and_job_system(
         bodies: Vec<Body>, 
         dt: f32, 
         theta: f32, 
         epsilon: f32, 
         job_system: Arc<JobSystem>
+    ) -> Self {
+        Self::from_parts(bodies, dt, theta, epsilon, job_system)
+    }
+
+    fn from_parts(
+        bodies: Vec<Body>,
+        dt: f32,
+        theta: f32,
+        epsilon: f32,
+        #[cfg(not(target_arch = "wasm32"))] job_system: Arc<JobSystem>,
     ) -> Self {
         let quadtree = Quadtree::new(theta, epsilon);
 
         Self {
             dt,
             frame: 0,
-            bodies,
+            time: 0.0,
+            origin: [0.0; 2],
+            bodies: bodies.into(),
             quadtree,
+            #[cfg(not(target_arch = "wasm32"))]
             job_system,
             use_rayon: false,
+            boundary: Boundary::default(),
+            collisions: Vec::new(),
+            external: Vec::new(),
+            wells: Wells::default(),
+            timeline: Timeline::default(),
+            drag: None,
+            forces: Vec::new(),
+            commands: CommandQueue::default(),
+            rng: Box::new(fastrand::Rng::new()),
+            collision_callback: None,
+            trajectory_logger: None,
+            trail_recorder: None,
+            energy_tracker: None,
+            input_log: None,
+            progress_callback: None,
+            step_stats_callback: None,
+            body_order: Vec::new(),
+            collision_rects: Vec::new(),
+            sweep: SweepAndPrune::default(),
+            collision_pairs: Vec::new(),
+            partition: Partition::default(),
+            subtrees: Vec::new(),
+            parent_levels: ParentLevels::default(),
+            reorders: Vec::new(),
+            node_layout: NodeLayout::default(),
+            morton_interval: 0,
+            adaptive_theta: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            granularity: ParallelPhase::ALL.map(Granularity::default_for),
+            #[cfg(not(target_arch = "wasm32"))]
+            chunk_tuner: None,
+            body_t_sq: Vec::new(),
+            leaf_walk: LeafWalk::default(),
+            multirate: None,
+            gravity_interval: 1,
+            collision_interval: 1,
+            integrator: Integrator::default(),
+            substeps: 1,
+            report: StepReport::default(),
+            tree_metrics: false,
+            #[cfg(feature = "gpu")]
+            gpu: None,
+        }
+    }
+
+    /// Creates a simulation from a saved state, on the shared job system.
+    pub fn from_state(state: SimulationState) -> Self {
+        let mut sim = Self::with_bodies(Vec::new(), state.dt, 1.0, 1.0);
+        sim.set_state(state);
+        sim
+    }
+
+    /// Copies out the plain-data state of the simulation.
+    pub fn state(&self) -> SimulationState {
+        SimulationState {
+            dt: self.dt,
+            frame: self.frame,
+            time: self.time,
+            origin: self.origin,
+            bodies: self.bodies.to_vec(),
+            ids: self.bodies.ids().to_vec(),
+            next_id: self.bodies.next_id(),
+            quadtree: self.quadtree.clone(),
+            use_rayon: self.use_rayon,
+            boundary: self.boundary,
+            external: self.external.clone(),
+            settings: self.step_settings(),
         }
     }
 
+    /// Replaces the plain-data state, keeping the job system and collision callback.
+    pub fn set_state(&mut self, state: SimulationState) {
+        self.dt = state.dt;
+        self.frame = state.frame;
+        self.time = state.time;
+        self.origin = state.origin;
+        self.bodies = state.bodies.into();
+        if !state.ids.is_empty() && state.ids.len() == self.bodies.len() {
+            self.bodies.set_ids(state.ids, state.next_id);
+        }
+        self.quadtree = state.quadtree;
+        self.use_rayon = state.use_rayon;
+        self.boundary = state.boundary;
+        self.external = state.external;
+        self.set_step_settings(state.settings);
+        self.collisions.clear();
+    }
+
+    /// Current integration settings, see `StepSettings`.
+    pub fn step_settings(&self) -> StepSettings {
+        StepSettings {
+            integrator: self.integrator,
+            substeps: self.substeps,
+            gravity_interval: self.gravity_interval,
+            collision_interval: self.collision_interval,
+            multirate: self.multirate,
+            adaptive_theta: self.adaptive_theta,
+            morton_interval: self.morton_interval,
+        }
+    }
+
+    /// Restores settings saved with `step_settings`, without recording them in the input log.
+    pub(crate) fn set_step_settings(&mut self, settings: StepSettings) {
+        self.integrator = settings.integrator;
+        self.substeps = settings.substeps.max(1);
+        self.gravity_interval = settings.gravity_interval;
+        self.collision_interval = settings.collision_interval;
+        self.multirate = settings.multirate;
+        self.adaptive_theta = settings.adaptive_theta;
+        self.morton_interval = settings.morton_interval;
+    }
+
     /// Resets the simulation with a new number of bodies.
     pub fn reset(&mut self, n: usize) {
-        self.bodies = crate::utils::uniform_disc(n);
+        self.reset_with_bodies(crate::utils::uniform_disc(n));
+    }
+
+    /// Resets the simulation with bodies from a named generator (see `generators::GENERATORS`).
+    /// `params_json` is a JSON object of generator parameters; missing ones use defaults.
+    /// Presets defined in specific units also set the gravitational constant.
+    /// The simulation is left untouched on error or if the progress callback cancels.
+    pub fn reset_with_generator(&mut self, name: &str, params_json: &str) -> Result<(), GeneratorError> {
+        let generator = generators::find(name)?;
+        let params = Params::from_json(params_json)?;
+
+        if !self.report_progress(ProgressOperation::Generator, 0.0) {
+            return Err(GeneratorError::Cancelled);
+        }
+        let g = generator.gravity_constant.unwrap_or(self.quadtree.g);
+        let seed = self.rng.get_seed();
+        let bodies = match (generator.generate)(&params, g, &mut *self.rng) {
+            Ok(bodies) => bodies,
+            Err(e) => {
+                self.rng.seed(seed);
+                return Err(e);
+            }
+        };
+        if !self.report_progress(ProgressOperation::Generator, 1.0) {
+            self.rng.seed(seed);
+            return Err(GeneratorError::Cancelled);
+        }
+
+        if let Some(g) = generator.gravity_constant {
+            self.set_gravity_constant(g);
+        }
+        self.reset_with_bodies(bodies);
+        Ok(())
+    }
+
+    /// Replaces all bodies and resets the frame counter and time.
+    pub fn reset_with_bodies(&mut self, bodies: Vec<Body>) {
+        self.log_input(|| InputEvent::ResetBodies(bodies.clone()));
+        self.bodies = bodies.into();
         self.frame = 0;
+        self.time = 0.0;
+        // Drop the stale tree so queries don't return indices into the old bodies
+        self.quadtree.nodes.clear();
+        self.quadtree.parents.clear();
+    }
+
+    /// Sets the gravitational constant G.
+    pub fn set_gravity_constant(&mut self, g: f32) {
+        self.log_input(|| InputEvent::SetGravityConstant(g));
+        self.quadtree.g = g;
+    }
+
+    /// Sets the exponent `k` of the force law |F| ~ 1 / r^k (2 for Newtonian gravity).
+    pub fn set_force_exponent(&mut self, k: f32) {
+        self.log_input(|| InputEvent::SetForceExponent(k));
+        self.quadtree.force_exponent = k;
+    }
+
+    /// Sets the time step per frame.
+    pub fn set_dt(&mut self, dt: f32) {
+        self.log_input(|| InputEvent::SetDt(dt));
+        self.dt = dt;
+    }
+
+    /// Adds a single body.
+    pub fn add_body(&mut self, body: Body) {
+        self.log_input(|| InputEvent::AddBody(body));
+        self.bodies.push(body);
+    }
+
+    /// Adds a rotating disc of `n` bodies centered on `center` (see `utils::disc_at`).
+    pub fn spawn_disc(&mut self, center: Vec2, n: usize, radius: f32, spin: f32) {
+        let seed = self.rng.get_seed();
+        self.log_input(|| InputEvent::SpawnDisc { center, n, radius, spin, seed });
+        let bodies = utils::disc_at(center, n, radius, spin, self.quadtree.g, &mut *self.rng);
+        self.bodies.extend(bodies);
+    }
+
+    /// Adds a Plummer cluster of `n` bodies centered on `center` (see `utils::plummer_at`).
+    pub fn spawn_cluster(&mut self, center: Vec2, n: usize, radius: f32) {
+        let seed = self.rng.get_seed();
+        self.log_input(|| InputEvent::SpawnCluster { center, n, radius, seed });
+        let bodies = utils::plummer_at(center, n, radius, self.quadtree.g, &mut *self.rng);
+        self.bodies.extend(bodies);
+    }
+
+    /// Sets the playfield bounds and how bodies leaving them are handled.
+    pub fn set_boundary(&mut self, boundary: Boundary) {
+        self.log_input(|| InputEvent::SetBoundary(boundary));
+        self.boundary = boundary;
     }
 
-    /// Sets whether to use Rayon for parallelism.
+    /// Adds a fixed background potential acting on all bodies.
+    pub fn add_external_potential(&mut self, potential: ExternalPotential) {
+        self.external.push(potential);
+    }
+
+    /// Adds a gravity well, applied from the next force calculation on, and returns its handle.
+    /// Wells with a lifetime are removed once it has elapsed in simulated time.
+    pub fn add_well(&mut self, well: GravityWell) -> WellId {
+        self.wells.add(well)
+    }
+
+    /// Removes the well `id`, returning it if it hadn't expired or been removed already.
+    pub fn remove_well(&mut self, id: WellId) -> Option<GravityWell> {
+        self.wells.remove(id)
+    }
+
+    /// Replaces the random number generator used by the generators and spawners, returning the
+    /// previous one. Defaults to a `fastrand::Rng` with a random seed.
+    pub fn set_rng(&mut self, rng: Box<dyn Rng>) -> Box<dyn Rng> {
+        std::mem::replace(&mut self.rng, rng)
+    }
+
+    /// Restarts the random number generator from `seed`, making the following generator and
+    /// spawner calls reproducible.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng.seed(seed);
+    }
+
+    pub fn rng(&self) -> &dyn Rng {
+        &*self.rng
+    }
+
+    /// The random number generator, e.g. for custom emitters drawing from the same stream.
+    pub fn rng_mut(&mut self) -> &mut dyn Rng {
+        &mut *self.rng
+    }
+
+    /// Sets whether to use Rayon for parallelism. Ignored on wasm32, which always uses Rayon.
     pub fn set_use_rayon(&mut self, use_rayon: bool) {
         self.use_rayon = use_rayon;
     }
 
+    /// Switches to `backend`, releasing the GPU when moving to a CPU backend. Returns false,
+    /// leaving the backend unchanged, if `Backend::Gpu` is requested without the `gpu` feature
+    /// or a GPU adapter.
+    pub fn set_backend(&mut self, backend: Backend) -> bool {
+        match backend {
+            Backend::RustFiber | Backend::Rayon => {
+                #[cfg(feature = "gpu")]
+                self.disable_gpu();
+                self.use_rayon = backend == Backend::Rayon;
+                true
+            }
+            #[cfg(feature = "gpu")]
+            Backend::Gpu => self.enable_gpu(),
+            #[cfg(not(feature = "gpu"))]
+            Backend::Gpu => false,
+        }
+    }
+
+    /// The backend selected with `set_backend` (or `set_use_rayon` and `enable_gpu`).
+    pub fn backend(&self) -> Backend {
+        #[cfg(feature = "gpu")]
+        if self.uses_gpu() {
+            return Backend::Gpu;
+        }
+        if self.use_rayon { Backend::Rayon } else { Backend::RustFiber }
+    }
+
+    /// Moves the force calculation to the GPU (see `GpuForces`). Returns false, leaving forces on
+    /// the CPU, if no GPU adapter is available. Steps the GPU can't handle, e.g. with a
+    /// non-Newtonian force law, still fall back to the CPU.
+    #[cfg(feature = "gpu")]
+    pub fn enable_gpu(&mut self) -> bool {
+        if self.gpu.is_none() {
+            self.gpu = GpuForces::new();
+        }
+        self.gpu.is_some()
+    }
+
+    /// Moves the force calculation back to the CPU and releases the GPU device.
+    #[cfg(feature = "gpu")]
+    pub fn disable_gpu(&mut self) {
+        self.gpu = None;
+    }
+
+    /// Whether forces are calculated on the GPU, see `enable_gpu`.
+    #[cfg(feature = "gpu")]
+    pub fn uses_gpu(&self) -> bool {
+        self.gpu.is_some()
+    }
+
+    /// Sets the order the tree nodes are stored in from the next step on (see `NodeLayout`).
+    /// With 1M bodies either reordered layout makes a step 5-15% faster, but for trees small
+    /// enough to stay in cache the extra pass costs more than it saves, hence the default
+    /// `NodeLayout::Build`.
+    pub fn set_node_layout(&mut self, layout: NodeLayout) {
+        self.node_layout = layout;
+    }
+
+    /// Order the tree nodes are stored in, see `set_node_layout`.
+    pub fn node_layout(&self) -> NodeLayout {
+        self.node_layout
+    }
+
+    /// Sorts the bodies along a Morton curve every `interval` frames (see `sort_bodies_morton`),
+    /// or never if 0 (the default). Keeping bodies that are close in space close in memory
+    /// speeds up the tree build and force pass of large simulations as they evolve, but body
+    /// indices then change between steps, so hosts should track bodies by id (`Bodies::ids`).
+    pub fn set_morton_interval(&mut self, interval: usize) {
+        self.log_input(|| InputEvent::SetMortonInterval(interval));
+        self.morton_interval = interval;
+    }
+
+    /// Frames between Morton sorts of the bodies, see `set_morton_interval`.
+    pub fn morton_interval(&self) -> usize {
+        self.morton_interval
+    }
+
+    /// Makes the opening angle vary with the local density around each body (see
+    /// `AdaptiveTheta`), around the tree's theta, or uniform again with `None` (the default).
+    /// Forces then stay on the CPU, as the GPU backend only supports a uniform opening angle.
+    /// Potentials (and so energies) are still evaluated with the uniform opening angle.
+    pub fn set_adaptive_theta(&mut self, adaptive: Option<AdaptiveTheta>) {
+        self.log_input(|| InputEvent::SetAdaptiveTheta(adaptive));
+        self.adaptive_theta = adaptive;
+    }
+
+    /// Density-dependent opening angle, see `set_adaptive_theta`.
+    pub fn adaptive_theta(&self) -> Option<AdaptiveTheta> {
+        self.adaptive_theta
+    }
+
+    /// Recomputes the forces of slow bodies only every few frames (see `MultiRate`), or of all
+    /// bodies every frame with `None` (the default). In a centrally concentrated disc most bodies
+    /// are in the slowly changing outskirts, so this cuts most of the force work, at the cost of
+    /// their orbits following slightly stale forces. The tree is still rebuilt every frame.
+    /// Forces computed on the GPU update every body.
+    pub fn set_multirate(&mut self, multirate: Option<MultiRate>) {
+        self.log_input(|| InputEvent::SetMultiRate(multirate));
+        self.multirate = multirate;
+    }
+
+    /// Reduced force update rate of slow bodies, see `set_multirate`.
+    pub fn multirate(&self) -> Option<MultiRate> {
+        self.multirate
+    }
+
+    /// Recomputes gravity only every `interval` frames in `step`, or never if 0; bodies keep their
+    /// last acceleration in between. Defaults to 1 (every frame).
+    pub fn set_gravity_interval(&mut self, interval: usize) {
+        self.log_input(|| InputEvent::SetGravityInterval(interval));
+        self.gravity_interval = interval;
+    }
+
+    /// Frames between gravity updates, see `set_gravity_interval`.
+    pub fn gravity_interval(&self) -> usize {
+        self.gravity_interval
+    }
+
+    /// Detects and resolves collisions only every `interval` frames in `step`, or never if 0.
+    /// Defaults to 1 (every frame). The broad phase often costs as much as gravity, while slow
+    /// bodies with small radii rarely need it at full rate; fast ones may pass through each
+    /// other between checks.
+    pub fn set_collision_interval(&mut self, interval: usize) {
+        self.log_input(|| InputEvent::SetCollisionInterval(interval));
+        self.collision_interval = interval;
+    }
+
+    /// Frames between collision checks, see `set_collision_interval`.
+    pub fn collision_interval(&self) -> usize {
+        self.collision_interval
+    }
+
+    /// Selects how `step` integrates the motion (see `Integrator`). Velocities match the positions
+    /// at the end of each step with either scheme, so it can change in the middle of a run.
+    pub fn set_integrator(&mut self, integrator: Integrator) {
+        self.log_input(|| InputEvent::SetIntegrator(integrator));
+        self.integrator = integrator;
+    }
+
+    /// Integration scheme, see `set_integrator`.
+    pub fn integrator(&self) -> Integrator {
+        self.integrator
+    }
+
+    /// Splits each `step` into `substeps` integration steps of `dt / substeps`, each with its own
+    /// collision and gravity pass, for close encounters too fast for `dt` without more frames.
+    /// The passes still run at the rates set with `set_collision_interval` and
+    /// `set_gravity_interval`, which count frames; commands, pulses and the after-step callbacks
+    /// run once per frame. 0 is taken as 1, the default.
+    pub fn set_substeps(&mut self, substeps: usize) {
+        self.log_input(|| InputEvent::SetSubsteps(substeps));
+        self.substeps = substeps.max(1);
+    }
+
+    /// Integration steps per `step`, see `set_substeps`.
+    pub fn substeps(&self) -> usize {
+        self.substeps
+    }
+
+    /// Sets how the fiber backend splits `phase` into jobs. The defaults suit typical desktop core
+    /// counts; on machines with many more (or fewer) cores, a coarser or finer split can keep
+    /// all of them busy with less scheduling overhead. Has no effect with rayon.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_granularity(&mut self, phase: ParallelPhase, granularity: Granularity) {
+        self.granularity[phase as usize] = granularity;
+    }
+
+    /// How the fiber backend splits `phase` into jobs, see `set_granularity`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn granularity(&self, phase: ParallelPhase) -> Granularity {
+        self.granularity[phase as usize]
+    }
+
+    /// Enables or disables automatic tuning of the force phase's job size on the fiber backend.
+    /// While enabled, the first few steps each time a different minimum job size (see
+    /// `ChunkTuner`), and the fastest one then replaces the `ParallelPhase::Force` granularity's
+    /// `min_chunk`; a large change in body count starts a new search.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn set_auto_tune(&mut self, enabled: bool) {
+        if enabled != self.chunk_tuner.is_some() {
+            self.chunk_tuner = enabled.then(ChunkTuner::default);
+        }
+    }
+
+    /// Job size search of the force phase while auto-tuning is enabled, see `set_auto_tune`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn chunk_tuner(&self) -> Option<&ChunkTuner> {
+        self.chunk_tuner.as_ref()
+    }
+
+    /// Runs `job` over `range` on the job system, split into jobs as set for `phase`, and waits
+    /// for it to finish.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn run_jobs(&self, phase: ParallelPhase, range: Range<usize>, job: impl Fn(Range<usize>) + Send + Sync + 'static) {
+        let Granularity { hint, min_chunk } = self.granularity[phase as usize];
+        let counter = if min_chunk > 0 {
+            self.job_system.parallel_for_chunked(range, min_chunk, job)
+        } else {
+            self.job_system.parallel_for_chunked_with_hint(range, hint, job)
+        };
+        self.job_system.wait_for_counter(&counter);
+    }
+
+    /// Sets the callback invoked (on the stepping thread) for each collision after every step.
+    pub fn set_collision_callback(&mut self, callback: Option<CollisionCallback>) {
+        self.collision_callback = callback;
+    }
+
+    /// Sets the logger recording selected bodies after each step, returning the previous one
+    /// so its file can be flushed and its error checked.
+    pub fn set_trajectory_logger(&mut self, logger: Option<TrajectoryLogger>) -> Option<TrajectoryLogger> {
+        std::mem::replace(&mut self.trajectory_logger, logger)
+    }
+
+    /// Sets the recorder keeping trails of selected bodies after each step, returning the
+    /// previous one.
+    pub fn set_trail_recorder(&mut self, recorder: Option<TrailRecorder>) -> Option<TrailRecorder> {
+        std::mem::replace(&mut self.trail_recorder, recorder)
+    }
+
+    /// Sets the tracker sampling the total energy after each step, returning the previous one.
+    pub fn set_energy_tracker(&mut self, tracker: Option<EnergyTracker>) -> Option<EnergyTracker> {
+        std::mem::replace(&mut self.energy_tracker, tracker)
+    }
+
+    /// Starts recording external interactions from the current state (see `InputLog`),
+    /// replacing any log in progress.
+    /// The quadtree from the last step is dropped so radius queries behave the same on replay.
+    pub fn start_input_log(&mut self) {
+        self.quadtree.nodes.clear();
+        self.quadtree.parents.clear();
+        self.input_log = Some(InputLog::new(self));
+    }
+
+    /// Stops recording and returns the log, if one was running.
+    pub fn take_input_log(&mut self) -> Option<InputLog> {
+        self.input_log.take()
+    }
+
+    pub(crate) fn log_input(&mut self, event: impl FnOnce() -> InputEvent) {
+        if let Some(log) = &mut self.input_log {
+            log.events.push((self.frame, event()));
+        }
+    }
+
     /// Advances the simulation by one step.
     /// This includes updating positions (iterate), handling collisions, and calculating gravitational forces (attract).
+    /// Collisions and gravity run at the rates set with `set_collision_interval` and `set_gravity_interval`,
+    /// once per substep (see `set_substeps`).
     pub fn step(&mut self) {
+        span!("step", frame = self.frame, bodies = self.bodies.len());
         // Signal start of frame to reset per-frame allocators (prevents memory leaks)
+        #[cfg(not(target_arch = "wasm32"))]
         self.job_system.start_new_frame();
 
-        self.iterate();
-        self.collide();
-        self.attract();
+        let mut timer = PhaseTimer::new(self.step_stats_callback.is_some());
+        self.apply_commands();
+        if !self.timeline.is_empty() {
+            self.apply_pulses();
+        }
+        // Every phase below integrates over `self.dt`, so it holds the substep's for the duration
+        let (dt, substeps) = (self.dt, self.substeps);
+        self.dt = dt / substeps as f32;
+        let (mut iterate, mut collide, mut gravity_time) = Default::default();
+        let mut gravity = false;
+        let mut collisions = Vec::new();
+        for substep in 0..substeps {
+            self.apply_drag();
+            self.iterate();
+            #[cfg(feature = "debug-checks")]
+            self.check_finite("iterate");
+            self.boundary.apply(&mut self.bodies);
+            #[cfg(feature = "debug-checks")]
+            self.check_finite("boundary");
+            // Only before the first substep, so the collisions of the frame all use the same indices
+            if substep == 0 && self.morton_interval > 0 && self.frame.is_multiple_of(self.morton_interval) {
+                self.sort_bodies_morton();
+            }
+            iterate += timer.lap();
+            if self.collision_interval > 0 && self.frame.is_multiple_of(self.collision_interval) {
+                #[cfg(feature = "debug-checks")]
+                let momentum = checks::momentum_sums(self);
+                self.collide();
+                #[cfg(feature = "debug-checks")]
+                {
+                    self.check_momentum(momentum);
+                    self.check_finite("collide");
+                }
+            } else {
+                self.collisions.clear();
+            }
+            if substeps > 1 {
+                collisions.append(&mut self.collisions);
+            }
+            collide += timer.lap();
+            gravity = self.gravity_interval > 0 && self.frame.is_multiple_of(self.gravity_interval);
+            if gravity {
+                self.attract();
+                #[cfg(feature = "debug-checks")]
+                self.check_finite("attract");
+                if !self.forces.is_empty() {
+                    self.apply_forces();
+                    #[cfg(feature = "debug-checks")]
+                    self.check_finite("forces");
+                }
+            }
+            if self.integrator == Integrator::Leapfrog {
+                self.kick();
+            }
+            gravity_time += timer.lap();
+            self.time += self.dt as f64;
+            if !self.wells.is_empty() {
+                self.wells.age(self.dt);
+            }
+        }
+        self.dt = dt;
+        if substeps > 1 {
+            self.collisions = collisions;
+        }
         self.frame += 1;
+        self.update_report(gravity);
+
+        if let Some(callback) = &mut self.collision_callback {
+            for collision in &self.collisions {
+                callback(collision);
+            }
+        }
+
+        if let Some(logger) = &mut self.trajectory_logger {
+            logger.record(self.frame, self.time, &self.bodies);
+        }
+
+        if let Some(trails) = &mut self.trail_recorder {
+            trails.record(self.frame, &self.bodies);
+        }
+
+        if self.energy_tracker.as_ref().is_some_and(|tracker| tracker.is_due(self.frame)) {
+            let sample = EnergySample {
+                frame: self.frame,
+                time: self.time,
+                kinetic: self.kinetic_energy(),
+                potential: self.potential_energy(),
+            };
+            if let Some(tracker) = &mut self.energy_tracker {
+                tracker.record(sample);
+            }
+        }
+
+        if self.step_stats_callback.is_some() {
+            let stats = StepStats {
+                frame: self.frame,
+                time: self.time,
+                total: timer.total(),
+                iterate,
+                collide,
+                gravity: gravity_time,
+                nodes: self.report.nodes,
+                collisions: self.collisions.len(),
+                max_speed: self.max_speed(),
+            };
+            if let Some(callback) = &mut self.step_stats_callback {
+                callback(&stats);
+            }
+        }
+    }
+
+    /// The background forces as applied during the last step, when the tree was built.
+    pub(crate) fn background(&self) -> Background<'_> {
+        let frame = self.frame.saturating_sub(1);
+        Background::new(&self.quadtree, &self.external, self.wells.as_slice(), &self.timeline, frame, self.time)
+    }
+
+    /// Gives the velocity kicks of the timeline pulses scheduled on the current frame.
+    fn apply_pulses(&mut self) {
+        let pulses: Vec<_> = self.timeline.pulses(self.frame).collect();
+        let mut indices = Vec::new();
+        for (center, strength, radius, falloff) in pulses {
+            indices.clear();
+            self.query_radius(center, radius, |i| indices.push(i));
+
+            let bodies = self.bodies.columns_mut();
+            for &i in &indices {
+                let d = bodies.positions[i] - center;
+                let dist = d.mag();
+                if dist == 0.0 {
+                    continue;
+                }
+                let weight = match falloff {
+                    Falloff::Constant => 1.0,
+                    Falloff::Linear => (1.0 - dist / radius).max(0.0),
+                    Falloff::InverseSquare => 1.0 / (1.0 + dist * dist),
+                };
+                bodies.velocities[i] += d * (strength * weight / dist);
+            }
+        }
+    }
+
+    /// Advances the simulation by `n` steps, reporting progress to the progress callback after
+    /// each one. Returns the number of steps taken, which is less than `n` if it cancelled.
+    ///
+    /// A convenience loop over `step`: every step still does its own frame setup, as the job
+    /// system's per-frame allocators must be reset each frame.
+    pub fn step_n(&mut self, n: usize) -> usize {
+        for i in 1..=n {
+            self.step();
+            if !self.report_progress(ProgressOperation::Step, i as f32 / n as f32) {
+                return i;
+            }
+        }
+        n
     }
 
     /// Calculates gravitational forces (acceleration) for all bodies using the Barnes-Hut algorithm.
     pub fn attract(&mut self) {
-        let quad = Quad::new_containing(&self.bodies);
-        self.quadtree.clear(quad);
+        self.build_tree();
+        span!("force", bodies = self.bodies.len());
+
+        // Walk the tree for 8 neighbouring bodies at a time (see `Quadtree::acc8`)
+        let len = self.bodies.len();
+        match &self.adaptive_theta {
+            Some(adaptive) => self.quadtree.leaf_order_adaptive(
+                len,
+                adaptive,
+                &mut self.body_order,
+                &mut self.body_t_sq,
+                &mut self.leaf_walk,
+            ),
+            None => {
+                self.quadtree.leaf_order(len, &mut self.body_order, &mut self.leaf_walk);
+                self.body_t_sq.clear();
+            }
+        }
+
+        #[cfg(feature = "gpu")]
+        if self.adaptive_theta.is_none() && let Some(gpu) = &mut self.gpu {
+            let bodies = self.bodies.columns_mut();
+            if gpu.compute(&self.quadtree, bodies.positions, &self.body_order, bodies.accs) {
+                let time = self.time + self.dt as f64;
+                let background = Background::new(&self.quadtree, &self.external, self.wells.as_slice(), &self.timeline, self.frame, time);
+                if !background.is_empty() {
+                    bodies.accs.par_iter_mut().zip(bodies.positions.par_iter()).for_each(|(acc, &pos)| {
+                        *acc += background.acc(pos);
+                    });
+                }
+                return;
+            }
+        }
+
+        if let Some(multirate) = self.multirate.filter(|multirate| multirate.interval > 1) {
+            self.select_multirate(&multirate);
+        }
 
-        for (i, body) in self.bodies.iter().enumerate() {
-            self.quadtree.insert(body.pos, body.mass, i);
+        // Pick the job size to try (or the best one found) while auto-tuning
+        #[cfg(not(target_arch = "wasm32"))]
+        if !self.use_rayon && let Some(tuner) = &mut self.chunk_tuner {
+            let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+            self.granularity[ParallelPhase::Force as usize].min_chunk = tuner.chunk(self.body_order.len().div_ceil(8), threads);
         }
 
-        self.quadtree.propagate();
+        let bodies = self.bodies.columns_mut();
+        let positions = &*bodies.positions;
+        let accs_ptr = bodies.accs.as_mut_ptr() as usize;
+        let quadtree = &self.quadtree;
+        // The positions are those at the end of the step
+        let time = self.time + self.dt as f64;
+        let background = Background::new(quadtree, &self.external, self.wells.as_slice(), &self.timeline, self.frame, time);
+        let order = &self.body_order[..];
+        let t_sq = &self.body_t_sq[..];
 
-        if self.use_rayon {
-             let quadtree = &self.quadtree;
-             self.bodies.par_iter_mut().for_each(|body| {
-                  body.acc = quadtree.acc(body.pos);
+        if self.use_rayon || cfg!(target_arch = "wasm32") {
+             order.par_chunks(8).enumerate().for_each_init(Vec::new, |scopes, (g, group)| {
+                  let t_sq = t_sq.get(g * 8..g * 8 + group.len()).unwrap_or_default();
+                  acc_group(quadtree, &background, positions, t_sq, group, scopes, |i, acc| {
+                      // SAFETY: `order` is a permutation of the body indices, so each group
+                      // writes distinct bodies
+                      unsafe { *(accs_ptr as *mut Vec2).add(i) = acc };
+                  });
              });
         } else {
-             // Optimized RustFiber path with manual chunking
-             let len = self.bodies.len();
-             if len == 0 { return; }
-
-             let bodies_ptr = self.bodies.as_mut_ptr() as usize;
-             let quadtree_ptr = &self.quadtree as *const Quadtree as usize;
-
-             let counter = self.job_system.parallel_for_chunked_with_hint(
-                 0..len,
-                 rustfiber::GranularityHint::Light, 
-                 move |range| {
-                     unsafe {
-                         let bodies = std::slice::from_raw_parts_mut(bodies_ptr as *mut Body, len);
-                         let qt = &*(quadtree_ptr as *const Quadtree);
+             #[cfg(not(target_arch = "wasm32"))]
+             {
+                 // Optimized RustFiber path with manual chunking
+                 if len == 0 { return; }
+
+                 let positions_ptr = positions.as_ptr() as usize;
+                 let order_ptr = order.as_ptr() as usize;
+                 let order_len = order.len();
+                 let quadtree_ptr = quadtree as *const Quadtree as usize;
+                 let background_ptr = &background as *const Background as usize;
+                 let t_sq_ptr = t_sq.as_ptr() as usize;
+                 let t_sq_len = t_sq.len();
+
+                 // Time the jobs while searching for the best job size
+                 let times = self.chunk_tuner.as_ref().filter(|tuner| tuner.is_searching()).map(|_| Arc::new(JobTimes::default()));
+                 let job_times = times.clone();
+                 let start = std::time::Instant::now();
+
+                 self.run_jobs(
+                     ParallelPhase::Force,
+                     0..order_len.div_ceil(8),
+                     move |range| {
+                         let job_start = job_times.as_ref().map(|_| std::time::Instant::now());
+                         unsafe {
+                             let positions = std::slice::from_raw_parts(positions_ptr as *const Vec2, len);
+                             let order = std::slice::from_raw_parts(order_ptr as *const u32, order_len);
+                             let qt = &*(quadtree_ptr as *const Quadtree);
+                             let background = &*(background_ptr as *const Background);
+                             let t_sq = std::slice::from_raw_parts(t_sq_ptr as *const f32, t_sq_len);
+                             let mut scopes = Vec::new();
                          
-                         for i in range {
-                             bodies.get_unchecked_mut(i).acc = qt.acc(bodies.get_unchecked(i).pos);
+                             for g in range {
+                                 let group = &order[g * 8..(g * 8 + 8).min(order_len)];
+                                 let t_sq = t_sq.get(g * 8..g * 8 + group.len()).unwrap_or_default();
+                                 acc_group(qt, background, positions, t_sq, group, &mut scopes, |i, acc| {
+                                     *(accs_ptr as *mut Vec2).add(i) = acc;
+                                 });
+                             }
+                         }
+                         if let (Some(times), Some(job_start)) = (&job_times, job_start) {
+                             times.record(job_start.elapsed());
                          }
                      }
+                 );
+
+                 if let (Some(tuner), Some(times)) = (&mut self.chunk_tuner, times) {
+                     tuner.record(start.elapsed(), &times);
                  }
-             );
-             self.job_system.wait_for_counter(&counter);
+             }
+        }
+    }
+
+    /// Drops the bodies that keep their acceleration this frame under `multirate` from
+    /// `body_order` (and `body_t_sq`). Bodies without an acceleration yet are always updated.
+    fn select_multirate(&mut self, multirate: &MultiRate) {
+        let center = self.quadtree.nodes.first().map_or(Vec2::zero(), |root| root.pos);
+        let (positions, accs, ids) = (self.bodies.positions(), self.bodies.accs(), self.bodies.ids());
+        let r_sq = multirate.radius * multirate.radius;
+        let min_acc_sq = multirate.min_acc * multirate.min_acc;
+        let interval = multirate.interval as u32;
+        let phase = (self.frame % multirate.interval) as u32;
+
+        let update = |i: usize| {
+            let acc_sq = accs[i].mag_sq();
+            acc_sq == 0.0
+                || ((positions[i] - center).mag_sq() <= r_sq && acc_sq >= min_acc_sq)
+                || ids[i] % interval == phase
+        };
+
+        if self.body_t_sq.is_empty() {
+            self.body_order.retain(|&i| update(i as usize));
+        } else {
+            let mut kept = 0;
+            for k in 0..self.body_order.len() {
+                if update(self.body_order[k] as usize) {
+                    self.body_order[kept] = self.body_order[k];
+                    self.body_t_sq[kept] = self.body_t_sq[k];
+                    kept += 1;
+                }
+            }
+            self.body_order.truncate(kept);
+            self.body_t_sq.truncate(kept);
+        }
+    }
+
+    /// Builds the quadtree over all bodies, with its nodes in `node_layout`. Large simulations
+    /// build the subtrees of the cells at `Quadtree::SPLIT_DEPTH` in parallel and splice them
+    /// together, see `Quadtree::partition`. Their nodes are also reordered per subtree, which
+    /// keeps the pass parallel and cache-sized; spliced in cell order they come out as a
+    /// depth-first layout below the top levels.
+    fn build_tree(&mut self) {
+        span!("build_tree", bodies = self.bodies.len());
+        let positions = self.bodies.positions();
+        let masses = self.bodies.masses();
+        let quad = Quad::new_containing(positions);
+
+        if positions.len() < PARALLEL_BUILD_MIN_BODIES {
+            self.quadtree.clear(quad);
+            for (i, (&pos, &mass)) in positions.iter().zip(masses).enumerate() {
+                self.quadtree.insert(pos, mass, i);
+            }
+            {
+                span!("propagate", nodes = self.quadtree.nodes.len());
+                self.quadtree.propagate();
+            }
+            self.reorders.resize_with(1, NodeReorder::default);
+            self.quadtree.reorder(self.node_layout, &mut self.reorders[0]);
+            return;
+        }
+
+        Quadtree::partition(quad, positions, &mut self.partition);
+        let partition = &self.partition;
+        let layout = self.node_layout;
+        self.subtrees.resize_with(partition.len(), Quadtree::default);
+        self.reorders.resize_with(partition.len(), NodeReorder::default);
+
+        // Share the node budget left after the top levels between the cells by body count
+        let budget = self.quadtree.node_budget;
+        let top_nodes = (4usize.pow(Quadtree::SPLIT_DEPTH + 1) - 1) / 3;
+        for (cell, subtree) in self.subtrees.iter_mut().enumerate() {
+            subtree.node_budget = if budget == usize::MAX {
+                usize::MAX
+            } else {
+                let share = budget.saturating_sub(top_nodes) as f64 * partition.bodies(cell).len() as f64 / positions.len() as f64;
+                (share as usize).max(1)
+            };
+        }
+
+        // Propagating each subtree on its own thread is the cheapest, but serializes on a cell
+        // holding a large share of the bodies; such trees are propagated by levels instead
+        let propagate_cells = partition.largest_cell() * UNBALANCED_CELL_SHARE <= positions.len();
+
+        if self.use_rayon || cfg!(target_arch = "wasm32") {
+             self.subtrees.par_iter_mut().zip(&mut self.reorders).enumerate().for_each(|(cell, (subtree, scratch))| {
+                  subtree.build_subtree(partition, cell, positions, masses);
+                  if propagate_cells {
+                      subtree.propagate();
+                  }
+                  subtree.reorder(layout, scratch);
+             });
+        } else {
+             #[cfg(not(target_arch = "wasm32"))]
+             {
+                 let len = positions.len();
+                 let positions_ptr = positions.as_ptr() as usize;
+                 let masses_ptr = masses.as_ptr() as usize;
+                 let partition_ptr = partition as *const Partition as usize;
+                 let subtrees_ptr = self.subtrees.as_mut_ptr() as usize;
+                 let reorders_ptr = self.reorders.as_mut_ptr() as usize;
+
+                 self.run_jobs(
+                     ParallelPhase::Build,
+                     0..partition.len(),
+                     move |range| {
+                         unsafe {
+                             let positions = std::slice::from_raw_parts(positions_ptr as *const Vec2, len);
+                             let masses = std::slice::from_raw_parts(masses_ptr as *const f32, len);
+                             let partition = &*(partition_ptr as *const Partition);
+
+                             for cell in range {
+                                 let subtree = &mut *(subtrees_ptr as *mut Quadtree).add(cell);
+                                 subtree.build_subtree(partition, cell, positions, masses);
+                                 if propagate_cells {
+                                     subtree.propagate();
+                                 }
+                                 subtree.reorder(layout, &mut *(reorders_ptr as *mut NodeReorder).add(cell));
+                             }
+                         }
+                     }
+                 );
+             }
+        }
+
+        self.quadtree.splice(quad, &self.subtrees);
+        if !propagate_cells {
+            self.propagate_tree();
+        }
+    }
+
+    /// Propagates the quadtree one level at a time, deepest first, splitting the parents of each
+    /// level across threads (see `Quadtree::parent_levels`). Levels with few parents, typically
+    /// the top and the sparse bottom of the tree, are propagated on the calling thread.
+    ///
+    /// Sweeping the node array once per level makes this about 2.5x the work of `propagate`,
+    /// so it only pays off with several threads and when the work can't be split otherwise.
+    fn propagate_tree(&mut self) {
+        span!("propagate", nodes = self.quadtree.nodes.len());
+        self.quadtree.parent_levels(&mut self.parent_levels);
+        let nodes_ptr = self.quadtree.nodes.as_mut_ptr() as usize;
+
+        // SAFETY: parents of one level are distinct and their children are at the next level,
+        // which has already been propagated and is only read
+        let propagate = move |parents: &[u32]| {
+            for &node in parents {
+                unsafe { Quadtree::propagate_node(nodes_ptr as *mut Node, node as usize) };
+            }
+        };
+
+        for depth in (0..self.parent_levels.len()).rev() {
+            let level = self.parent_levels.level(depth);
+            if level.len() < PARALLEL_PROPAGATE_MIN_PARENTS {
+                propagate(level);
+            } else if self.use_rayon || cfg!(target_arch = "wasm32") {
+                level.par_chunks(PARALLEL_PROPAGATE_MIN_PARENTS / 4).for_each(propagate);
+            } else {
+                #[cfg(not(target_arch = "wasm32"))]
+                {
+                    let level_ptr = level.as_ptr() as usize;
+                    let level_len = level.len();
+                    self.run_jobs(
+                        ParallelPhase::Propagate,
+                        0..level_len,
+                        move |range| {
+                            let level = unsafe { std::slice::from_raw_parts(level_ptr as *const u32, level_len) };
+                            propagate(&level[range]);
+                        }
+                    );
+                }
+            }
         }
     }
 
     /// Updates the position and velocity of all bodies based on their current acceleration and time step.
+    /// Uses semi-implicit Euler integration like `Body::update`, or the opening kick and drift of
+    /// `Integrator::Leapfrog`.
     pub fn iterate(&mut self) {
-        let dt = self.dt;
+        span!("iterate", bodies = self.bodies.len());
+        let kick = match self.integrator {
+            Integrator::SemiImplicitEuler => self.dt,
+            Integrator::Leapfrog => 0.5 * self.dt,
+        };
+        self.integrate(kick, self.dt);
+    }
+
+    /// Closing half kick of `Integrator::Leapfrog`, with the accelerations of the gravity pass.
+    fn kick(&mut self) {
+        self.integrate(0.5 * self.dt, 0.0);
+    }
+
+    /// Adds `acc * kick` to the velocities, then `vel * drift` to the positions.
+    fn integrate(&mut self, kick: f32, drift: f32) {
+        let bodies = self.bodies.columns_mut();
         
-        if self.use_rayon {
-             self.bodies.par_iter_mut().for_each(|body| {
-                 body.update(dt);
-             });
+        if self.use_rayon || cfg!(target_arch = "wasm32") {
+             bodies
+                 .positions
+                 .par_iter_mut()
+                 .zip(bodies.velocities.par_iter_mut())
+                 .zip(bodies.accs.par_iter())
+                 .for_each(|((pos, vel), &acc)| {
+                     *vel += acc * kick;
+                     *pos += *vel * drift;
+                 });
         } else {
-             self.bodies.fiber_iter_mut(&self.job_system).for_each(move |body| {
-                 body.update(dt);
-             });
+             #[cfg(not(target_arch = "wasm32"))]
+             {
+                 let len = bodies.positions.len();
+                 if len == 0 { return; }
+
+                 let positions_ptr = bodies.positions.as_mut_ptr() as usize;
+                 let velocities_ptr = bodies.velocities.as_mut_ptr() as usize;
+                 let accs_ptr = bodies.accs.as_ptr() as usize;
+
+                 self.run_jobs(
+                     ParallelPhase::Integrate,
+                     0..len,
+                     move |range| {
+                         // SAFETY: the columns outlive the wait below and the chunks don't overlap
+                         let (positions, velocities, accs) = unsafe {
+                             (
+                                 std::slice::from_raw_parts_mut((positions_ptr as *mut Vec2).add(range.start), range.len()),
+                                 std::slice::from_raw_parts_mut((velocities_ptr as *mut Vec2).add(range.start), range.len()),
+                                 std::slice::from_raw_parts((accs_ptr as *const Vec2).add(range.start), range.len()),
+                             )
+                         };
+                         for ((pos, vel), &acc) in positions.iter_mut().zip(velocities).zip(accs) {
+                             *vel += acc * kick;
+                             *pos += *vel * drift;
+                         }
+                     }
+                 );
+             }
         }
     }
 
     /// Detects and resolves collisions between bodies.
     /// Uses the `broccoli` crate (a broad-phase collision detection library) to find potentially colliding pairs efficiently.
+    /// From `PARALLEL_COLLIDE_MIN_BODIES` bodies on, a parallel `SweepAndPrune` on the rayon or
+    /// fiber backend finds them instead, as the broccoli query runs on a single thread.
     pub fn collide(&mut self) {
-        let mut rects = self
-            .bodies
-            .iter()
-            .enumerate()
-            .map(|(index, body)| {
-                let pos = body.pos;
-                let radius = body.radius;
-                let min = pos - Vec2::one() * radius;
-                let max = pos + Vec2::one() * radius;
-                (Rect::new(min.x, max.x, min.y, max.y), index)
-            })
-            .collect::<Vec<_>>();
+        span!("collide", bodies = self.bodies.len());
+        self.collisions.clear();
+        if self.bodies.len() >= PARALLEL_COLLIDE_MIN_BODIES {
+            self.collide_parallel();
+            return;
+        }
+
+        // Taken out for the duration of the query, as resolving needs `self`
+        let mut rects = std::mem::take(&mut self.collision_rects);
+        rects.clear();
+        rects.extend(
+            self.bodies
+                .positions()
+                .iter()
+                .zip(self.bodies.radii())
+                .enumerate()
+                .map(|(index, (&pos, &radius))| {
+                    let min = pos - Vec2::one() * radius;
+                    let max = pos + Vec2::one() * radius;
+                    (Rect::new(min.x, max.x, min.y, max.y), index)
+                }),
+        );
 
         let mut broccoli = Tree::new(&mut rects);
 
@@ -213,19 +1618,201 @@ impl Simulation {
 
             self.resolve(i, j);
         });
+
+        self.collision_rects = rects;
+    }
+
+    /// Broad phase of `collide` for large simulations: finds the candidate pairs with a parallel
+    /// sweep and prune, then resolves them in a fixed order.
+    fn collide_parallel(&mut self) {
+        let parallel = self.use_rayon || cfg!(target_arch = "wasm32");
+        self.sweep.prepare(self.bodies.positions(), self.bodies.radii(), parallel);
+
+        let chunks = self.sweep.chunks();
+        let mut pairs = std::mem::take(&mut self.collision_pairs);
+        pairs.resize_with(chunks, Vec::new);
+
+        if parallel {
+            let sweep = &self.sweep;
+            pairs.par_iter_mut().enumerate().for_each(|(chunk, out)| sweep.sweep(chunk, out));
+        } else {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let sweep_ptr = &self.sweep as *const SweepAndPrune as usize;
+                let pairs_ptr = pairs.as_mut_ptr() as usize;
+
+                self.run_jobs(
+                    ParallelPhase::Collide,
+                    0..chunks,
+                    move |range| {
+                        // SAFETY: the sweep and pairs outlive the wait below and each chunk has its own output
+                        let sweep = unsafe { &*(sweep_ptr as *const SweepAndPrune) };
+                        for chunk in range {
+                            let out = unsafe { &mut *(pairs_ptr as *mut Vec<(u32, u32)>).add(chunk) };
+                            sweep.sweep(chunk, out);
+                        }
+                    }
+                );
+            }
+        }
+
+        for &(i, j) in pairs[..chunks].iter().flatten() {
+            self.resolve(i as usize, j as usize);
+        }
+        self.collision_pairs = pairs;
+    }
+
+    /// Whether the quadtree from the last step references every body by its current index, so
+    /// index queries can walk it instead of testing every body. It doesn't if there is no tree,
+    /// if the last step skipped gravity and left it stale, if bodies were added or removed since,
+    /// or if it merged bodies into shared leaves.
+    pub(crate) fn tree_indexes_bodies(&self) -> bool {
+        let quadtree = &self.quadtree;
+        let report = self.step_report();
+        !quadtree.nodes.is_empty()
+            && report.nodes == quadtree.nodes.len()
+            && report.bodies == self.bodies.len()
+            && quadtree.merged == 0
+            && quadtree.coincident == 0
+    }
+
+    /// Calls `callback` with the index of every body within `radius` of `pos`.
+    /// Uses the quadtree from the last step as a broad phase, falling back to a linear scan
+    /// whenever the tree misses bodies (see `tree_indexes_bodies`).
+    pub fn query_radius(&self, pos: Vec2, radius: f32, mut callback: impl FnMut(usize)) {
+        let r_sq = radius * radius;
+        let positions = self.bodies.positions();
+
+        if !self.tree_indexes_bodies() {
+            for (i, &body_pos) in positions.iter().enumerate() {
+                if (body_pos - pos).mag_sq() < r_sq {
+                    callback(i);
+                }
+            }
+            return;
+        }
+
+        // No body has index u32::MAX, so nothing is excluded
+        self.quadtree.find_collisions(u32::MAX, pos, radius, |i| {
+            let i = i as usize;
+            if positions.get(i).is_some_and(|&body_pos| (body_pos - pos).mag_sq() < r_sq) {
+                callback(i);
+            }
+        });
+    }
+
+    /// Applies `force` to every body within `radius` of `pos`, scaled by `falloff`.
+    pub fn apply_force(&mut self, pos: Vec2, force: Vec2, radius: f32, falloff: Falloff, mode: ForceMode) {
+        self.log_input(|| InputEvent::ApplyForce { pos, force, radius, falloff, mode });
+        let mut indices = Vec::new();
+        self.query_radius(pos, radius, |i| indices.push(i));
+
+        let dt = self.dt;
+        let bodies = self.bodies.columns_mut();
+        for i in indices {
+            let d = (bodies.positions[i] - pos).mag();
+
+            let weight = match falloff {
+                Falloff::Constant => 1.0,
+                Falloff::Linear => (1.0 - d / radius).max(0.0),
+                Falloff::InverseSquare => 1.0 / (1.0 + d * d),
+            };
+
+            let dv = match mode {
+                ForceMode::Impulse => force * weight,
+                ForceMode::Force => force * (weight * dt / bodies.masses[i]),
+            };
+            bodies.velocities[i] += dv;
+        }
+    }
+
+    /// Total kinetic energy of all bodies: Sum(0.5 * m * v^2).
+    /// Like the other reductions below, this is summed in f64 and only rounded to f32 at the end.
+    pub fn kinetic_energy(&self) -> f32 {
+        self.bodies
+            .masses()
+            .par_iter()
+            .zip(self.bodies.velocities())
+            .map(|(&mass, vel)| 0.5 * mass as f64 * vel.mag_sq() as f64)
+            .sum::<f64>() as f32
+    }
+
+    /// Total gravitational potential energy, evaluated on the quadtree built during the last step.
+    /// Each body's softened self-interaction is excluded; external potentials are included.
+    /// Returns 0 if no tree has been built yet.
+    pub fn potential_energy(&self) -> f32 {
+        if self.quadtree.nodes.is_empty() {
+            return 0.0;
+        }
+
+        let quadtree = &self.quadtree;
+        let softened = quadtree.e_sq > 0.0;
+        let positions = self.bodies.positions();
+        let masses = self.bodies.masses();
+
+        // Each pair is counted twice when summing over bodies, hence the 0.5 factor
+        let internal = 0.5 * positions
+            .par_iter()
+            .zip(masses)
+            .map(|(&pos, &mass)| {
+                let self_term = if softened { quadtree.pair_potential(mass, 0.0) } else { 0.0 };
+                mass as f64 * (quadtree.potential(pos) - self_term) as f64
+            })
+            .sum::<f64>();
+
+        if self.external.is_empty() {
+            return internal as f32;
+        }
+
+        let external = &self.external;
+        let external_energy = positions
+            .par_iter()
+            .zip(masses)
+            .map(|(&pos, &mass)| {
+                let phi: f64 = external
+                    .iter()
+                    .map(|potential| potential.potential(pos, quadtree.g, quadtree.e_sq) as f64)
+                    .sum();
+                mass as f64 * phi
+            })
+            .sum::<f64>();
+        (internal + external_energy) as f32
+    }
+
+    /// Virial ratio 2T / |U| of kinetic to potential energy, which is 1 for a system in
+    /// equilibrium; below 1 it collapses, above 1 it expands. Uses `potential_energy`, so it
+    /// needs a tree: call it after a step or `attract` (e.g. right after generating initial
+    /// conditions). Returns 0 while the potential energy is 0.
+    pub fn virial_ratio(&self) -> f32 {
+        let potential = self.potential_energy() as f64;
+        if potential == 0.0 {
+            return 0.0;
+        }
+        (2.0 * self.kinetic_energy() as f64 / potential.abs()) as f32
+    }
+
+    /// Total linear momentum of all bodies: Sum(m * v).
+    pub fn momentum(&self) -> Vec2 {
+        let [x, y] = self
+            .bodies
+            .velocities()
+            .par_iter()
+            .zip(self.bodies.masses())
+            .map(|(&vel, &mass)| [vel.x as f64 * mass as f64, vel.y as f64 * mass as f64])
+            .reduce(|| [0.0; 2], |a, b| [a[0] + b[0], a[1] + b[1]]);
+        Vec2::new(x as f32, y as f32)
     }
 
     /// Resolves a collision between two bodies identified by indices `i` and `j`.
     /// Handles elastic collision response.
     fn resolve(&mut self, i: usize, j: usize) {
-        let b1 = &self.bodies[i];
-        let b2 = &self.bodies[j];
+        let bodies = self.bodies.columns_mut();
 
-        let p1 = b1.pos;
-        let p2 = b2.pos;
+        let p1 = bodies.positions[i];
+        let p2 = bodies.positions[j];
 
-        let r1 = b1.radius;
-        let r2 = b2.radius;
+        let r1 = bodies.radii[i];
+        let r2 = bodies.radii[j];
 
         let d = p2 - p1;
         let r = r1 + r2;
@@ -234,15 +1821,15 @@ impl Simulation {
             return;
         }
 
-        let v1 = b1.vel;
-        let v2 = b2.vel;
+        let v1 = bodies.velocities[i];
+        let v2 = bodies.velocities[j];
 
         let v = v2 - v1;
 
         let d_dot_v = d.dot(v);
 
-        let m1 = b1.mass;
-        let m2 = b2.mass;
+        let m1 = bodies.masses[i];
+        let m2 = bodies.masses[j];
 
         let weight1 = m2 / (m1 + m2);
         let weight2 = m1 / (m1 + m2);
@@ -250,8 +1837,8 @@ impl Simulation {
         // If bodies are moving apart or static, just separate them slightly without impulse
         if d_dot_v >= 0.0 && d != Vec2::zero() {
             let tmp = d * (r / d.mag() - 1.0);
-            self.bodies[i].pos -= weight1 * tmp;
-            self.bodies[j].pos += weight2 * tmp;
+            bodies.positions[i] -= weight1 * tmp;
+            bodies.positions[j] += weight2 * tmp;
             return;
         }
 
@@ -263,11 +1850,11 @@ impl Simulation {
         let t = (d_dot_v + (d_dot_v * d_dot_v - v_sq * (d_sq - r_sq)).max(0.0).sqrt()) / v_sq;
 
         // Rewind positions
-        self.bodies[i].pos -= v1 * t;
-        self.bodies[j].pos -= v2 * t;
+        bodies.positions[i] -= v1 * t;
+        bodies.positions[j] -= v2 * t;
 
-        let p1 = self.bodies[i].pos;
-        let p2 = self.bodies[j].pos;
+        let p1 = bodies.positions[i];
+        let p2 = bodies.positions[j];
         let d = p2 - p1;
         let d_dot_v = d.dot(v);
         let d_sq = d.mag_sq();
@@ -277,13 +1864,95 @@ impl Simulation {
         let v1 = v1 + tmp * weight1;
         let v2 = v2 - tmp * weight2;
 
-        self.bodies[i].vel = v1;
-        self.bodies[j].vel = v2;
+        bodies.velocities[i] = v1;
+        bodies.velocities[j] = v2;
         // Fast-forward positions after collision response
-        self.bodies[i].pos += v1 * t;
-        self.bodies[j].pos += v2 * t;
+        bodies.positions[i] += v1 * t;
+        bodies.positions[j] += v2 * t;
+        self.collisions.push(Collision {
+            i: i as u32,
+            j: j as u32,
+            impulse: tmp.mag() * m1 * weight1,
+        });
     }
     
     // Removed old resolve/collide methods.
 
 }
+
+/// The forces added to the tree forces during one force calculation: external potentials,
+/// gravity wells and the timeline fields active on the current frame.
+pub(crate) struct Background<'a> {
+    external: &'a [ExternalPotential],
+    wells: &'a [GravityWell],
+    timeline: &'a Timeline,
+    frame: usize,
+    time: f64,
+    g: f32,
+    e_sq: f32,
+    fields: bool,
+}
+
+impl<'a> Background<'a> {
+    fn new(
+        quadtree: &Quadtree,
+        external: &'a [ExternalPotential],
+        wells: &'a [GravityWell],
+        timeline: &'a Timeline,
+        frame: usize,
+        time: f64,
+    ) -> Self {
+        let fields = timeline.fields(frame).next().is_some();
+        Self { external, wells, timeline, frame, time, g: quadtree.g, e_sq: quadtree.e_sq, fields }
+    }
+
+    #[inline(always)]
+    fn is_empty(&self) -> bool {
+        self.external.is_empty() && self.wells.is_empty() && !self.fields
+    }
+
+    #[inline(always)]
+    pub(crate) fn acc(&self, pos: Vec2) -> Vec2 {
+        let mut acc = external::total_acc(self.external, pos, self.g, self.e_sq) + wells::total_acc(self.wells, pos);
+        if self.fields {
+            for field in self.timeline.fields(self.frame) {
+                acc += field.acc(pos, self.time, self.g, self.e_sq);
+            }
+        }
+        acc
+    }
+}
+
+/// Calculates the accelerations of a group of up to 8 bodies with one `Quadtree::acc8` walk,
+/// passing each body index and its acceleration (including the `background` forces) to `write`.
+/// `t_sq` holds the squared opening angle of each body of the group, or is empty for the tree's.
+#[inline(always)]
+fn acc_group(
+    quadtree: &Quadtree,
+    background: &Background,
+    positions: &[Vec2],
+    t_sq: &[f32],
+    group: &[u32],
+    scopes: &mut Vec<(u32, f32x8)>,
+    mut write: impl FnMut(usize, Vec2),
+) {
+    // Unused lanes repeat the first body
+    let pos = |lane: usize| positions[*group.get(lane).unwrap_or(&group[0]) as usize];
+    let x = f32x8::from(std::array::from_fn::<f32, 8, _>(|lane| pos(lane).x));
+    let y = f32x8::from(std::array::from_fn::<f32, 8, _>(|lane| pos(lane).y));
+    let (ax, ay) = if t_sq.is_empty() {
+        quadtree.acc8(x, y, scopes)
+    } else {
+        let t_sq = f32x8::from(std::array::from_fn::<f32, 8, _>(|lane| *t_sq.get(lane).unwrap_or(&t_sq[0])));
+        quadtree.acc8_with(x, y, t_sq, scopes)
+    };
+    let (ax, ay) = (ax.to_array(), ay.to_array());
+
+    for (lane, &i) in group.iter().enumerate() {
+        let mut acc = Vec2::new(ax[lane], ay[lane]);
+        if !background.is_empty() {
+            acc += background.acc(positions[i as usize]);
+        }
+        write(i as usize, acc);
+    }
+}
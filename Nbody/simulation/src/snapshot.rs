@@ -0,0 +1,352 @@
+use crate::{body::Body, simulation::Simulation};
+use ultraviolet::Vec2;
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Binary snapshot layout (all values little-endian):
+///
+/// | field   | type      |
+/// |---------|-----------|
+/// | magic   | `b"NBSS"` |
+/// | version | u32       |
+/// | frame   | u64       |
+/// | dt      | f32       |
+/// | t_sq    | f32       |
+/// | e_sq    | f32       |
+/// | count   | u64       |
+/// | bodies  | `count` records of 8 f32: pos.x, pos.y, vel.x, vel.y, acc.x, acc.y, mass, radius |
+/// | next id | u32 (absent in version 1) |
+/// | ids     | `count` x u32, the id of each body (absent in version 1, where bodies are numbered in order) |
+pub const MAGIC: [u8; 4] = *b"NBSS";
+pub const VERSION: u32 = 2;
+
+/// Body-array layout written by `save_binary` (all values little-endian):
+///
+/// | field   | type      |
+/// |---------|-----------|
+/// | magic   | `b"NBBA"` |
+/// | version | u32       |
+/// | count   | u64       |
+/// | bodies  | `count` body records, as in snapshots |
+pub const BODIES_MAGIC: [u8; 4] = *b"NBBA";
+pub const BODIES_VERSION: u32 = 1;
+
+/// Size in bytes of one serialized body record.
+pub const BODY_RECORD_SIZE: usize = 8 * 4;
+
+/// Most records reserved up front by `read_body_records`, as the count comes from the file.
+const MAX_RESERVED_RECORDS: usize = 1 << 16;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u32(r: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_f32(r: &mut impl Read) -> io::Result<f32> {
+    let mut buf = [0; 4];
+    r.read_exact(&mut buf)?;
+    Ok(f32::from_le_bytes(buf))
+}
+
+/// Writes the body records without any header.
+pub fn write_body_records(w: &mut impl Write, bodies: &[Body]) -> io::Result<()> {
+    for body in bodies {
+        for v in [
+            body.pos.x,
+            body.pos.y,
+            body.vel.x,
+            body.vel.y,
+            body.acc.x,
+            body.acc.y,
+            body.mass,
+            body.radius,
+        ] {
+            w.write_all(&v.to_le_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads `count` body records written by `write_body_records`.
+/// A truncated file fails with `UnexpectedEof` instead of first reserving room for all `count`.
+pub fn read_body_records(r: &mut impl Read, count: usize) -> io::Result<Vec<Body>> {
+    let mut bodies = Vec::with_capacity(count.min(MAX_RESERVED_RECORDS));
+    let mut record = [0u8; BODY_RECORD_SIZE];
+
+    for _ in 0..count {
+        r.read_exact(&mut record)?;
+        let f = |i: usize| f32::from_le_bytes(record[i * 4..i * 4 + 4].try_into().unwrap());
+
+        bodies.push(Body {
+            pos: Vec2::new(f(0), f(1)),
+            vel: Vec2::new(f(2), f(3)),
+            acc: Vec2::new(f(4), f(5)),
+            mass: f(6),
+            radius: f(7),
+        });
+    }
+
+    Ok(bodies)
+}
+
+/// Writes a body array with its header (see `BODIES_MAGIC`).
+pub fn write_bodies(w: &mut impl Write, bodies: &[Body]) -> io::Result<()> {
+    w.write_all(&BODIES_MAGIC)?;
+    w.write_all(&BODIES_VERSION.to_le_bytes())?;
+    w.write_all(&(bodies.len() as u64).to_le_bytes())?;
+    write_body_records(w, bodies)
+}
+
+/// Reads a body array written by `write_bodies`.
+pub fn read_bodies(r: &mut impl Read) -> io::Result<Vec<Body>> {
+    let mut magic = [0; 4];
+    r.read_exact(&mut magic)?;
+    if magic != BODIES_MAGIC {
+        return Err(invalid_data("not an nbody body array"));
+    }
+
+    let version = read_u32(r)?;
+    if version != BODIES_VERSION {
+        return Err(invalid_data("unsupported body array version"));
+    }
+
+    let count = read_u64(r)? as usize;
+    read_body_records(r, count)
+}
+
+/// Saves bodies to a compact binary file (see `BODIES_MAGIC`).
+pub fn save_binary(path: impl AsRef<Path>, bodies: &[Body]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    write_bodies(&mut w, bodies)?;
+    w.flush()
+}
+
+/// Loads bodies saved by `save_binary`.
+pub fn load_binary(path: impl AsRef<Path>) -> io::Result<Vec<Body>> {
+    let mut r = BufReader::new(File::open(path)?);
+    read_bodies(&mut r)
+}
+
+/// Simulation state stored in a snapshot.
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub frame: usize,
+    pub dt: f32,
+    pub t_sq: f32,
+    pub e_sq: f32,
+    pub bodies: Vec<Body>,
+    /// Id of each body (see `Bodies::ids`), or empty to number the bodies in order on restore.
+    pub ids: Vec<u32>,
+    /// See `Bodies::next_id`; unused if `ids` is empty.
+    pub next_id: u32,
+}
+
+impl Snapshot {
+    /// Captures the current state of a simulation.
+    pub fn capture(sim: &Simulation) -> Self {
+        Self {
+            frame: sim.frame,
+            dt: sim.dt,
+            t_sq: sim.quadtree.t_sq,
+            e_sq: sim.quadtree.e_sq,
+            bodies: sim.bodies.to_vec(),
+            ids: sim.bodies.ids().to_vec(),
+            next_id: sim.bodies.next_id(),
+        }
+    }
+
+    /// Restores the captured state into a simulation.
+    /// The quadtree is rebuilt on the next step.
+    pub fn restore(self, sim: &mut Simulation) {
+        sim.frame = self.frame;
+        sim.dt = self.dt;
+        // The format predates time tracking; assume a constant time step
+        sim.time = self.frame as f64 * self.dt as f64;
+        sim.quadtree.t_sq = self.t_sq;
+        sim.quadtree.e_sq = self.e_sq;
+        sim.quadtree.nodes.clear();
+        sim.quadtree.parents.clear();
+        sim.bodies = self.bodies.into();
+        if !self.ids.is_empty() {
+            sim.bodies.set_ids(self.ids, self.next_id);
+        }
+    }
+
+    pub fn write(&self, w: &mut impl Write) -> io::Result<()> {
+        w.write_all(&MAGIC)?;
+        w.write_all(&VERSION.to_le_bytes())?;
+        w.write_all(&(self.frame as u64).to_le_bytes())?;
+        w.write_all(&self.dt.to_le_bytes())?;
+        w.write_all(&self.t_sq.to_le_bytes())?;
+        w.write_all(&self.e_sq.to_le_bytes())?;
+        w.write_all(&(self.bodies.len() as u64).to_le_bytes())?;
+        write_body_records(w, &self.bodies)?;
+
+        // Without saved ids the bodies are numbered in order, as on restore
+        let ids = (self.ids.len() == self.bodies.len()).then_some(&self.ids[..]);
+        let next_id = ids.map_or(self.bodies.len() as u32, |_| self.next_id);
+        w.write_all(&next_id.to_le_bytes())?;
+        for i in 0..self.bodies.len() {
+            let id = ids.map_or(i as u32, |ids| ids[i]);
+            w.write_all(&id.to_le_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn read(r: &mut impl Read) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(invalid_data("not an nbody snapshot"));
+        }
+
+        let version = read_u32(r)?;
+        if !(1..=VERSION).contains(&version) {
+            return Err(invalid_data("unsupported snapshot version"));
+        }
+
+        let frame = read_u64(r)? as usize;
+        let dt = read_f32(r)?;
+        let t_sq = read_f32(r)?;
+        let e_sq = read_f32(r)?;
+        let count = read_u64(r)? as usize;
+        let bodies = read_body_records(r, count)?;
+
+        let (ids, next_id) = if version >= 2 {
+            let next_id = read_u32(r)?;
+            let ids = (0..bodies.len()).map(|_| read_u32(r)).collect::<io::Result<_>>()?;
+            (ids, next_id)
+        } else {
+            (Vec::new(), 0)
+        };
+
+        Ok(Self {
+            frame,
+            dt,
+            t_sq,
+            e_sq,
+            bodies,
+            ids,
+            next_id,
+        })
+    }
+}
+
+impl Simulation {
+    /// Saves the current state to a binary snapshot file.
+    pub fn save_snapshot(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        Snapshot::capture(self).write(&mut w)?;
+        w.flush()
+    }
+
+    /// Replaces the current state with the contents of a binary snapshot file.
+    /// The simulation is left untouched if the file cannot be read.
+    pub fn load_snapshot(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut r = BufReader::new(File::open(path)?);
+        Snapshot::read(&mut r)?.restore(self);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempFile, bodies_bits, sorted_multirate_sim, state_hash};
+
+    #[test]
+    fn snapshot_round_trips() {
+        let mut sim = sorted_multirate_sim(300);
+        for _ in 0..7 {
+            sim.step();
+        }
+        sim.add_body(Body::new(Vec2::new(3.0, -2.0), Vec2::new(0.5, 0.0), 2.0, 0.5));
+
+        let snapshot = Snapshot::capture(&sim);
+        let mut data = Vec::new();
+        snapshot.write(&mut data).unwrap();
+        let read = Snapshot::read(&mut &data[..]).unwrap();
+
+        assert_eq!(read.frame, snapshot.frame);
+        assert_eq!(read.dt.to_bits(), snapshot.dt.to_bits());
+        assert_eq!(read.t_sq.to_bits(), snapshot.t_sq.to_bits());
+        assert_eq!(read.e_sq.to_bits(), snapshot.e_sq.to_bits());
+        assert_eq!(bodies_bits(&read.bodies), bodies_bits(&snapshot.bodies));
+        assert_eq!(read.ids, sim.bodies.ids());
+        assert_eq!(read.next_id, sim.bodies.next_id());
+    }
+
+    #[test]
+    fn snapshot_file_restores_state() {
+        let mut sim = sorted_multirate_sim(300);
+        for _ in 0..7 {
+            sim.step();
+        }
+
+        let file = TempFile::new("snapshot.nbss");
+        sim.save_snapshot(file.path()).unwrap();
+        let mut loaded = Simulation::with_params(0, 1.0, 1.0, 1.0);
+        loaded.load_snapshot(file.path()).unwrap();
+
+        assert_eq!(loaded.frame, sim.frame);
+        assert_eq!(loaded.bodies.ids(), sim.bodies.ids());
+        assert_eq!(loaded.bodies.next_id(), sim.bodies.next_id());
+        assert_eq!(state_hash(&loaded), state_hash(&sim));
+    }
+
+    #[test]
+    fn reads_version_1_snapshots() {
+        let sim = Simulation::with_params(10, 0.1, 1.0, 1.0);
+        let mut data = Vec::new();
+        Snapshot::capture(&sim).write(&mut data).unwrap();
+        // Version 1 ends after the body records
+        data[4..8].copy_from_slice(&1u32.to_le_bytes());
+        data.truncate(data.len() - 4 * (sim.bodies.len() + 1));
+
+        let read = Snapshot::read(&mut &data[..]).unwrap();
+        assert_eq!(bodies_bits(&read.bodies), bodies_bits(&sim.bodies.to_vec()));
+        assert!(read.ids.is_empty());
+    }
+
+    #[test]
+    fn body_array_round_trips() {
+        let bodies = vec![
+            Body::new(Vec2::new(1.0, 2.0), Vec2::new(-0.5, 0.25), 3.0, 0.75),
+            Body {
+                acc: Vec2::new(0.125, -4.0),
+                ..Body::new(Vec2::new(-1e6, 1e-6), Vec2::zero(), 1e-3, 1.0)
+            },
+        ];
+
+        let file = TempFile::new("bodies.nbba");
+        save_binary(file.path(), &bodies).unwrap();
+        assert_eq!(bodies_bits(&load_binary(file.path()).unwrap()), bodies_bits(&bodies));
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let mut data = Vec::new();
+        write_bodies(&mut data, &[Body::default()]).unwrap();
+        assert_eq!(
+            Snapshot::read(&mut &data[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert_eq!(
+            read_bodies(&mut &b"NBSS"[..]).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use crate::simulation::Simulation;
+
+use rayon::prelude::*;
+
+use std::time::{Duration, Instant};
+
+/// Metrics of one step, published to the step stats callback (see
+/// `Simulation::set_step_stats_callback`).
+///
+/// Timings are zero on wasm32, which has no clock in the standard library, and for phases
+/// skipped this step (see `Simulation::set_gravity_interval`).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct StepStats {
+    /// Frame the step advanced to.
+    pub frame: usize,
+    /// Simulated time after the step.
+    pub time: f64,
+    /// Wall time of the whole step and of its phases; gravity includes building the tree.
+    pub total: Duration,
+    pub iterate: Duration,
+    pub collide: Duration,
+    pub gravity: Duration,
+    /// Nodes of the quadtree, or 0 if gravity was skipped.
+    pub nodes: usize,
+    /// Collisions resolved during the step.
+    pub collisions: usize,
+    /// Largest body speed after the step.
+    pub max_speed: f32,
+}
+
+/// Callback receiving the stats of each step.
+///
+/// It is called on the stepping thread, which may be a background thread; forward the stats to
+/// a channel to consume them elsewhere.
+pub type StepStatsCallback = Box<dyn FnMut(&StepStats) + Send>;
+
+/// Measures the phases of a step one after another, if enabled.
+pub(crate) struct PhaseTimer {
+    start: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl PhaseTimer {
+    pub(crate) fn new(enabled: bool) -> Self {
+        let now = (enabled && !cfg!(target_arch = "wasm32")).then(Instant::now);
+        Self { start: now, last: now }
+    }
+
+    /// Time since the last lap (or the start), or zero if disabled.
+    pub(crate) fn lap(&mut self) -> Duration {
+        let Some(last) = self.last else {
+            return Duration::ZERO;
+        };
+        let now = Instant::now();
+        self.last = Some(now);
+        now - last
+    }
+
+    /// Time since the start, or zero if disabled.
+    pub(crate) fn total(&self) -> Duration {
+        self.start.map_or(Duration::ZERO, |start| start.elapsed())
+    }
+}
+
+impl Simulation {
+    /// Sets the callback receiving `StepStats` after every step, so monitoring UIs and headless
+    /// loggers get metrics without polling the simulation. The stats are only gathered while a
+    /// callback is set.
+    pub fn set_step_stats_callback(&mut self, callback: Option<StepStatsCallback>) {
+        self.step_stats_callback = callback;
+    }
+
+    /// Largest body speed.
+    pub fn max_speed(&self) -> f32 {
+        self.bodies
+            .velocities()
+            .par_iter()
+            .map(|vel| vel.mag_sq())
+            .reduce(|| 0.0, f32::max)
+            .sqrt()
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{external::ExternalPotential, simulation::Falloff};
+use ultraviolet::Vec2;
+
+/// A background force whose strength or orientation changes with simulated time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimedField {
+    /// A fixed potential (see `ExternalPotential`), switched on and off by the event's frames.
+    Potential(ExternalPotential),
+    /// Quadrupole bar potential rotating rigidly about `center`:
+    /// Phi = -strength * (x'^2 - y'^2) / (r^2 + length^2), with x' along the bar.
+    /// The bar lies along the x axis rotated by `angle` at time 0 and turns at `pattern_speed`
+    /// radians per unit time.
+    RotatingBar { center: Vec2, strength: f32, length: f32, pattern_speed: f32, angle: f32 },
+}
+
+impl TimedField {
+    /// Acceleration at `pos` and simulated `time`, using the gravitational constant `g` and
+    /// softening `e_sq` for point masses.
+    #[inline(always)]
+    pub fn acc(&self, pos: Vec2, time: f64, g: f32, e_sq: f32) -> Vec2 {
+        match *self {
+            Self::Potential(potential) => potential.acc(pos, g, e_sq),
+            Self::RotatingBar { center, strength, length, pattern_speed, angle } => {
+                let theta = (angle as f64 + pattern_speed as f64 * time) as f32;
+                let (sin, cos) = theta.sin_cos();
+
+                // Into the bar frame, take -grad Phi there, and back out
+                let d = pos - center;
+                let (x, y) = (d.x * cos + d.y * sin, -d.x * sin + d.y * cos);
+                let s = x * x + y * y + length * length;
+                let q = (x * x - y * y) / s;
+                let (ax, ay) = (2.0 * strength * x * (1.0 - q) / s, -2.0 * strength * y * (1.0 + q) / s);
+                Vec2::new(ax * cos - ay * sin, ax * sin + ay * cos)
+            }
+        }
+    }
+}
+
+/// One scheduled perturbation of a `Timeline`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimelineEvent {
+    /// Radial velocity kick away from `center` (towards it if `strength` is negative) given once,
+    /// at the start of step `frame`, to every body within `radius`, scaled by `falloff`.
+    Pulse { frame: usize, center: Vec2, strength: f32, radius: f32, falloff: Falloff },
+    /// `field` added to the tree forces during the steps from `start` up to (excluding) `end`.
+    Field { start: usize, end: usize, field: TimedField },
+}
+
+/// Time-varying external forces scheduled by frame, so perturbation experiments repeat exactly
+/// without the host intervening each frame. Applied by `Simulation::step`.
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    pub events: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    pub fn new(events: Vec<TimelineEvent>) -> Self {
+        Self { events }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// The fields active during step `frame`.
+    pub fn fields(&self, frame: usize) -> impl Iterator<Item = &TimedField> {
+        self.events.iter().filter_map(move |event| match event {
+            TimelineEvent::Field { start, end, field } if (*start..*end).contains(&frame) => Some(field),
+            _ => None,
+        })
+    }
+
+    /// The pulses given at the start of step `frame`, as (center, strength, radius, falloff).
+    pub fn pulses(&self, frame: usize) -> impl Iterator<Item = (Vec2, f32, f32, Falloff)> + '_ {
+        self.events.iter().filter_map(move |event| match *event {
+            TimelineEvent::Pulse { frame: f, center, strength, radius, falloff } if f == frame => {
+                Some((center, strength, radius, falloff))
+            }
+            _ => None,
+        })
+    }
+}
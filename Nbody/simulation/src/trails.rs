@@ -0,0 +1,107 @@
+use crate::bodies::Bodies;
+
+use ultraviolet::Vec2;
+
+/// Keeps the last `length` positions of selected bodies, recorded every `interval` frames, so
+/// frontends can draw orbit trails without tracking the bodies themselves.
+///
+/// Each trail is a ring buffer; `write_vertices` flattens them oldest point first. Bodies are
+/// selected by their index and followed if the bodies are reordered (see
+/// `Simulation::sort_bodies_morton`); a trail whose body is out of range repeats its last point.
+/// Attach it with `Simulation::set_trail_recorder` to record after every step.
+#[derive(Clone, Debug)]
+pub struct TrailRecorder {
+    /// Current indices of the selected bodies, which change if the bodies are reordered.
+    indices: Vec<usize>,
+    length: usize,
+    interval: usize,
+    /// `length` slots per trail; slot `head` of each is written next.
+    points: Vec<Vec2>,
+    head: usize,
+    /// Points recorded per trail so far, up to `length`.
+    filled: usize,
+}
+
+impl TrailRecorder {
+    /// Records trails of `length` points for the bodies at `indices`. A `length` or `interval` of
+    /// 0 is treated as 1.
+    pub fn new(indices: Vec<usize>, length: usize, interval: usize) -> Self {
+        let length = length.max(1);
+        Self {
+            points: vec![Vec2::zero(); indices.len() * length],
+            indices,
+            length,
+            interval: interval.max(1),
+            head: 0,
+            filled: 0,
+        }
+    }
+
+    /// Appends the current position of each selected body if `frame` is a multiple of the
+    /// interval, dropping the oldest once the trails are full.
+    pub fn record(&mut self, frame: usize, bodies: &Bodies) {
+        if !frame.is_multiple_of(self.interval) {
+            return;
+        }
+
+        let positions = bodies.positions();
+        let previous = (self.head + self.length - 1) % self.length;
+        for (trail, &index) in self.indices.iter().enumerate() {
+            let slots = &mut self.points[trail * self.length..(trail + 1) * self.length];
+            slots[self.head] = match positions.get(index) {
+                Some(&pos) => pos,
+                None => slots[previous],
+            };
+        }
+        self.head = (self.head + 1) % self.length;
+        self.filled = (self.filled + 1).min(self.length);
+    }
+
+    /// Number of trails.
+    pub fn trails(&self) -> usize {
+        self.indices.len()
+    }
+
+    /// Points in each trail, the same for all of them.
+    pub fn len(&self) -> usize {
+        self.filled
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filled == 0
+    }
+
+    /// Points of `trail`, oldest first.
+    pub fn trail(&self, trail: usize) -> impl Iterator<Item = Vec2> + '_ {
+        let slots = &self.points[trail * self.length..(trail + 1) * self.length];
+        let start = (self.head + self.length - self.filled) % self.length;
+        (0..self.filled).map(move |k| slots[(start + k) % self.length])
+    }
+
+    /// Writes all trails to `out` as packed x, y pairs: `len()` points per trail, oldest first,
+    /// trails in the order their bodies were selected. Ready to upload as a line-strip vertex
+    /// buffer with one strip per trail.
+    pub fn write_vertices(&self, out: &mut Vec<f32>) {
+        out.clear();
+        out.reserve(2 * self.trails() * self.filled);
+        for trail in 0..self.trails() {
+            out.extend(self.trail(trail).flat_map(|pos| [pos.x, pos.y]));
+        }
+    }
+
+    /// Forgets the recorded points, e.g. after teleporting bodies.
+    pub fn clear(&mut self) {
+        self.head = 0;
+        self.filled = 0;
+    }
+
+    /// Follows the selected bodies to new indices after the bodies were reordered,
+    /// where `moved[i]` is the new index of the body previously at index `i`.
+    pub fn remap(&mut self, moved: &[u32]) {
+        for index in &mut self.indices {
+            if let Some(&new) = moved.get(*index) {
+                *index = new as usize;
+            }
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use crate::bodies::Bodies;
+
+#[cfg(feature = "parquet")]
+use crate::arrow_output::{trajectory_schema, writer_properties};
+#[cfg(feature = "parquet")]
+use arrow_array::{ArrayRef, Float32Array, Float64Array, RecordBatch, UInt64Array};
+#[cfg(feature = "parquet")]
+use parquet::arrow::ArrowWriter;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+/// Column header of trajectory CSV files.
+pub const TRAJECTORY_HEADER: &str = "frame,time,id,x,y,vx,vy";
+
+/// Where a `TrajectoryLogger` writes its rows.
+#[derive(Debug)]
+enum Output {
+    Csv(BufWriter<File>),
+    /// `finished` once the footer, without which the file can't be read, has been written.
+    #[cfg(feature = "parquet")]
+    Parquet { writer: Box<ArrowWriter<File>>, finished: bool },
+}
+
+/// Records the positions and velocities of selected bodies every `interval` frames to a CSV file
+/// (`TRAJECTORY_HEADER`), one row per body and recorded frame, or with the `parquet` feature to a
+/// Parquet file with the same columns (see `arrow_output::trajectory_schema`).
+///
+/// Bodies are selected by their index and followed if the bodies are reordered (see
+/// `Simulation::sort_bodies_morton`). Indices that are out of range at a recorded frame
+/// (e.g. after bodies were despawned) are skipped.
+/// Attach it with `Simulation::set_trajectory_logger` to record after every step.
+#[derive(Debug)]
+pub struct TrajectoryLogger {
+    output: Output,
+    /// Selected bodies as given, written to the `id` column.
+    ids: Vec<usize>,
+    /// Current indices of the selected bodies, which change if the bodies are reordered.
+    indices: Vec<usize>,
+    interval: usize,
+    /// First write error; logging stops once set.
+    error: Option<io::Error>,
+}
+
+impl TrajectoryLogger {
+    /// Creates the CSV file and writes its header. An `interval` of 0 is treated as 1.
+    pub fn new(path: impl AsRef<Path>, ids: Vec<usize>, interval: usize) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{TRAJECTORY_HEADER}")?;
+        Ok(Self::with_output(Output::Csv(writer), ids, interval))
+    }
+
+    /// Creates a zstd-compressed Parquet file instead of a CSV one. An `interval` of 0 is treated
+    /// as 1. The file is only readable once `finish` has run, which dropping the logger also does.
+    #[cfg(feature = "parquet")]
+    pub fn new_parquet(path: impl AsRef<Path>, ids: Vec<usize>, interval: usize) -> io::Result<Self> {
+        let writer = ArrowWriter::try_new(File::create(path)?, trajectory_schema(), Some(writer_properties()))
+            .map_err(io::Error::other)?;
+        Ok(Self::with_output(Output::Parquet { writer: Box::new(writer), finished: false }, ids, interval))
+    }
+
+    fn with_output(output: Output, ids: Vec<usize>, interval: usize) -> Self {
+        Self {
+            output,
+            indices: ids.clone(),
+            ids,
+            interval: interval.max(1),
+            error: None,
+        }
+    }
+
+    /// Records the selected bodies if `frame` is a multiple of the interval.
+    pub fn record(&mut self, frame: usize, time: f64, bodies: &Bodies) {
+        if self.error.is_some() || !frame.is_multiple_of(self.interval) {
+            return;
+        }
+
+        if let Err(e) = self.write_rows(frame, time, bodies) {
+            self.error = Some(e);
+        }
+    }
+
+    fn write_rows(&mut self, frame: usize, time: f64, bodies: &Bodies) -> io::Result<()> {
+        let selected = self
+            .ids
+            .iter()
+            .zip(&self.indices)
+            .filter_map(|(&id, &index)| Some((id, bodies.get(index)?)));
+        match &mut self.output {
+            Output::Csv(writer) => {
+                for (id, body) in selected {
+                    writeln!(
+                        writer,
+                        "{frame},{time},{id},{},{},{},{}",
+                        body.pos.x, body.pos.y, body.vel.x, body.vel.y
+                    )?;
+                }
+                Ok(())
+            }
+            #[cfg(feature = "parquet")]
+            Output::Parquet { writer, .. } => {
+                let (ids, bodies): (Vec<u64>, Vec<_>) = selected.map(|(id, body)| (id as u64, body)).unzip();
+                let n = ids.len();
+                let columns: Vec<ArrayRef> = vec![
+                    Arc::new(UInt64Array::from(vec![frame as u64; n])),
+                    Arc::new(Float64Array::from(vec![time; n])),
+                    Arc::new(UInt64Array::from(ids)),
+                    Arc::new(bodies.iter().map(|body| body.pos.x).collect::<Float32Array>()),
+                    Arc::new(bodies.iter().map(|body| body.pos.y).collect::<Float32Array>()),
+                    Arc::new(bodies.iter().map(|body| body.vel.x).collect::<Float32Array>()),
+                    Arc::new(bodies.iter().map(|body| body.vel.y).collect::<Float32Array>()),
+                ];
+                let batch = RecordBatch::try_new(trajectory_schema(), columns).map_err(io::Error::other)?;
+                writer.write(&batch).map_err(io::Error::other)
+            }
+        }
+    }
+
+    /// Follows the selected bodies to new indices after the bodies were reordered,
+    /// where `moved[i]` is the new index of the body previously at index `i`.
+    pub fn remap(&mut self, moved: &[u32]) {
+        for index in &mut self.indices {
+            if let Some(&new) = moved.get(*index) {
+                *index = new as usize;
+            }
+        }
+    }
+
+    /// Returns (and clears) the write error that stopped logging, if any.
+    pub fn take_error(&mut self) -> Option<io::Error> {
+        self.error.take()
+    }
+
+    /// Flushes buffered rows to the file (as a row group in a Parquet file).
+    pub fn flush(&mut self) -> io::Result<()> {
+        match &mut self.output {
+            Output::Csv(writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            Output::Parquet { writer, .. } => writer.flush().map_err(io::Error::other),
+        }
+    }
+
+    /// Flushes the remaining rows and, for a Parquet file, writes its footer; rows recorded
+    /// afterwards fail. Called on drop, where errors are lost.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match &mut self.output {
+            Output::Csv(writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            Output::Parquet { writer, finished } => {
+                if !std::mem::replace(finished, true) {
+                    writer.finish().map_err(io::Error::other)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Drop for TrajectoryLogger {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
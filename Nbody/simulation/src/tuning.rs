@@ -0,0 +1,125 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Durations of the jobs of one run of a parallel phase, recorded from the jobs themselves.
+#[derive(Debug, Default)]
+pub struct JobTimes {
+    total_ns: AtomicU64,
+    max_ns: AtomicU64,
+    jobs: AtomicU64,
+}
+
+impl JobTimes {
+    /// Records a job that took `elapsed`.
+    pub fn record(&self, elapsed: Duration) {
+        let ns = elapsed.as_nanos() as u64;
+        self.total_ns.fetch_add(ns, Ordering::Relaxed);
+        self.max_ns.fetch_max(ns, Ordering::Relaxed);
+        self.jobs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of jobs recorded.
+    pub fn jobs(&self) -> u64 {
+        self.jobs.load(Ordering::Relaxed)
+    }
+
+    /// Duration of the slowest job.
+    pub fn max(&self) -> Duration {
+        Duration::from_nanos(self.max_ns.load(Ordering::Relaxed))
+    }
+
+    /// Mean duration of the jobs.
+    pub fn mean(&self) -> Duration {
+        Duration::from_nanos(self.total_ns.load(Ordering::Relaxed) / self.jobs().max(1))
+    }
+}
+
+/// One job size tried by a `ChunkTuner`, with what it measured.
+#[derive(Clone, Copy, Debug)]
+pub struct ChunkTrial {
+    /// Minimum items per job.
+    pub chunk: usize,
+    /// Wall time of the phase.
+    pub wall: Duration,
+    /// Mean and longest job durations.
+    pub mean_job: Duration,
+    pub max_job: Duration,
+}
+
+/// Finds the job size of a parallel phase that balances the load best, by timing the phase
+/// with a different size on each of the first few frames (see `Simulation::set_auto_tune`).
+///
+/// The sizes tried double from a few dozen jobs per thread, where scheduling overhead starts to
+/// show, up to one job per thread. Once a single job takes over half the phase, the threads no
+/// longer share the load and larger sizes can only do worse, so the search stops early. The
+/// fastest size is kept until the item count changes by more than a factor of two.
+#[derive(Clone, Debug, Default)]
+pub struct ChunkTuner {
+    /// Item count the trials were made for.
+    items: usize,
+    candidates: Vec<usize>,
+    trials: Vec<ChunkTrial>,
+    best: Option<usize>,
+}
+
+impl ChunkTuner {
+    /// Jobs per thread of the finest job size tried.
+    pub const MAX_JOBS_PER_THREAD: usize = 64;
+
+    /// Job size for a run over `items` items on `threads` threads: the next one to try, or the
+    /// best one found so far if the search is over.
+    pub fn chunk(&mut self, items: usize, threads: usize) -> usize {
+        if self.candidates.is_empty() || items > 2 * self.items || 2 * items < self.items {
+            self.restart(items, threads.max(1));
+        }
+        self.best.unwrap_or_else(|| self.candidates[self.trials.len()])
+    }
+
+    /// Whether the search is still running, so the next run should be timed and `record`ed.
+    pub fn is_searching(&self) -> bool {
+        self.best.is_none()
+    }
+
+    /// Records the timings of a run with the size last returned by `chunk`.
+    pub fn record(&mut self, wall: Duration, jobs: &JobTimes) {
+        if self.best.is_some() {
+            return;
+        }
+
+        let max_job = jobs.max();
+        self.trials.push(ChunkTrial {
+            chunk: self.candidates[self.trials.len()],
+            wall,
+            mean_job: jobs.mean(),
+            max_job,
+        });
+        if self.trials.len() == self.candidates.len() || 2 * max_job > wall {
+            self.best = self.trials.iter().min_by_key(|trial| trial.wall).map(|trial| trial.chunk);
+        }
+    }
+
+    /// Job size found by the search, once it is over.
+    pub fn best(&self) -> Option<usize> {
+        self.best
+    }
+
+    /// Job sizes tried so far, in order.
+    pub fn trials(&self) -> &[ChunkTrial] {
+        &self.trials
+    }
+
+    fn restart(&mut self, items: usize, threads: usize) {
+        self.items = items;
+        self.trials.clear();
+        self.best = None;
+        self.candidates.clear();
+
+        let coarsest = items.div_ceil(threads).max(1);
+        let mut chunk = (items / (threads * Self::MAX_JOBS_PER_THREAD)).max(1);
+        while 2 * chunk <= coarsest {
+            self.candidates.push(chunk);
+            chunk *= 2;
+        }
+        self.candidates.push(coarsest);
+    }
+}
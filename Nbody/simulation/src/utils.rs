@@ -1,36 +1,181 @@
-use crate::body::Body;
+use crate::{body::Body, rng::Rng};
 use ultraviolet::Vec2;
 
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// Stellar initial mass function used to draw body masses.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MassFunction {
+    /// Salpeter (1955) power law dN/dm ~ m^-2.35 between `min` and `max` (`min` > 0).
+    Salpeter { min: f32, max: f32 },
+    /// Kroupa (2001) broken power law with slopes 0.3, 1.3 and 2.3 and breaks at 0.08 and 0.5,
+    /// between `min` and `max` (in the same units as the breaks, i.e. solar masses).
+    Kroupa { min: f32, max: f32 },
+}
+
+impl MassFunction {
+    /// Checks that the mass range is finite and non-empty, with `0 < min < max`.
+    pub fn validate(&self) -> Result<(), String> {
+        let (Self::Salpeter { min, max } | Self::Kroupa { min, max }) = *self;
+        if !(min.is_finite() && max.is_finite() && 0.0 < min && min < max) {
+            return Err(format!("mass function range must satisfy 0 < min < max, got {min}..{max}"));
+        }
+        Ok(())
+    }
+
+    /// Draws a mass from the distribution. Out of a range `validate` rejects, returns `min`.
+    pub fn sample(&self, rng: &mut dyn Rng) -> f32 {
+        match *self {
+            Self::Salpeter { min, max } => sample_broken_power_law(&[(min, 2.35)], min, max, rng),
+            Self::Kroupa { min, max } => {
+                sample_broken_power_law(&[(0.0, 0.3), (0.08, 1.3), (0.5, 2.3)], min, max, rng)
+            }
+        }
+    }
+}
+
+/// Body radius for a given mass, assuming constant density (as the other generators do).
+pub fn radius_for_mass(mass: f32) -> f32 {
+    mass.cbrt()
+}
+
+/// Samples dN/dm ~ m^-alpha_i on [min, max], where `segments` lists (lower break, alpha_i)
+/// in increasing order and the density is continuous across the breaks. Returns `min` if the
+/// range is empty or holds no finite probability.
+fn sample_broken_power_law(segments: &[(f32, f32)], min: f32, max: f32, rng: &mut dyn Rng) -> f32 {
+    // Integral of m^-alpha over [lo, hi]
+    let integral = |alpha: f32, lo: f32, hi: f32| {
+        if (alpha - 1.0).abs() < 1e-6 {
+            (hi / lo).ln()
+        } else {
+            (hi.powf(1.0 - alpha) - lo.powf(1.0 - alpha)) / (1.0 - alpha)
+        }
+    };
+
+    // Clip each segment to [min, max] and weight it, keeping the density continuous
+    let mut pieces = Vec::with_capacity(segments.len());
+    let mut scale = 1.0f32;
+    for (i, &(lo, alpha)) in segments.iter().enumerate() {
+        if i > 0 {
+            scale *= lo.powf(alpha - segments[i - 1].1);
+        }
+        let hi = segments.get(i + 1).map_or(f32::INFINITY, |s| s.0);
+        let (lo, hi) = (lo.max(min), hi.min(max));
+        if lo < hi {
+            pieces.push((lo, hi, alpha, scale * integral(alpha, lo, hi)));
+        }
+    }
+
+    let total: f32 = pieces.iter().map(|p| p.3).sum();
+    if !(total.is_finite() && total > 0.0) {
+        return min;
+    }
+    let mut u = rng.f32() * total;
+    let Some(&(lo, hi, alpha, _)) = pieces
+        .iter()
+        .find(|p| {
+            u -= p.3;
+            u <= 0.0
+        })
+        .or(pieces.last())
+    else {
+        return min;
+    };
+
+    // Invert the cumulative distribution within the segment
+    let v = rng.f32();
+    if (alpha - 1.0).abs() < 1e-6 {
+        lo * (hi / lo).powf(v)
+    } else {
+        let (a, b) = (lo.powf(1.0 - alpha), hi.powf(1.0 - alpha));
+        (a + v * (b - a)).powf(1.0 / (1.0 - alpha))
+    }
+}
+
+/// Parameters for `uniform_disc_with`. The defaults reproduce `uniform_disc`.
+#[derive(Clone, Copy, Debug)]
+pub struct DiscParams {
+    /// Seed for the random number generator.
+    pub seed: u64,
+    /// Radius of the central body; no other bodies are placed inside it.
+    pub inner_radius: f32,
+    /// Outer radius of the disc. `None` scales it with the body count (sqrt(n) * 5).
+    pub outer_radius: Option<f32>,
+    /// Mass of the central body.
+    pub central_mass: f32,
+    /// Range the masses of the other bodies are drawn uniformly from.
+    pub mass_range: (f32, f32),
+    /// Mass function the other bodies' masses are drawn from instead of `mass_range`, if set.
+    pub mass_function: Option<MassFunction>,
+    /// Orbital direction: positive is clockwise (as `uniform_disc`), negative counter-clockwise.
+    pub spin: f32,
+    /// Maximum relative deviation from circular speed, producing eccentric orbits.
+    pub eccentricity_spread: f32,
+    /// Random velocity dispersion relative to circular speed (see `add_velocity_dispersion`).
+    pub temperature: f32,
+}
+
+impl Default for DiscParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            inner_radius: 25.0,
+            outer_radius: None,
+            central_mass: 1e6,
+            mass_range: (1.0, 1.0),
+            mass_function: None,
+            spin: 1.0,
+            eccentricity_spread: 0.0,
+            temperature: 0.0,
+        }
+    }
+}
+
 /// Generates `n` bodies distributed in a uniform disc, suitable for a galaxy simulation.
 /// - Creates a massive central body.
 /// - Places other bodies in random circular orbits around the center.
 /// - Assigns velocities to ensure stable orbits based on accumulated mass.
 pub fn uniform_disc(n: usize) -> Vec<Body> {
-    fastrand::seed(0);
-    let inner_radius = 25.0;
-    let outer_radius = (n as f32).sqrt() * 5.0;
+    uniform_disc_with(n, &DiscParams::default())
+}
+
+/// Generates a uniform disc like `uniform_disc` with custom parameters.
+pub fn uniform_disc_with(n: usize, params: &DiscParams) -> Vec<Body> {
+    let rng: &mut dyn Rng = &mut fastrand::Rng::with_seed(params.seed);
+    let inner_radius = params.inner_radius;
+    let outer_radius = params.outer_radius.unwrap_or((n as f32).sqrt() * 5.0);
+    let (min_mass, max_mass) = params.mass_range;
+    let spin = params.spin.signum();
 
     let mut bodies: Vec<Body> = Vec::with_capacity(n);
 
     // Create a massive central black hole / star
-    let m = 1e6;
-    let center = Body::new(Vec2::zero(), Vec2::zero(), m as f32, inner_radius);
+    let center = Body::new(Vec2::zero(), Vec2::zero(), params.central_mass, inner_radius);
     bodies.push(center);
 
     while bodies.len() < n {
         // Random angle
-        let a = fastrand::f32() * std::f32::consts::TAU;
+        let a = rng.f32() * std::f32::consts::TAU;
         let (sin, cos) = a.sin_cos();
         
         // Random radius with uniform area distribution
         let t = inner_radius / outer_radius;
-        let r = fastrand::f32() * (1.0 - t * t) + t * t;
+        let r = rng.f32() * (1.0 - t * t) + t * t;
         let pos = Vec2::new(cos, sin) * outer_radius * r.sqrt();
         
         // Initial perpendicular velocity direction
-        let vel = Vec2::new(sin, -cos);
-        let mass = 1.0f32;
-        let radius = mass.cbrt();
+        let vel = Vec2::new(sin, -cos) * spin;
+        // Draw only when needed so the default disc is unchanged
+        let mass = if let Some(imf) = &params.mass_function {
+            imf.sample(rng)
+        } else if max_mass > min_mass {
+            min_mass + rng.f32() * (max_mass - min_mass)
+        } else {
+            min_mass
+        };
+        let radius = radius_for_mass(mass);
 
         bodies.push(Body::new(pos, vel, mass, radius));
     }
@@ -48,9 +193,647 @@ pub fn uniform_disc(n: usize) -> Vec<Body> {
 
         // Velocity for circular orbit: v = sqrt(GM / r)
         // Here G is implicitly 1
-        let v = (mass / bodies[i].pos.mag()).sqrt();
+        let mut v = (mass / bodies[i].pos.mag()).sqrt();
+        if params.eccentricity_spread > 0.0 {
+            v *= 1.0 + params.eccentricity_spread * (2.0 * rng.f32() - 1.0);
+        }
         bodies[i].vel *= v;
     }
 
+    if params.temperature > 0.0 {
+        add_velocity_dispersion(&mut bodies, params.temperature, rng);
+    }
+
+    bodies
+}
+
+/// Adds an isotropic random velocity with a per-component dispersion of `temperature` times
+/// each body's current speed. Cold discs (temperature near 0) fragment into clumps and arms
+/// quickly; hotter ones stay smooth for longer. Bodies at rest are unaffected.
+pub fn add_velocity_dispersion(bodies: &mut [Body], temperature: f32, rng: &mut dyn Rng) {
+    for body in bodies.iter_mut() {
+        let sigma = body.vel.mag() * temperature;
+        body.vel += Vec2::new(gaussian(rng), gaussian(rng)) * sigma;
+    }
+}
+
+/// Sets each body's speed for a circular orbit around `center` from the mass enclosed within its radius
+/// (v = sqrt(G * M / r)), keeping the current velocity direction.
+/// Bodies are sorted by distance from `center`.
+fn set_circular_velocities(bodies: &mut [Body], center: Vec2, g: f32) {
+    bodies.sort_by(|a, b| (a.pos - center).mag_sq().total_cmp(&(b.pos - center).mag_sq()));
+
+    let mut mass = 0.0;
+    for body in bodies.iter_mut() {
+        mass += body.mass;
+        let r = (body.pos - center).mag();
+        if r == 0.0 {
+            continue;
+        }
+        body.vel *= (g * mass / r).sqrt();
+    }
+}
+
+/// Samples a standard normal variate (Box-Muller).
+fn gaussian(rng: &mut dyn Rng) -> f32 {
+    let u1 = rng.f32().max(f32::MIN_POSITIVE);
+    let u2 = rng.f32();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+/// Generates a rotating disc of `n` bodies of the given `radius` around `center`,
+/// including a central mass equal to the combined mass of the other bodies.
+/// `spin` scales the orbital speed; positive values rotate the same way as `uniform_disc`.
+pub fn disc_at(center: Vec2, n: usize, radius: f32, spin: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let inner_radius = radius * 0.05;
+    let mut bodies: Vec<Body> = Vec::with_capacity(n);
+    bodies.push(Body::new(center, Vec2::zero(), (n - 1).max(1) as f32, inner_radius));
+
+    while bodies.len() < n {
+        let a = rng.f32() * std::f32::consts::TAU;
+        let (sin, cos) = a.sin_cos();
+
+        let t = inner_radius / radius;
+        let r = rng.f32() * (1.0 - t * t) + t * t;
+        let pos = center + Vec2::new(cos, sin) * radius * r.sqrt();
+
+        let vel = Vec2::new(sin, -cos) * spin;
+        let mass = 1.0f32;
+        bodies.push(Body::new(pos, vel, mass, mass.cbrt()));
+    }
+
+    set_circular_velocities(&mut bodies, center, g);
+    bodies
+}
+
+/// Generates a Plummer-like star cluster of `n` unit-mass bodies around `center`.
+/// `radius` is the Plummer scale length; velocities are isotropic with the Plummer dispersion.
+pub fn plummer_at(center: Vec2, n: usize, radius: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let total_mass = n as f32;
+    let mut bodies = Vec::with_capacity(n);
+
+    for _ in 0..n {
+        // Invert the Plummer cumulative mass profile M(r) / M = r^3 / (r^2 + a^2)^(3/2),
+        // truncated at 10 scale lengths
+        let m = rng.f32().clamp(1e-4, 0.999);
+        let r = (radius / (m.powf(-2.0 / 3.0) - 1.0).sqrt()).min(10.0 * radius);
+
+        let (sin, cos) = (rng.f32() * std::f32::consts::TAU).sin_cos();
+        let pos = center + Vec2::new(cos, sin) * r;
+
+        let sigma = (g * total_mass / (6.0 * (r * r + radius * radius).sqrt())).sqrt();
+        let vel = Vec2::new(gaussian(rng), gaussian(rng)) * sigma;
+
+        let mass = 1.0f32;
+        bodies.push(Body::new(pos, vel, mass, mass.cbrt()));
+    }
+
+    bodies
+}
+
+/// Generates a King-model globular cluster of `n` unit-mass bodies around `center`.
+/// - `core_radius`: King core radius r_c.
+/// - `concentration`: c = log10(r_t / r_c), where r_t is the tidal radius beyond which there are no bodies.
+///
+/// Positions follow King's (1962) projected density
+/// (1 / sqrt(1 + (R/r_c)^2) - 1 / sqrt(1 + (r_t/r_c)^2))^2, sampled by rejection.
+/// Velocities are isotropic, with a dispersion set by the enclosed mass and capped below escape speed.
+pub fn king_at(center: Vec2, n: usize, core_radius: f32, concentration: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let tidal_radius = core_radius * 10f32.powf(concentration.max(0.0));
+    let edge = 1.0 / (1.0 + (tidal_radius / core_radius).powi(2)).sqrt();
+    let density = |r: f32| {
+        let s = 1.0 / (1.0 + (r / core_radius).powi(2)).sqrt() - edge;
+        s * s
+    };
+
+    // Envelope for the rejection sampler of density(r) * r on [0, r_t]
+    let peak = (1..=256)
+        .map(|i| {
+            let r = tidal_radius * i as f32 / 256.0;
+            density(r) * r
+        })
+        .fold(0.0, f32::max)
+        * 1.1;
+
+    let mut bodies: Vec<Body> = Vec::with_capacity(n);
+    while bodies.len() < n {
+        let r = rng.f32() * tidal_radius;
+        if rng.f32() * peak > density(r) * r {
+            continue;
+        }
+
+        let (sin, cos) = (rng.f32() * std::f32::consts::TAU).sin_cos();
+        let pos = center + Vec2::new(cos, sin) * r;
+        let mass = 1.0f32;
+        bodies.push(Body::new(pos, Vec2::zero(), mass, mass.cbrt()));
+    }
+
+    bodies.sort_by(|a, b| (a.pos - center).mag_sq().total_cmp(&(b.pos - center).mag_sq()));
+
+    let total_mass = n as f32;
+    let mut mass = 0.0;
+    for body in bodies.iter_mut() {
+        mass += body.mass;
+        // The core softens the dispersion at the center, where the enclosed mass vanishes
+        let r = (body.pos - center).mag().max(core_radius);
+        let sigma = (g * mass / (2.0 * r)).sqrt();
+        let v_esc = (2.0 * g * total_mass / r).sqrt();
+
+        let vel = Vec2::new(gaussian(rng), gaussian(rng)) * sigma;
+        body.vel = if vel.mag() > 0.9 * v_esc {
+            vel.normalized() * 0.9 * v_esc
+        } else {
+            vel
+        };
+    }
+
+    bodies
+}
+
+/// Circular speed at radius `r` in the plane of a Kuzmin disc of total `mass` and scale length `scale`:
+/// v^2 = G M r^2 / (r^2 + a^2)^(3/2).
+pub fn kuzmin_circular_velocity(r: f32, scale: f32, mass: f32, g: f32) -> f32 {
+    let s_sq = r * r + scale * scale;
+    (g * mass * r * r / (s_sq * s_sq.sqrt())).sqrt()
+}
+
+/// Generates a razor-thin Kuzmin (Toomre n = 1) disc of `n` equal-mass bodies around `center`
+/// with surface density M a / (2 pi (r^2 + a^2)^(3/2)), truncated at 20 scale lengths.
+///
+/// Bodies orbit the same way as in `uniform_disc` at the analytic circular speed
+/// (`kuzmin_circular_velocity`), so the disc is in equilibrium if the tree forces
+/// reproduce the analytic rotation curve (up to softening and truncation).
+pub fn kuzmin_disc_at(center: Vec2, n: usize, scale: f32, mass: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let body_mass = mass / n.max(1) as f32;
+    let u_max = 1.0 - 1.0 / (1.0 + 400.0f32).sqrt();
+    let mut bodies = Vec::with_capacity(n);
+
+    for _ in 0..n {
+        // Invert the cumulative mass M(<r) / M = 1 - a / sqrt(r^2 + a^2)
+        let u = rng.f32() * u_max;
+        let r = scale * (1.0 / ((1.0 - u) * (1.0 - u)) - 1.0).sqrt();
+
+        let (sin, cos) = (rng.f32() * std::f32::consts::TAU).sin_cos();
+        let pos = center + Vec2::new(cos, sin) * r;
+        let vel = Vec2::new(sin, -cos) * kuzmin_circular_velocity(r, scale, mass, g);
+
+        bodies.push(Body::new(pos, vel, body_mass, body_mass.cbrt()));
+    }
+
+    bodies
+}
+
+/// Evaluates the polynomial with coefficients `c` (lowest order first) at `x`.
+fn poly(c: &[f64], x: f64) -> f64 {
+    c.iter().rev().fold(0.0, |acc, &c| acc * x + c)
+}
+
+// Modified Bessel functions, polynomial approximations from Abramowitz & Stegun 9.8.1-9.8.8
+
+fn bessel_i0(x: f64) -> f64 {
+    let t = x / 3.75;
+    if x <= 3.75 {
+        poly(&[1.0, 3.5156229, 3.0899424, 1.2067492, 0.2659732, 0.0360768, 0.0045813], t * t)
+    } else {
+        let c = [
+            0.39894228, 0.01328592, 0.00225319, -0.00157565, 0.00916281, -0.02057706, 0.02635537, -0.01647633,
+            0.00392377,
+        ];
+        x.exp() / x.sqrt() * poly(&c, 1.0 / t)
+    }
+}
+
+fn bessel_i1(x: f64) -> f64 {
+    let t = x / 3.75;
+    if x <= 3.75 {
+        x * poly(&[0.5, 0.87890594, 0.51498869, 0.15084934, 0.02658733, 0.00301532, 0.00032411], t * t)
+    } else {
+        let c = [
+            0.39894228, -0.03988024, -0.00362018, 0.00163801, -0.01031555, 0.02282967, -0.02895312, 0.01787654,
+            -0.00420059,
+        ];
+        x.exp() / x.sqrt() * poly(&c, 1.0 / t)
+    }
+}
+
+fn bessel_k0(x: f64) -> f64 {
+    if x <= 2.0 {
+        let u = x * x / 4.0;
+        let c = [-0.57721566, 0.42278420, 0.23069756, 0.03488590, 0.00262698, 0.00010750, 0.0000074];
+        -(x / 2.0).ln() * bessel_i0(x) + poly(&c, u)
+    } else {
+        let c = [1.25331414, -0.07832358, 0.02189568, -0.01062446, 0.00587872, -0.00251540, 0.00053208];
+        (-x).exp() / x.sqrt() * poly(&c, 2.0 / x)
+    }
+}
+
+fn bessel_k1(x: f64) -> f64 {
+    if x <= 2.0 {
+        let u = x * x / 4.0;
+        let c = [1.0, 0.15443144, -0.67278579, -0.18156897, -0.01919402, -0.00110404, -0.00004686];
+        (x * (x / 2.0).ln() * bessel_i1(x) + poly(&c, u)) / x
+    } else {
+        let c = [1.25331414, 0.23498619, -0.03655620, 0.01504268, -0.00780353, 0.00325614, -0.00068245];
+        (-x).exp() / x.sqrt() * poly(&c, 2.0 / x)
+    }
+}
+
+/// Circular speed at radius `r` in the plane of a razor-thin exponential disc of total `mass`
+/// and scale length `scale` (Freeman 1970): v^2 = 2 G M / R_d * y^2 (I0 K0 - I1 K1), y = r / (2 R_d).
+pub fn exponential_circular_velocity(r: f32, scale: f32, mass: f32, g: f32) -> f32 {
+    let y = r as f64 / (2.0 * scale as f64);
+    if y <= 0.0 {
+        return 0.0;
+    }
+
+    let bessel = bessel_i0(y) * bessel_k0(y) - bessel_i1(y) * bessel_k1(y);
+    let v_sq = 2.0 * (g * mass / scale) as f64 * y * y * bessel;
+    v_sq.max(0.0).sqrt() as f32
+}
+
+/// Generates a razor-thin exponential disc of `n` equal-mass bodies around `center`
+/// with surface density ~ exp(-r / scale), truncated at 10 scale lengths.
+///
+/// Bodies orbit the same way as in `uniform_disc` at the exact circular speed of the
+/// untruncated disc (`exponential_circular_velocity`), which rises to a peak near 2.2 scale lengths
+/// and then falls off, rather than the point-mass estimate from the enclosed mass.
+pub fn exponential_disc_at(center: Vec2, n: usize, scale: f32, mass: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let body_mass = mass / n.max(1) as f32;
+    let mut bodies = Vec::with_capacity(n);
+
+    while bodies.len() < n {
+        // The radial distribution r exp(-r / R_d) is a Gamma(2, R_d) distribution
+        let u = rng.f32().max(f32::MIN_POSITIVE) * rng.f32().max(f32::MIN_POSITIVE);
+        let r = -scale * u.ln();
+        if r > 10.0 * scale {
+            continue;
+        }
+
+        let (sin, cos) = (rng.f32() * std::f32::consts::TAU).sin_cos();
+        let pos = center + Vec2::new(cos, sin) * r;
+        let vel = Vec2::new(sin, -cos) * exponential_circular_velocity(r, scale, mass, g);
+
+        bodies.push(Body::new(pos, vel, body_mass, body_mass.cbrt()));
+    }
+
     bodies
 }
+
+/// Generates a disc like `uniform_disc` with logarithmic spiral arm perturbations.
+/// - `arms`: number of spiral arms.
+/// - `pitch`: pitch angle of the arms in radians (smaller is more tightly wound).
+/// - `amplitude`: density contrast of the arms in [0, 1].
+///
+/// Bodies are placed by rejection sampling against 1 + A cos(m (theta - ln(r) / tan(pitch))),
+/// and their circular velocities get the matching density-wave streaming motions.
+pub fn spiral_galaxy(n: usize, arms: u32, pitch: f32, amplitude: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let inner_radius = 25.0;
+    let outer_radius = (n as f32).sqrt() * 5.0;
+    let amplitude = amplitude.clamp(0.0, 1.0);
+    let m = arms as f32;
+    let winding = 1.0 / pitch.tan();
+
+    // Phase relative to the nearest arm; arms trail the clockwise rotation of `uniform_disc`
+    let phase = |pos: Vec2| m * (pos.y.atan2(pos.x) - pos.mag().ln() * winding);
+
+    let mut bodies: Vec<Body> = Vec::with_capacity(n);
+    let center = Body::new(Vec2::zero(), Vec2::zero(), 1e6, inner_radius);
+    bodies.push(center);
+
+    while bodies.len() < n {
+        let a = rng.f32() * std::f32::consts::TAU;
+        let (sin, cos) = a.sin_cos();
+
+        let t = inner_radius / outer_radius;
+        let r = rng.f32() * (1.0 - t * t) + t * t;
+        let pos = Vec2::new(cos, sin) * outer_radius * r.sqrt();
+
+        if rng.f32() * (1.0 + amplitude) > 1.0 + amplitude * phase(pos).cos() {
+            continue;
+        }
+
+        let vel = Vec2::new(sin, -cos);
+        let mass = 1.0f32;
+        bodies.push(Body::new(pos, vel, mass, mass.cbrt()));
+    }
+
+    set_circular_velocities(&mut bodies, Vec2::zero(), 1.0);
+
+    // Linear density-wave response: stars stream inward and slow down entering an arm
+    let streaming = 0.1 * amplitude;
+    for body in bodies.iter_mut().skip(1) {
+        let v_c = body.vel.mag();
+        let radial = body.pos.normalized();
+        let psi = phase(body.pos);
+        body.vel += radial * (-streaming * v_c * psi.sin()) - body.vel * (streaming * psi.cos());
+    }
+
+    bodies
+}
+
+/// Gravitational constant in astronomical units, solar masses and years (4 pi^2).
+pub const G_SOLAR: f32 = 39.478_418;
+
+/// Generates the Sun and the eight planets on circular orbits at their real semi-major axes.
+///
+/// Units are AU, solar masses and years, so the simulation's gravity constant must be set to
+/// `G_SOLAR` (Earth then completes an orbit in 1.0 time units). The softening length should be
+/// well below the planets' separations, e.g. 1e-4. Planets start on the +x axis moving
+/// counter-clockwise, and the Sun gets the recoil velocity that zeroes the total momentum.
+pub fn solar_system() -> Vec<Body> {
+    const SUN_MASS: f32 = 1.0;
+    const SUN_RADIUS: f32 = 4.65e-3;
+
+    // (semi-major axis [AU], mass [solar masses], radius [AU])
+    const PLANETS: [(f32, f32, f32); 8] = [
+        (0.387, 1.660e-7, 1.63e-5), // Mercury
+        (0.723, 2.447e-6, 4.05e-5), // Venus
+        (1.000, 3.003e-6, 4.26e-5), // Earth
+        (1.524, 3.227e-7, 2.27e-5), // Mars
+        (5.203, 9.545e-4, 4.78e-4), // Jupiter
+        (9.537, 2.858e-4, 4.03e-4), // Saturn
+        (19.19, 4.366e-5, 1.71e-4), // Uranus
+        (30.07, 5.151e-5, 1.65e-4), // Neptune
+    ];
+
+    let mut bodies = vec![Body::new(Vec2::zero(), Vec2::zero(), SUN_MASS, SUN_RADIUS)];
+    let mut momentum = Vec2::zero();
+
+    for (a, mass, radius) in PLANETS {
+        let v = (G_SOLAR * (SUN_MASS + mass) / a).sqrt();
+        let vel = Vec2::new(0.0, v);
+        momentum += vel * mass;
+        bodies.push(Body::new(Vec2::new(a, 0.0), vel, mass, radius));
+    }
+
+    bodies[0].vel = -momentum / SUN_MASS;
+    bodies
+}
+
+/// Generates `n` unit-mass bodies spread uniformly over a square of side `size` centered
+/// on the origin, all at rest (cold collapse).
+pub fn cold_square(n: usize, size: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let mut bodies = Vec::with_capacity(n);
+
+    for _ in 0..n {
+        let pos = Vec2::new(rng.f32() - 0.5, rng.f32() - 0.5) * size;
+        let mass = 1.0f32;
+        bodies.push(Body::new(pos, Vec2::zero(), mass, mass.cbrt()));
+    }
+
+    bodies
+}
+
+/// Generates the Chenciner-Montgomery figure-eight choreography: three unit masses chasing
+/// each other along a figure-eight with period ~6.3259 (G = 1).
+///
+/// The orbit is only reproduced with a gravity constant of 1 and a softening length
+/// far below the closest approach (e.g. 1e-4); the bodies are small enough not to collide.
+pub fn figure_eight() -> Vec<Body> {
+    let pos = Vec2::new(0.970_004_4, -0.243_087_53);
+    let vel = Vec2::new(-0.932_407_4, -0.864_731_5);
+    let mass = 1.0;
+    let radius = 1e-3;
+
+    vec![
+        Body::new(pos, vel * -0.5, mass, radius),
+        Body::new(-pos, vel * -0.5, mass, radius),
+        Body::new(Vec2::zero(), vel, mass, radius),
+    ]
+}
+
+/// Generates a planet at the origin surrounded by a dense annulus of `n` small ring particles
+/// between `inner_radius` and `outer_radius`, plus two shepherd moons just inside and outside it.
+///
+/// Ring particles are sized to cover about a tenth of the annulus and are given a small random
+/// velocity dispersion on top of their Keplerian orbits, so neighbours collide frequently.
+/// Their combined mass is negligible next to the planet's.
+pub fn planetary_ring(n: usize, planet_mass: f32, inner_radius: f32, outer_radius: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let mut bodies = Vec::with_capacity(n + 3);
+    bodies.push(Body::new(Vec2::zero(), Vec2::zero(), planet_mass, inner_radius * 0.5));
+
+    let orbit = |r: f32, angle: f32| {
+        let (sin, cos) = angle.sin_cos();
+        let pos = Vec2::new(cos, sin) * r;
+        let vel = Vec2::new(sin, -cos) * (g * planet_mass / r).sqrt();
+        (pos, vel)
+    };
+
+    let moon_mass = planet_mass * 1e-4;
+    let moon_radius = (outer_radius - inner_radius) * 0.02;
+    for (r, angle) in [(inner_radius * 0.95, 0.0), (outer_radius * 1.05, std::f32::consts::PI)] {
+        let (pos, vel) = orbit(r, angle);
+        bodies.push(Body::new(pos, vel, moon_mass, moon_radius));
+    }
+
+    let area = std::f32::consts::PI * (outer_radius * outer_radius - inner_radius * inner_radius);
+    let particle_radius = (0.1 * area / (n.max(1) as f32 * std::f32::consts::PI)).sqrt();
+    let particle_mass = planet_mass * 1e-9;
+
+    for _ in 0..n {
+        // Uniform area distribution across the annulus
+        let t = inner_radius / outer_radius;
+        let r = outer_radius * (rng.f32() * (1.0 - t * t) + t * t).sqrt();
+        let (pos, vel) = orbit(r, rng.f32() * std::f32::consts::TAU);
+
+        let dispersion = vel.mag() * 1e-3;
+        let vel = vel + Vec2::new(gaussian(rng), gaussian(rng)) * dispersion;
+        bodies.push(Body::new(pos, vel, particle_mass, particle_radius));
+    }
+
+    bodies
+}
+
+/// Generates `n` unit-mass bodies whose positions follow a density map of `width` x `height` cells
+/// (row-major, first row at the top), such as a grayscale image: density is proportional to brightness.
+///
+/// The map is stretched to `size` world units wide (keeping its aspect ratio) and centered on the origin.
+/// Bodies orbit the center of mass with speeds from the enclosed mass, rotating like `uniform_disc`.
+/// Returns an empty vector if the map is empty or has no positive cells.
+pub fn density_map(density: &[f32], width: usize, height: usize, n: usize, size: f32, g: f32, rng: &mut dyn Rng) -> Vec<Body> {
+    let cells = width * height;
+    if cells == 0 || density.len() < cells {
+        return Vec::new();
+    }
+
+    // Cumulative distribution over the cells
+    let mut cdf = Vec::with_capacity(cells);
+    let mut total = 0.0f32;
+    for &d in &density[..cells] {
+        total += if d.is_finite() { d.max(0.0) } else { 0.0 };
+        cdf.push(total);
+    }
+    if total <= 0.0 {
+        return Vec::new();
+    }
+
+    let cell_size = size / width as f32;
+    let origin = Vec2::new(-0.5 * size, 0.5 * cell_size * height as f32);
+
+    let mut bodies: Vec<Body> = Vec::with_capacity(n);
+    for _ in 0..n {
+        let u = rng.f32() * total;
+        let cell = cdf.partition_point(|&c| c <= u).min(cells - 1);
+        let (x, y) = ((cell % width) as f32, (cell / width) as f32);
+
+        let pos = origin + Vec2::new(x + rng.f32(), -(y + rng.f32())) * cell_size;
+        let mass = 1.0f32;
+        bodies.push(Body::new(pos, Vec2::zero(), mass, mass.cbrt()));
+    }
+
+    let center = bodies.iter().fold(Vec2::zero(), |acc, body| acc + body.pos) / n.max(1) as f32;
+    for body in bodies.iter_mut() {
+        let d = body.pos - center;
+        if d != Vec2::zero() {
+            let d = d.normalized();
+            body.vel = Vec2::new(d.y, -d.x);
+        }
+    }
+    set_circular_velocities(&mut bodies, center, g);
+
+    bodies
+}
+
+/// Generates a `width` x `height` fractal value-noise field in [0, 1] for `density_map`.
+/// `scale` is the size in cells of the coarsest features.
+pub fn noise_field(width: usize, height: usize, scale: f32, seed: u64) -> Vec<f32> {
+    const OCTAVES: u32 = 4;
+    const LATTICE: usize = 256;
+
+    let mut rng = fastrand::Rng::with_seed(seed);
+    let lattice: Vec<f32> = (0..LATTICE * LATTICE).map(|_| rng.f32()).collect();
+    let at = |x: i64, y: i64| {
+        lattice[(y.rem_euclid(LATTICE as i64) as usize) * LATTICE + x.rem_euclid(LATTICE as i64) as usize]
+    };
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+
+    let mut field = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            let (mut value, mut amplitude, mut frequency, mut norm) = (0.0, 1.0, 1.0 / scale.max(1.0), 0.0);
+
+            for _ in 0..OCTAVES {
+                let (fx, fy) = (x as f32 * frequency, y as f32 * frequency);
+                let (x0, y0) = (fx.floor() as i64, fy.floor() as i64);
+                let (tx, ty) = (smooth(fx.fract()), smooth(fy.fract()));
+
+                let top = at(x0, y0) + (at(x0 + 1, y0) - at(x0, y0)) * tx;
+                let bottom = at(x0, y0 + 1) + (at(x0 + 1, y0 + 1) - at(x0, y0 + 1)) * tx;
+                value += (top + (bottom - top) * ty) * amplitude;
+
+                norm += amplitude;
+                amplitude *= 0.5;
+                frequency *= 2.0;
+            }
+
+            field.push(value / norm);
+        }
+    }
+
+    field
+}
+
+/// Column header written by `save_csv`.
+pub const CSV_HEADER: &str = "x,y,vx,vy,mass,radius";
+
+/// Loads bodies from a CSV file with the columns `x,y,vx,vy,mass[,radius]`.
+///
+/// A header line, blank lines and lines starting with `#` are skipped. If the radius column
+/// is missing it is derived from the mass (`radius_for_mass`). Accelerations start at zero.
+pub fn load_csv(path: impl AsRef<Path>) -> io::Result<Vec<Body>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut bodies = Vec::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Result<Vec<f32>, _> = line.split(',').map(|f| f.trim().parse::<f32>()).collect();
+        let fields = match fields {
+            Ok(fields) => fields,
+            // Only the first line may be a header
+            Err(_) if i == 0 => continue,
+            Err(e) => {
+                let msg = format!("line {}: {e}", i + 1);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        };
+
+        let (mass, radius) = match fields[..] {
+            [_, _, _, _, mass] => (mass, radius_for_mass(mass)),
+            [_, _, _, _, mass, radius] => (mass, radius),
+            _ => {
+                let msg = format!("line {}: expected 5 or 6 columns, found {}", i + 1, fields.len());
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        };
+
+        let pos = Vec2::new(fields[0], fields[1]);
+        let vel = Vec2::new(fields[2], fields[3]);
+        bodies.push(Body::new(pos, vel, mass, radius));
+    }
+
+    Ok(bodies)
+}
+
+/// Saves bodies to a CSV file readable by `load_csv`, starting with a `CSV_HEADER` line.
+pub fn save_csv(path: impl AsRef<Path>, bodies: &[Body]) -> io::Result<()> {
+    let mut w = BufWriter::new(File::create(path)?);
+    writeln!(w, "{CSV_HEADER}")?;
+    for body in bodies {
+        writeln!(
+            w,
+            "{},{},{},{},{},{}",
+            body.pos.x, body.pos.y, body.vel.x, body.vel.y, body.mass, body.radius
+        )?;
+    }
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{TempFile, bodies_bits};
+
+    #[test]
+    fn csv_round_trips() {
+        // Accelerations are not saved, so the bodies start without any
+        let mut bodies = uniform_disc(100);
+        bodies.push(Body::new(Vec2::new(-1e-7, 3e9), Vec2::new(0.1, -0.2), 1e-30, 0.3));
+
+        let file = TempFile::new("bodies.csv");
+        save_csv(file.path(), &bodies).unwrap();
+        assert_eq!(bodies_bits(&load_csv(file.path()).unwrap()), bodies_bits(&bodies));
+    }
+
+    #[test]
+    fn csv_radius_defaults_to_mass() {
+        let file = TempFile::new("no-radius.csv");
+        std::fs::write(file.path(), "x,y,vx,vy,mass\n# comment\n\n1,2,3,4,5\n").unwrap();
+
+        let bodies = load_csv(file.path()).unwrap();
+        assert_eq!(
+            bodies_bits(&bodies),
+            bodies_bits(&[Body::new(
+                Vec2::new(1.0, 2.0),
+                Vec2::new(3.0, 4.0),
+                5.0,
+                radius_for_mass(5.0)
+            )])
+        );
+    }
+
+    #[test]
+    fn csv_rejects_bad_rows() {
+        let file = TempFile::new("bad.csv");
+        std::fs::write(file.path(), "x,y,vx,vy,mass\n1,2,3,4\n").unwrap();
+        assert_eq!(load_csv(file.path()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{
+    quadtree::{Quad, Quadtree},
+    simulation::Simulation,
+};
+
+use rayon::prelude::*;
+use ultraviolet::Vec2;
+
+/// Axis-aligned rectangle in world space, typically what a camera sees.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl ViewRect {
+    /// The rectangle spanned by two opposite corners, in any order.
+    pub fn new(a: Vec2, b: Vec2) -> Self {
+        Self {
+            min: a.min_by_component(b),
+            max: a.max_by_component(b),
+        }
+    }
+
+    pub fn contains(&self, pos: Vec2) -> bool {
+        pos.x >= self.min.x && pos.x <= self.max.x && pos.y >= self.min.y && pos.y <= self.max.y
+    }
+
+    fn overlaps(&self, quad: &Quad) -> bool {
+        let half = 0.5 * quad.size;
+        quad.center.x + half >= self.min.x
+            && quad.center.x - half <= self.max.x
+            && quad.center.y + half >= self.min.y
+            && quad.center.y - half <= self.max.y
+    }
+}
+
+/// A point to draw for one body or a cluster of bodies, see `Simulation::render_points`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderPoint {
+    /// Center of mass.
+    pub x: f32,
+    pub y: f32,
+    /// Total mass.
+    pub mass: f32,
+    /// Number of bodies.
+    pub count: u32,
+}
+
+impl Simulation {
+    /// Number of bodies in the quad of each node of the current tree, found by locating each body's
+    /// leaf (see `Quadtree::find_leaf`) and summing up the tree.
+    pub(crate) fn node_body_counts(&self) -> Vec<u32> {
+        let quadtree = &self.quadtree;
+        let leaves: Vec<u32> = self
+            .bodies
+            .positions()
+            .par_iter()
+            .map(|&pos| quadtree.find_leaf(pos) as u32)
+            .collect();
+
+        let mut counts = vec![0u32; quadtree.nodes.len()];
+        for &leaf in &leaves {
+            counts[leaf as usize] += 1;
+        }
+        for &node in quadtree.parents.iter().rev() {
+            let children = quadtree.nodes[node].children as usize;
+            counts[node] = counts[children..children + 4].iter().sum();
+        }
+        counts
+    }
+
+    /// Points to draw for the bodies in `view`, where clusters smaller than `pixel_size` (the world
+    /// size of a pixel) are merged into one point at their center of mass. Zoomed out, this is a
+    /// few points per pixel instead of every body; zoomed in, it is each visible body.
+    ///
+    /// Clusters are the nodes of the quadtree from the last step; without one, every body in view
+    /// is its own point. Clusters on the edge of the view may have their center just outside.
+    pub fn render_points(&self, view: ViewRect, pixel_size: f32, out: &mut Vec<RenderPoint>) {
+        out.clear();
+        let quadtree = &self.quadtree;
+        if quadtree.nodes.is_empty() {
+            let positions = self.bodies.positions();
+            out.par_extend(positions.par_iter().zip(self.bodies.masses()).filter_map(|(&pos, &mass)| {
+                view.contains(pos).then_some(RenderPoint { x: pos.x, y: pos.y, mass, count: 1 })
+            }));
+            return;
+        }
+
+        let counts = self.node_body_counts();
+        let mut node = Quadtree::ROOT;
+        loop {
+            let n = &quadtree.nodes[node];
+            let visible = view.overlaps(&n.quad);
+            if visible && n.is_branch() && n.quad.size > pixel_size {
+                node = n.children as usize;
+                continue;
+            }
+
+            if visible && counts[node] > 0 && (n.is_branch() || view.contains(n.pos)) {
+                out.push(RenderPoint { x: n.pos.x, y: n.pos.y, mass: n.mass, count: counts[node] });
+            }
+            if n.next == 0 {
+                break;
+            }
+            node = n.next as usize;
+        }
+    }
+
+    /// Writes the indices of the bodies inside `view` to `out`, in tree order, so renderers can
+    /// upload only the visible bodies when zoomed in.
+    ///
+    /// Walks the quadtree from the last step, skipping every node outside the view. Whenever the
+    /// tree misses bodies, as with `query_radius`, all bodies are tested instead (in index order).
+    pub fn bodies_in_view(&self, view: ViewRect, out: &mut Vec<u32>) {
+        out.clear();
+        let positions = self.bodies.positions();
+        let quadtree = &self.quadtree;
+        if !self.tree_indexes_bodies() {
+            out.par_extend(
+                positions
+                    .par_iter()
+                    .enumerate()
+                    .filter_map(|(i, &pos)| view.contains(pos).then_some(i as u32)),
+            );
+            return;
+        }
+
+        let mut node = Quadtree::ROOT;
+        loop {
+            let n = &quadtree.nodes[node];
+            let visible = view.overlaps(&n.quad);
+            if visible && n.is_branch() {
+                node = n.children as usize;
+                continue;
+            }
+
+            let i = n.body_index as usize;
+            if visible && n.mass > 0.0 && positions.get(i).is_some_and(|&pos| view.contains(pos)) {
+                out.push(n.body_index);
+            }
+            if n.next == 0 {
+                break;
+            }
+            node = n.next as usize;
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{bodies::Bodies, simulation::Simulation};
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes bodies as a VTK XML PolyData (`.vtp`) file that ParaView can open directly.
+///
+/// Each body is a vertex at (x, y, 0) with the point data arrays `mass`, `radius` and
+/// `velocity` (3 components, z = 0).
+pub fn write_vtp(w: &mut impl Write, bodies: &Bodies) -> io::Result<()> {
+    let n = bodies.len();
+
+    writeln!(w, r#"<?xml version="1.0"?>"#)?;
+    writeln!(w, r#"<VTKFile type="PolyData" version="1.0" byte_order="LittleEndian">"#)?;
+    writeln!(w, "<PolyData>")?;
+    writeln!(w, r#"<Piece NumberOfPoints="{n}" NumberOfVerts="{n}">"#)?;
+
+    writeln!(w, r#"<Points><DataArray type="Float32" NumberOfComponents="3" format="ascii">"#)?;
+    for pos in bodies.positions() {
+        writeln!(w, "{} {} 0", pos.x, pos.y)?;
+    }
+    writeln!(w, "</DataArray></Points>")?;
+
+    writeln!(w, r#"<Verts><DataArray type="Int64" Name="connectivity" format="ascii">"#)?;
+    for i in 0..n {
+        writeln!(w, "{i}")?;
+    }
+    writeln!(w, r#"</DataArray><DataArray type="Int64" Name="offsets" format="ascii">"#)?;
+    for i in 1..=n {
+        writeln!(w, "{i}")?;
+    }
+    writeln!(w, "</DataArray></Verts>")?;
+
+    writeln!(w, r#"<PointData Scalars="mass" Vectors="velocity">"#)?;
+    writeln!(w, r#"<DataArray type="Float32" Name="mass" format="ascii">"#)?;
+    for mass in bodies.masses() {
+        writeln!(w, "{mass}")?;
+    }
+    writeln!(w, r#"</DataArray><DataArray type="Float32" Name="radius" format="ascii">"#)?;
+    for radius in bodies.radii() {
+        writeln!(w, "{radius}")?;
+    }
+    writeln!(w, r#"</DataArray><DataArray type="Float32" Name="velocity" NumberOfComponents="3" format="ascii">"#)?;
+    for vel in bodies.velocities() {
+        writeln!(w, "{} {} 0", vel.x, vel.y)?;
+    }
+    writeln!(w, "</DataArray></PointData>")?;
+
+    writeln!(w, "</Piece>")?;
+    writeln!(w, "</PolyData>")?;
+    writeln!(w, "</VTKFile>")
+}
+
+/// Writes one `.vtp` file per output frame plus a `.pvd` collection indexing them by time,
+/// so ParaView can load the whole run as an animation.
+///
+/// Frames are written to `<dir>/<name>_<frame>.vtp` and the collection to `<dir>/<name>.pvd`,
+/// which is rewritten after every frame so it stays usable if the run is interrupted.
+#[derive(Debug)]
+pub struct VtkSeriesWriter {
+    dir: PathBuf,
+    name: String,
+    /// (time, file name) of every frame written so far.
+    frames: Vec<(f64, String)>,
+}
+
+impl VtkSeriesWriter {
+    /// Creates the output directory if needed.
+    pub fn new(dir: impl AsRef<Path>, name: &str) -> io::Result<Self> {
+        std::fs::create_dir_all(dir.as_ref())?;
+        Ok(Self {
+            dir: dir.as_ref().to_path_buf(),
+            name: name.to_owned(),
+            frames: Vec::new(),
+        })
+    }
+
+    /// Writes the current bodies of `sim` as a new frame and updates the collection file.
+    pub fn write_frame(&mut self, sim: &Simulation) -> io::Result<()> {
+        let file_name = format!("{}_{:08}.vtp", self.name, sim.frame);
+        let mut w = BufWriter::new(File::create(self.dir.join(&file_name))?);
+        write_vtp(&mut w, &sim.bodies)?;
+        w.flush()?;
+
+        self.frames.push((sim.time, file_name));
+        self.write_collection()
+    }
+
+    fn write_collection(&self) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(self.dir.join(format!("{}.pvd", self.name)))?);
+        writeln!(w, r#"<?xml version="1.0"?>"#)?;
+        writeln!(w, r#"<VTKFile type="Collection" version="1.0" byte_order="LittleEndian">"#)?;
+        writeln!(w, "<Collection>")?;
+        for (time, file) in &self.frames {
+            writeln!(w, r#"<DataSet timestep="{time}" group="" part="0" file="{file}"/>"#)?;
+        }
+        writeln!(w, "</Collection>")?;
+        writeln!(w, "</VTKFile>")?;
+        w.flush()
+    }
+}
+
+impl Simulation {
+    /// Saves the current bodies to a single `.vtp` file (see `write_vtp`).
+    pub fn save_vtp(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut w = BufWriter::new(File::create(path)?);
+        write_vtp(&mut w, &self.bodies)?;
+        w.flush()
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    body::Body,
+    generators::GENERATORS,
+    simulation::{Falloff, ForceMode, Simulation},
+};
+use ultraviolet::Vec2;
+use wasm_bindgen::prelude::*;
+
+/// JavaScript wrapper around `Simulation`, exported as `Simulation`.
+///
+/// Body data is returned as `Float32Array` copies; positions and velocities are interleaved
+/// as `[x0, y0, x1, y1, ...]`.
+#[wasm_bindgen(js_name = Simulation)]
+pub struct WasmSimulation {
+    sim: Simulation,
+}
+
+#[wasm_bindgen(js_class = Simulation)]
+impl WasmSimulation {
+    /// Creates a simulation with a uniform disc of `n` bodies.
+    #[wasm_bindgen(constructor)]
+    pub fn new(n: usize, dt: f32, theta: f32, epsilon: f32) -> Self {
+        Self { sim: Simulation::with_params(n, dt, theta, epsilon) }
+    }
+
+    /// Creates a simulation from a named generator and a JSON object of its parameters.
+    #[wasm_bindgen(js_name = fromGenerator)]
+    pub fn from_generator(name: &str, params: &str, dt: f32, theta: f32, epsilon: f32) -> Result<Self, JsError> {
+        let mut sim = Simulation::with_bodies(Vec::new(), dt, theta, epsilon);
+        sim.reset_with_generator(name, params)?;
+        Ok(Self { sim })
+    }
+
+    /// Advances the simulation by `n` steps.
+    pub fn step(&mut self, n: usize) {
+        self.sim.step_n(n);
+    }
+
+    /// Replaces all bodies with those of a named generator and resets time.
+    #[wasm_bindgen(js_name = resetWithGenerator)]
+    pub fn reset_with_generator(&mut self, name: &str, params: &str) -> Result<(), JsError> {
+        Ok(self.sim.reset_with_generator(name, params)?)
+    }
+
+    #[wasm_bindgen(js_name = addBody)]
+    pub fn add_body(&mut self, x: f32, y: f32, vx: f32, vy: f32, mass: f32, radius: f32) {
+        self.sim.add_body(Body::new(Vec2::new(x, y), Vec2::new(vx, vy), mass, radius));
+    }
+
+    #[wasm_bindgen(js_name = spawnDisc)]
+    pub fn spawn_disc(&mut self, x: f32, y: f32, n: usize, radius: f32, spin: f32) {
+        self.sim.spawn_disc(Vec2::new(x, y), n, radius, spin);
+    }
+
+    #[wasm_bindgen(js_name = spawnCluster)]
+    pub fn spawn_cluster(&mut self, x: f32, y: f32, n: usize, radius: f32) {
+        self.sim.spawn_cluster(Vec2::new(x, y), n, radius);
+    }
+
+    /// Adds an impulse to every body within `radius` of (x, y), fading linearly to the edge.
+    #[wasm_bindgen(js_name = applyImpulse)]
+    pub fn apply_impulse(&mut self, x: f32, y: f32, fx: f32, fy: f32, radius: f32) {
+        self.sim.apply_force(Vec2::new(x, y), Vec2::new(fx, fy), radius, Falloff::Linear, ForceMode::Impulse);
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn frame(&self) -> usize {
+        self.sim.frame
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn time(&self) -> f64 {
+        self.sim.time
+    }
+
+    #[wasm_bindgen(getter, js_name = bodyCount)]
+    pub fn body_count(&self) -> usize {
+        self.sim.bodies.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dt(&self) -> f32 {
+        self.sim.dt
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_dt(&mut self, dt: f32) {
+        self.sim.set_dt(dt);
+    }
+
+    #[wasm_bindgen(getter, js_name = gravityConstant)]
+    pub fn gravity_constant(&self) -> f32 {
+        self.sim.quadtree.g
+    }
+
+    #[wasm_bindgen(setter, js_name = gravityConstant)]
+    pub fn set_gravity_constant(&mut self, g: f32) {
+        self.sim.set_gravity_constant(g);
+    }
+
+    pub fn positions(&self) -> Vec<f32> {
+        self.sim.bodies.positions().iter().flat_map(|pos| [pos.x, pos.y]).collect()
+    }
+
+    pub fn velocities(&self) -> Vec<f32> {
+        self.sim.bodies.velocities().iter().flat_map(|vel| [vel.x, vel.y]).collect()
+    }
+
+    pub fn masses(&self) -> Vec<f32> {
+        self.sim.bodies.masses().to_vec()
+    }
+
+    pub fn radii(&self) -> Vec<f32> {
+        self.sim.bodies.radii().to_vec()
+    }
+
+    #[wasm_bindgen(js_name = kineticEnergy)]
+    pub fn kinetic_energy(&self) -> f32 {
+        self.sim.kinetic_energy()
+    }
+}
+
+/// Names of the available generators.
+#[wasm_bindgen(js_name = generatorNames)]
+pub fn generator_names() -> Vec<String> {
+    GENERATORS.iter().map(|generator| generator.name.to_owned()).collect()
+}
+
+/// JSON parameter description of a generator, or `undefined` for unknown names.
+#[wasm_bindgen(js_name = generatorParams)]
+pub fn generator_params(name: &str) -> Option<String> {
+    GENERATORS
+        .iter()
+        .find(|generator| generator.name == name)
+        .map(|generator| generator.params.to_owned())
+}
@@ -0,0 +1,124 @@
+use crate::simulation::Falloff;
+use ultraviolet::Vec2;
+
+/// An interactive attractor or repulsor that pulls bodies toward (or pushes them away from) a
+/// point without being a body itself: it has no mass, takes no part in collisions and is never
+/// moved by the bodies.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GravityWell {
+    pub pos: Vec2,
+    /// Acceleration at the center, toward the well if positive and away from it if negative.
+    pub strength: f32,
+    /// Distance beyond which the well has no effect; may be infinite.
+    pub radius: f32,
+    /// How the acceleration decreases with distance, as for `Simulation::apply_force`.
+    pub falloff: Falloff,
+    /// Simulated time left before the well is removed, or `None` to keep it until removed.
+    pub lifetime: Option<f32>,
+}
+
+impl GravityWell {
+    /// A permanent well at `pos`.
+    pub fn new(pos: Vec2, strength: f32, radius: f32, falloff: Falloff) -> Self {
+        Self { pos, strength, radius, falloff, lifetime: None }
+    }
+
+    /// Acceleration the well gives a body at `pos`, independent of the body's mass.
+    #[inline(always)]
+    pub fn acc(&self, pos: Vec2) -> Vec2 {
+        let d = self.pos - pos;
+        let dist = d.mag();
+        if dist > self.radius || dist == 0.0 {
+            return Vec2::zero();
+        }
+        let weight = match self.falloff {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => (1.0 - dist / self.radius).max(0.0),
+            Falloff::InverseSquare => 1.0 / (1.0 + dist * dist),
+        };
+        d * (self.strength * weight / dist)
+    }
+}
+
+/// Handle of a well added with `Simulation::add_well`, stable while other wells come and go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WellId(pub u32);
+
+/// The gravity wells of a simulation, applied during `Simulation::attract`.
+#[derive(Clone, Debug, Default)]
+pub struct Wells {
+    wells: Vec<GravityWell>,
+    ids: Vec<WellId>,
+    next_id: u32,
+}
+
+impl Wells {
+    pub fn len(&self) -> usize {
+        self.wells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.wells.is_empty()
+    }
+
+    /// The wells, in the order they were added.
+    pub fn as_slice(&self) -> &[GravityWell] {
+        &self.wells
+    }
+
+    /// The wells with their handles, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (WellId, &GravityWell)> {
+        self.ids.iter().copied().zip(&self.wells)
+    }
+
+    pub fn add(&mut self, well: GravityWell) -> WellId {
+        let id = WellId(self.next_id);
+        self.next_id = self.next_id.wrapping_add(1);
+        self.wells.push(well);
+        self.ids.push(id);
+        id
+    }
+
+    /// Removes the well `id`, returning it if it still existed.
+    pub fn remove(&mut self, id: WellId) -> Option<GravityWell> {
+        let index = self.ids.iter().position(|&other| other == id)?;
+        self.ids.remove(index);
+        Some(self.wells.remove(index))
+    }
+
+    /// The well `id`, to move or reshape it while the user drags it.
+    pub fn get_mut(&mut self, id: WellId) -> Option<&mut GravityWell> {
+        let index = self.ids.iter().position(|&other| other == id)?;
+        Some(&mut self.wells[index])
+    }
+
+    pub fn clear(&mut self) {
+        self.wells.clear();
+        self.ids.clear();
+    }
+
+    /// Counts down the lifetimes by `dt` and removes the wells whose lifetime ran out.
+    pub fn age(&mut self, dt: f32) {
+        for well in &mut self.wells {
+            if let Some(lifetime) = &mut well.lifetime {
+                *lifetime -= dt;
+            }
+        }
+        let mut kept = 0;
+        for k in 0..self.wells.len() {
+            if self.wells[k].lifetime.is_none_or(|lifetime| lifetime > 0.0) {
+                self.wells.swap(kept, k);
+                self.ids.swap(kept, k);
+                kept += 1;
+            }
+        }
+        self.wells.truncate(kept);
+        self.ids.truncate(kept);
+    }
+}
+
+/// Sum of the accelerations of all `wells` at `pos`.
+#[inline(always)]
+pub fn total_acc(wells: &[GravityWell], pos: Vec2) -> Vec2 {
+    wells.iter().fold(Vec2::zero(), |acc, well| acc + well.acc(pos))
+}